@@ -29,6 +29,32 @@ fn stage_csv(out_dir: &Path, name: &str, fallback: &str) -> bool {
     from_generated
 }
 
+/// `data/` に対応する CSV が無いテーブルを OUT_DIR へ集める。
+///
+/// バスの時刻表は GTFS からしか作れないので、generated/ が無ければ
+/// ヘッダだけの CSV を置く (時刻表が空の Worker になる)。
+fn stage_generated_only(out_dir: &Path, name: &str, header: &str) -> bool {
+    let generated = Path::new("generated").join(name);
+    println!("cargo:rerun-if-changed=generated/{name}");
+    if generated.is_file() {
+        fs::copy(&generated, out_dir.join(name))
+            .unwrap_or_else(|e| panic!("{} を配置できない: {e}", generated.display()));
+        true
+    } else {
+        fs::write(out_dir.join(name), format!("{header}\n"))
+            .unwrap_or_else(|e| panic!("{name} を書けない: {e}"));
+        false
+    }
+}
+
+/// ビルドの指紋に使う FNV-1a (64bit)。
+/// 暗号強度は要らず、データかコードが変われば値が変わればよい。
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
 fn main() {
     // 本番と同じデータを使うには preprocessor の出力が要る。preprocessor は
     // 列車種別を持たない路線へ各駅停車の系統を補う (約2,400行)。この行は
@@ -62,6 +88,22 @@ fn main() {
         stage_csv(&out_dir, "types.csv", "data/4!types.csv"),
         stage_csv(&out_dir, "aliases.csv", "data/6!aliases.csv"),
         stage_csv(&out_dir, "line_aliases.csv", "data/7!line_aliases.csv"),
+        stage_csv(&out_dir, "connections.csv", "data/8!connections.csv"),
+        stage_generated_only(
+            &out_dir,
+            "bus_calendars.csv",
+            "service_cd,monday,tuesday,wednesday,thursday,friday,saturday,sunday,start_date,end_date",
+        ),
+        stage_generated_only(
+            &out_dir,
+            "bus_calendar_dates.csv",
+            "id,service_cd,date,exception_type",
+        ),
+        stage_generated_only(
+            &out_dir,
+            "bus_timetables.csv",
+            "id,station_cd,service_cd,headsign,departures",
+        ),
         // station_station_types は下の sst 変換でも参照するが、
         // 混在判定に含めるためここでも存在を見る
         Path::new("generated/station_station_types.csv").is_file(),
@@ -140,4 +182,60 @@ fn main() {
 
     fs::write(out_dir.join("sst.bin"), &out).expect("sst.bin を書けない");
     println!("cargo:warning=sst.bin: {} 行", out.len() / 16);
+
+    // 応答は埋め込みデータとコードで決まるので、両方の指紋を HTTP キャッシュの
+    // ETag にする。ファイル名も混ぜ、中身が入れ替わっただけの場合も区別する
+    let mut embedded: Vec<PathBuf> = fs::read_dir(&out_dir)
+        .expect("OUT_DIR を読めない")
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|ext| ext == "csv" || ext == "bin")
+        })
+        .collect();
+    embedded.sort();
+    let hash = embedded.iter().fold(0xcbf2_9ce4_8422_2325, |hash, path| {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let bytes = fs::read(path).unwrap_or_else(|e| panic!("{} を読めない: {e}", path.display()));
+        fnv1a(fnv1a(hash, name.as_bytes()), &bytes)
+    });
+    // コードだけを直したデプロイでも ETag を変え、古い応答に 304 を返さないようにする
+    let hash = code_files().iter().fold(hash, |hash, path| {
+        let bytes = fs::read(path).unwrap_or_else(|e| panic!("{} を読めない: {e}", path.display()));
+        fnv1a(fnv1a(hash, path.to_string_lossy().as_bytes()), &bytes)
+    });
+    println!("cargo:rustc-env=BUILD_HASH={hash:016x}");
+}
+
+/// 応答を左右するコード。Worker とドメインのソース、依存のバージョンを含む。
+/// 並びはパスの昇順で、実行環境によらず同じ指紋になる。
+fn code_files() -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for dir in ["src", "stationapi/src"] {
+        println!("cargo:rerun-if-changed={dir}");
+        collect_files(Path::new(dir), &mut files);
+    }
+    for file in [
+        "build.rs",
+        "Cargo.toml",
+        "Cargo.lock",
+        "stationapi/Cargo.toml",
+    ] {
+        println!("cargo:rerun-if-changed={file}");
+        files.push(PathBuf::from(file));
+    }
+    files.sort();
+    files
+}
+
+fn collect_files(dir: &Path, out: &mut Vec<PathBuf>) {
+    let entries = fs::read_dir(dir).unwrap_or_else(|e| panic!("{} を読めない: {e}", dir.display()));
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if path.is_dir() {
+            collect_files(&path, out);
+        } else {
+            out.push(path);
+        }
+    }
 }
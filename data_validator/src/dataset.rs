@@ -0,0 +1,195 @@
+//! 検査対象の CSV 一式。
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use csv::{ReaderBuilder, StringRecord};
+
+use crate::schema::{self, TableSchema};
+
+/// 1 ファイル分。セルは文字列のまま持ち、解釈は規則ごとに行う。
+pub struct Table {
+    pub schema: &'static TableSchema,
+    pub headers: Vec<String>,
+    index_of: HashMap<String, usize>,
+    records: Vec<StringRecord>,
+}
+
+impl Table {
+    pub fn new(
+        schema: &'static TableSchema,
+        headers: Vec<String>,
+        records: Vec<StringRecord>,
+    ) -> Self {
+        let index_of = headers
+            .iter()
+            .enumerate()
+            .map(|(i, h)| (h.clone(), i))
+            .collect();
+        Table {
+            schema,
+            headers,
+            index_of,
+            records,
+        }
+    }
+
+    fn load(dir: &Path, schema: &'static TableSchema) -> Result<Self, csv::Error> {
+        let mut rdr = ReaderBuilder::new().from_path(dir.join(schema.file))?;
+        let headers = rdr.headers()?.iter().map(str::to_string).collect();
+        let records = rdr.records().collect::<Result<Vec<_>, _>>()?;
+        Ok(Table::new(schema, headers, records))
+    }
+
+    pub fn file(&self) -> &'static str {
+        self.schema.file
+    }
+
+    pub fn has_column(&self, name: &str) -> bool {
+        self.index_of.contains_key(name)
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = Row<'_>> {
+        self.records.iter().enumerate().map(move |(i, record)| Row {
+            table: self,
+            record,
+            index: i,
+        })
+    }
+
+    /// `column` の値の集合。外部キーの参照先を引くのに使う。
+    pub fn values(&self, column: &str) -> HashSet<&str> {
+        self.rows().map(|row| row.get(column)).collect()
+    }
+}
+
+/// 表の 1 行。
+#[derive(Clone, Copy)]
+pub struct Row<'a> {
+    table: &'a Table,
+    record: &'a StringRecord,
+    index: usize,
+}
+
+impl<'a> Row<'a> {
+    /// 列の値。列そのものが無いときは空文字 (列の欠落は `schema-header` が報告する)。
+    pub fn get(&self, column: &str) -> &'a str {
+        self.table
+            .index_of
+            .get(column)
+            .and_then(|&i| self.record.get(i))
+            .unwrap_or("")
+    }
+
+    pub fn int(&self, column: &str) -> Option<i64> {
+        self.get(column).parse().ok()
+    }
+
+    pub fn float(&self, column: &str) -> Option<f64> {
+        self.get(column)
+            .parse()
+            .ok()
+            .filter(|v: &f64| v.is_finite())
+    }
+
+    /// ファイル上の行番号 (ヘッダが 1 行目)。
+    pub fn line(&self) -> u64 {
+        self.record
+            .position()
+            .map(|p| p.line())
+            .unwrap_or(self.index as u64 + 2)
+    }
+
+    /// `station_cd=100201` のような行の鍵。許可リストはこの文字列で照合する。
+    pub fn key(&self) -> String {
+        self.table
+            .schema
+            .key
+            .iter()
+            .map(|column| format!("{column}={}", self.get(column)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+pub struct Dataset {
+    pub companies: Table,
+    pub lines: Table,
+    pub stations: Table,
+    pub types: Table,
+    pub station_station_types: Table,
+    pub aliases: Table,
+    pub line_aliases: Table,
+    pub connections: Table,
+}
+
+impl Dataset {
+    pub fn load(dir: &Path) -> Result<Self, csv::Error> {
+        Ok(Dataset {
+            companies: Table::load(dir, &schema::COMPANIES)?,
+            lines: Table::load(dir, &schema::LINES)?,
+            stations: Table::load(dir, &schema::STATIONS)?,
+            types: Table::load(dir, &schema::TYPES)?,
+            station_station_types: Table::load(dir, &schema::STATION_STATION_TYPES)?,
+            aliases: Table::load(dir, &schema::ALIASES)?,
+            line_aliases: Table::load(dir, &schema::LINE_ALIASES)?,
+            connections: Table::load(dir, &schema::CONNECTIONS)?,
+        })
+    }
+
+    /// 列定義から表を引く。
+    pub fn table(&self, schema: &TableSchema) -> &Table {
+        self.tables()
+            .into_iter()
+            .find(|t| std::ptr::eq(t.schema, schema))
+            .unwrap_or_else(|| panic!("{} が読み込まれていない", schema.file))
+    }
+
+    pub fn tables(&self) -> [&Table; 8] {
+        [
+            &self.companies,
+            &self.lines,
+            &self.stations,
+            &self.types,
+            &self.station_station_types,
+            &self.aliases,
+            &self.line_aliases,
+            &self.connections,
+        ]
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    /// 規則のテスト用。`tables` に無い表は README どおりの列だけを持つ空の表にする。
+    pub fn dataset(tables: &[(&'static TableSchema, &str)]) -> Dataset {
+        let table = |schema: &'static TableSchema| match tables
+            .iter()
+            .find(|(s, _)| std::ptr::eq(*s, schema))
+        {
+            Some((_, text)) => {
+                let mut rdr = ReaderBuilder::new().from_reader(text.as_bytes());
+                let headers = rdr.headers().unwrap().iter().map(str::to_string).collect();
+                let records = rdr.records().collect::<Result<Vec<_>, _>>().unwrap();
+                Table::new(schema, headers, records)
+            }
+            None => Table::new(
+                schema,
+                schema.columns.iter().map(|c| c.name.to_string()).collect(),
+                Vec::new(),
+            ),
+        };
+        Dataset {
+            companies: table(&schema::COMPANIES),
+            lines: table(&schema::LINES),
+            stations: table(&schema::STATIONS),
+            types: table(&schema::TYPES),
+            station_station_types: table(&schema::STATION_STATION_TYPES),
+            aliases: table(&schema::ALIASES),
+            line_aliases: table(&schema::LINE_ALIASES),
+            connections: table(&schema::CONNECTIONS),
+        }
+    }
+}
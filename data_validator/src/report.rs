@@ -0,0 +1,68 @@
+//! PR に貼る Markdown の報告。
+//!
+//! 先頭の `<!-- data-validator -->` は、コメント用のワークフローが既存のコメントを
+//! 探す目印なので変えない。
+
+use crate::rule::{Outcome, Severity};
+
+/// 1 規則あたりに並べる違反の上限。PR コメントの長さ制限 (65,536 文字) に収めるため。
+const MAX_ROWS_PER_RULE: usize = 100;
+
+pub fn build_markdown_report(outcomes: &[Outcome<'_>]) -> String {
+    let mut md = String::new();
+
+    md.push_str("<!-- data-validator -->\n");
+    md.push_str("## :x: データ整合性チェックに失敗しました\n\n");
+
+    for outcome in outcomes
+        .iter()
+        .filter(|o| o.severity() == Severity::Error && !o.violations.is_empty())
+    {
+        push_section(&mut md, outcome);
+    }
+
+    let warnings: Vec<&Outcome<'_>> = outcomes
+        .iter()
+        .filter(|o| o.severity() == Severity::Warning && !o.violations.is_empty())
+        .collect();
+    if !warnings.is_empty() {
+        md.push_str("## :warning: 警告\n\n");
+        md.push_str("次の項目は検査を失敗させませんが、確認してください。\n\n");
+        for outcome in warnings {
+            push_section(&mut md, outcome);
+        }
+    }
+
+    md
+}
+
+fn push_section(md: &mut String, outcome: &Outcome<'_>) {
+    let rule = outcome.rule;
+    md.push_str(&format!(
+        "### {} ({} 件)\n\n",
+        rule.title(),
+        outcome.violations.len()
+    ));
+    md.push_str(&format!("{} (`{}`)\n\n", rule.description(), rule.id()));
+    md.push_str("<details>\n<summary>該当レコード一覧</summary>\n\n");
+    md.push_str("| ファイル | キー | 内容 |\n|---|---|---|\n");
+    for v in outcome.violations.iter().take(MAX_ROWS_PER_RULE) {
+        md.push_str(&format!(
+            "| `{}` | `{}` | {} |\n",
+            escape_markdown_cell(v.file),
+            escape_markdown_cell(&v.key),
+            v.message.replace('|', "&#124;")
+        ));
+    }
+    if outcome.violations.len() > MAX_ROWS_PER_RULE {
+        md.push_str(&format!(
+            "\nほか {} 件は `cargo run -p data_validator` の出力を確認してください。\n",
+            outcome.violations.len() - MAX_ROWS_PER_RULE
+        ));
+    }
+    md.push_str("\n</details>\n\n");
+}
+
+fn escape_markdown_cell(s: &str) -> String {
+    s.replace('`', "&#96;").replace('|', "&#124;")
+}
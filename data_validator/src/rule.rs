@@ -0,0 +1,200 @@
+//! 検査規則の枠組み。
+//!
+//! 1 つの検査を 1 つの `Rule` として実装し、`rules::all()` に並べる。規則は
+//! 違反を `Violation` として積むだけで、深刻度の付与と許可リストの適用は
+//! `run` がまとめて行う。
+
+use crate::allow::Allow;
+use crate::dataset::{Dataset, Row};
+
+/// 違反の深刻度。`Error` が 1 件でもあれば検査は失敗する。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// 規則が見つけた違反 1 件。
+#[derive(Clone, Debug, PartialEq)]
+pub struct Violation {
+    pub file: &'static str,
+    /// 行の鍵 (`station_cd=100201` など)。許可リストはこれと照合する。
+    pub key: String,
+    pub message: String,
+}
+
+impl Violation {
+    pub fn new(file: &'static str, key: impl Into<String>, message: impl Into<String>) -> Self {
+        Violation {
+            file,
+            key: key.into(),
+            message: message.into(),
+        }
+    }
+
+    /// 行に対する違反。メッセージの先頭に行番号を付ける。
+    pub fn at(row: &Row<'_>, file: &'static str, message: impl Into<String>) -> Self {
+        Violation::new(
+            file,
+            row.key(),
+            format!("{} 行目: {}", row.line(), message.into()),
+        )
+    }
+}
+
+pub trait Rule {
+    /// 許可リストや報告で使う識別子 (`foreign-key` など)。
+    fn id(&self) -> &'static str;
+    /// 報告の見出し。
+    fn title(&self) -> &'static str;
+    /// 報告で見出しの下に添える説明。
+    fn description(&self) -> &'static str;
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+    fn check(&self, data: &Dataset, out: &mut Vec<Violation>);
+}
+
+/// 規則 1 つ分の結果。
+pub struct Outcome<'r> {
+    pub rule: &'r dyn Rule,
+    pub violations: Vec<Violation>,
+    /// 許可リストで見逃した件数。
+    pub allowed: usize,
+}
+
+impl Outcome<'_> {
+    pub fn severity(&self) -> Severity {
+        self.rule.severity()
+    }
+}
+
+/// 全規則を走らせ、許可リストに載った違反を取り除く。違反の無い規則は結果に含めない。
+///
+/// どの違反にも当たらなかった許可リストの項目は、直した後の消し忘れなので
+/// `allow-list` の警告として返す。
+pub fn run<'r>(rules: &'r [Box<dyn Rule>], data: &Dataset, allow: &[Allow]) -> Vec<Outcome<'r>> {
+    let mut used = vec![false; allow.len()];
+    let mut outcomes = Vec::new();
+
+    for rule in rules {
+        let mut found = Vec::new();
+        rule.check(data, &mut found);
+
+        let mut allowed = 0;
+        found.retain(|v| {
+            let hit = allow
+                .iter()
+                .position(|a| a.rule == rule.id() && a.key == v.key);
+            if let Some(i) = hit {
+                used[i] = true;
+                allowed += 1;
+            }
+            hit.is_none()
+        });
+
+        if !found.is_empty() || allowed > 0 {
+            outcomes.push(Outcome {
+                rule: rule.as_ref(),
+                violations: found,
+                allowed,
+            });
+        }
+    }
+
+    let stale: Vec<Violation> = allow
+        .iter()
+        .zip(&used)
+        .filter(|(_, used)| !**used)
+        .map(|(a, _)| {
+            Violation::new(
+                "allow.rs",
+                format!("{} {}", a.rule, a.key),
+                format!("該当する違反がありません ({})", a.reason),
+            )
+        })
+        .collect();
+    if !stale.is_empty() {
+        outcomes.push(Outcome {
+            rule: &StaleAllowList,
+            violations: stale,
+            allowed: 0,
+        });
+    }
+
+    outcomes
+}
+
+/// 使われなくなった許可リストの項目。`run` が直接積むので `check` は何もしない。
+struct StaleAllowList;
+
+impl Rule for StaleAllowList {
+    fn id(&self) -> &'static str {
+        "allow-list"
+    }
+    fn title(&self) -> &'static str {
+        "使われていない許可リストの項目"
+    }
+    fn description(&self) -> &'static str {
+        "`data_validator/src/allow.rs` の項目に当たる違反がありません。データを直したなら項目を消してください。"
+    }
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+    fn check(&self, _: &Dataset, _: &mut Vec<Violation>) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataset::tests::dataset;
+
+    struct Fixed(Vec<&'static str>);
+
+    impl Rule for Fixed {
+        fn id(&self) -> &'static str {
+            "fixed"
+        }
+        fn title(&self) -> &'static str {
+            "fixed"
+        }
+        fn description(&self) -> &'static str {
+            ""
+        }
+        fn check(&self, _: &Dataset, out: &mut Vec<Violation>) {
+            out.extend(
+                self.0
+                    .iter()
+                    .map(|key| Violation::new("x.csv", *key, "bad")),
+            );
+        }
+    }
+
+    #[test]
+    fn allow_list_removes_matching_violations_and_reports_stale_entries() {
+        let rules: Vec<Box<dyn Rule>> =
+            vec![Box::new(Fixed(vec!["a=1", "a=2"])), Box::new(Fixed(vec![]))];
+        let allow = [
+            Allow {
+                rule: "fixed",
+                key: "a=1",
+                reason: "test",
+            },
+            Allow {
+                rule: "fixed",
+                key: "a=3",
+                reason: "fixed already",
+            },
+        ];
+        let outcomes = run(&rules, &dataset(&[]), &allow);
+
+        assert_eq!(outcomes.len(), 2);
+        assert_eq!(outcomes[0].rule.id(), "fixed");
+        assert_eq!(outcomes[0].allowed, 1);
+        assert_eq!(outcomes[0].violations.len(), 1);
+        assert_eq!(outcomes[0].violations[0].key, "a=2");
+        assert_eq!(outcomes[1].rule.id(), "allow-list");
+        assert_eq!(outcomes[1].severity(), Severity::Warning);
+        assert_eq!(outcomes[1].violations[0].key, "fixed a=3");
+    }
+}
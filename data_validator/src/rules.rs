@@ -0,0 +1,948 @@
+//! 検査規則の一覧。
+//!
+//! 前半は `schema` の列定義を見て全ファイルの列を検査する規則、後半は表を
+//! またぐ整合性の規則。規則を足したら `all()` に並べる。
+
+mod geometry;
+
+use std::collections::{HashMap, HashSet};
+
+use crate::dataset::{Dataset, Row, Table};
+use crate::rule::{Rule, Severity, Violation};
+use crate::schema::{self, Column, Kind, TableSchema};
+
+pub fn all() -> Vec<Box<dyn Rule>> {
+    vec![
+        Box::new(Header),
+        Box::new(ColumnRule {
+            id: "required",
+            title: "必須の列が空",
+            description: "`data/README.md` で必須 (✓) とされている列が空です。",
+            check: check_required,
+        }),
+        Box::new(ColumnRule {
+            id: "integer",
+            title: "整数でない値",
+            description: "数値の列に整数として読めない値があります。",
+            check: check_integer,
+        }),
+        Box::new(ColumnRule {
+            id: "number",
+            title: "数値でない値",
+            description: "座標・距離の列に数値として読めない値があります。",
+            check: check_number,
+        }),
+        Box::new(ColumnRule {
+            id: "code-range",
+            title: "区分値の範囲外",
+            description: "区分値 (`e_status`・`line_type`・`kind`・`pass` など) や都道府県コードが定義された範囲にありません。",
+            check: check_range,
+        }),
+        Box::new(ColumnRule {
+            id: "hex-color",
+            title: "カラーコードの形式",
+            description: "色の列は `#` で始まる 6 桁の 16 進数 (`#RRGGBB`) で入力してください。",
+            check: check_hex_color,
+        }),
+        Box::new(ColumnRule {
+            id: "date-format",
+            title: "日付の形式",
+            description: "日付は実在する `YYYY-MM-DD` で入力してください (不明・現役は `0000-00-00`)。",
+            check: check_date,
+        }),
+        Box::new(ColumnRule {
+            id: "url",
+            title: "URL の形式",
+            description: "URL は `http://` または `https://` で始めてください。",
+            check: check_url,
+        }),
+        Box::new(ColumnRule {
+            id: "postal-code",
+            title: "郵便番号の形式",
+            description: "郵便番号は `000-0000` 形式で入力してください。",
+            check: check_postal_code,
+        }),
+        Box::new(ColumnRule {
+            id: "symbol-shape",
+            title: "路線記号の形状",
+            description: "`line_symbolN_shape` は `data/README.md` の一覧にある値を使ってください。",
+            check: check_symbol_shape,
+        }),
+        Box::new(UniqueKey),
+        Box::new(ForeignKey),
+        Box::new(CoordinatesInJapan),
+        Box::new(CloseAfterOpen),
+        Box::new(ClosedStationStatus),
+        Box::new(LineStationStatus),
+        Box::new(EmptyLine),
+        Box::new(LineSymbolOrder),
+        Box::new(AliasPlaceholder),
+        Box::new(ConnectionPair),
+        Box::new(StationOrder),
+        Box::new(geometry::StationHopDistance),
+        Box::new(geometry::StationZigzag),
+        Box::new(geometry::StopPatternZigzag),
+    ]
+}
+
+// ---------------------------------------------------------------- 列の形式
+
+/// README に載っている列が揃っていること、載っていない列が無いこと。
+/// `#` で始まる列はメモ用で取り込まれないので対象外。
+struct Header;
+
+impl Rule for Header {
+    fn id(&self) -> &'static str {
+        "schema-header"
+    }
+    fn title(&self) -> &'static str {
+        "列の過不足"
+    }
+    fn description(&self) -> &'static str {
+        "ヘッダ行が `data/README.md` の列定義と一致しません。"
+    }
+    fn check(&self, data: &Dataset, out: &mut Vec<Violation>) {
+        for table in data.tables() {
+            for column in table.schema.columns {
+                if !table.has_column(column.name) {
+                    out.push(Violation::new(
+                        table.file(),
+                        format!("column={}", column.name),
+                        format!("列 `{}` がありません", column.name),
+                    ));
+                }
+            }
+            for header in &table.headers {
+                if !header.starts_with('#') && table.schema.column(header).is_none() {
+                    out.push(Violation::new(
+                        table.file(),
+                        format!("column={header}"),
+                        format!("列 `{header}` は定義されていません"),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// 列定義の形式ごとに全ファイルの全セルを見る規則。`check` が `Some` を返したセルが違反。
+struct ColumnRule {
+    id: &'static str,
+    title: &'static str,
+    description: &'static str,
+    check: fn(&Column, &str) -> Option<String>,
+}
+
+impl Rule for ColumnRule {
+    fn id(&self) -> &'static str {
+        self.id
+    }
+    fn title(&self) -> &'static str {
+        self.title
+    }
+    fn description(&self) -> &'static str {
+        self.description
+    }
+    fn check(&self, data: &Dataset, out: &mut Vec<Violation>) {
+        for table in data.tables() {
+            let columns: Vec<&Column> = table
+                .schema
+                .columns
+                .iter()
+                .filter(|c| table.has_column(c.name))
+                .collect();
+            for row in table.rows() {
+                for column in &columns {
+                    if let Some(message) = (self.check)(column, row.get(column.name)) {
+                        out.push(Violation::at(&row, table.file(), message));
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn check_required(column: &Column, value: &str) -> Option<String> {
+    (column.required && value.trim().is_empty()).then(|| format!("`{}` が空です", column.name))
+}
+
+fn check_integer(column: &Column, value: &str) -> Option<String> {
+    let integer = match column.kind {
+        Kind::Int | Kind::Range(..) => true,
+        Kind::Id => value != "DEFAULT",
+        _ => false,
+    };
+    (integer && !value.is_empty() && value.parse::<i64>().is_err())
+        .then(|| format!("`{}` = `{value}` は整数ではありません", column.name))
+}
+
+fn check_number(column: &Column, value: &str) -> Option<String> {
+    (column.kind == Kind::Number
+        && !value.is_empty()
+        && !value.parse::<f64>().is_ok_and(f64::is_finite))
+    .then(|| format!("`{}` = `{value}` は数値ではありません", column.name))
+}
+
+fn check_range(column: &Column, value: &str) -> Option<String> {
+    let Kind::Range(lo, hi) = column.kind else {
+        return None;
+    };
+    // 整数として読めないものは `integer` が報告する
+    let v = value.parse::<i64>().ok()?;
+    (!(lo..=hi).contains(&v)).then(|| format!("`{}` = {v} は {lo}〜{hi} の範囲外です", column.name))
+}
+
+fn check_hex_color(column: &Column, value: &str) -> Option<String> {
+    (column.kind == Kind::HexColor && !value.is_empty() && !is_hex_color(value))
+        .then(|| format!("`{}` = `{value}` は #RRGGBB ではありません", column.name))
+}
+
+fn check_date(column: &Column, value: &str) -> Option<String> {
+    (column.kind == Kind::Date && !value.is_empty() && parse_ymd(value).is_none()).then(|| {
+        format!(
+            "`{}` = `{value}` は YYYY-MM-DD の日付ではありません",
+            column.name
+        )
+    })
+}
+
+fn check_url(column: &Column, value: &str) -> Option<String> {
+    (column.kind == Kind::Url
+        && !value.is_empty()
+        && !(value.starts_with("http://") || value.starts_with("https://")))
+    .then(|| {
+        format!(
+            "`{}` = `{value}` は http(s) の URL ではありません",
+            column.name
+        )
+    })
+}
+
+fn check_postal_code(column: &Column, value: &str) -> Option<String> {
+    let well_formed = value.len() == 8
+        && value.bytes().enumerate().all(|(i, b)| match i {
+            3 => b == b'-',
+            _ => b.is_ascii_digit(),
+        });
+    (column.kind == Kind::PostalCode && !value.is_empty() && !well_formed).then(|| {
+        format!(
+            "`{}` = `{value}` は 000-0000 形式ではありません",
+            column.name
+        )
+    })
+}
+
+fn check_symbol_shape(column: &Column, value: &str) -> Option<String> {
+    (column.kind == Kind::SymbolShape
+        && !value.is_empty()
+        && !schema::SYMBOL_SHAPES.contains(&value))
+    .then(|| format!("`{}` = `{value}` は定義されていない形状です", column.name))
+}
+
+fn is_hex_color(value: &str) -> bool {
+    value.len() == 7 && value.starts_with('#') && value[1..].bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// `YYYY-MM-DD` を `(年, 月, 日)` に。`0000-00-00` は `(0, 0, 0)` として通す。
+fn parse_ymd(value: &str) -> Option<(u32, u32, u32)> {
+    if value == "0000-00-00" {
+        return Some((0, 0, 0));
+    }
+    let bytes = value.as_bytes();
+    if bytes.len() != 10 || bytes[4] != b'-' || bytes[7] != b'-' {
+        return None;
+    }
+    let year: u32 = value[0..4].parse().ok()?;
+    let month: u32 = value[5..7].parse().ok()?;
+    let day: u32 = value[8..10].parse().ok()?;
+    let leap = (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400);
+    let days = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return None,
+    };
+    (year > 0 && (1..=days).contains(&day)).then_some((year, month, day))
+}
+
+// ---------------------------------------------------------------- 表の整合性
+
+/// 主キーの重複。
+struct UniqueKey;
+
+impl Rule for UniqueKey {
+    fn id(&self) -> &'static str {
+        "unique-key"
+    }
+    fn title(&self) -> &'static str {
+        "コードの重複"
+    }
+    fn description(&self) -> &'static str {
+        "`company_cd`・`line_cd`・`station_cd`・`type_cd` などのコードは、ファイルの中で一意にしてください。"
+    }
+    fn check(&self, data: &Dataset, out: &mut Vec<Violation>) {
+        for table in data.tables().into_iter().filter(|t| t.schema.unique) {
+            let mut first_line: HashMap<String, u64> = HashMap::new();
+            for row in table.rows() {
+                let key = row.key();
+                match first_line.get(&key) {
+                    Some(first) => out.push(Violation::at(
+                        &row,
+                        table.file(),
+                        format!("{key} は {first} 行目と重複しています"),
+                    )),
+                    None => {
+                        first_line.insert(key, row.line());
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// 他のファイルのコードを指す列。`(参照元, 列, 参照先, 列)`。
+const FOREIGN_KEYS: &[(&TableSchema, &str, &TableSchema, &str)] = &[
+    (
+        &schema::LINES,
+        "company_cd",
+        &schema::COMPANIES,
+        "company_cd",
+    ),
+    (&schema::STATIONS, "line_cd", &schema::LINES, "line_cd"),
+    (
+        &schema::STATION_STATION_TYPES,
+        "station_cd",
+        &schema::STATIONS,
+        "station_cd",
+    ),
+    (
+        &schema::STATION_STATION_TYPES,
+        "type_cd",
+        &schema::TYPES,
+        "type_cd",
+    ),
+    (
+        &schema::LINE_ALIASES,
+        "station_cd",
+        &schema::STATIONS,
+        "station_cd",
+    ),
+    (&schema::LINE_ALIASES, "alias_cd", &schema::ALIASES, "id"),
+    (
+        &schema::CONNECTIONS,
+        "station_cd1",
+        &schema::STATIONS,
+        "station_cd",
+    ),
+    (
+        &schema::CONNECTIONS,
+        "station_cd2",
+        &schema::STATIONS,
+        "station_cd",
+    ),
+];
+
+struct ForeignKey;
+
+impl Rule for ForeignKey {
+    fn id(&self) -> &'static str {
+        "foreign-key"
+    }
+    fn title(&self) -> &'static str {
+        "存在しないコードへの参照"
+    }
+    fn description(&self) -> &'static str {
+        "参照先のファイルに存在しないコードが使われています。"
+    }
+    fn check(&self, data: &Dataset, out: &mut Vec<Violation>) {
+        for &(from, column, to, to_column) in FOREIGN_KEYS {
+            let from = data.table(from);
+            let to = data.table(to);
+            let known = to.values(to_column);
+            for row in from.rows() {
+                let value = row.get(column);
+                // 空は `required` が報告する
+                if !value.is_empty() && !known.contains(value) {
+                    out.push(Violation::at(
+                        &row,
+                        from.file(),
+                        format!(
+                            "`{column}` = {value} は `{}` の `{to_column}` にありません",
+                            to.file()
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// 日本の領域を囲む緯度経度の範囲 (沖ノ鳥島・南鳥島・与那国島・択捉島を含む)。
+const JAPAN_LAT: (f64, f64) = (20.0, 46.0);
+const JAPAN_LON: (f64, f64) = (122.0, 154.0);
+
+/// 駅の座標が日本の範囲に収まること。緯度と経度の取り違えもここで引っかかる。
+struct CoordinatesInJapan;
+
+impl Rule for CoordinatesInJapan {
+    fn id(&self) -> &'static str {
+        "coordinates-in-japan"
+    }
+    fn title(&self) -> &'static str {
+        "日本の範囲外の座標"
+    }
+    fn description(&self) -> &'static str {
+        "駅の座標が日本の範囲 (北緯 20〜46 度、東経 122〜154 度) にありません。`lat` と `lon` の取り違えを確認してください。"
+    }
+    fn check(&self, data: &Dataset, out: &mut Vec<Violation>) {
+        let table = &data.stations;
+        for row in table.rows() {
+            // 数値として読めないものは `number` が報告する
+            let (Some(lat), Some(lon)) = (row.float("lat"), row.float("lon")) else {
+                continue;
+            };
+            let inside = (JAPAN_LAT.0..=JAPAN_LAT.1).contains(&lat)
+                && (JAPAN_LON.0..=JAPAN_LON.1).contains(&lon);
+            if !inside {
+                out.push(Violation::at(
+                    &row,
+                    table.file(),
+                    format!("({lat}, {lon}) は日本の範囲外です"),
+                ));
+            }
+        }
+    }
+}
+
+/// 廃止日が開業日より前になっていないこと。
+struct CloseAfterOpen;
+
+impl Rule for CloseAfterOpen {
+    fn id(&self) -> &'static str {
+        "close-after-open"
+    }
+    fn title(&self) -> &'static str {
+        "開業日と廃止日の前後"
+    }
+    fn description(&self) -> &'static str {
+        "`close_ymd` が `open_ymd` より前の日付になっています。"
+    }
+    fn check(&self, data: &Dataset, out: &mut Vec<Violation>) {
+        let table = &data.stations;
+        for row in table.rows() {
+            let open = parse_ymd(row.get("open_ymd"));
+            let close = parse_ymd(row.get("close_ymd"));
+            if let (Some(open), Some(close)) = (open, close) {
+                if open.0 != 0 && close.0 != 0 && close < open {
+                    out.push(Violation::at(
+                        &row,
+                        table.file(),
+                        format!(
+                            "廃止日 {} が開業日 {} より前です",
+                            row.get("close_ymd"),
+                            row.get("open_ymd")
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// 廃止日のある駅が運用中になっていないこと。
+struct ClosedStationStatus;
+
+impl Rule for ClosedStationStatus {
+    fn id(&self) -> &'static str {
+        "closed-station-status"
+    }
+    fn title(&self) -> &'static str {
+        "廃止日のある運用中の駅"
+    }
+    fn description(&self) -> &'static str {
+        "`close_ymd` が入っている駅は `e_status` を `2` (廃止) にしてください。"
+    }
+    fn check(&self, data: &Dataset, out: &mut Vec<Violation>) {
+        let table = &data.stations;
+        for row in table.rows() {
+            let closed = !matches!(row.get("close_ymd"), "" | "0000-00-00");
+            if closed && row.int("e_status") == Some(0) {
+                out.push(Violation::at(
+                    &row,
+                    table.file(),
+                    format!(
+                        "close_ymd = {} ですが e_status = 0 です",
+                        row.get("close_ymd")
+                    ),
+                ));
+            }
+        }
+    }
+}
+
+/// 運用中でない路線に運用中の駅が残っていないこと。路線単位で 1 件にまとめる。
+struct LineStationStatus;
+
+impl Rule for LineStationStatus {
+    fn id(&self) -> &'static str {
+        "line-station-status"
+    }
+    fn title(&self) -> &'static str {
+        "路線と駅の状態の食い違い"
+    }
+    fn description(&self) -> &'static str {
+        "運用中でない (`e_status` が 0 以外の) 路線に、運用中 (`e_status` = 0) の駅があります。"
+    }
+    fn check(&self, data: &Dataset, out: &mut Vec<Violation>) {
+        let mut active: HashMap<&str, Vec<&str>> = HashMap::new();
+        for row in data.stations.rows() {
+            if row.int("e_status") == Some(0) {
+                active
+                    .entry(row.get("line_cd"))
+                    .or_default()
+                    .push(row.get("station_cd"));
+            }
+        }
+        for line in data.lines.rows() {
+            let status = line.int("e_status");
+            if status == Some(0) {
+                continue;
+            }
+            if let Some(stations) = active.get(line.get("line_cd")) {
+                out.push(Violation::at(
+                    &line,
+                    data.lines.file(),
+                    format!(
+                        "e_status = {} の {} に運用中の駅が {} 件あります ({})",
+                        line.get("e_status"),
+                        line.get("line_name"),
+                        stations.len(),
+                        sample(stations)
+                    ),
+                ));
+            }
+        }
+    }
+}
+
+/// 運用中の路線に運用中の駅が 1 つも無い。
+struct EmptyLine;
+
+impl Rule for EmptyLine {
+    fn id(&self) -> &'static str {
+        "empty-line"
+    }
+    fn title(&self) -> &'static str {
+        "駅の無い運用中の路線"
+    }
+    fn description(&self) -> &'static str {
+        "運用中の路線に運用中の駅がありません。API ではこの路線を駅から辿れません。"
+    }
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+    fn check(&self, data: &Dataset, out: &mut Vec<Violation>) {
+        let served: HashSet<&str> = data
+            .stations
+            .rows()
+            .filter(|row| row.int("e_status") == Some(0))
+            .map(|row| row.get("line_cd"))
+            .collect();
+        for line in data.lines.rows() {
+            if line.int("e_status") == Some(0) && !served.contains(line.get("line_cd")) {
+                out.push(Violation::at(
+                    &line,
+                    data.lines.file(),
+                    format!("{} に運用中の駅がありません", line.get("line_name")),
+                ));
+            }
+        }
+    }
+}
+
+/// 路線記号は 1 から詰めて入れ、色と形状は記号のある番号にだけ入れる。
+struct LineSymbolOrder;
+
+impl Rule for LineSymbolOrder {
+    fn id(&self) -> &'static str {
+        "line-symbol-order"
+    }
+    fn title(&self) -> &'static str {
+        "路線記号の並び"
+    }
+    fn description(&self) -> &'static str {
+        "路線記号は `line_symbol1` から詰めて入力し、色・形状は記号のある番号にだけ入力してください。"
+    }
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+    fn check(&self, data: &Dataset, out: &mut Vec<Violation>) {
+        for row in data.lines.rows() {
+            let mut problems = Vec::new();
+            let mut gap = None;
+            for n in 1..=4 {
+                let symbol = row.get(&format!("line_symbol{n}"));
+                if symbol.is_empty() {
+                    gap.get_or_insert(n);
+                    for suffix in ["color", "shape"] {
+                        // 形状の NOOP は「記号なし」の明示なので許す
+                        if !matches!(row.get(&format!("line_symbol{n}_{suffix}")), "" | "NOOP") {
+                            problems.push(format!(
+                                "line_symbol{n} が空なのに line_symbol{n}_{suffix} があります"
+                            ));
+                        }
+                    }
+                } else if let Some(gap) = gap {
+                    problems.push(format!(
+                        "line_symbol{gap} が空なのに line_symbol{n} があります"
+                    ));
+                }
+            }
+            if !problems.is_empty() {
+                out.push(Violation::at(&row, data.lines.file(), problems.join("、")));
+            }
+        }
+    }
+}
+
+/// 欠番の別名は名前以外を空にし、駅から参照しない。
+struct AliasPlaceholder;
+
+const ALIAS_PLACEHOLDER: &str = "欠番";
+
+impl Rule for AliasPlaceholder {
+    fn id(&self) -> &'static str {
+        "alias-placeholder"
+    }
+    fn title(&self) -> &'static str {
+        "欠番の別名"
+    }
+    fn description(&self) -> &'static str {
+        "欠番の別名は `line_name` 以外を空欄にし、`7!line_aliases.csv` から参照しないでください。"
+    }
+    fn check(&self, data: &Dataset, out: &mut Vec<Violation>) {
+        let mut placeholders = HashSet::new();
+        for row in data.aliases.rows() {
+            if row.get("line_name") != ALIAS_PLACEHOLDER {
+                continue;
+            }
+            placeholders.insert(row.get("id"));
+            let filled: Vec<&str> = data
+                .aliases
+                .schema
+                .columns
+                .iter()
+                .map(|c| c.name)
+                .filter(|&name| !matches!(name, "id" | "line_name") && !row.get(name).is_empty())
+                .collect();
+            if !filled.is_empty() {
+                out.push(Violation::at(
+                    &row,
+                    data.aliases.file(),
+                    format!("欠番なのに {} が入っています", filled.join(", ")),
+                ));
+            }
+        }
+        for row in data.line_aliases.rows() {
+            if placeholders.contains(row.get("alias_cd")) {
+                out.push(Violation::at(
+                    &row,
+                    data.line_aliases.file(),
+                    format!("欠番の別名 {} を参照しています", row.get("alias_cd")),
+                ));
+            }
+        }
+    }
+}
+
+/// 徒歩連絡は別の駅グループどうしを 1 行で結び、同じ組を重ねない。
+struct ConnectionPair;
+
+impl Rule for ConnectionPair {
+    fn id(&self) -> &'static str {
+        "connection-pair"
+    }
+    fn title(&self) -> &'static str {
+        "徒歩連絡の組"
+    }
+    fn description(&self) -> &'static str {
+        "徒歩連絡は駅グループの異なる 2 駅を 1 行 (片方向) で結び、距離は正の値にしてください。"
+    }
+    fn check(&self, data: &Dataset, out: &mut Vec<Violation>) {
+        let table = &data.connections;
+        let group_of: HashMap<&str, &str> = data
+            .stations
+            .rows()
+            .map(|row| (row.get("station_cd"), row.get("station_g_cd")))
+            .collect();
+        let mut seen: HashMap<(&str, &str), u64> = HashMap::new();
+
+        for row in table.rows() {
+            let (a, b) = (row.get("station_cd1"), row.get("station_cd2"));
+            let mut problem =
+                |message: String| out.push(Violation::at(&row, table.file(), message));
+
+            if a == b {
+                problem(format!("同じ駅 {a} どうしを結んでいます"));
+            } else if let (Some(ga), Some(gb)) = (group_of.get(a), group_of.get(b)) {
+                if ga == gb {
+                    problem(format!("{a} と {b} は同じ駅グループ {ga} です"));
+                }
+            }
+            let pair = if a <= b { (a, b) } else { (b, a) };
+            if let Some(first) = seen.get(&pair) {
+                problem(format!("{a} と {b} の組は {first} 行目と重複しています"));
+            } else {
+                seen.insert(pair, row.line());
+            }
+            if row.float("distance").is_some_and(|d| d <= 0.0) {
+                problem(format!(
+                    "distance = {} は正の値ではありません",
+                    row.get("distance")
+                ));
+            }
+        }
+    }
+}
+
+/// 並び順(`ORDER BY e_sort, station_cd`)が崩れると API の経路スライスから
+/// 駅が欠落する箇所の期待並び。`(line_cd, 連続して並ぶべき station_cd 列)`。
+///
+/// 都営大江戸線は都庁前が環状部始端(9930100)と放射部接続側(9930101)の
+/// 2レコードで表現される「6の字」路線で、放射部側の都庁前が
+/// 「…→新宿→都庁前→西新宿五丁目→…」の位置から外れると、新宿⇔放射部を
+/// 跨ぐ区間の到着推定から都庁前が丸ごと欠落し、ETAが一駅分短くなる
+/// (#1589 で e_sort 重複時の station_cd タイブレークが反転して発生、
+/// #1595 で修正)。この退行を CI で検知する。
+const EXPECTED_CONSECUTIVE_STATION_ORDERS: &[(u32, &[u32])] = &[
+    // 都営大江戸線: 環状部始端の都庁前の直後は新宿西口。
+    (99301, &[9930100, 9930102]),
+    // 都営大江戸線: 新宿 → 都庁前(放射部側) → 西新宿五丁目。
+    (99301, &[9930128, 9930101, 9930129]),
+];
+
+/// `EXPECTED_CONSECUTIVE_STATION_ORDERS` の各エントリについて、対象路線を
+/// API と同じ `ORDER BY e_sort, station_cd` で並べたとき期待の駅列が
+/// この順で連続して現れることを検証する。
+struct StationOrder;
+
+impl Rule for StationOrder {
+    fn id(&self) -> &'static str {
+        "station-order"
+    }
+    fn title(&self) -> &'static str {
+        "駅の並び順エラー"
+    }
+    fn description(&self) -> &'static str {
+        "`3!stations.csv` の `e_sort` 順(同値は `station_cd` 順)が期待する駅の並びと一致しません。"
+    }
+    fn check(&self, data: &Dataset, out: &mut Vec<Violation>) {
+        let table = &data.stations;
+        for &(line_cd, expected) in EXPECTED_CONSECUTIVE_STATION_ORDERS {
+            let ordered = ordered_active_stations(table, line_cd);
+            let key = format!("line_cd={line_cd}");
+
+            let Some(start) = ordered.iter().position(|&cd| cd == expected[0]) else {
+                out.push(Violation::new(
+                    table.file(),
+                    key,
+                    format!(
+                        "line_cd {line_cd}: station_cd {} が見つかりません",
+                        expected[0]
+                    ),
+                ));
+                continue;
+            };
+            let actual = &ordered[start..(start + expected.len()).min(ordered.len())];
+            if actual != expected {
+                out.push(Violation::new(
+                    table.file(),
+                    key,
+                    format!(
+                        "line_cd {line_cd}: e_sort 順で {expected:?} の並びを期待しましたが {actual:?} でした。\
+                         e_sort の重複や採番ミスで経路の並び順が崩れると、到着推定(ETA)から駅が欠落します"
+                    ),
+                ));
+            }
+        }
+    }
+}
+
+/// 路線の運用中の駅を (e_sort, station_cd) の昇順 (SQL の ORDER BY と同じタイブレーク) で。
+fn ordered_active_stations(stations: &Table, line_cd: u32) -> Vec<u32> {
+    let mut line_stations: Vec<(u32, u32)> = stations
+        .rows()
+        .filter(|row| {
+            row.get("line_cd").parse::<u32>().ok() == Some(line_cd) && row.get("e_status") == "0"
+        })
+        .filter_map(|row: Row<'_>| {
+            let e_sort = row.get("e_sort").parse::<u32>().ok()?;
+            let station_cd = row.get("station_cd").parse::<u32>().ok()?;
+            Some((e_sort, station_cd))
+        })
+        .collect();
+    line_stations.sort_unstable();
+    line_stations.iter().map(|&(_, cd)| cd).collect()
+}
+
+/// 報告用に先頭の数件だけ並べる。
+fn sample(values: &[&str]) -> String {
+    const SHOWN: usize = 5;
+    let head = values
+        .iter()
+        .take(SHOWN)
+        .copied()
+        .collect::<Vec<_>>()
+        .join(", ");
+    if values.len() > SHOWN {
+        format!("{head} ほか {} 件", values.len() - SHOWN)
+    } else {
+        head
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataset::tests::dataset;
+
+    fn violations(rule: &dyn Rule, data: &Dataset) -> Vec<Violation> {
+        let mut out = Vec::new();
+        rule.check(data, &mut out);
+        out
+    }
+
+    const STATIONS_HEADER: &str = "station_cd,station_g_cd,station_name,station_name_k,station_name_r,station_name_rn,station_name_zh,station_name_ko,station_number1,station_number2,station_number3,station_number4,three_letter_code,line_cd,pref_cd,post,address,lon,lat,open_ymd,close_ymd,e_status,e_sort";
+
+    fn station(
+        cd: u32,
+        line_cd: u32,
+        lat: &str,
+        lon: &str,
+        open: &str,
+        close: &str,
+        e_status: u8,
+    ) -> String {
+        format!("{cd},{cd},駅,エキ,,,,,,,,,,{line_cd},13,100-0005,東京都,{lon},{lat},{open},{close},{e_status},{cd}")
+    }
+
+    #[test]
+    fn column_checks_follow_the_schema() {
+        let column = |kind, required| Column {
+            name: "c",
+            kind,
+            required,
+        };
+        assert!(check_required(&column(Kind::Text, true), "").is_some());
+        assert!(check_required(&column(Kind::Text, false), "").is_none());
+        assert!(check_integer(&column(Kind::Int, true), "12a").is_some());
+        assert!(check_integer(&column(Kind::Id, true), "DEFAULT").is_none());
+        assert!(check_range(&column(Kind::Range(0, 3), true), "4").is_some());
+        assert!(check_range(&column(Kind::Range(0, 3), true), "3").is_none());
+        assert!(check_hex_color(&column(Kind::HexColor, true), "#00b48D").is_none());
+        assert!(check_hex_color(&column(Kind::HexColor, true), "00B48D").is_some());
+        assert!(check_hex_color(&column(Kind::HexColor, true), "#00B48").is_some());
+        assert!(check_date(&column(Kind::Date, true), "0000-00-00").is_none());
+        assert!(check_date(&column(Kind::Date, true), "2024-02-29").is_none());
+        assert!(check_date(&column(Kind::Date, true), "2023-02-29").is_some());
+        assert!(check_date(&column(Kind::Date, true), "2023/02/01").is_some());
+        assert!(check_postal_code(&column(Kind::PostalCode, true), "1000005").is_some());
+        assert!(check_url(&column(Kind::Url, false), "www.example.com").is_some());
+        assert!(check_symbol_shape(&column(Kind::SymbolShape, false), "CIRCLE").is_some());
+    }
+
+    #[test]
+    fn foreign_keys_and_duplicates_are_reported_with_row_keys() {
+        let lines = "line_cd,company_cd,line_name\n1,1,A線\n2,9,B線\n2,1,C線\n";
+        let companies = "company_cd\n1\n";
+        let data = dataset(&[(&schema::LINES, lines), (&schema::COMPANIES, companies)]);
+
+        let fk = violations(&ForeignKey, &data);
+        assert_eq!(fk.len(), 1);
+        assert_eq!(fk[0].key, "line_cd=2");
+        assert!(fk[0].message.starts_with("3 行目"));
+
+        let dup = violations(&UniqueKey, &data);
+        assert_eq!(dup.len(), 1);
+        assert_eq!(
+            dup[0].message,
+            "4 行目: line_cd=2 は 3 行目と重複しています"
+        );
+    }
+
+    #[test]
+    fn station_rules_check_coordinates_dates_and_status() {
+        let stations = [
+            STATIONS_HEADER.to_string(),
+            station(1, 10, "35.68", "139.76", "1914-12-20", "0000-00-00", 0),
+            station(2, 10, "139.76", "35.68", "1914-12-20", "0000-00-00", 0),
+            station(3, 10, "35.68", "139.76", "2000-01-01", "1999-12-31", 2),
+            station(4, 10, "35.68", "139.76", "0000-00-00", "2020-03-14", 0),
+        ]
+        .join("\n");
+        let lines = "line_cd,line_name,e_status\n10,A線,2\n";
+        let data = dataset(&[(&schema::STATIONS, &stations), (&schema::LINES, lines)]);
+
+        let keys = |rule: &dyn Rule| {
+            violations(rule, &data)
+                .into_iter()
+                .map(|v| v.key)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(keys(&CoordinatesInJapan), ["station_cd=2"]);
+        assert_eq!(keys(&CloseAfterOpen), ["station_cd=3"]);
+        assert_eq!(keys(&ClosedStationStatus), ["station_cd=4"]);
+
+        let status = violations(&LineStationStatus, &data);
+        assert_eq!(status.len(), 1);
+        assert_eq!(status[0].key, "line_cd=10");
+        assert!(status[0]
+            .message
+            .contains("運用中の駅が 3 件あります (1, 2, 4)"));
+    }
+
+    #[test]
+    fn symbol_gaps_and_orphan_colours_are_warnings() {
+        let lines = "line_cd,line_symbol1,line_symbol2,line_symbol1_color,line_symbol2_color\n1,A,,#000000,#FFFFFF\n2,,B,,\n3,A,B,,\n";
+        let data = dataset(&[(&schema::LINES, lines)]);
+
+        assert_eq!(LineSymbolOrder.severity(), Severity::Warning);
+        let found = violations(&LineSymbolOrder, &data);
+        assert_eq!(found.len(), 2);
+        assert!(found[0]
+            .message
+            .contains("line_symbol2 が空なのに line_symbol2_color があります"));
+        assert!(found[1]
+            .message
+            .contains("line_symbol1 が空なのに line_symbol2 があります"));
+    }
+
+    #[test]
+    fn connections_must_join_distinct_groups_once() {
+        let stations = [
+            STATIONS_HEADER.to_string(),
+            station(1, 10, "35.68", "139.76", "0000-00-00", "0000-00-00", 0),
+            station(2, 10, "35.68", "139.76", "0000-00-00", "0000-00-00", 0),
+        ]
+        .join("\n");
+        let connections = "id,station_cd1,station_cd2,distance\n1,1,2,100\n2,2,1,100\n3,1,1,0\n";
+        let data = dataset(&[
+            (&schema::STATIONS, &stations),
+            (&schema::CONNECTIONS, connections),
+        ]);
+
+        let messages: Vec<String> = violations(&ConnectionPair, &data)
+            .into_iter()
+            .map(|v| v.message)
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "3 行目: 2 と 1 の組は 2 行目と重複しています",
+                "4 行目: 同じ駅 1 どうしを結んでいます",
+                "4 行目: distance = 0 は正の値ではありません",
+            ]
+        );
+    }
+}
@@ -0,0 +1,350 @@
+//! 駅の座標から並び順の誤りを推定する規則。
+//!
+//! `e_sort` や停車パターンの行順の誤りは、座標に直すと「極端に長い駅間」や
+//! 「行って戻る」並びとして現れる。支線を同じ路線に続けて並べている路線など、
+//! 正しいのに引っかかるものは許可リストで除く。
+//!
+//! 環状路線は到着推定と同じ `is_circular_path` で判定し、末尾→先頭のつなぎ目も
+//! 駅間として見る。
+
+use std::collections::HashMap;
+
+use stationapi::domain::arrival_estimation::{haversine_distance, is_circular_path};
+
+use crate::dataset::{Dataset, Row};
+use crate::rule::{Rule, Violation};
+
+/// 駅間距離が路線の中央値のこの倍数を超えたら疑う。
+const HOP_MEDIAN_RATIO: f64 = 4.0;
+/// 駅間距離が `average_distance` のこの倍数を超えたら疑う。
+const HOP_AVERAGE_RATIO: f64 = 4.0;
+/// これより短い駅間は疑わない。駅間の短い路面電車で数百 m の差を拾わないため。
+const HOP_MIN_METERS: f64 = 3_000.0;
+/// k+1 駅から k-1 駅までの距離の、この倍数より k 駅からの距離が長ければ「戻っている」。
+const ZIGZAG_RATIO: f64 = 2.0;
+/// k 駅から k+1 駅までがこれより短い並びは見ない。
+const ZIGZAG_MIN_METERS: f64 = 750.0;
+
+/// 駅 1 つ分。座標の読めない駅は `number` が報告するので並びから外す。
+struct Stop<'a> {
+    station_cd: &'a str,
+    name: &'a str,
+    point: (f64, f64),
+}
+
+impl<'a> Stop<'a> {
+    fn from_row(row: &Row<'a>) -> Option<Self> {
+        Some(Stop {
+            station_cd: row.get("station_cd"),
+            name: row.get("station_name"),
+            point: (row.float("lat")?, row.float("lon")?),
+        })
+    }
+
+    fn distance(&self, other: &Stop<'_>) -> f64 {
+        haversine_distance(self.point.0, self.point.1, other.point.0, other.point.1)
+    }
+}
+
+/// 並びの隣り合う組。環状ならつなぎ目 (末尾, 先頭) も含める。
+fn hops<'s, 'a>(stops: &'s [Stop<'a>]) -> Vec<(&'s Stop<'a>, &'s Stop<'a>)> {
+    let mut pairs: Vec<_> = stops.windows(2).map(|w| (&w[0], &w[1])).collect();
+    if is_circular(stops) {
+        pairs.push((&stops[stops.len() - 1], &stops[0]));
+    }
+    pairs
+}
+
+/// 並びの連続する 3 駅。環状ならつなぎ目をまたぐ組も含める。
+fn triples<'s, 'a>(stops: &'s [Stop<'a>]) -> Vec<[&'s Stop<'a>; 3]> {
+    let n = stops.len();
+    if n < 3 {
+        return Vec::new();
+    }
+    let wrap = if is_circular(stops) { n } else { n - 2 };
+    (0..wrap)
+        .map(|i| [&stops[i], &stops[(i + 1) % n], &stops[(i + 2) % n]])
+        .collect()
+}
+
+fn is_circular(stops: &[Stop<'_>]) -> bool {
+    let points: Vec<(f64, f64)> = stops.iter().map(|s| s.point).collect();
+    is_circular_path(&points)
+}
+
+/// `[k-1, k, k+1]` が「k+1 が k より k-1 の近くへ戻る」並びか。戻っていれば
+/// `(k-1 から k+1, k から k+1)` の距離を返す。
+fn zigzag(triple: [&Stop<'_>; 3]) -> Option<(f64, f64)> {
+    let [prev, here, next] = triple;
+    let back = prev.distance(next);
+    let forward = here.distance(next);
+    (forward > ZIGZAG_MIN_METERS && back * ZIGZAG_RATIO < forward).then_some((back, forward))
+}
+
+/// 路線ごとの運用中の駅を `(e_sort, station_cd)` の順 (API の並び) で。
+fn ordered_lines(data: &Dataset) -> Vec<(Row<'_>, Vec<Stop<'_>>)> {
+    let mut by_line: HashMap<&str, Vec<(i64, i64, Stop<'_>)>> = HashMap::new();
+    for row in data.stations.rows() {
+        if row.int("e_status") != Some(0) {
+            continue;
+        }
+        let (Some(e_sort), Some(station_cd)) = (row.int("e_sort"), row.int("station_cd")) else {
+            continue;
+        };
+        if let Some(stop) = Stop::from_row(&row) {
+            by_line
+                .entry(row.get("line_cd"))
+                .or_default()
+                .push((e_sort, station_cd, stop));
+        }
+    }
+    data.lines
+        .rows()
+        .filter_map(|line| {
+            let mut stops = by_line.remove(line.get("line_cd"))?;
+            stops.sort_by_key(|&(e_sort, station_cd, _)| (e_sort, station_cd));
+            Some((line, stops.into_iter().map(|(_, _, stop)| stop).collect()))
+        })
+        .collect()
+}
+
+fn km(meters: f64) -> String {
+    format!("{:.1}km", meters / 1_000.0)
+}
+
+/// 路線の駅間が中央値や `average_distance` に比べて極端に長い。
+pub struct StationHopDistance;
+
+impl Rule for StationHopDistance {
+    fn id(&self) -> &'static str {
+        "station-hop-distance"
+    }
+    fn title(&self) -> &'static str {
+        "極端に長い駅間"
+    }
+    fn description(&self) -> &'static str {
+        "`e_sort` 順で隣り合う駅の距離が、路線の駅間の中央値または `average_distance` の 4 倍 (かつ 3km) を超えています。`e_sort` の採番や `line_cd` の誤りを確認してください。"
+    }
+    fn check(&self, data: &Dataset, out: &mut Vec<Violation>) {
+        for (line, stops) in ordered_lines(data) {
+            let pairs = hops(&stops);
+            if pairs.len() < 2 {
+                continue;
+            }
+            let mut distances: Vec<f64> = pairs.iter().map(|(a, b)| a.distance(b)).collect();
+            distances.sort_by(|a, b| a.total_cmp(b));
+            let median = distances[distances.len() / 2];
+            let average = line.float("average_distance").unwrap_or(0.0);
+            let limit = (HOP_MEDIAN_RATIO * median)
+                .max(HOP_AVERAGE_RATIO * average)
+                .max(HOP_MIN_METERS);
+
+            for (a, b) in pairs {
+                let d = a.distance(b);
+                if d > limit {
+                    out.push(Violation::new(
+                        data.lines.file(),
+                        format!(
+                            "line_cd={} hop={}-{}",
+                            line.get("line_cd"),
+                            a.station_cd,
+                            b.station_cd
+                        ),
+                        format!(
+                            "{}: {} ({}) → {} ({}) が {} (駅間の中央値 {}、average_distance {})",
+                            line.get("line_name"),
+                            a.name,
+                            a.station_cd,
+                            b.name,
+                            b.station_cd,
+                            km(d),
+                            km(median),
+                            km(average)
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// 路線の `e_sort` 順で、次の駅が 1 つ前の駅の側へ戻っている。
+pub struct StationZigzag;
+
+impl Rule for StationZigzag {
+    fn id(&self) -> &'static str {
+        "station-zigzag"
+    }
+    fn title(&self) -> &'static str {
+        "行って戻る駅の並び"
+    }
+    fn description(&self) -> &'static str {
+        "`e_sort` 順で k+1 番目の駅が、k 番目の駅よりも k-1 番目の駅のほうにずっと近い位置にあります。駅の `e_sort` が入れ替わっていないか確認してください。"
+    }
+    fn check(&self, data: &Dataset, out: &mut Vec<Violation>) {
+        for (line, stops) in ordered_lines(data) {
+            for triple in triples(&stops) {
+                let Some((back, forward)) = zigzag(triple) else {
+                    continue;
+                };
+                let [prev, here, next] = triple;
+                out.push(Violation::new(
+                    data.lines.file(),
+                    format!(
+                        "line_cd={} station_cd={}",
+                        line.get("line_cd"),
+                        here.station_cd
+                    ),
+                    format!(
+                        "{}: {} → {} ({}) → {} で、{} は {} から {}、{} から {}",
+                        line.get("line_name"),
+                        prev.name,
+                        here.name,
+                        here.station_cd,
+                        next.name,
+                        next.name,
+                        here.name,
+                        km(forward),
+                        prev.name,
+                        km(back)
+                    ),
+                ));
+            }
+        }
+    }
+}
+
+/// 停車パターン (`line_group_cd` ごとの行順) が行って戻っている。
+pub struct StopPatternZigzag;
+
+impl Rule for StopPatternZigzag {
+    fn id(&self) -> &'static str {
+        "stop-pattern-zigzag"
+    }
+    fn title(&self) -> &'static str {
+        "行って戻る停車パターン"
+    }
+    fn description(&self) -> &'static str {
+        "`5!station_station_types.csv` の `line_group_cd` ごとの行順で、次の駅が 1 つ前の駅の側へ戻っています。行の並びは停車順そのものなので、行の入れ替わりや重複を確認してください。"
+    }
+    fn check(&self, data: &Dataset, out: &mut Vec<Violation>) {
+        let stations: HashMap<&str, Stop<'_>> = data
+            .stations
+            .rows()
+            .filter_map(|row| Stop::from_row(&row).map(|stop| (row.get("station_cd"), stop)))
+            .collect();
+
+        // ファイルに現れた順に line_group_cd を並べる (報告の順序を安定させるため)
+        let mut order: Vec<&str> = Vec::new();
+        let mut groups: HashMap<&str, Vec<&Stop<'_>>> = HashMap::new();
+        for row in data.station_station_types.rows() {
+            let group = row.get("line_group_cd");
+            // 存在しない駅は `foreign-key` が報告する
+            let Some(stop) = stations.get(row.get("station_cd")) else {
+                continue;
+            };
+            groups
+                .entry(group)
+                .or_insert_with(|| {
+                    order.push(group);
+                    Vec::new()
+                })
+                .push(stop);
+        }
+
+        for group in order {
+            let stops = &groups[group];
+            for window in stops.windows(3) {
+                let triple = [window[0], window[1], window[2]];
+                let Some((back, forward)) = zigzag(triple) else {
+                    continue;
+                };
+                let [prev, here, next] = triple;
+                out.push(Violation::new(
+                    data.station_station_types.file(),
+                    format!("line_group_cd={group} station_cd={}", here.station_cd),
+                    format!(
+                        "{} → {} ({}) → {} で、{} は {} から {}、{} から {}",
+                        prev.name,
+                        here.name,
+                        here.station_cd,
+                        next.name,
+                        next.name,
+                        here.name,
+                        km(forward),
+                        prev.name,
+                        km(back)
+                    ),
+                ));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataset::tests::dataset;
+    use crate::schema;
+
+    const STATIONS_HEADER: &str =
+        "station_cd,station_g_cd,station_name,line_cd,lon,lat,e_status,e_sort";
+
+    /// 北へ約 1.1km 間隔で並ぶ駅。`order` は e_sort の並び。
+    fn straight_line(order: &[u32], lat_offsets: &[f64]) -> String {
+        let mut csv = STATIONS_HEADER.to_string();
+        for (i, (&cd, &offset)) in order.iter().zip(lat_offsets).enumerate() {
+            csv.push_str(&format!(
+                "\n{cd},{cd},駅{cd},1,139.0,{},0,{}",
+                35.0 + offset,
+                i + 1
+            ));
+        }
+        csv
+    }
+
+    fn keys(rule: &dyn Rule, data: &Dataset) -> Vec<String> {
+        let mut out = Vec::new();
+        rule.check(data, &mut out);
+        out.into_iter().map(|v| v.key).collect()
+    }
+
+    const LINES: &str = "line_cd,line_name,average_distance\n1,A線,1000\n";
+
+    #[test]
+    fn long_hops_are_measured_against_the_median() {
+        // 5 駅目だけ 30km 先にある
+        let stations = straight_line(&[1, 2, 3, 4, 5], &[0.0, 0.01, 0.02, 0.03, 0.3]);
+        let data = dataset(&[(&schema::STATIONS, &stations), (&schema::LINES, LINES)]);
+        assert_eq!(keys(&StationHopDistance, &data), ["line_cd=1 hop=4-5"]);
+
+        let stations = straight_line(&[1, 2, 3, 4, 5], &[0.0, 0.01, 0.02, 0.03, 0.04]);
+        let data = dataset(&[(&schema::STATIONS, &stations), (&schema::LINES, LINES)]);
+        assert!(keys(&StationHopDistance, &data).is_empty());
+    }
+
+    #[test]
+    fn misplaced_e_sort_shows_up_as_a_zigzag() {
+        // 駅 5 は路線の先にあるのに、e_sort では 2 と 3 の間に入っている
+        let stations = straight_line(&[1, 2, 5, 3, 4], &[0.0, 0.01, 0.06, 0.02, 0.03]);
+        let data = dataset(&[(&schema::STATIONS, &stations), (&schema::LINES, LINES)]);
+        assert_eq!(keys(&StationZigzag, &data), ["line_cd=1 station_cd=5"]);
+    }
+
+    #[test]
+    fn stop_patterns_are_read_in_file_order_per_group() {
+        let stations = straight_line(&[1, 2, 3, 4], &[0.0, 0.01, 0.02, 0.05]);
+        // 10 は素直な並び、20 は 4 を 1 と 2 の間に挟んでいる
+        let sst = "id,station_cd,type_cd,line_group_cd,pass\n\
+                   DEFAULT,1,1,10,0\nDEFAULT,2,1,10,0\nDEFAULT,3,1,10,0\n\
+                   DEFAULT,1,1,20,0\nDEFAULT,4,1,20,0\nDEFAULT,2,1,20,0\nDEFAULT,3,1,20,0\n";
+        let data = dataset(&[
+            (&schema::STATIONS, &stations),
+            (&schema::STATION_STATION_TYPES, sst),
+        ]);
+        assert_eq!(
+            keys(&StopPatternZigzag, &data),
+            ["line_group_cd=20 station_cd=4"]
+        );
+    }
+}
@@ -0,0 +1,269 @@
+//! `data/*.csv` の列定義。
+//!
+//! `data/README.md` の「フィールド説明」をそのまま写したもの。列の形式に関する
+//! 規則 (必須・整数・色・日付など) はこの定義を見て検査するので、README に列を
+//! 足したらここにも足す。
+
+/// 列の値の形式。
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kind {
+    /// 自由な文字列。
+    Text,
+    /// 整数。
+    Int,
+    /// 整数で、`lo..=hi` に収まること。区分値や都道府県コードに使う。
+    Range(i64, i64),
+    /// 実数。
+    Number,
+    /// `DEFAULT` (取り込み時に採番) か整数。
+    Id,
+    /// `#RRGGBB`。
+    HexColor,
+    /// `YYYY-MM-DD`。`0000-00-00` は「不明・未定」として許す。
+    Date,
+    /// `http://` か `https://` で始まる URL。
+    Url,
+    /// `000-0000` 形式の郵便番号。
+    PostalCode,
+    /// 路線記号の形状 (`SYMBOL_SHAPES` のどれか)。
+    SymbolShape,
+}
+
+pub struct Column {
+    pub name: &'static str,
+    pub kind: Kind,
+    pub required: bool,
+}
+
+const fn req(name: &'static str, kind: Kind) -> Column {
+    Column {
+        name,
+        kind,
+        required: true,
+    }
+}
+
+const fn opt(name: &'static str, kind: Kind) -> Column {
+    Column {
+        name,
+        kind,
+        required: false,
+    }
+}
+
+pub struct TableSchema {
+    /// `data/` からのファイル名。
+    pub file: &'static str,
+    /// 行を指し示すのに使う列。報告と許可リストの照合に使う。
+    pub key: &'static [&'static str],
+    /// `key` が表の中で一意であるべきか。停車パターンは環状線で同じ駅が
+    /// 2 回現れるので一意にならない。
+    pub unique: bool,
+    pub columns: &'static [Column],
+}
+
+impl TableSchema {
+    pub fn column(&self, name: &str) -> Option<&Column> {
+        self.columns.iter().find(|c| c.name == name)
+    }
+}
+
+/// 状態フラグ (0:運用中, 1:運用前, 2:廃止, 3:無効化)。
+const E_STATUS: Kind = Kind::Range(0, 3);
+
+pub static COMPANIES: TableSchema = TableSchema {
+    file: "1!companies.csv",
+    key: &["company_cd"],
+    unique: true,
+    columns: &[
+        req("company_cd", Kind::Int),
+        req("rr_cd", Kind::Int),
+        req("company_name", Kind::Text),
+        req("company_name_k", Kind::Text),
+        req("company_name_h", Kind::Text),
+        req("company_name_r", Kind::Text),
+        req("company_name_en", Kind::Text),
+        req("company_name_full_en", Kind::Text),
+        opt("company_url", Kind::Url),
+        req("company_type", Kind::Range(0, 3)),
+        req("e_status", E_STATUS),
+        req("e_sort", Kind::Int),
+    ],
+};
+
+pub static LINES: TableSchema = TableSchema {
+    file: "2!lines.csv",
+    key: &["line_cd"],
+    unique: true,
+    columns: &[
+        req("line_cd", Kind::Int),
+        req("company_cd", Kind::Int),
+        req("line_name", Kind::Text),
+        req("line_name_k", Kind::Text),
+        req("line_name_h", Kind::Text),
+        req("line_name_r", Kind::Text),
+        req("line_name_rn", Kind::Text),
+        opt("line_name_zh", Kind::Text),
+        opt("line_name_ko", Kind::Text),
+        req("line_color_c", Kind::HexColor),
+        req("line_type", Kind::Range(0, 5)),
+        opt("line_symbol1", Kind::Text),
+        opt("line_symbol2", Kind::Text),
+        opt("line_symbol3", Kind::Text),
+        opt("line_symbol4", Kind::Text),
+        opt("line_symbol1_color", Kind::HexColor),
+        opt("line_symbol2_color", Kind::HexColor),
+        opt("line_symbol3_color", Kind::HexColor),
+        opt("line_symbol4_color", Kind::HexColor),
+        opt("line_symbol1_shape", Kind::SymbolShape),
+        opt("line_symbol2_shape", Kind::SymbolShape),
+        opt("line_symbol3_shape", Kind::SymbolShape),
+        opt("line_symbol4_shape", Kind::SymbolShape),
+        req("e_status", E_STATUS),
+        req("e_sort", Kind::Int),
+        opt("average_distance", Kind::Number),
+    ],
+};
+
+pub static STATIONS: TableSchema = TableSchema {
+    file: "3!stations.csv",
+    key: &["station_cd"],
+    unique: true,
+    columns: &[
+        req("station_cd", Kind::Int),
+        req("station_g_cd", Kind::Int),
+        req("station_name", Kind::Text),
+        req("station_name_k", Kind::Text),
+        opt("station_name_r", Kind::Text),
+        opt("station_name_rn", Kind::Text),
+        opt("station_name_zh", Kind::Text),
+        opt("station_name_ko", Kind::Text),
+        opt("station_number1", Kind::Text),
+        opt("station_number2", Kind::Text),
+        opt("station_number3", Kind::Text),
+        opt("station_number4", Kind::Text),
+        opt("three_letter_code", Kind::Text),
+        req("line_cd", Kind::Int),
+        req("pref_cd", Kind::Range(1, 47)),
+        req("post", Kind::PostalCode),
+        req("address", Kind::Text),
+        req("lon", Kind::Number),
+        req("lat", Kind::Number),
+        req("open_ymd", Kind::Date),
+        req("close_ymd", Kind::Date),
+        req("e_status", E_STATUS),
+        req("e_sort", Kind::Int),
+    ],
+};
+
+pub static TYPES: TableSchema = TableSchema {
+    file: "4!types.csv",
+    key: &["type_cd"],
+    unique: true,
+    columns: &[
+        req("id", Kind::Id),
+        req("type_cd", Kind::Int),
+        req("type_name", Kind::Text),
+        req("type_name_k", Kind::Text),
+        req("type_name_r", Kind::Text),
+        req("type_name_zh", Kind::Text),
+        req("type_name_ko", Kind::Text),
+        req("color", Kind::HexColor),
+        opt("direction", Kind::Range(0, 2)),
+        // 7 (バス系統バリエーション) は GTFS 取り込みが振る値で、CSV では使わない
+        opt("kind", Kind::Range(0, 6)),
+        req("priority", Kind::Int),
+    ],
+};
+
+pub static STATION_STATION_TYPES: TableSchema = TableSchema {
+    file: "5!station_station_types.csv",
+    key: &["line_group_cd", "station_cd", "type_cd"],
+    unique: false,
+    columns: &[
+        req("id", Kind::Id),
+        req("station_cd", Kind::Int),
+        req("type_cd", Kind::Int),
+        req("line_group_cd", Kind::Int),
+        opt("pass", Kind::Range(0, 5)),
+    ],
+};
+
+pub static ALIASES: TableSchema = TableSchema {
+    file: "6!aliases.csv",
+    key: &["id"],
+    unique: true,
+    columns: &[
+        req("id", Kind::Int),
+        opt("line_name", Kind::Text),
+        opt("line_name_k", Kind::Text),
+        opt("line_name_h", Kind::Text),
+        opt("line_name_r", Kind::Text),
+        opt("line_name_zh", Kind::Text),
+        opt("line_name_ko", Kind::Text),
+        opt("line_color_c", Kind::HexColor),
+    ],
+};
+
+pub static LINE_ALIASES: TableSchema = TableSchema {
+    file: "7!line_aliases.csv",
+    key: &["id"],
+    unique: true,
+    columns: &[
+        req("id", Kind::Int),
+        req("station_cd", Kind::Int),
+        req("alias_cd", Kind::Int),
+    ],
+};
+
+pub static CONNECTIONS: TableSchema = TableSchema {
+    file: "8!connections.csv",
+    key: &["id"],
+    unique: true,
+    columns: &[
+        req("id", Kind::Int),
+        req("station_cd1", Kind::Int),
+        req("station_cd2", Kind::Int),
+        req("distance", Kind::Number),
+    ],
+};
+
+/// `line_symbolN_shape` に使える値。`data/README.md` の一覧と同じ。
+pub const SYMBOL_SHAPES: &[&str] = &[
+    "NOOP",
+    "ROUND",
+    "ROUND_HORIZONTAL",
+    "REVERSED_ROUND",
+    "REVERSED_ROUND_HORIZONTAL",
+    "MONOCHROME_ROUND",
+    "SQUARE",
+    "REVERSED_SQUARE",
+    "REVERSED_SQUARE_DARK_TEXT",
+    "REVERSED_SQUARE_WEST",
+    "REVERSED_SQUARE_WEST_DARK_TEXT",
+    "REVERSED_SQUARE_HORIZONTAL",
+    "HALF_SQUARE",
+    "HALF_SQUARE_WITHOUT_ROUND",
+    "HALF_SQUARE_DARK_TEXT",
+    "ODAKYU",
+    "HAKONE",
+    "KEIO",
+    "TWR",
+    "NEW_SHUTTLE",
+    "KEIKYU",
+    "KINTETSU",
+    "NANKAI",
+    "KEIHAN",
+    "HANKYU",
+    "HANSHIN",
+    "SANYO",
+    "JR_UNION",
+    "BULLET_TRAIN_UNION",
+    "NUMBER_ONLY",
+    "KEISEI",
+    "NTL",
+    "SMR",
+    "NISHITETSU",
+    "IZUHAKONE",
+    "ENODEN",
+];
@@ -0,0 +1,346 @@
+//! 駅グループをまたぐ徒歩連絡 (`connections`) の生成。
+//!
+//! 同じ `station_g_cd` にまとめられていない駅同士でも、駅前を歩いて乗り換えられる
+//! 組み合わせは多い (渋谷↔神泉、有楽町↔日比谷など)。`data/8!connections.csv` に
+//! 手で書かれた行に加えて、駅座標と駅名からその組み合わせを導いて補う。
+//!
+//! 連絡とみなすのは次のいずれか。
+//!
+//! - 直線距離が [`NEARBY_MAX_METERS`] 以内
+//! - 駅名が同じか、一方が他方に駅前・口・丁目などの接尾辞を足したもの
+//!   (新宿↔新宿三丁目) で、直線距離が [`SAME_NAME_MAX_METERS`] 以内
+//!
+//! 同じ路線の駅同士 (隣の停留場など) は乗ればよいので連絡にしない。
+//! 駅グループの組ごとに最も近い駅の組を 1 行だけ出す。`distance` は直線距離
+//! (メートル)。徒歩時間への換算は Worker 側で行う。
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::Result;
+use stationapi::domain::arrival_estimation::haversine_distance;
+
+use crate::info;
+use crate::rail::{assign_serial, Dataset};
+use crate::table::{cell_i32, int, text, Cell};
+
+/// 駅名に関係なく徒歩連絡とみなす距離 (メートル)。
+pub const NEARBY_MAX_METERS: f64 = 500.0;
+
+/// 駅名が関連している場合に徒歩連絡とみなす距離 (メートル)。
+pub const SAME_NAME_MAX_METERS: f64 = 1_000.0;
+
+/// 近傍探索の格子の大きさ (度)。緯度 0.01 度はおよそ 1.1km で
+/// [`SAME_NAME_MAX_METERS`] より大きいので、南北は隣の行まで見れば足りる。
+const GRID_DEGREES: f64 = 0.01;
+
+/// 東西に見る列数 (片側)。経度 0.01 度は北緯 35 度でおよそ 0.91km、
+/// 北緯 45 度で 0.79km と 1km を割るので、隣の列だけでは取りこぼす。
+/// 2 列先まで見れば北緯 63 度付近まで [`SAME_NAME_MAX_METERS`] を覆える。
+const GRID_COLUMN_REACH: i64 = 2;
+
+/// 連絡の判定に使う駅。
+struct Candidate {
+    station_cd: i32,
+    station_g_cd: i32,
+    line_cd: i32,
+    name: String,
+    lat: f64,
+    lon: f64,
+}
+
+impl Dataset {
+    /// 近接する駅グループ同士の徒歩連絡を `connections` に追加する。
+    ///
+    /// 対象は有効な鉄道駅だけ。バス停は件数が多く、駅前の停留所は
+    /// 近傍検索で既に引けるため含めない。手書きの行がある駅グループの組は
+    /// そちらを優先し、生成しない。
+    pub fn generate_walking_connections(&mut self) -> Result<()> {
+        let candidates = self.connection_candidates();
+        let group_of: HashMap<i32, i32> = candidates
+            .iter()
+            .map(|c| (c.station_cd, c.station_g_cd))
+            .collect();
+
+        let c_station_cd1 = self.connections.col("station_cd1");
+        let c_station_cd2 = self.connections.col("station_cd2");
+        let c_distance = self.connections.col("distance");
+
+        // 手書きの行が既に結んでいる駅グループの組
+        let curated: HashSet<(i32, i32)> = self
+            .connections
+            .rows()
+            .iter()
+            .filter_map(|row| {
+                let a = group_of.get(&cell_i32(row, c_station_cd1)?)?;
+                let b = group_of.get(&cell_i32(row, c_station_cd2)?)?;
+                Some(ordered(*a, *b))
+            })
+            .collect();
+
+        let mut generated = 0usize;
+        for pair in nearby_group_pairs(&candidates) {
+            if curated.contains(&pair.groups) {
+                continue;
+            }
+            let mut row: Vec<Cell> = self.connections.blank_row();
+            row[c_station_cd1] = int(pair.station_cd1);
+            row[c_station_cd2] = int(pair.station_cd2);
+            row[c_distance] = text(format!("{:.1}", pair.distance));
+            self.connections.push(row);
+            generated += 1;
+        }
+
+        assign_serial(&mut self.connections, "id");
+        info!("駅グループ間の徒歩連絡を {generated} 行生成した");
+        Ok(())
+    }
+
+    fn connection_candidates(&self) -> Vec<Candidate> {
+        let s_station_cd = self.stations.col("station_cd");
+        let s_station_g_cd = self.stations.col("station_g_cd");
+        let s_line_cd = self.stations.col("line_cd");
+        let s_name = self.stations.col("station_name");
+        let s_lat = self.stations.col("lat");
+        let s_lon = self.stations.col("lon");
+        let s_e_status = self.stations.col("e_status");
+        let s_transport = self.stations.col("transport_type");
+
+        self.stations
+            .rows()
+            .iter()
+            .filter(|row| {
+                cell_i32(row, s_e_status) == Some(0) && cell_i32(row, s_transport) == Some(0)
+            })
+            .filter_map(|row| {
+                let coord = |idx: usize| row[idx].as_deref().and_then(|v| v.parse::<f64>().ok());
+                Some(Candidate {
+                    station_cd: cell_i32(row, s_station_cd)?,
+                    station_g_cd: cell_i32(row, s_station_g_cd)?,
+                    line_cd: cell_i32(row, s_line_cd)?,
+                    name: row[s_name].clone().unwrap_or_default(),
+                    lat: coord(s_lat)?,
+                    lon: coord(s_lon)?,
+                })
+            })
+            .collect()
+    }
+}
+
+/// 駅グループの組に対して選んだ、最も近い駅の組。
+#[derive(Clone, Copy, Debug)]
+struct WalkingPair {
+    /// 駅グループの組 (小さい方が先)。
+    groups: (i32, i32),
+    station_cd1: i32,
+    station_cd2: i32,
+    distance: f64,
+}
+
+/// 徒歩連絡になる駅グループの組を、それぞれ最も近い駅の組で返す。
+///
+/// 結果は駅グループの組の昇順。出力の行順を実行ごとに変えないため。
+fn nearby_group_pairs(candidates: &[Candidate]) -> Vec<WalkingPair> {
+    let cell_of = |lat: f64, lon: f64| {
+        (
+            (lat / GRID_DEGREES).floor() as i64,
+            (lon / GRID_DEGREES).floor() as i64,
+        )
+    };
+    let mut grid: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+    for (i, c) in candidates.iter().enumerate() {
+        grid.entry(cell_of(c.lat, c.lon)).or_default().push(i);
+    }
+
+    let mut best: HashMap<(i32, i32), WalkingPair> = HashMap::new();
+    for (i, a) in candidates.iter().enumerate() {
+        let (row, col) = cell_of(a.lat, a.lon);
+        for d_row in -1..=1 {
+            for d_col in -GRID_COLUMN_REACH..=GRID_COLUMN_REACH {
+                let Some(neighbors) = grid.get(&(row + d_row, col + d_col)) else {
+                    continue;
+                };
+                for &j in neighbors {
+                    // 各組を一度だけ見る
+                    if j <= i {
+                        continue;
+                    }
+                    let b = &candidates[j];
+                    if a.station_g_cd == b.station_g_cd || a.line_cd == b.line_cd {
+                        continue;
+                    }
+                    let distance = haversine_distance(a.lat, a.lon, b.lat, b.lon);
+                    if !is_walking_connection(&a.name, &b.name, distance) {
+                        continue;
+                    }
+                    let groups = ordered(a.station_g_cd, b.station_g_cd);
+                    let (station_cd1, station_cd2) = ordered(a.station_cd, b.station_cd);
+                    let closer = best.get(&groups).is_none_or(|current| {
+                        distance < current.distance
+                            || (distance == current.distance
+                                && (station_cd1, station_cd2)
+                                    < (current.station_cd1, current.station_cd2))
+                    });
+                    if closer {
+                        best.insert(
+                            groups,
+                            WalkingPair {
+                                groups,
+                                station_cd1,
+                                station_cd2,
+                                distance,
+                            },
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    let mut pairs: Vec<WalkingPair> = best.into_values().collect();
+    pairs.sort_by_key(|pair| pair.groups);
+    pairs
+}
+
+fn is_walking_connection(name_a: &str, name_b: &str, distance: f64) -> bool {
+    distance <= NEARBY_MAX_METERS
+        || (distance <= SAME_NAME_MAX_METERS && names_are_related(name_a, name_b))
+}
+
+/// 駅名の後ろに付いても同じ場所を指すとみなす接尾辞。
+const RELATED_SUFFIXES: [&str; 4] = ["駅", "駅前", "口", "前"];
+
+/// 駅名が同じか、一方が他方に [`RELATED_SUFFIXES`] か「〇丁目」を足したものか。
+///
+/// 前方一致だけでは弘前↔弘前東高前のような別の場所まで拾うので、残りの部分が
+/// 接尾辞そのものであることを求める。括弧書きの補足 (「浅草(つくばEXP)」の
+/// 「(つくばEXP)」) は比べる前に落とす。
+fn names_are_related(name_a: &str, name_b: &str) -> bool {
+    let a = base_name(name_a);
+    let b = base_name(name_b);
+    if a.is_empty() || b.is_empty() {
+        return false;
+    }
+    let (short, long) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    match long.strip_prefix(short) {
+        Some("") => true,
+        Some(rest) => RELATED_SUFFIXES.contains(&rest) || is_chome(rest),
+        None => false,
+    }
+}
+
+/// 「三丁目」のような漢数字の丁目か。
+fn is_chome(s: &str) -> bool {
+    s.strip_suffix("丁目")
+        .is_some_and(|n| !n.is_empty() && n.chars().all(|c| "一二三四五六七八九十".contains(c)))
+}
+
+fn base_name(name: &str) -> &str {
+    name.split(['(', '（']).next().unwrap_or("").trim()
+}
+
+fn ordered(a: i32, b: i32) -> (i32, i32) {
+    if a <= b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(station_cd: i32, station_g_cd: i32, name: &str, lat: f64, lon: f64) -> Candidate {
+        Candidate {
+            station_cd,
+            station_g_cd,
+            line_cd: station_cd,
+            name: name.to_string(),
+            lat,
+            lon,
+        }
+    }
+
+    #[test]
+    fn related_names_ignore_parenthesized_suffix() {
+        assert!(names_are_related("浅草", "浅草(つくばEXP)"));
+        assert!(names_are_related("新宿", "新宿三丁目"));
+        assert!(!names_are_related("渋谷", "神泉"));
+        assert!(!names_are_related("", "神泉"));
+    }
+
+    #[test]
+    fn related_names_need_a_known_suffix() {
+        assert!(names_are_related("富山", "富山駅"));
+        assert!(names_are_related("弘前", "弘前駅前"));
+        assert!(names_are_related("中央前橋", "中央前橋(上毛)"));
+        assert!(!names_are_related("弘前", "弘前東高前"));
+        assert!(!names_are_related("新宿", "新宿西口"));
+        assert!(!names_are_related("本町", "本町三丁目前"));
+    }
+
+    #[test]
+    fn nearby_groups_are_connected_regardless_of_name() {
+        // 緯度 0.004 度 ≒ 445m
+        let candidates = [
+            candidate(1, 10, "渋谷", 35.6580, 139.7016),
+            candidate(2, 20, "神泉", 35.6620, 139.7016),
+        ];
+        let pairs = nearby_group_pairs(&candidates);
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].groups, (10, 20));
+        assert_eq!((pairs[0].station_cd1, pairs[0].station_cd2), (1, 2));
+        assert!((pairs[0].distance - 445.0).abs() < 5.0);
+    }
+
+    #[test]
+    fn distant_groups_need_related_names() {
+        // 緯度 0.007 度 ≒ 780m
+        let unrelated = [
+            candidate(1, 10, "渋谷", 35.6580, 139.7016),
+            candidate(2, 20, "代官山", 35.6510, 139.7016),
+        ];
+        assert!(nearby_group_pairs(&unrelated).is_empty());
+
+        let related = [
+            candidate(1, 10, "新宿", 35.6900, 139.7000),
+            candidate(2, 20, "新宿三丁目", 35.6970, 139.7000),
+        ];
+        assert_eq!(nearby_group_pairs(&related).len(), 1);
+    }
+
+    #[test]
+    fn east_west_pairs_two_cells_apart_are_found() {
+        // 北緯 35 度では経度 0.01 度 ≒ 911m なので、950m 離れると格子の 2 列先に入る
+        let candidates = [
+            candidate(1, 10, "新宿", 35.0, 139.0099),
+            candidate(2, 20, "新宿三丁目", 35.0, 139.02033),
+        ];
+        let pairs = nearby_group_pairs(&candidates);
+        assert_eq!(pairs.len(), 1);
+        assert!((pairs[0].distance - 950.0).abs() < 5.0);
+    }
+
+    #[test]
+    fn one_row_per_group_pair_using_closest_stations() {
+        let candidates = [
+            candidate(1, 10, "A", 35.0000, 139.0),
+            candidate(2, 10, "A", 35.0010, 139.0),
+            candidate(3, 20, "B", 35.0030, 139.0),
+            // 同じ駅グループ同士は連絡にしない
+            candidate(4, 20, "B", 35.0031, 139.0),
+        ];
+        let pairs = nearby_group_pairs(&candidates);
+        assert_eq!(pairs.len(), 1);
+        assert_eq!((pairs[0].station_cd1, pairs[0].station_cd2), (2, 3));
+    }
+
+    #[test]
+    fn neighboring_stops_on_the_same_line_are_not_connected() {
+        let mut a = candidate(1, 10, "A", 35.0000, 139.0);
+        let mut b = candidate(2, 20, "B", 35.0030, 139.0);
+        a.line_cd = 99305;
+        b.line_cd = 99305;
+        assert!(nearby_group_pairs(&[a, b]).is_empty());
+    }
+}
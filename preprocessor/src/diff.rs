@@ -0,0 +1,726 @@
+//! 2 つの `generated/` を比べる (`preprocessor diff <old_dir> <new_dir>`)。
+//!
+//! `station_station_types.id` や `types.id` は `assign_serial` で毎回振り直すので、
+//! 行を `id` で突き合わせると 1 行の挿入で後ろがすべて「変更」になる。ここでは
+//! 各テーブルを自然キーで突き合わせ、追加・削除・変更をテーブル別と事業者別に数える。
+//!
+//! - companies: `company_cd` / lines: `line_cd` / stations: `station_cd` / types: `type_cd`
+//! - station_station_types: (`line_group_cd`, `station_cd`, `type_cd`)。同じ組が
+//!   系統内に 2 回現れる (環状線の起終点など) ときは出現順で区別する。停車順は
+//!   `id` の代わりに「直前の停車駅」を比べて、差し込まれた駅の前後だけを変更にする。
+//!
+//! 結果は Markdown で標準出力へ、`--json <path>` を付けると JSON でも書く。
+//! GTFS の取得に失敗して事業者がまるごと消えるような変化を CI で止めるため、
+//! 削除率が閾値を超えたら非ゼロで終わる。
+//!
+//! ```text
+//! preprocessor diff <old_dir> <new_dir> [--json <path>]
+//!     [--max-removed-ratio 0.05] [--max-operator-removed-ratio 0.5]
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+
+use crate::info;
+use crate::rail::{
+    load_csv, COMPANY_COLUMNS, LINE_COLUMNS, SST_COLUMNS, STATION_COLUMNS, TYPE_COLUMNS,
+};
+use crate::table::{cell_i32, Table};
+
+/// Markdown に並べる行の数。全件は JSON で見る。
+const MARKDOWN_SAMPLE_ROWS: usize = 20;
+
+/// 非ゼロで終わる条件。比率は旧データの行数に対する削除行数。
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Thresholds {
+    /// テーブルごとの削除率の上限。
+    pub max_removed_ratio: f64,
+    /// 事業者ごと (路線・駅・停車駅の合計) の削除率の上限。
+    pub max_operator_removed_ratio: f64,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self {
+            max_removed_ratio: 0.05,
+            max_operator_removed_ratio: 0.5,
+        }
+    }
+}
+
+/// `diff` サブコマンドの入口。`args` は `diff` より後ろの引数。
+pub fn run(args: &[String]) -> Result<()> {
+    let options = Options::parse(args)?;
+    let old = Snapshot::load(&options.old_dir)?;
+    let new = Snapshot::load(&options.new_dir)?;
+    let report = compare(&old, &new, &options.thresholds);
+
+    print!("{}", report.to_markdown());
+    if let Some(path) = &options.json {
+        let json = serde_json::to_string_pretty(&report)?;
+        std::fs::write(path, json).with_context(|| format!("{} へ書けない", path.display()))?;
+        info!("{} へ JSON を書き出した", path.display());
+    }
+    if !report.violations.is_empty() {
+        bail!("閾値を超えた変化が {} 件ある", report.violations.len());
+    }
+    Ok(())
+}
+
+struct Options {
+    old_dir: PathBuf,
+    new_dir: PathBuf,
+    json: Option<PathBuf>,
+    thresholds: Thresholds,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Result<Self> {
+        let mut dirs = Vec::new();
+        let mut json = None;
+        let mut thresholds = Thresholds::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| {
+                args.next()
+                    .with_context(|| format!("{name} に値がない"))
+                    .cloned()
+            };
+            match arg.as_str() {
+                "--json" => json = Some(PathBuf::from(value(arg)?)),
+                "--max-removed-ratio" => {
+                    thresholds.max_removed_ratio = parse_ratio(arg, &value(arg)?)?;
+                }
+                "--max-operator-removed-ratio" => {
+                    thresholds.max_operator_removed_ratio = parse_ratio(arg, &value(arg)?)?;
+                }
+                flag if flag.starts_with("--") => bail!("未知のオプション {flag}"),
+                dir => dirs.push(PathBuf::from(dir)),
+            }
+        }
+        let [old_dir, new_dir] = <[PathBuf; 2]>::try_from(dirs).map_err(|_| {
+            anyhow::anyhow!("使い方: preprocessor diff <old_dir> <new_dir> [--json <path>]")
+        })?;
+        Ok(Self {
+            old_dir,
+            new_dir,
+            json,
+            thresholds,
+        })
+    }
+}
+
+fn parse_ratio(name: &str, value: &str) -> Result<f64> {
+    let ratio: f64 = value
+        .parse()
+        .with_context(|| format!("{name} は数値で指定する: {value}"))?;
+    if !(0.0..=1.0).contains(&ratio) {
+        bail!("{name} は 0 から 1 の範囲で指定する: {value}");
+    }
+    Ok(ratio)
+}
+
+/// 比べる 5 テーブル。
+struct Snapshot {
+    companies: Table,
+    lines: Table,
+    stations: Table,
+    types: Table,
+    sst: Table,
+}
+
+impl Snapshot {
+    fn load(dir: &Path) -> Result<Self> {
+        let load = |name: &str, columns: &[&str]| -> Result<Table> {
+            let mut table = Table::new(columns, None);
+            load_csv(&mut table, &dir.join(format!("{name}.csv")))?;
+            Ok(table)
+        };
+        Ok(Self {
+            companies: load("companies", COMPANY_COLUMNS)?,
+            lines: load("lines", LINE_COLUMNS)?,
+            stations: load("stations", STATION_COLUMNS)?,
+            types: load("types", TYPE_COLUMNS)?,
+            sst: load("station_station_types", SST_COLUMNS)?,
+        })
+    }
+
+    /// 行を自然キーで引ける形にする。テーブルの並びは出力順。
+    fn keyed(&self) -> Vec<(&'static str, BTreeMap<String, KeyedRow>)> {
+        let line_company: HashMap<i32, i32> = pairs(&self.lines, "line_cd", "company_cd");
+        let station_line: HashMap<i32, i32> = pairs(&self.stations, "station_cd", "line_cd");
+        let station_company = |station_cd: i32| {
+            station_line
+                .get(&station_cd)
+                .and_then(|line_cd| line_company.get(line_cd))
+                .copied()
+        };
+
+        let companies = keyed_by(&self.companies, "company_cd", "company_name", |row| {
+            cell_i32(row, self.companies.col("company_cd"))
+        });
+        let lines = keyed_by(&self.lines, "line_cd", "line_name", |row| {
+            cell_i32(row, self.lines.col("company_cd"))
+        });
+        let stations = keyed_by(&self.stations, "station_cd", "station_name", |row| {
+            cell_i32(row, self.stations.col("line_cd"))
+                .and_then(|line_cd| line_company.get(&line_cd).copied())
+        });
+        let types = keyed_by(&self.types, "type_cd", "type_name", |_| None);
+        let sst = self.keyed_sst(&station_company);
+
+        vec![
+            ("companies", companies),
+            ("lines", lines),
+            ("stations", stations),
+            ("types", types),
+            ("station_station_types", sst),
+        ]
+    }
+
+    fn keyed_sst(
+        &self,
+        station_company: &impl Fn(i32) -> Option<i32>,
+    ) -> BTreeMap<String, KeyedRow> {
+        let t = &self.sst;
+        let (id, station_cd, type_cd, line_group_cd, pass) = (
+            t.col("id"),
+            t.col("station_cd"),
+            t.col("type_cd"),
+            t.col("line_group_cd"),
+            t.col("pass"),
+        );
+        let station_names: HashMap<i32, &str> = self
+            .stations
+            .rows()
+            .iter()
+            .filter_map(|row| {
+                let cd = cell_i32(row, self.stations.col("station_cd"))?;
+                Some((
+                    cd,
+                    row[self.stations.col("station_name")]
+                        .as_deref()
+                        .unwrap_or(""),
+                ))
+            })
+            .collect();
+
+        // 停車順は系統ごとの id 順
+        let mut rows: Vec<&Vec<_>> = t.rows().iter().collect();
+        rows.sort_by_key(|row| cell_i32(row, id).unwrap_or(i32::MAX));
+
+        let mut out = BTreeMap::new();
+        let mut seen: HashMap<(String, String, String), usize> = HashMap::new();
+        let mut previous: HashMap<String, String> = HashMap::new();
+        for row in rows {
+            let cell = |i: usize| row[i].clone().unwrap_or_default();
+            let (group, station, ty) = (cell(line_group_cd), cell(station_cd), cell(type_cd));
+            let nth = seen
+                .entry((group.clone(), station.clone(), ty.clone()))
+                .and_modify(|n| *n += 1)
+                .or_insert(0);
+            let key = match nth {
+                0 => format!("{group}/{station}/{ty}"),
+                n => format!("{group}/{station}/{ty}#{n}"),
+            };
+            let prev_station = previous
+                .insert(group.clone(), station.clone())
+                .unwrap_or_default();
+
+            let mut fields = BTreeMap::new();
+            fields.insert("pass".to_string(), cell(pass));
+            fields.insert("prev_station_cd".to_string(), prev_station);
+            let station_cd = station.parse().ok();
+            out.insert(
+                key,
+                KeyedRow {
+                    label: station_cd
+                        .and_then(|cd| station_names.get(&cd))
+                        .map(|name| name.to_string())
+                        .unwrap_or_default(),
+                    operator: station_cd.and_then(station_company),
+                    fields,
+                },
+            );
+        }
+        out
+    }
+
+    fn company_names(&self) -> HashMap<i32, String> {
+        let t = &self.companies;
+        t.rows()
+            .iter()
+            .filter_map(|row| {
+                let cd = cell_i32(row, t.col("company_cd"))?;
+                Some((cd, row[t.col("company_name")].clone().unwrap_or_default()))
+            })
+            .collect()
+    }
+}
+
+fn pairs(table: &Table, key: &str, value: &str) -> HashMap<i32, i32> {
+    let (k, v) = (table.col(key), table.col(value));
+    table
+        .rows()
+        .iter()
+        .filter_map(|row| Some((cell_i32(row, k)?, cell_i32(row, v)?)))
+        .collect()
+}
+
+/// 自然キーで引いた 1 行。`fields` は比べる列 (連番の `id` は含めない)。
+struct KeyedRow {
+    label: String,
+    operator: Option<i32>,
+    fields: BTreeMap<String, String>,
+}
+
+fn keyed_by(
+    table: &Table,
+    key: &str,
+    label: &str,
+    operator: impl Fn(&[Option<String>]) -> Option<i32>,
+) -> BTreeMap<String, KeyedRow> {
+    let (k, l) = (table.col(key), table.col(label));
+    table
+        .rows()
+        .iter()
+        .map(|row| {
+            let fields = table
+                .columns()
+                .iter()
+                .zip(row)
+                .filter(|(column, _)| column.as_str() != "id")
+                .map(|(column, cell)| (column.clone(), cell.clone().unwrap_or_default()))
+                .collect();
+            (
+                row[k].clone().unwrap_or_default(),
+                KeyedRow {
+                    label: row[l].clone().unwrap_or_default(),
+                    operator: operator(row),
+                    fields,
+                },
+            )
+        })
+        .collect()
+}
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub tables: Vec<TableDiff>,
+    pub operators: Vec<OperatorDiff>,
+    pub violations: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TableDiff {
+    pub table: &'static str,
+    pub old_rows: usize,
+    pub new_rows: usize,
+    pub added: Vec<RowRef>,
+    pub removed: Vec<RowRef>,
+    pub changed: Vec<RowChange>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RowRef {
+    pub key: String,
+    pub label: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RowChange {
+    pub key: String,
+    pub label: String,
+    pub fields: Vec<FieldChange>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FieldChange {
+    pub column: String,
+    pub old: String,
+    pub new: String,
+}
+
+/// 事業者ごとの件数。路線・駅・停車駅を合わせて数える。
+#[derive(Debug, Default, Serialize)]
+pub struct OperatorDiff {
+    pub company_cd: i32,
+    pub name: String,
+    pub old_rows: usize,
+    pub added: usize,
+    pub removed: usize,
+    pub changed: usize,
+}
+
+impl OperatorDiff {
+    fn removed_ratio(&self) -> f64 {
+        ratio(self.removed, self.old_rows)
+    }
+}
+
+fn ratio(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 / whole as f64
+    }
+}
+
+fn compare(old: &Snapshot, new: &Snapshot, thresholds: &Thresholds) -> Report {
+    let mut names = old.company_names();
+    names.extend(new.company_names());
+
+    let mut tables = Vec::new();
+    let mut operators: BTreeMap<i32, OperatorDiff> = BTreeMap::new();
+    let mut operator = |cd: Option<i32>, update: fn(&mut OperatorDiff)| {
+        let Some(cd) = cd else {
+            return;
+        };
+        let counts = operators.entry(cd).or_insert_with(|| OperatorDiff {
+            company_cd: cd,
+            name: names.get(&cd).cloned().unwrap_or_default(),
+            ..OperatorDiff::default()
+        });
+        update(counts);
+    };
+
+    for ((table, old_rows), (_, new_rows)) in old.keyed().into_iter().zip(new.keyed()) {
+        // 事業者そのもの (companies) は事業者別の集計に入れない
+        let per_operator = table != "companies";
+        let mut diff = TableDiff {
+            table,
+            old_rows: old_rows.len(),
+            new_rows: new_rows.len(),
+            added: Vec::new(),
+            removed: Vec::new(),
+            changed: Vec::new(),
+        };
+        for (key, before) in &old_rows {
+            if per_operator {
+                operator(before.operator, |c| c.old_rows += 1);
+            }
+            let Some(after) = new_rows.get(key) else {
+                if per_operator {
+                    operator(before.operator, |c| c.removed += 1);
+                }
+                diff.removed.push(RowRef {
+                    key: key.clone(),
+                    label: before.label.clone(),
+                });
+                continue;
+            };
+            let fields: Vec<FieldChange> = before
+                .fields
+                .iter()
+                .filter_map(|(column, old_value)| {
+                    let new_value = after.fields.get(column)?;
+                    (old_value != new_value).then(|| FieldChange {
+                        column: column.clone(),
+                        old: old_value.clone(),
+                        new: new_value.clone(),
+                    })
+                })
+                .collect();
+            if !fields.is_empty() {
+                if per_operator {
+                    operator(after.operator, |c| c.changed += 1);
+                }
+                diff.changed.push(RowChange {
+                    key: key.clone(),
+                    label: after.label.clone(),
+                    fields,
+                });
+            }
+        }
+        for (key, after) in &new_rows {
+            if old_rows.contains_key(key) {
+                continue;
+            }
+            if per_operator {
+                operator(after.operator, |c| c.added += 1);
+            }
+            diff.added.push(RowRef {
+                key: key.clone(),
+                label: after.label.clone(),
+            });
+        }
+        tables.push(diff);
+    }
+
+    let mut violations = Vec::new();
+    for diff in &tables {
+        let removed = ratio(diff.removed.len(), diff.old_rows);
+        if removed > thresholds.max_removed_ratio {
+            violations.push(format!(
+                "{}: {} 行中 {} 行が消えた ({:.2}% > {:.2}%)",
+                diff.table,
+                diff.old_rows,
+                diff.removed.len(),
+                removed * 100.0,
+                thresholds.max_removed_ratio * 100.0
+            ));
+        }
+    }
+    for counts in operators.values() {
+        if counts.removed_ratio() > thresholds.max_operator_removed_ratio {
+            violations.push(format!(
+                "{} ({}): {} 行中 {} 行が消えた ({:.2}% > {:.2}%)",
+                counts.name,
+                counts.company_cd,
+                counts.old_rows,
+                counts.removed,
+                counts.removed_ratio() * 100.0,
+                thresholds.max_operator_removed_ratio * 100.0
+            ));
+        }
+    }
+
+    Report {
+        tables,
+        operators: operators
+            .into_values()
+            .filter(|counts| counts.added + counts.removed + counts.changed > 0)
+            .collect(),
+        violations,
+    }
+}
+
+impl Report {
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# generated/ の差分\n");
+        let _ = writeln!(out, "| テーブル | 旧 | 新 | 追加 | 削除 | 変更 |");
+        let _ = writeln!(out, "|---|---:|---:|---:|---:|---:|");
+        for diff in &self.tables {
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} | {} | {} | {} |",
+                diff.table,
+                diff.old_rows,
+                diff.new_rows,
+                diff.added.len(),
+                diff.removed.len(),
+                diff.changed.len()
+            );
+        }
+
+        if !self.operators.is_empty() {
+            let _ = writeln!(out, "\n## 事業者別 (路線・駅・停車駅)\n");
+            let _ = writeln!(out, "| 事業者 | 旧 | 追加 | 削除 | 変更 | 削除率 |");
+            let _ = writeln!(out, "|---|---:|---:|---:|---:|---:|");
+            for counts in &self.operators {
+                let _ = writeln!(
+                    out,
+                    "| {} ({}) | {} | {} | {} | {} | {:.1}% |",
+                    counts.name,
+                    counts.company_cd,
+                    counts.old_rows,
+                    counts.added,
+                    counts.removed,
+                    counts.changed,
+                    counts.removed_ratio() * 100.0
+                );
+            }
+        }
+
+        for diff in &self.tables {
+            if diff.added.is_empty() && diff.removed.is_empty() && diff.changed.is_empty() {
+                continue;
+            }
+            let _ = writeln!(out, "\n## {}\n", diff.table);
+            for (title, rows) in [("追加", &diff.added), ("削除", &diff.removed)] {
+                if rows.is_empty() {
+                    continue;
+                }
+                let _ = writeln!(out, "{title} ({} 行)\n", rows.len());
+                for row in rows.iter().take(MARKDOWN_SAMPLE_ROWS) {
+                    let _ = writeln!(out, "- `{}` {}", row.key, row.label);
+                }
+                write_omitted(&mut out, rows.len());
+            }
+            if !diff.changed.is_empty() {
+                let _ = writeln!(out, "変更 ({} 行)\n", diff.changed.len());
+                for row in diff.changed.iter().take(MARKDOWN_SAMPLE_ROWS) {
+                    let fields: Vec<String> = row
+                        .fields
+                        .iter()
+                        .map(|f| format!("{}: `{}` → `{}`", f.column, f.old, f.new))
+                        .collect();
+                    let _ = writeln!(out, "- `{}` {}: {}", row.key, row.label, fields.join(", "));
+                }
+                write_omitted(&mut out, diff.changed.len());
+            }
+        }
+
+        if !self.violations.is_empty() {
+            let _ = writeln!(out, "\n## 閾値超過\n");
+            for violation in &self.violations {
+                let _ = writeln!(out, "- {violation}");
+            }
+        }
+        out
+    }
+}
+
+fn write_omitted(out: &mut String, total: usize) {
+    if total > MARKDOWN_SAMPLE_ROWS {
+        let _ = writeln!(out, "- ほか {} 行", total - MARKDOWN_SAMPLE_ROWS);
+    }
+    out.push('\n');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::table::{int, text};
+
+    fn snapshot(stations: &[(i32, &str, i32)], sst: &[(i32, i32, i32)]) -> Snapshot {
+        let mut s = Snapshot {
+            companies: Table::new(COMPANY_COLUMNS, None),
+            lines: Table::new(LINE_COLUMNS, None),
+            stations: Table::new(STATION_COLUMNS, None),
+            types: Table::new(TYPE_COLUMNS, None),
+            sst: Table::new(SST_COLUMNS, None),
+        };
+        for (company_cd, name) in [(1, "A 鉄道"), (2, "B バス")] {
+            let t = &s.companies;
+            let mut row = t.blank_row();
+            row[t.col("company_cd")] = int(company_cd);
+            row[t.col("company_name")] = text(name);
+            s.companies.push(row);
+            let t = &s.lines;
+            let mut row = t.blank_row();
+            row[t.col("line_cd")] = int(company_cd * 100);
+            row[t.col("company_cd")] = int(company_cd);
+            s.lines.push(row);
+        }
+        for &(station_cd, name, line_cd) in stations {
+            let t = &s.stations;
+            let mut row = t.blank_row();
+            row[t.col("station_cd")] = int(station_cd);
+            row[t.col("station_name")] = text(name);
+            row[t.col("line_cd")] = int(line_cd);
+            s.stations.push(row);
+        }
+        for (i, &(station_cd, type_cd, line_group_cd)) in sst.iter().enumerate() {
+            let t = &s.sst;
+            let mut row = t.blank_row();
+            row[t.col("id")] = int(i as i32 + 1);
+            row[t.col("station_cd")] = int(station_cd);
+            row[t.col("type_cd")] = int(type_cd);
+            row[t.col("line_group_cd")] = int(line_group_cd);
+            row[t.col("pass")] = int(0);
+            s.sst.push(row);
+        }
+        s
+    }
+
+    fn table<'a>(report: &'a Report, name: &str) -> &'a TableDiff {
+        report.tables.iter().find(|t| t.table == name).unwrap()
+    }
+
+    #[test]
+    fn renumbered_sst_ids_are_not_changes() {
+        let stations = [(10, "甲", 100), (11, "乙", 100), (12, "丙", 100)];
+        let old = snapshot(&stations, &[(10, 1, 500), (11, 1, 500), (12, 1, 500)]);
+        // 別の系統を前に差し込んで id をずらしても、停車駅そのものは同じ
+        let new = snapshot(
+            &stations,
+            &[(12, 2, 400), (10, 1, 500), (11, 1, 500), (12, 1, 500)],
+        );
+        let report = compare(&old, &new, &Thresholds::default());
+        let sst = table(&report, "station_station_types");
+        assert_eq!(sst.added.len(), 1);
+        assert!(sst.removed.is_empty());
+        assert!(sst.changed.is_empty());
+    }
+
+    #[test]
+    fn inserted_stop_changes_only_its_neighbour() {
+        let stations = [(10, "甲", 100), (11, "乙", 100), (12, "丙", 100)];
+        let old = snapshot(&stations, &[(10, 1, 500), (12, 1, 500)]);
+        let new = snapshot(&stations, &[(10, 1, 500), (11, 1, 500), (12, 1, 500)]);
+        let report = compare(&old, &new, &Thresholds::default());
+        let sst = table(&report, "station_station_types");
+        assert_eq!(sst.added.len(), 1);
+        assert_eq!(sst.changed.len(), 1);
+        assert_eq!(sst.changed[0].key, "500/12/1");
+        assert_eq!(sst.changed[0].fields[0].column, "prev_station_cd");
+    }
+
+    #[test]
+    fn dropped_operator_exceeds_threshold() {
+        let old = snapshot(
+            &[
+                (10, "甲", 100),
+                (20, "バス停 1", 200),
+                (21, "バス停 2", 200),
+            ],
+            &[(20, 9, 900), (21, 9, 900)],
+        );
+        let new = snapshot(&[(10, "甲", 100)], &[]);
+        let report = compare(
+            &old,
+            &new,
+            &Thresholds {
+                max_removed_ratio: 1.0,
+                max_operator_removed_ratio: 0.5,
+            },
+        );
+        let bus = report.operators.iter().find(|o| o.company_cd == 2).unwrap();
+        assert_eq!((bus.old_rows, bus.removed), (5, 4));
+        assert_eq!(report.violations.len(), 1);
+        assert!(report.violations[0].starts_with("B バス (2)"));
+        assert!(report.to_markdown().contains("## 閾値超過"));
+    }
+
+    #[test]
+    fn changed_columns_are_listed() {
+        let old = snapshot(&[(10, "甲", 100)], &[]);
+        let new = snapshot(&[(10, "甲駅", 100)], &[]);
+        let report = compare(&old, &new, &Thresholds::default());
+        let stations = table(&report, "stations");
+        assert_eq!(stations.changed.len(), 1);
+        let field = &stations.changed[0].fields[0];
+        assert_eq!(
+            (
+                field.column.as_str(),
+                field.old.as_str(),
+                field.new.as_str()
+            ),
+            ("station_name", "甲", "甲駅")
+        );
+        assert!(report.violations.is_empty());
+    }
+
+    #[test]
+    fn options_take_two_dirs_and_thresholds() {
+        let args: Vec<String> = [
+            "old",
+            "new",
+            "--json",
+            "d.json",
+            "--max-removed-ratio",
+            "0.1",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        let options = Options::parse(&args).unwrap();
+        assert_eq!(options.old_dir, PathBuf::from("old"));
+        assert_eq!(options.json, Some(PathBuf::from("d.json")));
+        assert_eq!(options.thresholds.max_removed_ratio, 0.1);
+        assert!(Options::parse(&args[..1]).is_err());
+        assert!(Options::parse(&[
+            "a".into(),
+            "b".into(),
+            "--max-removed-ratio".into(),
+            "2".into()
+        ])
+        .is_err());
+    }
+}
@@ -22,6 +22,10 @@ const OUTPUTS: &[(&str, &str)] = &[
     ("station_station_types", "id"),
     ("aliases", "id"),
     ("line_aliases", "id"),
+    ("connections", "id"),
+    ("bus_calendars", "service_cd"),
+    ("bus_calendar_dates", "id"),
+    ("bus_timetables", "id"),
 ];
 
 pub fn write_all(dataset: &mut Dataset, out_dir: &Path) -> Result<()> {
@@ -37,6 +41,10 @@ pub fn write_all(dataset: &mut Dataset, out_dir: &Path) -> Result<()> {
             "station_station_types" => &mut dataset.sst,
             "aliases" => &mut dataset.aliases,
             "line_aliases" => &mut dataset.line_aliases,
+            "connections" => &mut dataset.connections,
+            "bus_calendars" => &mut dataset.bus_calendars,
+            "bus_calendar_dates" => &mut dataset.bus_calendar_dates,
+            "bus_timetables" => &mut dataset.bus_timetables,
             other => unreachable!("未知のテーブル {other}"),
         };
         table.sort_by_int_col(order_by);
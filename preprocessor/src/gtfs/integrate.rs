@@ -9,6 +9,7 @@ use stationapi::domain::arrival_estimation::haversine_distance;
 use stationapi::domain::romaji::strip_macrons;
 
 use super::model::{GtfsData, Stop};
+use super::timetable::timetables_to_tables;
 use anyhow::Result;
 
 use crate::codes::{bus_station_g_cd, company_cd_for_gtfs_route, hiragana_to_katakana, BusCodes};
@@ -39,6 +40,7 @@ pub fn integrate(dataset: &mut Dataset, gtfs: &GtfsData) -> Result<()> {
     info!("物理停留所 {} 件の停車順を決めた", stop_route_map.len());
     stops_to_stations(dataset, gtfs, &stop_route_map, &mut codes)?;
     trip_variations_to_types(dataset, gtfs, &mut codes)?;
+    timetables_to_tables(dataset, gtfs, &codes)?;
     if codes.collisions() > 0 {
         info!(
             "ハッシュがぶつかった {} 件は次の空き ID へずらした",
@@ -1160,6 +1162,6 @@ fn build_type_name_roman(
 }
 
 /// 列名を指定して 1 セルを埋める。
-fn set(table: &crate::table::Table, row: &mut [Cell], column: &str, value: Cell) {
+pub(super) fn set(table: &crate::table::Table, row: &mut [Cell], column: &str, value: Cell) {
     row[table.col(column)] = value;
 }
@@ -5,6 +5,7 @@ pub mod integrate;
 pub mod model;
 pub mod odpt;
 pub mod parse;
+pub mod timetable;
 
 use anyhow::{bail, Result};
 
@@ -1,7 +1,8 @@
 //! GTFS の中間表現。
 //!
 //! 統合処理が実際に読む列だけを持たせてある
-//! (calendar / shapes / feed_info / agencies は出力に一切効かないので取り込まない)。
+//! (shapes / feed_info / agencies は出力に一切効かないので取り込まない)。
+//! calendar / calendar_dates と発着時刻はバスの時刻表を書き出すために持つ。
 //!
 //! 主キーの重複は先勝ちで捨てる。
 
@@ -46,6 +47,7 @@ impl Stop {
 pub struct Trip {
     pub trip_id: String,
     pub route_id: String,
+    pub service_id: String,
     pub trip_headsign: Option<String>,
     pub direction_id: Option<i32>,
     pub shape_id: Option<String>,
@@ -56,9 +58,32 @@ pub struct StopTime {
     pub trip_id: String,
     pub stop_id: String,
     pub stop_sequence: i32,
+    /// GTFS の `HH:MM:SS`。深夜便は 24 時を超える。
+    pub arrival_time: Option<String>,
+    pub departure_time: Option<String>,
+    /// 停留所ごとの行先表示。あれば便の行先より優先する。
+    pub stop_headsign: Option<String>,
     pub shape_dist_traveled: Option<f64>,
 }
 
+/// `calendar.txt` の 1 行。曜日は月曜から日曜の順。
+#[derive(Debug, Clone)]
+pub struct Calendar {
+    pub service_id: String,
+    pub weekdays: [bool; 7],
+    /// `YYYYMMDD`。
+    pub start_date: String,
+    pub end_date: String,
+}
+
+/// `calendar_dates.txt` の 1 行。`exception_type` は 1 = 運行追加、2 = 運休。
+#[derive(Debug, Clone)]
+pub struct CalendarDate {
+    pub service_id: String,
+    pub date: String,
+    pub exception_type: i32,
+}
+
 /// 取り込んだ GTFS 一式。行の並びは取り込み順を保つ。
 #[derive(Default)]
 pub struct GtfsData {
@@ -66,12 +91,17 @@ pub struct GtfsData {
     pub stops: Vec<Stop>,
     pub trips: Vec<Trip>,
     pub stop_times: Vec<StopTime>,
+    pub calendars: Vec<Calendar>,
+    pub calendar_dates: Vec<CalendarDate>,
 
     route_ids: HashSet<String>,
     stop_ids: HashSet<String>,
     trip_ids: HashSet<String>,
     /// (trip_id, stop_sequence) の一意制約。
     stop_time_keys: HashSet<(String, i32)>,
+    calendar_ids: HashSet<String>,
+    /// (service_id, date) の一意制約。
+    calendar_date_keys: HashSet<(String, String)>,
 }
 
 impl GtfsData {
@@ -114,6 +144,19 @@ impl GtfsData {
         }
     }
 
+    pub fn push_calendar(&mut self, calendar: Calendar) {
+        if self.calendar_ids.insert(calendar.service_id.clone()) {
+            self.calendars.push(calendar);
+        }
+    }
+
+    pub fn push_calendar_date(&mut self, calendar_date: CalendarDate) {
+        let key = (calendar_date.service_id.clone(), calendar_date.date.clone());
+        if self.calendar_date_keys.insert(key) {
+            self.calendar_dates.push(calendar_date);
+        }
+    }
+
     pub fn has_stop(&self, stop_id: &str) -> bool {
         self.stop_ids.contains(stop_id)
     }
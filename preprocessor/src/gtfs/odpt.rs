@@ -16,7 +16,7 @@ use serde::de::{DeserializeOwned, DeserializeSeed, SeqAccess, Visitor};
 use serde::{Deserialize, Serialize};
 use stationapi::domain::romaji::romaji_display_name;
 
-use super::model::{GtfsData, Route, Stop, StopTime, Trip};
+use super::model::{Calendar, GtfsData, Route, Stop, StopTime, Trip};
 use crate::codes::hiragana_to_katakana;
 use crate::{info, warn};
 
@@ -27,6 +27,9 @@ const CACHE_DIR: &str = "data/TokyuBus-ODPT";
 const CACHE_MAX_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
 /// ODPT JSON には路線色が無いため、東急バスの色を決め打ちで入れる。
 const ROUTE_COLOR: &str = "DD1133";
+/// ODPT のカレンダーは期間を持たないので、十分に広い期間を入れる。
+const CALENDAR_START: &str = "20000101";
+const CALENDAR_END: &str = "20991231";
 
 #[derive(Debug, Deserialize, Serialize)]
 struct BusroutePattern {
@@ -66,6 +69,8 @@ struct BusTimetable {
     operator: String,
     #[serde(rename = "odpt:busroutePattern")]
     busroute_pattern: String,
+    #[serde(rename = "odpt:calendar", default)]
+    calendar: Option<String>,
     #[serde(rename = "odpt:busTimetableObject", default)]
     objects: Vec<BusTimetableObject>,
 }
@@ -213,6 +218,26 @@ fn parse_odpt_time(value: &str) -> Option<String> {
     Some(format!("{hours:02}:{minutes:02}:00"))
 }
 
+/// ODPT のカレンダー名を GTFS の曜日 (月曜から日曜) へ。
+///
+/// ODPT の時刻表は運行期間を持たないので、曜日だけで運行日を表す。祝日を
+/// `calendar_dates` に並べると期間 (2000〜2099 年) ぶんの例外を抱えることになるので、
+/// 日曜の列を「日曜・休日」として使う。Worker は祝日・年末年始をこの列で引くので
+/// (`BusTimetable::runs_on`)、`Holiday` の便は祝日に走り、`Weekday`・`Saturday` の便は
+/// 祝日に走らない。日曜だけを指す `Sunday` も同じ列に載るので、祝日にも走る。
+fn calendar_weekdays(calendar: &str) -> Option<[bool; 7]> {
+    const SUNDAY_AND_HOLIDAYS: [bool; 7] = [false, false, false, false, false, false, true];
+    let weekdays = match strip_prefix(calendar) {
+        "Weekday" => [true, true, true, true, true, false, false],
+        "Saturday" => [false, false, false, false, false, true, false],
+        "Holiday" | "Sunday" | "SundayHoliday" => SUNDAY_AND_HOLIDAYS,
+        "SaturdayHoliday" => [false, false, false, false, false, true, true],
+        "Everyday" => [true; 7],
+        _ => return None,
+    };
+    Some(weekdays)
+}
+
 /// 資源を取得する。7 日以内のキャッシュがあればそれを使う。
 ///
 /// トークンが無いときはキャッシュだけを頼りにする。ここで諦めてしまうと
@@ -367,6 +392,7 @@ pub fn load(data: &mut GtfsData, odpt: &OdptData) {
 
     let mut trip_count = 0usize;
     let mut stop_time_count = 0usize;
+    let mut unknown_calendars: Vec<&str> = Vec::new();
     for timetable in &odpt.timetables {
         if timetable.operator != OPERATOR {
             continue;
@@ -375,6 +401,28 @@ pub fn load(data: &mut GtfsData, odpt: &OdptData) {
         else {
             continue;
         };
+        // 運行日が分からない便は時刻表に出せないので、service_id を空にしておく
+        let service_id = match timetable.calendar.as_deref() {
+            Some(calendar) => match calendar_weekdays(calendar) {
+                Some(weekdays) => {
+                    let service_id = scoped(calendar);
+                    data.push_calendar(Calendar {
+                        service_id: service_id.clone(),
+                        weekdays,
+                        start_date: CALENDAR_START.to_string(),
+                        end_date: CALENDAR_END.to_string(),
+                    });
+                    service_id
+                }
+                None => {
+                    if !unknown_calendars.contains(&calendar) {
+                        unknown_calendars.push(calendar);
+                    }
+                    String::new()
+                }
+            },
+            None => String::new(),
+        };
         let trip_id = scoped(&timetable.same_as);
         // 運行パターンを shape_id に見立てる。系統の運行パターンごとに
         // 1 つの TrainType を作る仕組みをそのまま使うため。
@@ -382,6 +430,7 @@ pub fn load(data: &mut GtfsData, odpt: &OdptData) {
         data.push_trip(Trip {
             trip_id: trip_id.clone(),
             route_id: route_id.clone(),
+            service_id,
             trip_headsign: timetable
                 .objects
                 .iter()
@@ -414,12 +463,21 @@ pub fn load(data: &mut GtfsData, odpt: &OdptData) {
                 trip_id: trip_id.clone(),
                 stop_id,
                 stop_sequence: object.index,
+                arrival_time: arrival,
+                departure_time: departure,
+                stop_headsign: object.destination_sign.clone(),
                 shape_dist_traveled: None,
             });
             stop_time_count += 1;
         }
     }
 
+    if !unknown_calendars.is_empty() {
+        warn!(
+            "東急バスの ODPT JSON に曜日へ直せないカレンダーがある。該当便は時刻表に出ない: {}",
+            unknown_calendars.join(", ")
+        );
+    }
     info!(
         "東急バス ODPT JSON: 系統 {route_count} / 停留所 {stop_count} / 便 {trip_count} / 停車 {stop_time_count}"
     );
@@ -456,6 +514,58 @@ mod tests {
         assert!(!is_community_route("odpt.Busroute:TokyuBus.Shibu41"));
     }
 
+    #[test]
+    fn calendar_names_map_to_weekdays() {
+        assert_eq!(
+            calendar_weekdays("odpt.Calendar:Weekday"),
+            Some([true, true, true, true, true, false, false])
+        );
+        assert_eq!(
+            calendar_weekdays("odpt.Calendar:SaturdayHoliday"),
+            Some([false, false, false, false, false, true, true])
+        );
+        assert_eq!(calendar_weekdays("odpt.Calendar:Specific.20240401"), None);
+    }
+
+    #[test]
+    fn holiday_calendar_runs_on_substitute_holidays() {
+        use stationapi::domain::calendar::Date;
+        use stationapi::domain::entity::gtfs::GtfsCalendar;
+        use stationapi::domain::timetable::BusTimetable;
+
+        let runs_on = |calendar: &str, date: &str| {
+            let w = calendar_weekdays(calendar).unwrap();
+            BusTimetable {
+                station_cd: 1,
+                line_cd: 1,
+                headsign: String::new(),
+                calendar: Some(GtfsCalendar::new(
+                    scoped(calendar),
+                    w[0],
+                    w[1],
+                    w[2],
+                    w[3],
+                    w[4],
+                    w[5],
+                    w[6],
+                    CALENDAR_START.to_string(),
+                    CALENDAR_END.to_string(),
+                )),
+                calendar_dates: vec![],
+                departure_minutes: vec![],
+            }
+            .runs_on(Date::parse(date).unwrap())
+        };
+        // 2024-02-12 は振替休日 (月曜)
+        assert!(runs_on("odpt.Calendar:Holiday", "2024-02-12"));
+        assert!(!runs_on("odpt.Calendar:Weekday", "2024-02-12"));
+        assert!(runs_on("odpt.Calendar:Weekday", "2024-02-13"));
+        assert!(!runs_on("odpt.Calendar:Holiday", "2024-02-13"));
+        // 祝日の土曜は休日ダイヤ
+        assert!(!runs_on("odpt.Calendar:Saturday", "2024-11-23"));
+        assert!(runs_on("odpt.Calendar:SaturdayHoliday", "2024-11-23"));
+    }
+
     #[test]
     fn direction_maps_to_gtfs_values() {
         assert_eq!(direction_id(Some("1")), Some(0));
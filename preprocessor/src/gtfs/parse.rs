@@ -11,7 +11,7 @@ use anyhow::{Context, Result};
 use stationapi::domain::romaji::{romaji_display_name, to_fullwidth_katakana};
 
 use super::feed::{scoped_id, GtfsFeed};
-use super::model::{GtfsData, Route, Stop, StopTime, Trip};
+use super::model::{Calendar, CalendarDate, GtfsData, Route, Stop, StopTime, Trip};
 use crate::warn;
 
 /// `translations.txt` の 1 エントリ。
@@ -38,6 +38,8 @@ pub fn load_feed(data: &mut GtfsData, feed: &GtfsFeed) -> Result<()> {
     load_stops(data, dir, feed, &translations)?;
     load_trips(data, dir, feed)?;
     load_stop_times(data, dir, feed)?;
+    load_calendar(data, dir, feed)?;
+    load_calendar_dates(data, dir, feed)?;
     Ok(())
 }
 
@@ -151,6 +153,7 @@ fn load_trips(data: &mut GtfsData, dir: &Path, feed: &GtfsFeed) -> Result<()> {
         data.push_trip(Trip {
             trip_id: scoped_id(feed, record.get(2).unwrap_or("")),
             route_id: scoped_id(feed, record.get(0).unwrap_or("")),
+            service_id: scoped_id(feed, record.get(1).unwrap_or("")),
             trip_headsign: cell(&record, 3).map(str::to_string),
             direction_id: cell(&record, 5).and_then(|v| v.parse().ok()),
             shape_id: cell(&record, 7).map(|s| scoped_id(feed, s)),
@@ -171,12 +174,65 @@ fn load_stop_times(data: &mut GtfsData, dir: &Path, feed: &GtfsFeed) -> Result<(
             trip_id: scoped_id(feed, record.get(0).unwrap_or("")),
             stop_id: scoped_id(feed, record.get(3).unwrap_or("")),
             stop_sequence: record.get(4).unwrap_or("0").parse().unwrap_or(0),
+            arrival_time: cell(&record, 1).map(|v| v.trim().to_string()),
+            departure_time: cell(&record, 2).map(|v| v.trim().to_string()),
+            stop_headsign: cell(&record, 5).map(str::to_string),
             shape_dist_traveled: cell(&record, 8).and_then(|v| v.parse().ok()),
         });
     }
     Ok(())
 }
 
+fn load_calendar(data: &mut GtfsData, dir: &Path, feed: &GtfsFeed) -> Result<()> {
+    // calendar.txt は calendar_dates.txt だけで運行日を表すフィードでは無い
+    let path = dir.join("calendar.txt");
+    if !path.exists() {
+        return Ok(());
+    }
+    let Some(mut rdr) = reader(&path)? else {
+        return Ok(());
+    };
+    // service_id,monday,tuesday,wednesday,thursday,friday,saturday,sunday,
+    // start_date,end_date
+    for record in rdr.records() {
+        let record = record?;
+        let mut weekdays = [false; 7];
+        for (day, runs) in weekdays.iter_mut().enumerate() {
+            *runs = record.get(day + 1).map(str::trim) == Some("1");
+        }
+        data.push_calendar(Calendar {
+            service_id: scoped_id(feed, record.get(0).unwrap_or("")),
+            weekdays,
+            start_date: record.get(8).unwrap_or("").trim().to_string(),
+            end_date: record.get(9).unwrap_or("").trim().to_string(),
+        });
+    }
+    Ok(())
+}
+
+fn load_calendar_dates(data: &mut GtfsData, dir: &Path, feed: &GtfsFeed) -> Result<()> {
+    let path = dir.join("calendar_dates.txt");
+    if !path.exists() {
+        return Ok(());
+    }
+    let Some(mut rdr) = reader(&path)? else {
+        return Ok(());
+    };
+    // service_id,date,exception_type
+    for record in rdr.records() {
+        let record = record?;
+        let Some(exception_type) = cell(&record, 2).and_then(|v| v.trim().parse().ok()) else {
+            continue;
+        };
+        data.push_calendar_date(CalendarDate {
+            service_id: scoped_id(feed, record.get(0).unwrap_or("")),
+            date: record.get(1).unwrap_or("").trim().to_string(),
+            exception_type,
+        });
+    }
+    Ok(())
+}
+
 /// `translations.txt` から stop_name の訳語だけを読む。
 ///
 /// キーは `record_id` (= stop_id、末尾に "-01" のような柱番号が付くことがある) の
@@ -0,0 +1,181 @@
+//! GTFS の発車時刻を bus_calendars / bus_calendar_dates / bus_timetables へ。
+//!
+//! Worker は停留所ごとに「次の便」を引くだけなので、便単位では持たずに
+//! (駅, 運行日, 行先) ごとの発車時刻の列へ畳む。1 便ずつ持つと stop_times を
+//! ほぼそのまま抱えることになり、isolate の起動が重くなる。
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use anyhow::Result;
+
+use super::integrate::set;
+use super::model::GtfsData;
+use crate::codes::BusCodes;
+use crate::info;
+use crate::rail::{assign_serial, Dataset};
+use crate::table::{int, text};
+
+/// `bus_calendars` の曜日列。`Calendar::weekdays` と同じ月曜始まり。
+const WEEKDAY_COLUMNS: [&str; 7] = [
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+    "sunday",
+];
+
+/// 時刻表を書き出す。`stops_to_stations` の後に呼ぶ (駅として書き出した
+/// 停留所の `station_cd` だけを使う)。
+pub fn timetables_to_tables(
+    dataset: &mut Dataset,
+    gtfs: &GtfsData,
+    codes: &BusCodes,
+) -> Result<()> {
+    let stop_times_by_trip = gtfs.stop_times_by_trip();
+    let parent_of = gtfs.parent_stop_ids();
+
+    // (station_cd, service_id, 行先) -> 発車時刻 (分)
+    let mut departures: BTreeMap<(i32, &str, &str), BTreeSet<u32>> = BTreeMap::new();
+    for trip in &gtfs.trips {
+        if trip.service_id.is_empty() {
+            continue;
+        }
+        let Some(times) = stop_times_by_trip.get(trip.trip_id.as_str()) else {
+            continue;
+        };
+        // 終点は降車だけなので発車に数えない
+        let Some((_, boarding)) = times.split_last() else {
+            continue;
+        };
+        for stop_time in boarding {
+            let Some(minutes) = stop_time
+                .departure_time
+                .as_deref()
+                .or(stop_time.arrival_time.as_deref())
+                .and_then(gtfs_time_minutes)
+            else {
+                continue;
+            };
+            let stop_id = parent_of
+                .get(stop_time.stop_id.as_str())
+                .copied()
+                .unwrap_or(stop_time.stop_id.as_str());
+            let Some(station_cd) = codes.existing_station_cd(stop_id, &trip.route_id) else {
+                continue;
+            };
+            let headsign = stop_time
+                .stop_headsign
+                .as_deref()
+                .or(trip.trip_headsign.as_deref())
+                .unwrap_or("");
+            departures
+                .entry((station_cd, trip.service_id.as_str(), headsign))
+                .or_default()
+                .insert(minutes);
+        }
+    }
+
+    // 使われている service_id にだけ、名前順で連番を振る
+    let service_cds: HashMap<&str, i32> = departures
+        .keys()
+        .map(|(_, service_id, _)| *service_id)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .zip(1..)
+        .collect();
+
+    for calendar in &gtfs.calendars {
+        let Some(&service_cd) = service_cds.get(calendar.service_id.as_str()) else {
+            continue;
+        };
+        let t = &dataset.bus_calendars;
+        let mut row = t.blank_row();
+        set(t, &mut row, "service_cd", int(service_cd));
+        for (column, runs) in WEEKDAY_COLUMNS.iter().zip(calendar.weekdays) {
+            set(t, &mut row, column, int(i32::from(runs)));
+        }
+        set(t, &mut row, "start_date", text(calendar.start_date.clone()));
+        set(t, &mut row, "end_date", text(calendar.end_date.clone()));
+        dataset.bus_calendars.push(row);
+    }
+
+    let mut calendar_dates: Vec<(i32, &str, i32)> = gtfs
+        .calendar_dates
+        .iter()
+        .filter_map(|date| {
+            let service_cd = *service_cds.get(date.service_id.as_str())?;
+            Some((service_cd, date.date.as_str(), date.exception_type))
+        })
+        .collect();
+    calendar_dates.sort_unstable();
+    for (service_cd, date, exception_type) in calendar_dates {
+        let t = &dataset.bus_calendar_dates;
+        let mut row = t.blank_row();
+        set(t, &mut row, "service_cd", int(service_cd));
+        set(t, &mut row, "date", text(date));
+        set(t, &mut row, "exception_type", int(exception_type));
+        dataset.bus_calendar_dates.push(row);
+    }
+
+    for ((station_cd, service_id, headsign), minutes) in &departures {
+        let t = &dataset.bus_timetables;
+        let mut row = t.blank_row();
+        set(t, &mut row, "station_cd", int(*station_cd));
+        set(t, &mut row, "service_cd", int(service_cds[service_id]));
+        set(t, &mut row, "headsign", text(*headsign));
+        set(t, &mut row, "departures", text(join_minutes(minutes)));
+        dataset.bus_timetables.push(row);
+    }
+
+    assign_serial(&mut dataset.bus_calendar_dates, "id");
+    assign_serial(&mut dataset.bus_timetables, "id");
+    info!(
+        "バス時刻表: 運行日 {} / 例外 {} / 時刻表 {}",
+        dataset.bus_calendars.len(),
+        dataset.bus_calendar_dates.len(),
+        dataset.bus_timetables.len()
+    );
+    Ok(())
+}
+
+/// GTFS の `HH:MM:SS` を運行日 0 時からの分へ。秒は切り捨てる。
+/// 深夜便の 24 時以降もそのまま分にする。
+fn gtfs_time_minutes(value: &str) -> Option<u32> {
+    let mut parts = value.trim().split(':');
+    let hours: u32 = parts.next()?.parse().ok()?;
+    let minutes: u32 = parts.next()?.parse().ok()?;
+    if minutes >= 60 {
+        return None;
+    }
+    Some(hours * 60 + minutes)
+}
+
+fn join_minutes(minutes: &BTreeSet<u32>) -> String {
+    minutes
+        .iter()
+        .map(u32::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gtfs_time_keeps_hours_past_midnight() {
+        assert_eq!(gtfs_time_minutes("08:05:30"), Some(485));
+        assert_eq!(gtfs_time_minutes("25:10:00"), Some(1510));
+        assert_eq!(gtfs_time_minutes("8:05:00"), Some(485));
+        assert_eq!(gtfs_time_minutes("08:60:00"), None);
+        assert_eq!(gtfs_time_minutes(""), None);
+    }
+
+    #[test]
+    fn minutes_are_space_separated_in_order() {
+        let minutes: BTreeSet<u32> = [600, 485, 1510].into_iter().collect();
+        assert_eq!(join_minutes(&minutes), "485 600 1510");
+    }
+}
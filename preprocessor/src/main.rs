@@ -15,12 +15,15 @@
 //! ```text
 //! preprocessor [出力先]            # 既定は generated
 //! DISABLE_BUS_FEATURE=true preprocessor   # 鉄道のみ
+//! preprocessor diff <old_dir> <new_dir> [--json <path>]   # 2 つの出力を比べる
 //! ```
 //!
 //! バスの一部フィードは `ODPT_ACCESS_TOKEN` を要求する。設定が無ければ
 //! トークン不要なフィード (都営バス) と、7 日以内のキャッシュだけが使われる。
 
 mod codes;
+mod connections;
+mod diff;
 mod emit;
 mod gtfs;
 mod rail;
@@ -50,14 +53,21 @@ fn bus_feature_disabled() -> bool {
 }
 
 fn main() -> Result<()> {
-    let out_dir: PathBuf = std::env::args()
-        .nth(1)
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("diff") {
+        return diff::run(&args[1..]);
+    }
+
+    let out_dir: PathBuf = args
+        .first()
+        .cloned()
         .unwrap_or_else(|| "generated".to_string())
         .into();
     let data_dir = Path::new("data");
 
     let mut dataset = rail::Dataset::load(data_dir)?;
     dataset.generate_virtual_local_rail_services()?;
+    dataset.generate_walking_connections()?;
 
     if bus_feature_disabled() {
         info!("DISABLE_BUS_FEATURE が立っているのでバスを取り込まない");
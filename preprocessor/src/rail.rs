@@ -8,7 +8,7 @@ use anyhow::{bail, Context, Result};
 use crate::table::{cell_i32, int, Table};
 use crate::{info, warn};
 
-/// 出力する 7 テーブルの列。Worker 側 (`src/index.rs` と `build.rs`) が
+/// 出力するテーブルの列。Worker 側 (`src/index.rs` と `build.rs`) が
 /// この並びを前提に読むので、順序を変えない。
 pub const COMPANY_COLUMNS: &[&str] = &[
     "company_cd",
@@ -111,6 +111,28 @@ pub const ALIAS_COLUMNS: &[&str] = &[
 
 pub const LINE_ALIAS_COLUMNS: &[&str] = &["id", "station_cd", "alias_cd"];
 
+pub const CONNECTION_COLUMNS: &[&str] = &["id", "station_cd1", "station_cd2", "distance"];
+
+/// バスの運行日。`service_cd` は GTFS の service_id に振った連番。
+pub const BUS_CALENDAR_COLUMNS: &[&str] = &[
+    "service_cd",
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+    "sunday",
+    "start_date",
+    "end_date",
+];
+
+pub const BUS_CALENDAR_DATE_COLUMNS: &[&str] = &["id", "service_cd", "date", "exception_type"];
+
+/// `departures` は運行日 0 時からの分を空白区切りで昇順に並べたもの。
+pub const BUS_TIMETABLE_COLUMNS: &[&str] =
+    &["id", "station_cd", "service_cd", "headsign", "departures"];
+
 /// 種別を持たない路線へ補う各駅停車の既定種別。
 const DEFAULT_RAIL_TYPE_CD: i32 = 100;
 /// 「各駅停車」と呼ぶ路線に使う種別。
@@ -144,6 +166,12 @@ pub struct Dataset {
     pub sst: Table,
     pub aliases: Table,
     pub line_aliases: Table,
+    /// 駅グループをまたぐ徒歩連絡。手書きの行に `generate_walking_connections` が足す。
+    pub connections: Table,
+    /// バスの時刻表。`data/` には無く、GTFS の統合でだけ埋まる。
+    pub bus_calendars: Table,
+    pub bus_calendar_dates: Table,
+    pub bus_timetables: Table,
 }
 
 impl Dataset {
@@ -160,6 +188,10 @@ impl Dataset {
             sst: Table::new(SST_COLUMNS, None),
             aliases: Table::new(ALIAS_COLUMNS, Some("id")),
             line_aliases: Table::new(LINE_ALIAS_COLUMNS, Some("id")),
+            connections: Table::new(CONNECTION_COLUMNS, None),
+            bus_calendars: Table::new(BUS_CALENDAR_COLUMNS, Some("service_cd")),
+            bus_calendar_dates: Table::new(BUS_CALENDAR_DATE_COLUMNS, None),
+            bus_timetables: Table::new(BUS_TIMETABLE_COLUMNS, None),
         };
 
         load_csv(&mut dataset.companies, &data_dir.join("1!companies.csv"))?;
@@ -175,6 +207,10 @@ impl Dataset {
             &mut dataset.line_aliases,
             &data_dir.join("7!line_aliases.csv"),
         )?;
+        load_csv(
+            &mut dataset.connections,
+            &data_dir.join("8!connections.csv"),
+        )?;
 
         // transport_type は CSV に無いので既定値を入れる (0 = 鉄道)。
         fill_default(&mut dataset.lines, "transport_type", "0");
@@ -187,7 +223,7 @@ impl Dataset {
         assign_serial(&mut dataset.sst, "id");
 
         info!(
-            "取り込み: companies={} lines={} stations={} types={} sst={} aliases={} line_aliases={}",
+            "取り込み: companies={} lines={} stations={} types={} sst={} aliases={} line_aliases={} connections={}",
             dataset.companies.len(),
             dataset.lines.len(),
             dataset.stations.len(),
@@ -195,6 +231,7 @@ impl Dataset {
             dataset.sst.len(),
             dataset.aliases.len(),
             dataset.line_aliases.len(),
+            dataset.connections.len(),
         );
 
         Ok(dataset)
@@ -368,7 +405,7 @@ impl Dataset {
 }
 
 /// CSV を読んでテーブルへ流し込む。`#` 始まりの列は捨てる。
-fn load_csv(table: &mut Table, path: &Path) -> Result<()> {
+pub fn load_csv(table: &mut Table, path: &Path) -> Result<()> {
     let mut reader = csv::ReaderBuilder::new()
         .from_path(path)
         .with_context(|| format!("{} を開けない", path.display()))?;
@@ -0,0 +1,77 @@
+//! Automatic Persisted Queries (APQ)。
+//!
+//! モバイルアプリは駅・路線・種別・TTS をまとめた大きなクエリを何度も送る。
+//! Apollo の APQ に合わせ、2 回目以降は `extensions.persistedQuery.sha256Hash`
+//! だけで送れるようにする。ハッシュの照合と登録は async-graphql の
+//! `ApolloPersistedQueries` に任せ、ここでは登録先の LRU を持つ。
+//!
+//! LRU は isolate ごとに持つ。isolate が入れ替わると消えるが、クライアントは
+//! `PersistedQueryNotFound` を受けて全文を送り直すので結果は変わらない。
+//!
+//! 持つのは解析済みのドキュメント (`ExecutableDocument`) で、全文で登録したときに
+//! 1 回だけ解析する。ハッシュだけのリクエストは引いたドキュメントをそのまま使うので
+//! 解析し直さない。エイリアス数や深さの上限は、引いたドキュメントにも毎回掛かる。
+
+use std::sync::OnceLock;
+
+use async_graphql::extensions::apollo_persisted_queries::LruCacheStorage;
+
+/// 保持するクエリ数。アプリが送るクエリは十数種類なので、画面の追加を見込んでも足りる。
+const CAPACITY: usize = 256;
+
+static STORAGE: OnceLock<LruCacheStorage> = OnceLock::new();
+
+/// ハッシュから解析済みのドキュメントを引く LRU。スキーマはリクエストごとに組み立てるので、
+/// isolate 内で共有するものをここから配る。
+pub fn storage() -> LruCacheStorage {
+    STORAGE
+        .get_or_init(|| LruCacheStorage::new(CAPACITY))
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::extensions::apollo_persisted_queries::CacheStorage;
+    use async_graphql::{value, Request};
+
+    use crate::graphql::{build_schema, limits::QueryLimits};
+
+    const QUERY: &str = "query ApqParsedOnce { __typename }";
+    /// `QUERY` の SHA-256。
+    const HASH: &str = "ad57126971ff9481533b2916944fa9d9bdb8c274c0ff8558aee1bd609cd84cf6";
+
+    fn persisted(query: &str) -> Request {
+        let mut request = Request::new(query);
+        request.extensions.insert(
+            "persistedQuery".to_string(),
+            value!({ "version": 1, "sha256Hash": HASH }),
+        );
+        request
+    }
+
+    #[tokio::test]
+    async fn hash_only_requests_reuse_the_parsed_document() {
+        let schema = || {
+            build_schema(
+                crate::interactor_for(None, None),
+                QueryLimits::default(),
+                None,
+            )
+        };
+
+        let registered = schema().execute(persisted(QUERY)).await;
+        assert!(registered.is_ok(), "{:?}", registered.errors);
+
+        // 登録したのは解析済みのドキュメント
+        let document = super::storage().get(HASH.to_string()).await.unwrap();
+        assert!(document
+            .operations
+            .iter()
+            .any(|(name, _)| name.is_some_and(|name| name.as_str() == "ApqParsedOnce")));
+
+        // クエリ文字列が空でも通るので、解析し直していない
+        let replayed = schema().execute(persisted("")).await;
+        assert!(replayed.is_ok(), "{:?}", replayed.errors);
+        assert_eq!(replayed.data, registered.data);
+    }
+}
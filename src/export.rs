@@ -0,0 +1,586 @@
+//! 埋め込んだデータを丸ごと書き出す (`/__export/*`)。
+//!
+//! 解析や地図タイルの生成は GraphQL を 1 件ずつ叩くより全件を一度に取るほうが
+//! 速いので、索引をそのまま GeoJSON と GTFS 風の zip にする。データはデプロイで
+//! しか変わらないため、応答は GraphQL の GET と同じ ETag でキャッシュさせる。
+//!
+//! 列名は generated/*.csv に合わせる (station_cd, line_cd ...)。
+
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use serde_json::{json, Value};
+use stationapi::domain::entity::gtfs::TransportType;
+use stationapi::domain::entity::line::Line;
+
+use crate::index::{self, StationRecord};
+
+/// 書き出せるファイル。`/__export/` に続くパスで選ぶ。
+#[derive(Clone, Copy)]
+pub enum Export {
+    StationsGeoJson,
+    LinesGeoJson,
+    GtfsZip,
+}
+
+impl Export {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "stations.geojson" => Some(Self::StationsGeoJson),
+            "lines.geojson" => Some(Self::LinesGeoJson),
+            "gtfs.zip" => Some(Self::GtfsZip),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::StationsGeoJson | Self::LinesGeoJson => "application/geo+json",
+            Self::GtfsZip => "application/zip",
+        }
+    }
+
+    pub fn render(self) -> Vec<u8> {
+        match self {
+            Self::StationsGeoJson => stations_geojson().into_bytes(),
+            Self::LinesGeoJson => lines_geojson().into_bytes(),
+            Self::GtfsZip => gtfs_zip(),
+        }
+    }
+}
+
+// ---------------------------------------------------------------- GeoJSON
+
+/// 駅 (バス停を含む) を 1 地点ずつ並べる。
+pub fn stations_geojson() -> String {
+    let features: Vec<Value> = index::stations()
+        .iter()
+        .map(|s| {
+            json!({
+                "type": "Feature",
+                "geometry": { "type": "Point", "coordinates": [s.lon, s.lat] },
+                "properties": {
+                    "station_cd": s.station_cd,
+                    "station_g_cd": s.station_g_cd,
+                    "station_name": s.name,
+                    "station_name_k": s.name_katakana,
+                    "station_name_r": s.name_roman,
+                    "line_cd": s.line_cd,
+                    "pref_cd": s.pref_cd,
+                    "open_ymd": s.opened_at,
+                    "close_ymd": s.closed_at,
+                    "e_status": s.e_status,
+                    "transport_type": transport_type_code(s.transport_type),
+                },
+            })
+        })
+        .collect();
+    feature_collection(features)
+}
+
+/// 路線を停車順の折れ線にする。
+///
+/// 並びはその路線の駅を最も多く含む系統 (sst) の順に従う。系統を持たない路線は
+/// e_sort 順。駅が 1 つしかない路線は線にならないので geometry を null にする。
+pub fn lines_geojson() -> String {
+    let features: Vec<Value> = index::lines()
+        .iter()
+        .map(|line| {
+            let (line_group_cd, stations) = line_stops(line.line_cd);
+            let coordinates: Vec<[f64; 2]> = stations.iter().map(|s| [s.lon, s.lat]).collect();
+            let geometry = if coordinates.len() >= 2 {
+                json!({ "type": "LineString", "coordinates": coordinates })
+            } else {
+                Value::Null
+            };
+            json!({
+                "type": "Feature",
+                "geometry": geometry,
+                "properties": {
+                    "line_cd": line.line_cd,
+                    "company_cd": line.company_cd,
+                    "line_name": line.line_name,
+                    "line_name_k": line.line_name_k,
+                    "line_name_r": line.line_name_r,
+                    "line_color_c": line.line_color_c,
+                    "line_type": line.line_type,
+                    "e_status": line.e_status,
+                    "transport_type": transport_type_code(line.transport_type),
+                    "line_group_cd": line_group_cd,
+                    "station_cds": stations.iter().map(|s| s.station_cd).collect::<Vec<_>>(),
+                },
+            })
+        })
+        .collect();
+    feature_collection(features)
+}
+
+fn feature_collection(features: Vec<Value>) -> String {
+    json!({ "type": "FeatureCollection", "features": features }).to_string()
+}
+
+/// CSV と同じ 0 = 鉄道, 1 = バス。GraphQL の enum の値 (`i32::from`) とは違う。
+fn transport_type_code(transport_type: TransportType) -> i32 {
+    transport_type as i32
+}
+
+/// 路線の駅を停車順に返す。順序の元にした系統があればその line_group_cd も返す。
+fn line_stops(line_cd: i32) -> (Option<i32>, Vec<&'static StationRecord>) {
+    let members: HashSet<i32> = index::stations_by_line(line_cd)
+        .map(|s| s.station_cd)
+        .collect();
+
+    // 駅の数が同じなら line_group_cd の小さいほうを使い、出力を安定させる
+    let mut counts: BTreeMap<i32, usize> = BTreeMap::new();
+    for station_cd in &members {
+        let groups: HashSet<i32> = index::sst_by_station(*station_cd)
+            .filter_map(|sst| sst.line_group_cd)
+            .collect();
+        for group in groups {
+            *counts.entry(group).or_default() += 1;
+        }
+    }
+    let best = counts
+        .iter()
+        .max_by_key(|&(&group, &count)| (count, std::cmp::Reverse(group)))
+        .map(|(&group, _)| group);
+
+    if let Some(group) = best {
+        let mut seen = HashSet::new();
+        let stations = index::sst_by_group(group)
+            .filter(|sst| members.contains(&sst.station_cd) && seen.insert(sst.station_cd))
+            .filter_map(|sst| index::station_by_cd(sst.station_cd))
+            .collect();
+        return (Some(group), stations);
+    }
+
+    let mut stations: Vec<_> = index::stations_by_line(line_cd).collect();
+    stations.sort_by_key(|s| (s.e_sort, s.station_cd));
+    (None, stations)
+}
+
+// ---------------------------------------------------------------- GTFS
+
+/// stops / routes / trips / stop_times を GTFS の列で書き出す。
+///
+/// 系統 (line_group_cd) を 1 本の trip とし、通過駅を除いた停車駅を
+/// stop_times に並べる。時刻と運行日は持たないので、arrival_time /
+/// departure_time と service_id は空にし、calendar.txt も付けない。
+/// 駅グループは `g` を前置した親駅 (location_type=1) にする。
+pub fn gtfs_zip() -> Vec<u8> {
+    let mut zip = ZipWriter::default();
+    zip.add("agency.txt", &agency_txt());
+    zip.add("stops.txt", &stops_txt());
+    zip.add("routes.txt", &routes_txt());
+    let (trips, stop_times) = trips_and_stop_times();
+    zip.add("trips.txt", &trips);
+    zip.add("stop_times.txt", &stop_times);
+    zip.finish()
+}
+
+fn csv_file<const N: usize>(
+    header: [&str; N],
+    rows: impl IntoIterator<Item = [String; N]>,
+) -> Vec<u8> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    // Vec への書き込みは失敗しない
+    writer.write_record(header).expect("write to Vec");
+    for row in rows {
+        writer.write_record(&row).expect("write to Vec");
+    }
+    writer.into_inner().expect("flush to Vec")
+}
+
+fn agency_txt() -> Vec<u8> {
+    csv_file(
+        [
+            "agency_id",
+            "agency_name",
+            "agency_url",
+            "agency_timezone",
+            "agency_lang",
+        ],
+        index::companies().iter().map(|c| {
+            [
+                c.company_cd.to_string(),
+                c.company_name.clone(),
+                c.company_url.clone().unwrap_or_default(),
+                "Asia/Tokyo".to_string(),
+                "ja".to_string(),
+            ]
+        }),
+    )
+}
+
+fn stops_txt() -> Vec<u8> {
+    let stations = index::stations();
+    // 親駅の座標はグループの先頭の駅に合わせる
+    let mut parents: Vec<&StationRecord> = Vec::new();
+    let mut seen = HashSet::new();
+    for s in stations {
+        if seen.insert(s.station_g_cd) {
+            parents.push(s);
+        }
+    }
+    let parent_rows = parents.into_iter().map(|s| {
+        [
+            format!("g{}", s.station_g_cd),
+            String::new(),
+            s.name.clone(),
+            s.lat.to_string(),
+            s.lon.to_string(),
+            "1".to_string(),
+            String::new(),
+        ]
+    });
+    let child_rows = stations.iter().map(|s| {
+        [
+            s.station_cd.to_string(),
+            s.station_number1.clone().unwrap_or_default(),
+            s.name.clone(),
+            s.lat.to_string(),
+            s.lon.to_string(),
+            "0".to_string(),
+            format!("g{}", s.station_g_cd),
+        ]
+    });
+    csv_file(
+        [
+            "stop_id",
+            "stop_code",
+            "stop_name",
+            "stop_lat",
+            "stop_lon",
+            "location_type",
+            "parent_station",
+        ],
+        parent_rows.chain(child_rows),
+    )
+}
+
+fn routes_txt() -> Vec<u8> {
+    csv_file(
+        [
+            "route_id",
+            "agency_id",
+            "route_short_name",
+            "route_long_name",
+            "route_type",
+            "route_color",
+        ],
+        index::lines().iter().map(|line| {
+            [
+                line.line_cd.to_string(),
+                line.company_cd.to_string(),
+                line.line_name.clone(),
+                line.line_name_r.clone().unwrap_or_default(),
+                route_type(line).to_string(),
+                line.line_color_c
+                    .as_deref()
+                    .unwrap_or_default()
+                    .trim_start_matches('#')
+                    .to_string(),
+            ]
+        }),
+    )
+}
+
+/// GTFS の route_type。line_type は LineType enum の並び
+/// (0: その他, 1: 新幹線, 2: 普通, 3: 地下鉄, 4: 路面電車, 5: モノレール・AGT)。
+fn route_type(line: &Line) -> i32 {
+    if line.transport_type == TransportType::Bus {
+        return 3;
+    }
+    match line.line_type {
+        Some(3) => 1,
+        Some(4) => 0,
+        Some(5) => 12,
+        _ => 2,
+    }
+}
+
+/// 系統ごとの trip と、その停車駅の stop_times。
+/// trip の route_id は系統の最初の駅の路線にする (直通する系統も 1 路線に寄せる)。
+fn trips_and_stop_times() -> (Vec<u8>, Vec<u8>) {
+    let mut groups: Vec<i32> = Vec::new();
+    let mut type_by_group: HashMap<i32, i32> = HashMap::new();
+    for sst in index::ssts() {
+        if let Some(group) = sst.line_group_cd {
+            if let Entry::Vacant(entry) = type_by_group.entry(group) {
+                entry.insert(sst.type_cd);
+                groups.push(group);
+            }
+        }
+    }
+
+    let mut trips = Vec::with_capacity(groups.len());
+    let mut stop_times = Vec::new();
+    for group in groups {
+        let stops: Vec<i32> = index::sst_by_group(group)
+            .filter(|sst| sst.pass != Some(1))
+            .map(|sst| sst.station_cd)
+            .collect();
+        let Some(route) = stops
+            .first()
+            .and_then(|&station_cd| index::station_by_cd(station_cd))
+            .map(|s| s.line_cd)
+        else {
+            continue;
+        };
+        let type_name = index::type_by_cd(type_by_group[&group])
+            .map(|t| t.type_name.clone())
+            .unwrap_or_default();
+        trips.push([
+            route.to_string(),
+            String::new(),
+            group.to_string(),
+            type_name,
+        ]);
+        for (sequence, station_cd) in stops.into_iter().enumerate() {
+            stop_times.push([
+                group.to_string(),
+                String::new(),
+                String::new(),
+                station_cd.to_string(),
+                (sequence + 1).to_string(),
+            ]);
+        }
+    }
+
+    (
+        csv_file(
+            ["route_id", "service_id", "trip_id", "trip_short_name"],
+            trips,
+        ),
+        csv_file(
+            [
+                "trip_id",
+                "arrival_time",
+                "departure_time",
+                "stop_id",
+                "stop_sequence",
+            ],
+            stop_times,
+        ),
+    )
+}
+
+// ---------------------------------------------------------------- zip
+
+/// 無圧縮 (stored) の zip を組み立てる。圧縮ライブラリを wasm に持ち込まずに済むよう
+/// 書き出しだけを自前で持つ。エントリは 4GiB 未満を前提に ZIP64 は使わない。
+#[derive(Default)]
+struct ZipWriter {
+    out: Vec<u8>,
+    central: Vec<u8>,
+    entries: u16,
+}
+
+/// MS-DOS 形式の 1980-01-01 00:00。中身がデプロイで決まるので、時刻は固定して
+/// 同じデータなら同じバイト列になるようにする。
+const DOS_DATE: u16 = (1 << 5) | 1;
+const DOS_TIME: u16 = 0;
+
+impl ZipWriter {
+    fn add(&mut self, name: &str, data: &[u8]) {
+        let offset = self.out.len() as u32;
+        let crc = crc32(data);
+        let size = data.len() as u32;
+        let name_len = name.len() as u16;
+
+        // local file header
+        self.out.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+        self.out.extend_from_slice(&20u16.to_le_bytes()); // version needed
+        self.out.extend_from_slice(&0u16.to_le_bytes()); // flags
+        self.out.extend_from_slice(&0u16.to_le_bytes()); // stored
+        self.out.extend_from_slice(&DOS_TIME.to_le_bytes());
+        self.out.extend_from_slice(&DOS_DATE.to_le_bytes());
+        self.out.extend_from_slice(&crc.to_le_bytes());
+        self.out.extend_from_slice(&size.to_le_bytes()); // compressed
+        self.out.extend_from_slice(&size.to_le_bytes()); // uncompressed
+        self.out.extend_from_slice(&name_len.to_le_bytes());
+        self.out.extend_from_slice(&0u16.to_le_bytes()); // extra
+        self.out.extend_from_slice(name.as_bytes());
+        self.out.extend_from_slice(data);
+
+        // central directory header
+        self.central
+            .extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+        self.central.extend_from_slice(&20u16.to_le_bytes()); // version made by
+        self.central.extend_from_slice(&20u16.to_le_bytes()); // version needed
+        self.central.extend_from_slice(&0u16.to_le_bytes()); // flags
+        self.central.extend_from_slice(&0u16.to_le_bytes()); // stored
+        self.central.extend_from_slice(&DOS_TIME.to_le_bytes());
+        self.central.extend_from_slice(&DOS_DATE.to_le_bytes());
+        self.central.extend_from_slice(&crc.to_le_bytes());
+        self.central.extend_from_slice(&size.to_le_bytes());
+        self.central.extend_from_slice(&size.to_le_bytes());
+        self.central.extend_from_slice(&name_len.to_le_bytes());
+        self.central.extend_from_slice(&0u16.to_le_bytes()); // extra
+        self.central.extend_from_slice(&0u16.to_le_bytes()); // comment
+        self.central.extend_from_slice(&0u16.to_le_bytes()); // disk
+        self.central.extend_from_slice(&0u16.to_le_bytes()); // internal attributes
+        self.central.extend_from_slice(&0u32.to_le_bytes()); // external attributes
+        self.central.extend_from_slice(&offset.to_le_bytes());
+        self.central.extend_from_slice(name.as_bytes());
+
+        self.entries += 1;
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        let central_offset = self.out.len() as u32;
+        let central_size = self.central.len() as u32;
+        self.out.append(&mut self.central);
+
+        // end of central directory
+        self.out.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+        self.out.extend_from_slice(&0u16.to_le_bytes()); // this disk
+        self.out.extend_from_slice(&0u16.to_le_bytes()); // disk with central directory
+        self.out.extend_from_slice(&self.entries.to_le_bytes());
+        self.out.extend_from_slice(&self.entries.to_le_bytes());
+        self.out.extend_from_slice(&central_size.to_le_bytes());
+        self.out.extend_from_slice(&central_offset.to_le_bytes());
+        self.out.extend_from_slice(&0u16.to_le_bytes()); // comment
+        self.out
+    }
+}
+
+/// zip が要求する CRC-32 (IEEE 802.3, 反転多項式 0xEDB88320)。
+fn crc32(data: &[u8]) -> u32 {
+    const TABLE: [u32; 256] = {
+        let mut table = [0u32; 256];
+        let mut i = 0;
+        while i < 256 {
+            let mut c = i as u32;
+            let mut k = 0;
+            while k < 8 {
+                c = if c & 1 != 0 {
+                    0xEDB8_8320 ^ (c >> 1)
+                } else {
+                    c >> 1
+                };
+                k += 1;
+            }
+            table[i] = c;
+            i += 1;
+        }
+        table
+    };
+    let mut crc = !0u32;
+    for &b in data {
+        crc = TABLE[((crc ^ b as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn u16_at(bytes: &[u8], at: usize) -> u16 {
+        u16::from_le_bytes([bytes[at], bytes[at + 1]])
+    }
+
+    fn u32_at(bytes: &[u8], at: usize) -> u32 {
+        u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+    }
+
+    /// 中央ディレクトリから (名前, 中身) を読み戻す。
+    fn read_zip(zip: &[u8]) -> Vec<(String, Vec<u8>)> {
+        let end = zip.len() - 22;
+        assert_eq!(u32_at(zip, end), 0x0605_4b50);
+        let entries = u16_at(zip, end + 10) as usize;
+        let mut at = u32_at(zip, end + 16) as usize;
+        assert_eq!(at + u32_at(zip, end + 12) as usize, end);
+
+        let mut out = Vec::new();
+        for _ in 0..entries {
+            assert_eq!(u32_at(zip, at), 0x0201_4b50);
+            let crc = u32_at(zip, at + 16);
+            let size = u32_at(zip, at + 20) as usize;
+            let name_len = u16_at(zip, at + 28) as usize;
+            let offset = u32_at(zip, at + 42) as usize;
+            let name = String::from_utf8(zip[at + 46..at + 46 + name_len].to_vec()).unwrap();
+
+            // ローカルヘッダが中央ディレクトリと食い違っていないこと
+            assert_eq!(u32_at(zip, offset), 0x0403_4b50);
+            assert_eq!(u16_at(zip, offset + 8), 0); // stored
+            assert_eq!(u32_at(zip, offset + 14), crc);
+            assert_eq!(u32_at(zip, offset + 18) as usize, size);
+            assert_eq!(&zip[offset + 30..offset + 30 + name_len], name.as_bytes());
+            let data_at = offset + 30 + name_len;
+            let data = zip[data_at..data_at + size].to_vec();
+            assert_eq!(crc32(&data), crc);
+
+            out.push((name, data));
+            at += 46 + name_len;
+        }
+        out
+    }
+
+    #[test]
+    fn crc32_matches_the_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn zip_entries_read_back() {
+        let mut zip = ZipWriter::default();
+        zip.add("a.txt", b"stop_id\n1\n");
+        zip.add("empty.txt", b"");
+        let bytes = zip.finish();
+        assert_eq!(
+            read_zip(&bytes),
+            vec![
+                ("a.txt".to_string(), b"stop_id\n1\n".to_vec()),
+                ("empty.txt".to_string(), Vec::new()),
+            ]
+        );
+    }
+
+    #[test]
+    fn gtfs_zip_has_the_gtfs_files() {
+        let names: Vec<String> = read_zip(&gtfs_zip())
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "agency.txt",
+                "stops.txt",
+                "routes.txt",
+                "trips.txt",
+                "stop_times.txt"
+            ]
+        );
+    }
+
+    #[test]
+    fn line_string_follows_the_train_type_order() {
+        let collection: Value = serde_json::from_str(&lines_geojson()).unwrap();
+        let musashino = collection["features"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|f| f["properties"]["line_cd"] == 11305)
+            .unwrap();
+        // e_sort では府中本町が先頭だが、系統 (sst) の順に西船橋から並ぶ
+        assert_eq!(musashino["properties"]["line_group_cd"], 261);
+        let station_cds: Vec<i32> =
+            serde_json::from_value(musashino["properties"]["station_cds"].clone()).unwrap();
+        let first = index::station_by_cd(station_cds[0]).unwrap();
+        assert_eq!(first.name, "西船橋");
+
+        let coordinates: Vec<[f64; 2]> =
+            serde_json::from_value(musashino["geometry"]["coordinates"].clone()).unwrap();
+        assert_eq!(musashino["geometry"]["type"], "LineString");
+        let expected: Vec<[f64; 2]> = station_cds
+            .iter()
+            .map(|&cd| index::station_by_cd(cd).unwrap())
+            .map(|s| [s.lon, s.lat])
+            .collect();
+        assert_eq!(coordinates, expected);
+    }
+}
@@ -2,19 +2,47 @@
 //! 公開スキーマの正は schema/public.graphql。
 
 pub mod enums;
+pub mod limits;
+pub mod loader;
+pub mod pagination;
 pub mod query;
 pub mod scalar;
+pub mod tracing;
 pub mod types;
 
+use async_graphql::extensions::apollo_persisted_queries::ApolloPersistedQueries;
 use async_graphql::{EmptyMutation, EmptySubscription, Schema};
 
 use crate::Interactor;
+use limits::QueryLimits;
+use loader::{CompanyLoader, DataLoader, StationLinesLoader, TrainTypeLoader};
 use query::QueryRoot;
+use tracing::Tracing;
 
 pub type AppSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
 
-pub fn build_schema(interactor: Interactor) -> AppSchema {
-    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+/// `tracing` を渡すとリクエストの所要時間を記録する (`/__schema` のように
+/// クエリを実行しない用途では `None`)。
+///
+/// スキーマはリクエストごとに組み立てるので、ローダーのキャッシュも
+/// 1 リクエスト (バッチならその中の全操作) の間だけ生きる。
+pub fn build_schema(
+    interactor: Interactor,
+    limits: QueryLimits,
+    tracing: Option<Tracing>,
+) -> AppSchema {
+    let mut builder = Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .data(DataLoader::new(CompanyLoader(interactor.clone())))
+        .data(DataLoader::new(TrainTypeLoader(interactor.clone())))
+        .data(DataLoader::new(StationLinesLoader(interactor.clone())))
         .data(interactor)
+        .data(limits);
+    // 最初に登録したものが一番外側で動くので、上限超過で弾いたリクエストも記録される
+    if let Some(tracing) = tracing {
+        builder = builder.extension(tracing);
+    }
+    builder
+        .extension(limits)
+        .extension(ApolloPersistedQueries::new(crate::apq::storage()))
         .finish()
 }
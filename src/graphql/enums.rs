@@ -6,6 +6,7 @@
 #![allow(clippy::enum_variant_names)]
 
 use async_graphql::Enum;
+use stationapi::domain::{announcement, romaji, stop_pattern};
 
 #[derive(Enum, Copy, Clone, Eq, PartialEq)]
 #[graphql(rename_items = "PascalCase", name = "LineType")]
@@ -117,3 +118,65 @@ from_i32!(TransportType, TransportType::TransportTypeUnspecified,
     0 => TransportTypeUnspecified, 1 => Rail, 2 => Bus, 3 => RailAndBus);
 from_i32!(TtsAlphabet, TtsAlphabet::TtsAlphabetUnspecified,
     0 => TtsAlphabetUnspecified, 1 => Ipa, 2 => Yomigana, 3 => Plain);
+
+// `Station.announcementSsml` の文面
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+#[graphql(rename_items = "PascalCase", name = "AnnouncementTemplate")]
+pub enum AnnouncementTemplate {
+    // 次は〇〇 / The next station is 〇〇
+    NextStation,
+    // まもなく〇〇 / We will soon be arriving at 〇〇
+    Arriving,
+}
+
+impl From<AnnouncementTemplate> for announcement::AnnouncementTemplate {
+    fn from(value: AnnouncementTemplate) -> Self {
+        match value {
+            AnnouncementTemplate::NextStation => Self::NextStation,
+            AnnouncementTemplate::Arriving => Self::Arriving,
+        }
+    }
+}
+
+// `Station.nameRoman(system:)` の綴り方
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+#[graphql(rename_items = "PascalCase", name = "RomanizationSystem")]
+pub enum RomanizationSystem {
+    Hepburn,
+    HepburnNoMacron,
+    Kunrei,
+    Nihon,
+    Passport,
+}
+
+impl From<RomanizationSystem> for romaji::RomanizationSystem {
+    fn from(value: RomanizationSystem) -> Self {
+        match value {
+            RomanizationSystem::Hepburn => Self::Hepburn,
+            RomanizationSystem::HepburnNoMacron => Self::HepburnNoMacron,
+            RomanizationSystem::Kunrei => Self::Kunrei,
+            RomanizationSystem::Nihon => Self::Nihon,
+            RomanizationSystem::Passport => Self::Passport,
+        }
+    }
+}
+
+// `compareTrainTypes` で、ある駅に系統が止まるか
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+#[graphql(rename_items = "PascalCase", name = "StopStatus")]
+pub enum StopStatus {
+    Stops,
+    Passes,
+    // その系統が走らない駅
+    NotServed,
+}
+
+impl From<stop_pattern::StopStatus> for StopStatus {
+    fn from(value: stop_pattern::StopStatus) -> Self {
+        match value {
+            stop_pattern::StopStatus::Stops => Self::Stops,
+            stop_pattern::StopStatus::Passes => Self::Passes,
+            stop_pattern::StopStatus::NotServed => Self::NotServed,
+        }
+    }
+}
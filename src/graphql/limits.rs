@@ -0,0 +1,274 @@
+//! クエリの深さ・複雑さ・エイリアス数・ID 配列の長さの上限。
+//!
+//! 公開スキーマは `Station.lines[].station.lines[]...` のように再帰できるため、
+//! 上限が無いと 1 本のクエリで巨大なオブジェクトを組み立てさせ、isolate の
+//! CPU 時間を使い切らせることができる。1 回の POST に詰められる操作の数も
+//! 同じ理由で抑える。
+//!
+//! 深さと複雑さは async-graphql の検証結果をそのまま使う (重いリゾルバには
+//! `complexity` で件数に応じたコストを付けてある)。async-graphql の既定の
+//! エラーは上限も実際の値も出さないので、ここで自前の文言とコードを付けて返す。
+
+use std::sync::Arc;
+
+use async_graphql::extensions::{
+    Extension, ExtensionContext, ExtensionFactory, NextParseQuery, NextValidation,
+};
+use async_graphql::parser::types::{ExecutableDocument, Selection, SelectionSet};
+use async_graphql::{ErrorExtensionValues, ServerError, ServerResult, ValidationResult, Variables};
+
+/// 上限の一式。既定値はアプリが送る最大のクエリに余裕を持たせたもの。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QueryLimits {
+    pub max_depth: usize,
+    pub max_complexity: usize,
+    pub max_aliases: usize,
+    /// `lineIds` などの ID 配列の長さ。1 件ごとに駅一覧を引くため別に抑える。
+    pub max_list_len: usize,
+    /// 配列で送るバッチリクエストの操作数。各操作には上の上限が別々に掛かる。
+    pub max_batch: usize,
+}
+
+impl Default for QueryLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: 12,
+            max_complexity: 50_000,
+            max_aliases: 30,
+            max_list_len: 100,
+            max_batch: 10,
+        }
+    }
+}
+
+impl QueryLimits {
+    /// `GRAPHQL_MAX_DEPTH` などの設定値で既定値を上書きする。
+    /// 数値として読めない値は無視して既定値を使う。
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let read = |name: &str, default: usize| {
+            lookup(name)
+                .and_then(|value| value.trim().parse().ok())
+                .unwrap_or(default)
+        };
+        let default = Self::default();
+        Self {
+            max_depth: read("GRAPHQL_MAX_DEPTH", default.max_depth),
+            max_complexity: read("GRAPHQL_MAX_COMPLEXITY", default.max_complexity),
+            max_aliases: read("GRAPHQL_MAX_ALIASES", default.max_aliases),
+            max_list_len: read("GRAPHQL_MAX_LIST_LEN", default.max_list_len),
+            max_batch: read("GRAPHQL_MAX_BATCH", default.max_batch),
+        }
+    }
+
+    /// バッチの操作数が上限を超えていればエラーを返す。超えたバッチは 1 件も実行しない。
+    pub fn check_batch(&self, len: usize) -> Result<(), ServerError> {
+        if len > self.max_batch {
+            return Err(limit_error(
+                format!(
+                    "バッチに {len} 個の操作があります。上限は {} 個です",
+                    self.max_batch
+                ),
+                "BATCH_TOO_LARGE",
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl ExtensionFactory for QueryLimits {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(QueryLimitsExtension { limits: *self })
+    }
+}
+
+struct QueryLimitsExtension {
+    limits: QueryLimits,
+}
+
+#[async_trait::async_trait]
+impl Extension for QueryLimitsExtension {
+    async fn parse_query(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        query: &str,
+        variables: &Variables,
+        next: NextParseQuery<'_>,
+    ) -> ServerResult<ExecutableDocument> {
+        let document = next.run(ctx, query, variables).await?;
+        let aliases = count_aliases(&document);
+        if aliases > self.limits.max_aliases {
+            return Err(limit_error(
+                format!(
+                    "エイリアスが {aliases} 個あります。上限は {} 個です",
+                    self.limits.max_aliases
+                ),
+                "TOO_MANY_ALIASES",
+            ));
+        }
+        Ok(document)
+    }
+
+    async fn validation(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        next: NextValidation<'_>,
+    ) -> Result<ValidationResult, Vec<ServerError>> {
+        let result = next.run(ctx).await?;
+        if result.depth > self.limits.max_depth {
+            return Err(vec![limit_error(
+                format!(
+                    "クエリの深さが {} あります。上限は {} です",
+                    result.depth, self.limits.max_depth
+                ),
+                "QUERY_TOO_DEEP",
+            )]);
+        }
+        if result.complexity > self.limits.max_complexity {
+            return Err(vec![limit_error(
+                format!(
+                    "クエリの複雑さが {} あります。上限は {} です",
+                    result.complexity, self.limits.max_complexity
+                ),
+                "QUERY_TOO_COMPLEX",
+            )]);
+        }
+        Ok(result)
+    }
+}
+
+fn limit_error(message: String, code: &str) -> ServerError {
+    let mut extensions = ErrorExtensionValues::default();
+    extensions.set("code", code);
+    let mut error = ServerError::new(message, None);
+    error.extensions = Some(extensions);
+    error
+}
+
+/// 文書中のエイリアスの数。フラグメントは展開回数によらず 1 回だけ数える。
+fn count_aliases(document: &ExecutableDocument) -> usize {
+    let operations = document
+        .operations
+        .iter()
+        .map(|(_, operation)| count_in(&operation.node.selection_set.node))
+        .sum::<usize>();
+    let fragments = document
+        .fragments
+        .values()
+        .map(|fragment| count_in(&fragment.node.selection_set.node))
+        .sum::<usize>();
+    operations + fragments
+}
+
+fn count_in(selection_set: &SelectionSet) -> usize {
+    selection_set
+        .items
+        .iter()
+        .map(|selection| match &selection.node {
+            Selection::Field(field) => {
+                usize::from(field.node.alias.is_some()) + count_in(&field.node.selection_set.node)
+            }
+            Selection::InlineFragment(fragment) => count_in(&fragment.node.selection_set.node),
+            Selection::FragmentSpread(_) => 0,
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use async_graphql::parser::parse_query;
+
+    use super::*;
+    use crate::graphql::build_schema;
+
+    fn aliases(query: &str) -> usize {
+        count_aliases(&parse_query(query).unwrap())
+    }
+
+    fn code(error: &ServerError) -> Option<String> {
+        let value = error.extensions.as_ref()?.get("code")?;
+        Some(value.to_string().trim_matches('"').to_string())
+    }
+
+    async fn run(query: &str, limits: QueryLimits) -> Vec<Option<String>> {
+        build_schema(crate::interactor_for(None, None), limits, None)
+            .execute(query)
+            .await
+            .errors
+            .iter()
+            .map(code)
+            .collect()
+    }
+
+    #[test]
+    fn fragments_are_counted_once_and_inline_fragments_are_counted() {
+        assert_eq!(aliases("{ station(id: 1) { id } }"), 0);
+        // F は 2 回展開しても中の 1 個だけ
+        assert_eq!(
+            aliases(
+                "{ a: station(id: 1) { ...F } b: station(id: 2) { ...F } }
+                 fragment F on Station { x: id }"
+            ),
+            3
+        );
+        assert_eq!(
+            aliases("{ a: station(id: 1) { ... on Station { y: id z: name } } }"),
+            3
+        );
+    }
+
+    #[test]
+    fn unparsable_settings_fall_back_to_the_defaults() {
+        let vars = HashMap::from([
+            ("GRAPHQL_MAX_DEPTH", " 20 "),
+            ("GRAPHQL_MAX_COMPLEXITY", "lots"),
+            ("GRAPHQL_MAX_ALIASES", "-1"),
+            ("GRAPHQL_MAX_BATCH", ""),
+        ]);
+        let limits = QueryLimits::from_lookup(|name| vars.get(name).map(|v| v.to_string()));
+        assert_eq!(
+            limits,
+            QueryLimits {
+                max_depth: 20,
+                ..QueryLimits::default()
+            }
+        );
+    }
+
+    #[test]
+    fn batches_over_the_limit_are_rejected() {
+        let limits = QueryLimits::default();
+        assert!(limits.check_batch(limits.max_batch).is_ok());
+        let error = limits.check_batch(limits.max_batch + 1).unwrap_err();
+        assert_eq!(code(&error).as_deref(), Some("BATCH_TOO_LARGE"));
+    }
+
+    #[tokio::test]
+    async fn schema_rejects_queries_over_the_limits() {
+        let deep = "{ station(id: 1130205) { lines { station { lines { station { id } } } } } }";
+        let wide = "{ station(id: 1130205) { id name lines { id } } }";
+        let aliased = "{ a: station(id: 1130205) { id } b: station(id: 1130205) { id } }";
+
+        let defaults = QueryLimits::default();
+        for query in [deep, wide, aliased] {
+            assert_eq!(run(query, defaults).await, Vec::<Option<String>>::new());
+        }
+
+        let tight = QueryLimits {
+            max_depth: 3,
+            max_complexity: 3,
+            max_aliases: 1,
+            ..defaults
+        };
+        assert_eq!(run(deep, tight).await, [Some("QUERY_TOO_DEEP".to_string())]);
+        assert_eq!(
+            run(wide, tight).await,
+            [Some("QUERY_TOO_COMPLEX".to_string())]
+        );
+        assert_eq!(
+            run(aliased, tight).await,
+            [Some("TOO_MANY_ALIASES".to_string())]
+        );
+    }
+}
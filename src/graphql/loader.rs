@@ -0,0 +1,375 @@
+//! リクエスト単位のデータローダー。
+//!
+//! 駅の乗り入れ路線・会社・列車種別は、クエリで選ばれたときだけ引く。
+//! 一覧の各要素が同じフィールドを解決するときは、同じ周回で出そろったキーを
+//! 1 回の UseCase 呼び出しにまとめ、結果はリクエストの間キャッシュする。
+//!
+//! async-graphql の `DataLoader` はタイマーで待ち合わせるが、wasm32 では
+//! タイマーが未実装で panic する。ここでは 1 回だけ実行を譲り、その間に
+//! 兄弟のフィールドが出したキーをまとめて引く。
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll};
+
+use async_trait::async_trait;
+use stationapi::domain::calendar::{Date, ServiceDay};
+use stationapi::domain::entity::company::Company;
+use stationapi::domain::entity::gtfs::TransportTypeFilter;
+use stationapi::domain::entity::line::Line;
+use stationapi::domain::entity::station::Station;
+use stationapi::domain::entity::train_type::TrainType;
+use stationapi::use_case::error::UseCaseError;
+use stationapi::use_case::traits::query::QueryUseCase;
+
+use crate::Interactor;
+
+/// キーの集まりをまとめて引く処理。
+#[async_trait]
+pub trait Loader: Send + Sync + 'static {
+    type Key: Clone + Eq + Hash + Send + Sync + 'static;
+    type Value: Clone + Send + Sync + 'static;
+
+    /// 見つからなかったキーは結果に含めない。
+    async fn load(
+        &self,
+        keys: &[Self::Key],
+    ) -> Result<HashMap<Self::Key, Self::Value>, UseCaseError>;
+}
+
+pub struct DataLoader<L: Loader> {
+    loader: L,
+    state: Mutex<State<L::Key, L::Value>>,
+}
+
+struct State<K, V> {
+    /// 引き終えたキー。失敗もキーごとに覚え、同じキーを何度も引き直さない。
+    loaded: HashMap<K, Result<Option<V>, String>>,
+    /// 次にまとめて引くキー。要求された順に並べる。
+    queue: Vec<K>,
+    /// 待ち行列にあるか、引いている最中のキー。
+    queued: HashSet<K>,
+    loading: bool,
+}
+
+impl<L: Loader> DataLoader<L> {
+    pub fn new(loader: L) -> Self {
+        Self {
+            loader,
+            state: Mutex::new(State {
+                loaded: HashMap::new(),
+                queue: Vec::new(),
+                queued: HashSet::new(),
+                loading: false,
+            }),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, State<L::Key, L::Value>> {
+        // 中でパニックしても状態は壊れない (引き直すだけ) ので、毒化は無視する
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub async fn load_one(&self, key: L::Key) -> async_graphql::Result<Option<L::Value>> {
+        let mut found = self.load_many([key.clone()]).await?;
+        Ok(found.remove(&key))
+    }
+
+    /// 同じ周回で他のフィールドが出したキーと合わせて引く。
+    ///
+    /// 読み込みは索引を引くだけで途中で止まらないので、誰かが引いている
+    /// 最中に待たされることは実際には無い。その場合も譲りながら待つ。
+    pub async fn load_many(
+        &self,
+        keys: impl IntoIterator<Item = L::Key>,
+    ) -> async_graphql::Result<HashMap<L::Key, L::Value>> {
+        let keys: Vec<L::Key> = keys.into_iter().collect();
+        let mut yielded = false;
+        loop {
+            let batch = {
+                let mut state = self.state();
+                if keys.iter().all(|k| state.loaded.contains_key(k)) {
+                    let mut found = HashMap::with_capacity(keys.len());
+                    for key in &keys {
+                        match &state.loaded[key] {
+                            Ok(Some(value)) => {
+                                found.insert(key.clone(), value.clone());
+                            }
+                            Ok(None) => {}
+                            Err(message) => return Err(async_graphql::Error::new(message)),
+                        }
+                    }
+                    return Ok(found);
+                }
+                for key in &keys {
+                    if !state.loaded.contains_key(key) && state.queued.insert(key.clone()) {
+                        state.queue.push(key.clone());
+                    }
+                }
+                if state.loading || !yielded || state.queue.is_empty() {
+                    None
+                } else {
+                    state.loading = true;
+                    Some(std::mem::take(&mut state.queue))
+                }
+            };
+
+            let Some(batch) = batch else {
+                YieldNow(false).await;
+                yielded = true;
+                continue;
+            };
+            let result = self.loader.load(&batch).await;
+            let mut state = self.state();
+            state.loading = false;
+            for key in batch {
+                let value = match &result {
+                    Ok(found) => Ok(found.get(&key).cloned()),
+                    Err(e) => Err(e.to_string()),
+                };
+                state.queued.remove(&key);
+                state.loaded.insert(key, value);
+            }
+        }
+    }
+}
+
+/// 1 回だけ `Pending` を返し、すぐ起こしてもらう。
+struct YieldNow(bool);
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.0 {
+            return Poll::Ready(());
+        }
+        self.0 = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+/// 会社を `company_cd` で引く。
+pub struct CompanyLoader(pub Interactor);
+
+#[async_trait]
+impl Loader for CompanyLoader {
+    type Key = i32;
+    type Value = Company;
+
+    async fn load(&self, keys: &[i32]) -> Result<HashMap<i32, Company>, UseCaseError> {
+        let ids: Vec<u32> = keys.iter().map(|&k| k as u32).collect();
+        let companies = self.0.find_company_by_id_vec(&ids).await?;
+        Ok(companies.into_iter().map(|c| (c.company_cd, c)).collect())
+    }
+}
+
+/// (`station_cd`, `line_group_cd`) で、その系統での駅の列車種別を引く。
+pub struct TrainTypeLoader(pub Interactor);
+
+#[async_trait]
+impl Loader for TrainTypeLoader {
+    type Key = (i32, u32);
+    type Value = TrainType;
+
+    async fn load(
+        &self,
+        keys: &[(i32, u32)],
+    ) -> Result<HashMap<(i32, u32), TrainType>, UseCaseError> {
+        let mut by_group: BTreeMap<u32, Vec<u32>> = BTreeMap::new();
+        for &(station_cd, line_group_cd) in keys {
+            by_group
+                .entry(line_group_cd)
+                .or_default()
+                .push(station_cd as u32);
+        }
+        let mut out = HashMap::new();
+        for (line_group_cd, station_ids) in by_group {
+            let train_types = self
+                .0
+                .get_train_types_by_station_id_vec(&station_ids, Some(line_group_cd))
+                .await?;
+            // 同じ駅に複数あるときは後のものを使う (一括で付けていた頃と同じ)
+            for tt in train_types {
+                if let Some(station_cd) = tt.station_cd {
+                    out.insert((station_cd, line_group_cd), tt);
+                }
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// 駅の乗り入れ路線を引くキー。近くのバス路線は駅の座標で決まり、鉄道の路線にも
+/// その駅グループでの停車駅を付けるので、`line_cd` ではなく駅ごとに引く。
+/// 駅の行は引き直さずにキーで運ぶが、同じ駅かどうかは `station_cd` だけで判定する。
+#[derive(Clone)]
+pub struct StationLinesKey {
+    pub station: Arc<Station>,
+    pub transport_type: TransportTypeFilter,
+    pub skip_types_join: bool,
+    /// 駅一覧を `asOf` で引いたときは、乗り入れ路線もその日付で絞る。
+    pub as_of: Option<Date>,
+    /// 運転日を指定した一覧では、その日に止まらない系統の路線を出さない。
+    pub service_day: Option<ServiceDay>,
+}
+
+impl PartialEq for StationLinesKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.station.station_cd == other.station.station_cd
+            && self.transport_type == other.transport_type
+            && self.skip_types_join == other.skip_types_join
+            && self.as_of == other.as_of
+            && self.service_day == other.service_day
+    }
+}
+
+impl Eq for StationLinesKey {}
+
+impl Hash for StationLinesKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.station.station_cd.hash(state);
+        self.transport_type.hash(state);
+        self.skip_types_join.hash(state);
+        self.as_of.hash(state);
+        self.service_day.hash(state);
+    }
+}
+
+/// 駅の乗り入れ路線。各路線にはその駅グループでの停車駅が付く。
+pub struct StationLinesLoader(pub Interactor);
+
+#[async_trait]
+impl Loader for StationLinesLoader {
+    type Key = StationLinesKey;
+    type Value = Arc<Vec<Line>>;
+
+    async fn load(
+        &self,
+        keys: &[StationLinesKey],
+    ) -> Result<HashMap<StationLinesKey, Arc<Vec<Line>>>, UseCaseError> {
+        // 条件ごとに、要求された順のまま 1 回で引く
+        type Condition = (TransportTypeFilter, bool, Option<Date>, Option<ServiceDay>);
+        let mut groups: Vec<(Condition, Vec<&StationLinesKey>)> = Vec::new();
+        for key in keys {
+            let condition = (
+                key.transport_type,
+                key.skip_types_join,
+                key.as_of,
+                key.service_day,
+            );
+            match groups.iter_mut().find(|(c, _)| *c == condition) {
+                Some((_, group)) => group.push(key),
+                None => groups.push((condition, vec![key])),
+            }
+        }
+        let mut out = HashMap::new();
+        for ((transport_type, skip_types_join, as_of, service_day), group) in groups {
+            let stations: Vec<Station> = group.iter().map(|k| (*k.station).clone()).collect();
+            let interactor = if as_of.is_none() && service_day.is_none() {
+                self.0.clone()
+            } else {
+                crate::interactor_for(as_of, service_day)
+            };
+            let lines = interactor
+                .get_station_lines(&stations, transport_type, skip_types_join)
+                .await?;
+            for (key, lines) in group.into_iter().zip(lines) {
+                out.insert(key.clone(), Arc::new(lines));
+            }
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 引いたキーの並びを覚える。13 を含む一括は失敗させ、0 は見つからない扱いにする。
+    /// 途中で 1 回実行を譲り、引いている最中に他のフィールドが割り込めるようにする。
+    #[derive(Default)]
+    struct Recording {
+        batches: Mutex<Vec<Vec<u32>>>,
+    }
+
+    #[async_trait]
+    impl Loader for Recording {
+        type Key = u32;
+        type Value = String;
+
+        async fn load(&self, keys: &[u32]) -> Result<HashMap<u32, String>, UseCaseError> {
+            self.batches.lock().unwrap().push(keys.to_vec());
+            YieldNow(false).await;
+            if keys.contains(&13) {
+                return Err(UseCaseError::Unexpected("13 は引けない".to_string()));
+            }
+            Ok(keys
+                .iter()
+                .filter(|&&k| k != 0)
+                .map(|&k| (k, format!("v{k}")))
+                .collect())
+        }
+    }
+
+    fn batches(loader: &DataLoader<Recording>) -> Vec<Vec<u32>> {
+        loader.loader.batches.lock().unwrap().clone()
+    }
+
+    #[tokio::test]
+    async fn sibling_keys_are_loaded_in_one_batch() {
+        let loader = DataLoader::new(Recording::default());
+        let (a, b, c) = tokio::join!(
+            loader.load_one(1),
+            loader.load_one(2),
+            loader.load_many([3, 0]),
+        );
+        assert_eq!(a.unwrap().as_deref(), Some("v1"));
+        assert_eq!(b.unwrap().as_deref(), Some("v2"));
+        let c = c.unwrap();
+        assert_eq!(c.get(&3).map(String::as_str), Some("v3"));
+        // 見つからないキーは結果に含めない
+        assert!(!c.contains_key(&0));
+        assert_eq!(batches(&loader), vec![vec![1, 2, 3, 0]]);
+
+        // 引き終えたキー (見つからなかったものも) はキャッシュから返す
+        assert_eq!(loader.load_one(2).await.unwrap().as_deref(), Some("v2"));
+        assert_eq!(loader.load_one(0).await.unwrap(), None);
+        assert_eq!(batches(&loader).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn failures_are_cached_per_key() {
+        let loader = DataLoader::new(Recording::default());
+        let (failed, sibling) = tokio::join!(loader.load_one(13), loader.load_one(5));
+        assert!(failed.is_err());
+        // 同じ一括で引いたキーは同じ失敗を受け取る
+        assert!(sibling.is_err());
+
+        // 失敗したキーは引き直さず、別のキーは改めて引く
+        assert!(loader.load_one(13).await.is_err());
+        assert_eq!(loader.load_one(6).await.unwrap().as_deref(), Some("v6"));
+        assert_eq!(batches(&loader), vec![vec![13, 5], vec![6]]);
+    }
+
+    #[tokio::test]
+    async fn concurrent_waiters_share_one_load() {
+        let loader = DataLoader::new(Recording::default());
+        // 1 を引いている最中に 1 と 2 を求める。引いている 1 は待って受け取り、
+        // 2 だけを次の一括で引く
+        let late = async {
+            YieldNow(false).await;
+            loader.load_many([1, 2]).await
+        };
+        let (a, b, late) = tokio::join!(loader.load_one(1), loader.load_one(1), late);
+        assert_eq!(a.unwrap().as_deref(), Some("v1"));
+        assert_eq!(b.unwrap().as_deref(), Some("v1"));
+        let late = late.unwrap();
+        assert_eq!(late.len(), 2);
+        assert_eq!(batches(&loader), vec![vec![1], vec![2]]);
+    }
+}
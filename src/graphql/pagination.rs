@@ -0,0 +1,307 @@
+//! routes / routeTypes のカーソルページング。
+//!
+//! 経路候補は `build_route_tree_map` の順 (line_group_cd 昇順)、列車種別は
+//! sst.id 順で返るため、どちらも「直前のページ末尾の line_group_cd」で
+//! 再開位置を一意に表せる。ページトークンはその位置と問い合わせ条件を
+//! 16 進で包んだ不透明な文字列で、クライアントは中身を解釈しない前提。
+//! routes は運転日 (`date`) で停車駅が変わるので、解いた平日・土休日の別も条件に含める。
+//!
+//! 改ざん (チェックサム不一致・別条件のトークン流用) は INVALID_PAGE_TOKEN、
+//! データ更新で再開位置の系統が消えた場合は STALE_PAGE_TOKEN として返す。
+
+use async_graphql::{Error, ErrorExtensions};
+use stationapi::domain::calendar::ServiceDay;
+
+/// pageSize の上限。これを超える指定は上限に丸める。
+pub const MAX_PAGE_SIZE: usize = 100;
+
+const TOKEN_VERSION: u8 = 2;
+
+/// トークンの用途。routes のトークンを routeTypes に渡すような流用を弾く。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PageKind {
+    Routes = 1,
+    RouteTypes = 2,
+}
+
+/// トークンを発行した問い合わせ条件。再開時に一致を検証する。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PageQuery {
+    pub kind: PageKind,
+    pub from_station_group_id: u32,
+    pub to_station_group_id: u32,
+    pub via_line_id: Option<u32>,
+    /// `date` を解いたダイヤ。日付を指定しない問い合わせは `None`。
+    pub service_day: Option<ServiceDay>,
+}
+
+/// 1 ページ分の結果と、続きがあればその再開トークン。
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_page_token: String,
+}
+
+/// pageSize を検証する。未指定と 0 は従来どおり全件を返す。
+pub fn to_page_size(value: Option<i32>) -> Result<Option<usize>, Error> {
+    match value {
+        None | Some(0) => Ok(None),
+        Some(v) if v < 0 => Err(Error::new("pageSize には 0 以上を指定してください")),
+        Some(v) => Ok(Some((v as usize).min(MAX_PAGE_SIZE))),
+    }
+}
+
+/// 全候補からトークンの位置に続く 1 ページを切り出す。
+///
+/// `key` は各要素の line_group_cd を返す。並びは呼び出しごとに同じである必要がある。
+pub fn paginate<T>(
+    items: Vec<T>,
+    key: impl Fn(&T) -> u32,
+    page_size: Option<usize>,
+    page_token: Option<&str>,
+    query: PageQuery,
+) -> Result<Page<T>, Error> {
+    let start = match page_token.filter(|t| !t.is_empty()) {
+        None => 0,
+        Some(token) => {
+            let last = decode(token, query)?;
+            match items.iter().position(|item| key(item) == last) {
+                Some(pos) => pos + 1,
+                None => return Err(stale_token_error()),
+            }
+        }
+    };
+
+    let remaining = items.len().saturating_sub(start);
+    let take = page_size.map_or(remaining, |size| size.min(remaining));
+    let items: Vec<T> = items.into_iter().skip(start).take(take).collect();
+
+    let next_page_token = match items.last() {
+        Some(last) if take < remaining => encode(query, key(last)),
+        _ => String::new(),
+    };
+
+    Ok(Page {
+        items,
+        next_page_token,
+    })
+}
+
+fn encode(query: PageQuery, last_line_group_id: u32) -> String {
+    let payload = payload(query, last_line_group_id);
+    let mut out = String::with_capacity((payload.len() + 4) * 2);
+    for byte in payload.iter().chain(fnv1a(&payload).to_le_bytes().iter()) {
+        out.push_str(&format!("{byte:02x}"));
+    }
+    out
+}
+
+fn decode(token: &str, query: PageQuery) -> Result<u32, Error> {
+    let bytes = hex_to_bytes(token).ok_or_else(invalid_token_error)?;
+    let Some((body, checksum)) = bytes.split_last_chunk::<4>() else {
+        return Err(invalid_token_error());
+    };
+    if fnv1a(body) != u32::from_le_bytes(*checksum) {
+        return Err(invalid_token_error());
+    }
+    let Some((_, last)) = body.split_last_chunk::<4>() else {
+        return Err(invalid_token_error());
+    };
+    let last = u32::from_le_bytes(*last);
+    // 同じ条件で発行したトークンなら payload はバイト列として一致する
+    if body != payload(query, last).as_slice() {
+        return Err(invalid_token_error());
+    }
+    Ok(last)
+}
+
+/// version, kind, from, to, via の有無と値, ダイヤ, 末尾の line_group_cd の順に並べる
+fn payload(query: PageQuery, last_line_group_id: u32) -> Vec<u8> {
+    let mut out = Vec::with_capacity(24);
+    out.push(TOKEN_VERSION);
+    out.push(query.kind as u8);
+    out.extend_from_slice(&query.from_station_group_id.to_le_bytes());
+    out.extend_from_slice(&query.to_station_group_id.to_le_bytes());
+    out.push(u8::from(query.via_line_id.is_some()));
+    out.extend_from_slice(&query.via_line_id.unwrap_or(0).to_le_bytes());
+    out.push(match query.service_day {
+        None => 0,
+        Some(ServiceDay::Weekday) => 1,
+        Some(ServiceDay::Holiday) => 2,
+    });
+    out.extend_from_slice(&last_line_group_id.to_le_bytes());
+    out
+}
+
+fn hex_to_bytes(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) || !s.is_ascii() {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).ok())
+        .collect()
+}
+
+fn fnv1a(bytes: &[u8]) -> u32 {
+    const FNV_OFFSET_BASIS: u32 = 2_166_136_261;
+    const FNV_PRIME: u32 = 16_777_619;
+
+    let mut hash = FNV_OFFSET_BASIS;
+    for byte in bytes {
+        hash ^= u32::from(*byte);
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+fn invalid_token_error() -> Error {
+    Error::new("pageToken が不正です。最初のページから取得し直してください")
+        .extend_with(|_, e| e.set("code", "INVALID_PAGE_TOKEN"))
+}
+
+fn stale_token_error() -> Error {
+    Error::new(
+        "pageToken の位置がデータ更新により失われました。最初のページから取得し直してください",
+    )
+    .extend_with(|_, e| e.set("code", "STALE_PAGE_TOKEN"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn routes(service_day: Option<ServiceDay>) -> PageQuery {
+        PageQuery {
+            kind: PageKind::Routes,
+            from_station_group_id: 1130101,
+            to_station_group_id: 1130208,
+            via_line_id: None,
+            service_day,
+        }
+    }
+
+    fn code(error: &Error) -> Option<String> {
+        let value = error.extensions.as_ref()?.get("code")?;
+        Some(value.to_string().trim_matches('"').to_string())
+    }
+
+    fn page(
+        items: &[u32],
+        page_size: usize,
+        token: Option<&str>,
+        query: PageQuery,
+    ) -> Result<Page<u32>, Error> {
+        paginate(items.to_vec(), |v| *v, Some(page_size), token, query)
+    }
+
+    #[test]
+    fn pages_round_trip_until_the_last_one() {
+        let items = [10, 20, 30, 40, 50];
+        let query = routes(None);
+        let mut seen = Vec::new();
+        let mut token: Option<String> = None;
+        loop {
+            let p = page(&items, 2, token.as_deref(), query).unwrap();
+            seen.extend(p.items);
+            if p.next_page_token.is_empty() {
+                break;
+            }
+            assert_eq!(decode(&p.next_page_token, query).ok(), seen.last().copied());
+            token = Some(p.next_page_token);
+        }
+        assert_eq!(seen, items);
+    }
+
+    #[test]
+    fn last_page_has_an_empty_token() {
+        let query = routes(None);
+        assert!(page(&[1, 2], 2, None, query)
+            .unwrap()
+            .next_page_token
+            .is_empty());
+        assert!(page(&[1, 2], 5, None, query)
+            .unwrap()
+            .next_page_token
+            .is_empty());
+        assert!(page(&[], 5, None, query)
+            .unwrap()
+            .next_page_token
+            .is_empty());
+        // 空のトークンは最初のページとして扱う
+        assert_eq!(page(&[1, 2], 1, Some(""), query).unwrap().items, vec![1]);
+    }
+
+    #[test]
+    fn tampered_token_is_invalid() {
+        let query = routes(None);
+        let token = page(&[1, 2, 3], 1, None, query).unwrap().next_page_token;
+        // 再開位置の 1 バイトを書き換えるとチェックサムが合わなくなる
+        let position = token.len() - 16;
+        let mut tampered = token.clone();
+        let digit = if &token[position..position + 1] == "0" {
+            "1"
+        } else {
+            "0"
+        };
+        tampered.replace_range(position..position + 1, digit);
+        for bad in [tampered.as_str(), "zz", "abc", &token[..token.len() - 2]] {
+            let error = page(&[1, 2, 3], 1, Some(bad), query).err().unwrap();
+            assert_eq!(code(&error).as_deref(), Some("INVALID_PAGE_TOKEN"), "{bad}");
+        }
+    }
+
+    #[test]
+    fn token_cannot_be_reused_for_another_query() {
+        let query = routes(None);
+        let token = page(&[1, 2, 3], 1, None, query).unwrap().next_page_token;
+        let others = [
+            PageQuery {
+                kind: PageKind::RouteTypes,
+                ..query
+            },
+            PageQuery {
+                to_station_group_id: 1130205,
+                ..query
+            },
+            PageQuery {
+                via_line_id: Some(11302),
+                ..query
+            },
+        ];
+        for other in others {
+            let error = page(&[1, 2, 3], 1, Some(&token), other).err().unwrap();
+            assert_eq!(code(&error).as_deref(), Some("INVALID_PAGE_TOKEN"));
+        }
+    }
+
+    #[test]
+    fn token_for_a_vanished_position_is_stale() {
+        let query = routes(None);
+        let token = page(&[1, 2, 3], 1, None, query).unwrap().next_page_token;
+        let error = page(&[2, 3], 1, Some(&token), query).err().unwrap();
+        assert_eq!(code(&error).as_deref(), Some("STALE_PAGE_TOKEN"));
+    }
+
+    #[test]
+    fn token_from_another_service_day_is_invalid() {
+        let weekday = routes(Some(ServiceDay::Weekday));
+        let first = paginate(vec![1u32, 2, 3], |v| *v, Some(1), None, weekday).unwrap();
+        let token = first.next_page_token;
+
+        let same = paginate(vec![1u32, 2, 3], |v| *v, Some(1), Some(&token), weekday).unwrap();
+        assert_eq!(same.items, vec![2]);
+
+        for other in [Some(ServiceDay::Holiday), None] {
+            let Err(error) = paginate(
+                vec![1u32, 2, 3],
+                |v| *v,
+                Some(1),
+                Some(&token),
+                routes(other),
+            ) else {
+                panic!("{other:?} のトークンとして通った");
+            };
+            assert_eq!(code(&error).as_deref(), Some("INVALID_PAGE_TOKEN"));
+        }
+    }
+}
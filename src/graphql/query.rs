@@ -1,16 +1,26 @@
-//! Query リゾルバ。公開スキーマの 18 クエリを提供する。
+//! Query リゾルバ。公開スキーマの 21 クエリを提供する。
 //!
 //! 各リゾルバは UseCase 層を呼び、返ってきた domain エンティティを
-//! モデルへ変換してから GraphQL 型にする。モデルを経由するのは、
-//! IPA や TTS セグメントの計算が use_case の DTO 側にあるため。
+//! GraphQL 型にする。駅一覧は駅の行と引いた条件だけを渡し、路線や
+//! 列車種別は選ばれたときに各フィールドのリゾルバが引く (`types` を参照)。
 
-use async_graphql::{Context, Object, Result as GqlResult};
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use async_graphql::{Context, ErrorExtensions, Object, Result as GqlResult};
+use stationapi::domain::calendar::{Date, ServiceDay};
 use stationapi::domain::entity::gtfs::TransportTypeFilter;
+use stationapi::domain::geo::BoundingBox;
+use stationapi::domain::timetable::parse_clock;
 use stationapi::model;
-use stationapi::use_case::traits::query::QueryUseCase;
+use stationapi::use_case::interactor::query::{
+    STATIONS_IN_BOUNDS_DEFAULT_LIMIT, STATIONS_IN_BOUNDS_MAX_LIMIT,
+};
+use stationapi::use_case::traits::query::{QueryUseCase, StationList};
 
-use super::enums::TransportType as GqlTransportType;
-use super::scalar::UInt32;
+use super::enums::{LineType as GqlLineType, TransportType as GqlTransportType};
+use super::limits::QueryLimits;
+use super::pagination::{paginate, to_page_size, PageKind, PageQuery, MAX_PAGE_SIZE};
 use super::types::*;
 use crate::Interactor;
 
@@ -55,18 +65,100 @@ fn to_opt_id(value: Option<i32>, name: &str) -> Result<Option<u32>, async_graphq
     value.map(|v| to_id(v, name)).transpose()
 }
 
-fn use_case<'a>(ctx: &Context<'a>) -> &'a Interactor {
+/// ID の配列。1 件ごとに索引を引くので、件数も上限で抑える。
+fn to_ids(
+    ctx: &Context<'_>,
+    values: Vec<i32>,
+    name: &str,
+) -> Result<Vec<u32>, async_graphql::Error> {
+    let max = ctx.data_unchecked::<QueryLimits>().max_list_len;
+    if values.len() > max {
+        return Err(async_graphql::Error::new(format!(
+            "{name} は {max} 件までです ({} 件指定されました)",
+            values.len()
+        ))
+        .extend_with(|_, e| e.set("code", "LIST_TOO_LONG")));
+    }
+    values.into_iter().map(|v| to_id(v, name)).collect()
+}
+
+// 重いリゾルバの複雑さ。返す件数の見込みに子の複雑さを掛ける
+
+/// 1 路線あたりの駅数の見込み。
+const STATIONS_PER_LINE: usize = 30;
+/// connectedRoutes が返す経路数の見込み。
+const CONNECTED_ROUTES_ESTIMATE: usize = 20;
+
+/// routes の 1 ページの件数。pageSize 未指定は全件なので上限で見積もる。
+fn routes_page_cost(page_size: Option<i32>) -> usize {
+    match page_size {
+        Some(v) if v > 0 => (v as usize).min(MAX_PAGE_SIZE),
+        _ => MAX_PAGE_SIZE,
+    }
+}
+
+/// stationsInBounds の件数。UseCase 側と同じく未指定は既定件数、上限で切る。
+fn bounds_cost(limit: Option<i32>) -> usize {
+    limit
+        .map_or(STATIONS_IN_BOUNDS_DEFAULT_LIMIT, |v| v.max(0) as u32)
+        .min(STATIONS_IN_BOUNDS_MAX_LIMIT) as usize
+}
+
+pub(super) fn use_case<'a>(ctx: &Context<'a>) -> &'a Interactor {
     ctx.data_unchecked::<Interactor>()
 }
 
-/// domain エンティティ列を GraphQL の Station 列へ変換する
-fn stations_to_gql(stations: Vec<stationapi::domain::entity::station::Station>) -> Vec<Station> {
-    stations
+/// `asOf` を読む。書式は departures の `date` と同じ。
+fn to_as_of(value: Option<String>) -> Result<Option<Date>, async_graphql::Error> {
+    value
+        .map(|v| {
+            Date::parse(&v)
+                .ok_or_else(|| async_graphql::Error::new("asOf は YYYY-MM-DD で指定してください"))
+        })
+        .transpose()
+}
+
+/// 運転日 `date` を平日・土休日のダイヤに読み替える。
+fn to_service_day(value: Option<String>) -> Result<Option<ServiceDay>, async_graphql::Error> {
+    value
+        .map(|v| {
+            Date::parse(&v)
+                .map(ServiceDay::of)
+                .ok_or_else(|| async_graphql::Error::new("date は YYYY-MM-DD で指定してください"))
+        })
+        .transpose()
+}
+
+/// 日付を指定したときは、その日付で引く UseCase を使う。`as_of` は駅・路線の
+/// 開業・廃止、`service_day` は平日のみ・土休日のみの停車の判定に効く。
+fn use_case_for<'a>(
+    ctx: &Context<'a>,
+    as_of: Option<Date>,
+    service_day: Option<ServiceDay>,
+) -> Cow<'a, Interactor> {
+    if as_of.is_none() && service_day.is_none() {
+        Cow::Borrowed(use_case(ctx))
+    } else {
+        Cow::Owned(crate::interactor_for(as_of, service_day))
+    }
+}
+
+/// 駅一覧を GraphQL の Station 列へ変換する。乗り入れ路線も `as_of` の日付で引く
+fn stations_to_gql(mut found: StationList, as_of: Option<Date>) -> Vec<Station> {
+    found.scope.as_of = as_of;
+    let scope = Arc::new(found.scope);
+    found
+        .stations
         .into_iter()
-        .map(|s| Station::from(model::Station::from(s)))
+        .map(|s| Station::from_row(s, &scope))
         .collect()
 }
 
+/// 路線の行を GraphQL の Line へ変換する。駅一覧から辿った路線ではないので停車駅は無い
+fn line_to_gql(line: stationapi::domain::entity::line::Line) -> Line {
+    Line::from_entity(line, &Arc::default())
+}
+
 pub struct QueryRoot;
 
 #[Object(name = "Query")]
@@ -76,11 +168,13 @@ impl QueryRoot {
         ctx: &Context<'_>,
         id: i32,
         transport_type: Option<GqlTransportType>,
+        as_of: Option<String>,
     ) -> GqlResult<Option<Station>> {
-        let found = use_case(ctx)
+        let as_of = to_as_of(as_of)?;
+        let found = use_case_for(ctx, as_of, None)
             .find_station_by_id(to_id(id, "id")?, to_filter(transport_type))
             .await?;
-        Ok(found.map(|s| Station::from(model::Station::from(s))))
+        Ok(stations_to_gql(found, as_of).into_iter().next())
     }
 
     async fn stations(
@@ -88,15 +182,14 @@ impl QueryRoot {
         ctx: &Context<'_>,
         ids: Vec<i32>,
         transport_type: Option<GqlTransportType>,
+        as_of: Option<String>,
     ) -> GqlResult<Vec<Station>> {
-        let ids: Vec<u32> = ids
-            .into_iter()
-            .map(|v| to_id(v, "ids"))
-            .collect::<Result<_, _>>()?;
-        let found = use_case(ctx)
+        let as_of = to_as_of(as_of)?;
+        let ids = to_ids(ctx, ids, "ids")?;
+        let found = use_case_for(ctx, as_of, None)
             .get_stations_by_id_vec(&ids, to_filter(transport_type))
             .await?;
-        Ok(stations_to_gql(found))
+        Ok(stations_to_gql(found, as_of))
     }
 
     async fn stations_nearby(
@@ -106,8 +199,10 @@ impl QueryRoot {
         longitude: f64,
         limit: Option<i32>,
         transport_type: Option<GqlTransportType>,
+        as_of: Option<String>,
     ) -> GqlResult<Vec<Station>> {
-        let found = use_case(ctx)
+        let as_of = to_as_of(as_of)?;
+        let found = use_case_for(ctx, as_of, None)
             .get_stations_by_coordinates(
                 latitude,
                 longitude,
@@ -115,7 +210,41 @@ impl QueryRoot {
                 to_filter(transport_type),
             )
             .await?;
-        Ok(stations_to_gql(found))
+        Ok(stations_to_gql(found, as_of))
+    }
+
+    #[graphql(complexity = "bounds_cost(limit) * child_complexity")]
+    #[allow(clippy::too_many_arguments)]
+    async fn stations_in_bounds(
+        &self,
+        ctx: &Context<'_>,
+        min_lat: f64,
+        min_lon: f64,
+        max_lat: f64,
+        max_lon: f64,
+        line_type: Option<GqlLineType>,
+        transport_type: Option<GqlTransportType>,
+        limit: Option<i32>,
+        dedupe_by_group: Option<bool>,
+        as_of: Option<String>,
+    ) -> GqlResult<Vec<Station>> {
+        let as_of = to_as_of(as_of)?;
+        let Some(bounds) = BoundingBox::new(min_lat, min_lon, max_lat, max_lon) else {
+            return Err(async_graphql::Error::new(
+                "minLat/minLon/maxLat/maxLon は緯度経度の範囲内で、min <= max にしてください",
+            ));
+        };
+        // enum の並びはモデルの line_type と同じ
+        let found = use_case_for(ctx, as_of, None)
+            .get_stations_in_bounds(
+                bounds,
+                line_type.map(|t| t as i32),
+                to_limit(limit)?,
+                dedupe_by_group.unwrap_or(false),
+                to_filter(transport_type),
+            )
+            .await?;
+        Ok(stations_to_gql(found, as_of))
     }
 
     async fn stations_by_name(
@@ -125,8 +254,10 @@ impl QueryRoot {
         limit: Option<i32>,
         from_station_group_id: Option<i32>,
         transport_type: Option<GqlTransportType>,
+        as_of: Option<String>,
     ) -> GqlResult<Vec<Station>> {
-        let found = use_case(ctx)
+        let as_of = to_as_of(as_of)?;
+        let found = use_case_for(ctx, as_of, None)
             .get_stations_by_name(
                 name,
                 to_limit(limit)?,
@@ -134,7 +265,7 @@ impl QueryRoot {
                 to_filter(transport_type),
             )
             .await?;
-        Ok(stations_to_gql(found))
+        Ok(stations_to_gql(found, as_of))
     }
 
     async fn station_group_stations(
@@ -142,11 +273,13 @@ impl QueryRoot {
         ctx: &Context<'_>,
         group_id: i32,
         transport_type: Option<GqlTransportType>,
+        as_of: Option<String>,
     ) -> GqlResult<Vec<Station>> {
-        let found = use_case(ctx)
+        let as_of = to_as_of(as_of)?;
+        let found = use_case_for(ctx, as_of, None)
             .get_stations_by_group_id(to_id(group_id, "groupId")?, to_filter(transport_type))
             .await?;
-        Ok(stations_to_gql(found))
+        Ok(stations_to_gql(found, as_of))
     }
 
     async fn line_group_stations(
@@ -156,33 +289,47 @@ impl QueryRoot {
         // NOTE: 公開スキーマにはあるが UseCase 側に対応する引数が無い
         #[graphql(name = "directionId")] _direction_id: Option<i32>,
         transport_type: Option<GqlTransportType>,
+        as_of: Option<String>,
+        // 運転日。平日のみ・土休日のみの停車をその日のダイヤで解く
+        date: Option<String>,
     ) -> GqlResult<Vec<Station>> {
-        let found = use_case(ctx)
+        let as_of = to_as_of(as_of)?;
+        let service_day = to_service_day(date)?;
+        let mut found = use_case_for(ctx, as_of, service_day)
             .get_stations_by_line_group_id(
                 to_id(line_group_id, "lineGroupId")?,
                 to_filter(transport_type),
             )
             .await?;
-        Ok(stations_to_gql(found))
+        found.scope.service_day = service_day;
+        Ok(stations_to_gql(found, as_of))
     }
 
-    async fn line(&self, ctx: &Context<'_>, line_id: i32) -> GqlResult<Option<Line>> {
-        let found = use_case(ctx)
+    async fn line(
+        &self,
+        ctx: &Context<'_>,
+        line_id: i32,
+        as_of: Option<String>,
+    ) -> GqlResult<Option<Line>> {
+        let as_of = to_as_of(as_of)?;
+        let found = use_case_for(ctx, as_of, None)
             .find_line_by_id(to_id(line_id, "lineId")?)
             .await?;
-        Ok(found.map(|l| Line::from(model::Line::from(l))))
+        Ok(found.map(line_to_gql))
     }
 
-    async fn lines(&self, ctx: &Context<'_>, line_ids: Vec<i32>) -> GqlResult<Vec<Line>> {
-        let ids: Vec<u32> = line_ids
-            .into_iter()
-            .map(|v| to_id(v, "lineIds"))
-            .collect::<Result<_, _>>()?;
-        let found = use_case(ctx).get_lines_by_id_vec(&ids).await?;
-        Ok(found
-            .into_iter()
-            .map(|l| Line::from(model::Line::from(l)))
-            .collect())
+    async fn lines(
+        &self,
+        ctx: &Context<'_>,
+        line_ids: Vec<i32>,
+        as_of: Option<String>,
+    ) -> GqlResult<Vec<Line>> {
+        let as_of = to_as_of(as_of)?;
+        let ids = to_ids(ctx, line_ids, "lineIds")?;
+        let found = use_case_for(ctx, as_of, None)
+            .get_lines_by_id_vec(&ids)
+            .await?;
+        Ok(found.into_iter().map(line_to_gql).collect())
     }
 
     async fn lines_by_name(
@@ -190,14 +337,13 @@ impl QueryRoot {
         ctx: &Context<'_>,
         name: String,
         limit: Option<i32>,
+        as_of: Option<String>,
     ) -> GqlResult<Vec<Line>> {
-        let found = use_case(ctx)
+        let as_of = to_as_of(as_of)?;
+        let found = use_case_for(ctx, as_of, None)
             .get_lines_by_name(name, limit.map(|v| v as u32))
             .await?;
-        Ok(found
-            .into_iter()
-            .map(|l| Line::from(model::Line::from(l)))
-            .collect())
+        Ok(found.into_iter().map(line_to_gql).collect())
     }
 
     async fn line_stations(
@@ -207,8 +353,10 @@ impl QueryRoot {
         station_id: Option<i32>,
         direction_id: Option<i32>,
         transport_type: Option<GqlTransportType>,
+        as_of: Option<String>,
     ) -> GqlResult<Vec<Station>> {
-        let found = use_case(ctx)
+        let as_of = to_as_of(as_of)?;
+        let found = use_case_for(ctx, as_of, None)
             .get_stations_by_line_id(
                 to_id(line_id, "lineId")?,
                 to_opt_id(station_id, "stationId")?,
@@ -216,23 +364,23 @@ impl QueryRoot {
                 to_filter(transport_type),
             )
             .await?;
-        Ok(stations_to_gql(found))
+        Ok(stations_to_gql(found, as_of))
     }
 
+    #[graphql(complexity = "line_ids.len() * STATIONS_PER_LINE * child_complexity")]
     async fn line_list_stations(
         &self,
         ctx: &Context<'_>,
         line_ids: Vec<i32>,
         transport_type: Option<GqlTransportType>,
+        as_of: Option<String>,
     ) -> GqlResult<Vec<Station>> {
-        let ids: Vec<u32> = line_ids
-            .into_iter()
-            .map(|v| to_id(v, "lineIds"))
-            .collect::<Result<_, _>>()?;
-        let found = use_case(ctx)
+        let as_of = to_as_of(as_of)?;
+        let ids = to_ids(ctx, line_ids, "lineIds")?;
+        let found = use_case_for(ctx, as_of, None)
             .get_stations_by_line_id_vec(&ids, to_filter(transport_type))
             .await?;
-        Ok(stations_to_gql(found))
+        Ok(stations_to_gql(found, as_of))
     }
 
     async fn line_group_list_stations(
@@ -240,15 +388,14 @@ impl QueryRoot {
         ctx: &Context<'_>,
         line_group_ids: Vec<i32>,
         transport_type: Option<GqlTransportType>,
+        as_of: Option<String>,
     ) -> GqlResult<Vec<Station>> {
-        let ids: Vec<u32> = line_group_ids
-            .into_iter()
-            .map(|v| to_id(v, "lineGroupIds"))
-            .collect::<Result<_, _>>()?;
-        let found = use_case(ctx)
+        let as_of = to_as_of(as_of)?;
+        let ids = to_ids(ctx, line_group_ids, "lineGroupIds")?;
+        let found = use_case_for(ctx, as_of, None)
             .get_stations_by_line_group_id_vec(&ids, to_filter(transport_type))
             .await?;
-        Ok(stations_to_gql(found))
+        Ok(stations_to_gql(found, as_of))
     }
 
     async fn station_train_types(
@@ -265,26 +412,45 @@ impl QueryRoot {
             .collect())
     }
 
+    #[graphql(complexity = "routes_page_cost(page_size) * child_complexity")]
+    #[allow(clippy::too_many_arguments)]
     async fn routes(
         &self,
         ctx: &Context<'_>,
         from_station_group_id: i32,
         to_station_group_id: i32,
         via_line_id: Option<i32>,
-        // NOTE: ページングは未対応。公開スキーマに合わせて引数だけ受ける
-        #[graphql(name = "pageSize")] _page_size: Option<i32>,
-        #[graphql(name = "pageToken")] _page_token: Option<String>,
+        page_size: Option<i32>,
+        page_token: Option<String>,
+        date: Option<String>,
     ) -> GqlResult<RoutePage> {
-        let found = use_case(ctx)
+        let service_day = to_service_day(date)?;
+        let query = PageQuery {
+            kind: PageKind::Routes,
+            from_station_group_id: to_id(from_station_group_id, "fromStationGroupId")?,
+            to_station_group_id: to_id(to_station_group_id, "toStationGroupId")?,
+            via_line_id: to_opt_id(via_line_id, "viaLineId")?,
+            service_day,
+        };
+        let page_size = to_page_size(page_size)?;
+        let found = use_case_for(ctx, None, service_day)
             .get_routes(
-                to_id(from_station_group_id, "fromStationGroupId")?,
-                to_id(to_station_group_id, "toStationGroupId")?,
-                to_opt_id(via_line_id, "viaLineId")?,
+                query.from_station_group_id,
+                query.to_station_group_id,
+                query.via_line_id,
             )
             .await?;
+        // Route.id は系統 (line_group_cd) で、build_route_tree_map の昇順に並ぶ
+        let page = paginate(
+            found,
+            |route| route.id,
+            page_size,
+            page_token.as_deref(),
+            query,
+        )?;
         Ok(RoutePage {
-            routes: Some(found.into_iter().map(Into::into).collect()),
-            next_page_token: Some(String::new()),
+            routes: Some(page.items.into_iter().map(Into::into).collect()),
+            next_page_token: Some(page.next_page_token),
         })
     }
 
@@ -294,27 +460,50 @@ impl QueryRoot {
         from_station_group_id: i32,
         to_station_group_id: i32,
         via_line_id: Option<i32>,
-        #[graphql(name = "pageSize")] _page_size: Option<i32>,
-        #[graphql(name = "pageToken")] _page_token: Option<String>,
+        page_size: Option<i32>,
+        page_token: Option<String>,
     ) -> GqlResult<RouteTypePage> {
+        let query = PageQuery {
+            kind: PageKind::RouteTypes,
+            from_station_group_id: to_id(from_station_group_id, "fromStationGroupId")?,
+            to_station_group_id: to_id(to_station_group_id, "toStationGroupId")?,
+            via_line_id: to_opt_id(via_line_id, "viaLineId")?,
+            service_day: None,
+        };
+        let page_size = to_page_size(page_size)?;
         let found = use_case(ctx)
             .get_train_types(
-                to_id(from_station_group_id, "fromStationGroupId")?,
-                to_id(to_station_group_id, "toStationGroupId")?,
-                to_opt_id(via_line_id, "viaLineId")?,
+                query.from_station_group_id,
+                query.to_station_group_id,
+                query.via_line_id,
             )
             .await?;
+        // 列車種別は系統ごとに 1 件へ畳まれているので line_group_cd で位置を表せる。
+        // 系統を持たない種別は再開位置を表せない (0 を共有すると別の種別と取り違える)
+        // ので出さない
+        let found: Vec<_> = found
+            .into_iter()
+            .filter(|t| t.line_group_cd.is_some())
+            .collect();
+        let page = paginate(
+            found,
+            |t| t.line_group_cd.unwrap_or_default() as u32,
+            page_size,
+            page_token.as_deref(),
+            query,
+        )?;
         Ok(RouteTypePage {
             train_types: Some(
-                found
+                page.items
                     .into_iter()
                     .map(|t| TrainType::from(model::TrainType::from(t)))
                     .collect(),
             ),
-            next_page_token: Some(String::new()),
+            next_page_token: Some(page.next_page_token),
         })
     }
 
+    #[graphql(complexity = "CONNECTED_ROUTES_ESTIMATE * child_complexity")]
     async fn connected_routes(
         &self,
         ctx: &Context<'_>,
@@ -330,6 +519,69 @@ impl QueryRoot {
         Ok(found.into_iter().map(Into::into).collect())
     }
 
+    async fn journeys(
+        &self,
+        ctx: &Context<'_>,
+        from_station_group_id: i32,
+        to_station_group_id: i32,
+        depart_after_minutes: Option<i32>,
+        max_transfers: Option<i32>,
+    ) -> GqlResult<Vec<Journey>> {
+        let found = use_case(ctx)
+            .get_journeys(
+                to_id(from_station_group_id, "fromStationGroupId")?,
+                to_id(to_station_group_id, "toStationGroupId")?,
+                to_opt_id(depart_after_minutes, "departAfterMinutes")?,
+                to_opt_id(max_transfers, "maxTransfers")?,
+            )
+            .await?;
+        Ok(found.into_iter().map(Into::into).collect())
+    }
+
+    async fn compare_train_types(
+        &self,
+        ctx: &Context<'_>,
+        line_group_id_a: i32,
+        line_group_id_b: i32,
+        // 運転日。平日のみ・土休日のみの停車をその日のダイヤで解く
+        date: Option<String>,
+    ) -> GqlResult<TrainTypeComparison> {
+        let found = use_case_for(ctx, None, to_service_day(date)?)
+            .compare_train_types(
+                to_id(line_group_id_a, "lineGroupIdA")?,
+                to_id(line_group_id_b, "lineGroupIdB")?,
+            )
+            .await?;
+        Ok(found.into())
+    }
+
+    async fn departures(
+        &self,
+        ctx: &Context<'_>,
+        station_id: i32,
+        date: String,
+        after_time: Option<String>,
+        limit: Option<i32>,
+    ) -> GqlResult<Vec<Departure>> {
+        let date = Date::parse(&date)
+            .ok_or_else(|| async_graphql::Error::new("date は YYYY-MM-DD で指定してください"))?;
+        let after_minutes = match after_time.as_deref() {
+            None => 0,
+            Some(v) => parse_clock(v).ok_or_else(|| {
+                async_graphql::Error::new("afterTime は HH:MM (00:00〜23:59) で指定してください")
+            })?,
+        };
+        let found = use_case(ctx)
+            .get_departures(
+                to_id(station_id, "stationId")?,
+                date,
+                after_minutes,
+                to_limit(limit)?,
+            )
+            .await?;
+        Ok(found.into_iter().map(Into::into).collect())
+    }
+
     async fn estimate_arrival_times(
         &self,
         ctx: &Context<'_>,
@@ -337,13 +589,11 @@ impl QueryRoot {
         to_station_id: i32,
         via_line_ids: Option<Vec<i32>>,
         direction_id: Option<i32>,
+        date: Option<String>,
     ) -> GqlResult<EstimatedArrivalPage> {
-        let via: Vec<u32> = via_line_ids
-            .unwrap_or_default()
-            .into_iter()
-            .map(|v| to_id(v, "viaLineIds"))
-            .collect::<Result<_, _>>()?;
-        let stops = use_case(ctx)
+        let service_day = to_service_day(date)?;
+        let via = to_ids(ctx, via_line_ids.unwrap_or_default(), "viaLineIds")?;
+        let stops = use_case_for(ctx, None, service_day)
             .estimate_route_arrival_times(
                 to_id(from_station_id, "fromStationId")?,
                 to_id(to_station_id, "toStationId")?,
@@ -354,17 +604,11 @@ impl QueryRoot {
 
         // presentation 層と同じ畳み込み: line_group_cd が連続する区間を 1 ルートにまとめる
         let mut routes: Vec<EstimatedArrivalRoute> = Vec::new();
-        for stop in &stops {
-            let gql_stop = EstimatedArrivalStop {
-                station_id: Some(stop.station_cd),
-                station_group_id: Some(UInt32(stop.station_g_cd as u32)),
-                cumulative_minutes: Some(stop.cumulative_minutes),
-                stops_here: Some(stop.stops_here),
-                departure_cumulative_minutes: Some(stop.departure_cumulative_minutes),
-            };
+        for stop in stops {
             let route_id = stop.line_group_cd.unwrap_or(0);
             let merge = stop.line_group_cd.is_some()
                 && routes.last().is_some_and(|r| r.id == Some(route_id));
+            let gql_stop = EstimatedArrivalStop::from(stop);
 
             if merge {
                 if let Some(last) = routes.last_mut() {
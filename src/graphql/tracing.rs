@@ -0,0 +1,286 @@
+//! 操作ごとの所要時間の記録。
+//!
+//! 1 操作につき 1 行、操作名・所要時間・コールドスタートか・エラー数を
+//! JSON でログに出す。バッチで送られた POST は操作の数だけ行を出し、
+//! コールドスタートはそのうち最初に実行した操作にだけ付ける。デバッグ用ヘッダ (`TRACE_HEADER`) が付いたリクエストでは、
+//! 解析・検証・実行の各段階とフィールドごとの所要時間を応答の
+//! `extensions.tracing` にも載せる。
+//!
+//! フィールドの時間は子のフィールドの解決を含む。索引 (`OnceLock`) の初期化は
+//! 最初にそれを触ったフィールドの時間に乗るので、コールドスタートの応答で
+//! どのフィールドが重いかを見れば初期化と本来の処理を切り分けられる。
+//!
+//! Workers の時計は Spectre 対策で I/O を挟むまで進まないため、本番では
+//! CPU だけを使う区間が 0 ms に見えることがある。`wrangler dev` などでは
+//! 実時間で測れる。
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use async_graphql::extensions::{
+    Extension, ExtensionContext, ExtensionFactory, NextExecute, NextParseQuery, NextRequest,
+    NextResolve, NextValidation, ResolveInfo,
+};
+use async_graphql::parser::types::{DocumentOperations, ExecutableDocument};
+use async_graphql::{
+    QueryPathSegment, Response, ServerError, ServerResult, ValidationResult, Value, Variables,
+};
+use serde_json::json;
+
+/// これが付いたリクエストだけ `extensions.tracing` を返す (値は問わない)。
+pub const TRACE_HEADER: &str = "x-stationapi-trace";
+
+/// 応答に載せるフィールドの種類数の上限。重い順に残す。
+const MAX_FIELDS: usize = 50;
+
+/// 1 リクエスト分の設定。スキーマはリクエストごとに組み立てるので、
+/// そのつど作って `build_schema` に渡す。
+#[derive(Clone, Copy)]
+pub struct Tracing {
+    /// ミリ秒単位の現在時刻。Workers では `Date.now()` を渡す。
+    pub clock: fn() -> f64,
+    /// 構造化ログ 1 行の出力先。
+    pub log: fn(&str),
+    /// この isolate で最初の操作なら true を返す。操作ごとに 1 回呼び、
+    /// 2 回目以降は false を返すもの (Workers では isolate の静的変数) を渡す。
+    pub cold_start: fn() -> bool,
+    /// `extensions.tracing` を応答に載せるか。
+    pub expose: bool,
+}
+
+impl ExtensionFactory for Tracing {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(TracingExtension {
+            config: *self,
+            state: Mutex::new(State::default()),
+        })
+    }
+}
+
+#[derive(Default)]
+struct State {
+    operation_name: Option<String>,
+    cold_start: bool,
+    parse_ms: f64,
+    validation_ms: f64,
+    execution_ms: f64,
+    /// `型.フィールド` ごとの集計。
+    fields: BTreeMap<String, FieldTiming>,
+}
+
+#[derive(Default)]
+struct FieldTiming {
+    calls: u32,
+    total_ms: f64,
+    max_ms: f64,
+}
+
+struct TracingExtension {
+    config: Tracing,
+    state: Mutex<State>,
+}
+
+impl TracingExtension {
+    fn now(&self) -> f64 {
+        (self.config.clock)()
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        // 中でパニックしても計測を諦めるだけなので、毒化は無視して続ける
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn summary(&self, duration_ms: f64, errors: usize) -> serde_json::Value {
+        let state = self.state();
+        json!({
+            "event": "graphql",
+            "operationName": state.operation_name,
+            "durationMs": duration_ms,
+            "coldStart": state.cold_start,
+            "errors": errors,
+        })
+    }
+
+    fn trace(&self, duration_ms: f64) -> serde_json::Value {
+        let state = self.state();
+        let mut fields: Vec<(&String, &FieldTiming)> = state.fields.iter().collect();
+        fields.sort_by(|a, b| b.1.total_ms.total_cmp(&a.1.total_ms).then(a.0.cmp(b.0)));
+        let omitted = fields.len().saturating_sub(MAX_FIELDS);
+        let resolvers: Vec<serde_json::Value> = fields
+            .into_iter()
+            .take(MAX_FIELDS)
+            .map(|(field, t)| {
+                json!({
+                    "field": field,
+                    "calls": t.calls,
+                    "totalMs": t.total_ms,
+                    "maxMs": t.max_ms,
+                })
+            })
+            .collect();
+        json!({
+            "durationMs": duration_ms,
+            "coldStart": state.cold_start,
+            "parseMs": state.parse_ms,
+            "validationMs": state.validation_ms,
+            "executionMs": state.execution_ms,
+            "resolvers": resolvers,
+            "omittedResolvers": omitted,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl Extension for TracingExtension {
+    async fn request(&self, ctx: &ExtensionContext<'_>, next: NextRequest<'_>) -> Response {
+        // バッチの操作は順に実行されるので、最初の操作だけがコールドスタートを引き受ける
+        self.state().cold_start = (self.config.cold_start)();
+        let start = self.now();
+        let mut response = next.run(ctx).await;
+        let duration_ms = self.now() - start;
+
+        let summary = self.summary(duration_ms, response.errors.len());
+        (self.config.log)(&summary.to_string());
+
+        if self.config.expose {
+            if let Ok(trace) = Value::from_json(self.trace(duration_ms)) {
+                response = response.extension("tracing", trace);
+            }
+        }
+        response
+    }
+
+    async fn parse_query(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        query: &str,
+        variables: &Variables,
+        next: NextParseQuery<'_>,
+    ) -> ServerResult<ExecutableDocument> {
+        let start = self.now();
+        let result = next.run(ctx, query, variables).await;
+        let mut state = self.state();
+        state.parse_ms = self.now() - start;
+        // operationName を省いた名前付きクエリも名前で記録する
+        if let Ok(ExecutableDocument {
+            operations: DocumentOperations::Multiple(operations),
+            ..
+        }) = &result
+        {
+            if operations.len() == 1 {
+                state.operation_name = operations.keys().next().map(|name| name.to_string());
+            }
+        }
+        drop(state);
+        result
+    }
+
+    async fn validation(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        next: NextValidation<'_>,
+    ) -> Result<ValidationResult, Vec<ServerError>> {
+        let start = self.now();
+        let result = next.run(ctx).await;
+        self.state().validation_ms = self.now() - start;
+        result
+    }
+
+    async fn execute(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        operation_name: Option<&str>,
+        next: NextExecute<'_>,
+    ) -> Response {
+        if let Some(name) = operation_name {
+            self.state().operation_name = Some(name.to_string());
+        }
+        let start = self.now();
+        let response = next.run(ctx, operation_name).await;
+        self.state().execution_ms = self.now() - start;
+        response
+    }
+
+    async fn resolve(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        info: ResolveInfo<'_>,
+        next: NextResolve<'_>,
+    ) -> ServerResult<Option<Value>> {
+        // 応答に載せないならフィールド単位の集計は要らない。リストの要素は
+        // 親のフィールドの時間に含まれるので数えない
+        let list_item = matches!(info.path_node.segment, QueryPathSegment::Index(_));
+        if !self.config.expose || info.is_for_introspection || list_item {
+            return next.run(ctx, info).await;
+        }
+        let field = format!("{}.{}", info.parent_type, info.name);
+        let start = self.now();
+        let result = next.run(ctx, info).await;
+        let elapsed = self.now() - start;
+
+        let mut state = self.state();
+        let timing = state.fields.entry(field).or_default();
+        timing.calls += 1;
+        timing.total_ms += elapsed;
+        timing.max_ms = timing.max_ms.max(elapsed);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use async_graphql::{BatchRequest, Request};
+
+    use super::*;
+    use crate::graphql::{build_schema, limits::QueryLimits};
+
+    static LINES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+    static WARM: AtomicBool = AtomicBool::new(false);
+
+    fn tracing() -> Tracing {
+        Tracing {
+            clock: || 0.0,
+            log: |line| LINES.lock().unwrap().push(line.to_string()),
+            cold_start: || !WARM.swap(true, Ordering::Relaxed),
+            expose: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn batch_logs_each_operation_and_only_the_first_is_cold() {
+        let batch = BatchRequest::Batch(vec![
+            Request::new("query First { station(id: 1130205) { id } }"),
+            Request::new("query Second { station(id: 1130205) { id } }"),
+            Request::new("query Third { station(id: 1130205) { id } }"),
+        ]);
+        build_schema(
+            crate::interactor_for(None, None),
+            QueryLimits::default(),
+            Some(tracing()),
+        )
+        .execute_batch(batch)
+        .await;
+
+        let lines: Vec<serde_json::Value> = LINES
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let logged: Vec<(&str, bool)> = lines
+            .iter()
+            .map(|line| {
+                (
+                    line["operationName"].as_str().unwrap(),
+                    line["coldStart"].as_bool().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            logged,
+            [("First", true), ("Second", false), ("Third", false)]
+        );
+    }
+}
@@ -4,16 +4,45 @@
 //! 同一構造の型を 2 つ持つ。SDL を一致させる必要があるので、こちらも
 //! マクロで同じ定義から 2 つの型を作る。
 //!
-//! 値は `stationapi::model` の型から変換する。IPA や TTS セグメントは
-//! use_case の DTO が計算しているので、domain エンティティから直接ではなく
-//! モデルを経由することでそのロジックをそのまま使える。
+//! スカラーのフィールドは作るときに埋め、路線・会社・列車種別・IPA などの
+//! 入れ子のフィールドはリゾルバで解決する。駅一覧のクエリは駅の行だけを
+//! 受け取り、入れ子のフィールドが選ばれたときだけ `loader` で引く。
+//! 経路系のクエリは UseCase が組み立てたモデルをそのまま持ち、要求された
+//! 部分だけを GraphQL の型へ変換する。
 
-use async_graphql::SimpleObject;
+use std::sync::Arc;
+
+use async_graphql::{ComplexObject, Context, Result as GqlResult, SimpleObject};
+use stationapi::domain::announcement::{announcement_ssml, AnnouncedStation, AnnouncementLang};
+use stationapi::domain::arrival_estimation::EstimatedStop;
+use stationapi::domain::entity::connection;
+use stationapi::domain::entity::gtfs::TransportTypeFilter;
+use stationapi::domain::ipa::{
+    compute_ipa_cached, compute_line_ipa_cached, station_name_to_accented_ipa, IpaResult,
+};
+use stationapi::domain::journey;
+use stationapi::domain::romaji::romaji_display_name_in;
+use stationapi::domain::stop_pattern;
+use stationapi::domain::timetable;
 use stationapi::model;
+use stationapi::use_case::dto::tts::to_tts_segments;
+use stationapi::use_case::traits::query::{QueryUseCase, StationScope};
 
 use super::enums::*;
+use super::loader::{
+    CompanyLoader, DataLoader, StationLinesKey, StationLinesLoader, TrainTypeLoader,
+};
+use super::query::use_case;
 use super::scalar::UInt32;
 
+/// UseCase が返す domain エンティティ。GraphQL の同名の型と区別する。
+mod entity {
+    pub use stationapi::domain::entity::gtfs::TransportType;
+    pub use stationapi::domain::entity::line::Line;
+    pub use stationapi::domain::entity::station::Station;
+    pub use stationapi::domain::entity::train_type::TrainType;
+}
+
 #[derive(SimpleObject, Clone)]
 #[graphql(name = "TtsSegment")]
 pub struct TtsSegment {
@@ -110,16 +139,58 @@ impl From<model::Company> for Company {
     }
 }
 
+/// 駅の入れ子のフィールド (路線・列車種別・駅ナンバリング・IPA) をどこから解決するか。
+#[derive(Clone)]
+enum StationSource {
+    /// UseCase が組み立て済みのモデル。経路系のクエリはこちら。
+    Model(Arc<model::Station>),
+    /// 駅の行だけ。入れ子のフィールドは要求されたときに引く。
+    /// 路線の下にぶら下がる駅 (`nested`) は、これまでどおり路線を持たない。
+    Row {
+        row: Arc<entity::Station>,
+        scope: Arc<StationScope>,
+        nested: bool,
+    },
+}
+
+/// 一覧を引いた条件に従って、駅の列車種別を決める。
+async fn row_train_type(
+    ctx: &Context<'_>,
+    row: &entity::Station,
+    scope: &StationScope,
+) -> GqlResult<Option<entity::TrainType>> {
+    let loader = ctx.data_unchecked::<DataLoader<TrainTypeLoader>>();
+    if !scope.line_group_ids.is_empty() {
+        // 複数系統の一覧は、挙げた系統の種別のうち id が最大のもの
+        let found = loader
+            .load_many(
+                scope
+                    .line_group_ids
+                    .iter()
+                    .map(|&line_group_cd| (row.station_cd, line_group_cd)),
+            )
+            .await?;
+        return Ok(found.into_values().max_by_key(|t| t.id));
+    }
+    if let Some(line_group_cd) = scope.line_group_id {
+        if let Some(found) = loader.load_one((row.station_cd, line_group_cd)).await? {
+            return Ok(Some(found));
+        }
+    }
+    Ok(row.train_type.as_deref().cloned())
+}
+
 /// Station と StationNested は同一構造。SDL を合わせるため両方を定義する。
 macro_rules! define_station {
     ($ident:ident, $name:literal) => {
         #[derive(SimpleObject, Clone)]
-        #[graphql(name = $name)]
+        #[graphql(name = $name, complex)]
         pub struct $ident {
             pub id: Option<i32>,
             pub group_id: Option<UInt32>,
             pub name: Option<String>,
             pub name_katakana: Option<String>,
+            #[graphql(skip)]
             pub name_roman: Option<String>,
             pub name_chinese: Option<String>,
             pub name_korean: Option<String>,
@@ -132,17 +203,12 @@ macro_rules! define_station {
             pub opened_at: Option<String>,
             pub closed_at: Option<String>,
             pub status: Option<OperationStatus>,
-            pub station_numbers: Option<Vec<StationNumber>>,
             pub stop_condition: Option<StopCondition>,
             pub distance: Option<f64>,
             pub has_train_types: Option<bool>,
-            pub train_type: Option<Box<TrainTypeNested>>,
-            pub lines: Option<Vec<LineNested>>,
-            pub line: Option<Box<LineNested>>,
             pub transport_type: Option<TransportType>,
-            pub name_ipa: Option<String>,
-            pub name_roman_ipa: Option<String>,
-            pub name_tts_segments: Option<Vec<TtsSegment>>,
+            #[graphql(skip)]
+            source: StationSource,
         }
 
         impl From<model::Station> for $ident {
@@ -150,43 +216,231 @@ macro_rules! define_station {
                 Self {
                     id: Some(v.id as i32),
                     group_id: Some(UInt32(v.group_id)),
-                    name: Some(v.name),
-                    name_katakana: Some(v.name_katakana),
-                    name_roman: v.name_roman,
-                    name_chinese: v.name_chinese,
-                    name_korean: v.name_korean,
-                    three_letter_code: v.three_letter_code,
+                    name: Some(v.name.clone()),
+                    name_katakana: Some(v.name_katakana.clone()),
+                    name_roman: v.name_roman.clone(),
+                    name_chinese: v.name_chinese.clone(),
+                    name_korean: v.name_korean.clone(),
+                    three_letter_code: v.three_letter_code.clone(),
                     prefecture_id: Some(v.prefecture_id as i32),
-                    postal_code: Some(v.postal_code),
-                    address: Some(v.address),
+                    postal_code: Some(v.postal_code.clone()),
+                    address: Some(v.address.clone()),
                     latitude: Some(v.latitude),
                     longitude: Some(v.longitude),
-                    opened_at: Some(v.opened_at),
-                    closed_at: Some(v.closed_at),
+                    opened_at: Some(v.opened_at.clone()),
+                    closed_at: Some(v.closed_at.clone()),
                     status: Some(OperationStatus::from(v.status)),
-                    station_numbers: Some(v.station_numbers.into_iter().map(Into::into).collect()),
                     stop_condition: Some(StopCondition::from(v.stop_condition)),
                     distance: v.distance,
                     has_train_types: v.has_train_types,
-                    train_type: v.train_type.map(|t| Box::new((*t).into())),
-                    lines: Some(v.lines.into_iter().map(Into::into).collect()),
-                    line: v.line.map(|l| Box::new((*l).into())),
                     transport_type: Some(TransportType::from(v.transport_type)),
-                    name_ipa: v.name_ipa,
-                    name_roman_ipa: v.name_roman_ipa,
-                    name_tts_segments: Some(
-                        v.name_tts_segments.into_iter().map(Into::into).collect(),
-                    ),
+                    source: StationSource::Model(Arc::new(v)),
                 }
             }
         }
+
+        impl $ident {
+            fn row(row: Arc<entity::Station>, scope: Arc<StationScope>, nested: bool) -> Self {
+                Self {
+                    id: Some(row.station_cd),
+                    group_id: Some(UInt32(row.station_g_cd as u32)),
+                    name: Some(row.station_name.clone()),
+                    name_katakana: Some(row.station_name_k.clone()),
+                    name_roman: row.station_name_r.clone(),
+                    name_chinese: row.station_name_zh.clone(),
+                    name_korean: row.station_name_ko.clone(),
+                    three_letter_code: row.three_letter_code.clone(),
+                    prefecture_id: Some(row.pref_cd),
+                    postal_code: Some(row.post.clone()),
+                    address: Some(row.address.clone()),
+                    latitude: Some(row.lat),
+                    longitude: Some(row.lon),
+                    opened_at: Some(row.open_ymd.clone()),
+                    closed_at: Some(row.close_ymd.clone()),
+                    status: Some(OperationStatus::from(row.e_status)),
+                    stop_condition: Some(StopCondition::from(i32::from(row.stop_condition))),
+                    distance: row.distance,
+                    has_train_types: Some(row.has_train_types),
+                    transport_type: Some(TransportType::from(i32::from(row.transport_type))),
+                    source: StationSource::Row { row, scope, nested },
+                }
+            }
+
+            fn ipa(&self) -> Option<Arc<IpaResult>> {
+                match &self.source {
+                    StationSource::Model(_) => None,
+                    StationSource::Row { row, .. } => Some(compute_ipa_cached(
+                        &row.station_name_k,
+                        row.station_name_r.as_deref(),
+                    )),
+                }
+            }
+        }
+
+        #[ComplexObject]
+        impl $ident {
+            // system を指定すると読みからその方式で綴る。読みが無ければ収録済みの表記
+            async fn name_roman(&self, system: Option<RomanizationSystem>) -> Option<String> {
+                system
+                    .and_then(|system| {
+                        romaji_display_name_in(self.name_katakana.as_deref()?, system.into())
+                    })
+                    .or_else(|| self.name_roman.clone())
+            }
+
+            async fn station_numbers(&self, ctx: &Context<'_>) -> Option<Vec<StationNumber>> {
+                let numbers = match &self.source {
+                    StationSource::Model(v) => v.station_numbers.clone(),
+                    StationSource::Row { row, .. } => use_case(ctx)
+                        .get_station_numbers(row)
+                        .into_iter()
+                        .map(Into::into)
+                        .collect(),
+                };
+                Some(numbers.into_iter().map(Into::into).collect())
+            }
+
+            async fn train_type(
+                &self,
+                ctx: &Context<'_>,
+            ) -> GqlResult<Option<Box<TrainTypeNested>>> {
+                let found = match &self.source {
+                    StationSource::Model(v) => v.train_type.clone().map(|t| *t),
+                    StationSource::Row { row, scope, .. } => {
+                        row_train_type(ctx, row, scope).await?.map(Into::into)
+                    }
+                };
+                Ok(found.map(|t| Box::new(t.into())))
+            }
+
+            async fn lines(&self, ctx: &Context<'_>) -> GqlResult<Option<Vec<LineNested>>> {
+                let (row, scope) = match &self.source {
+                    StationSource::Model(v) => {
+                        return Ok(Some(v.lines.iter().cloned().map(Into::into).collect()));
+                    }
+                    StationSource::Row { nested: true, .. } => return Ok(Some(vec![])),
+                    StationSource::Row { row, scope, .. } => (row, scope),
+                };
+                let key = StationLinesKey {
+                    station: row.clone(),
+                    transport_type: scope.transport_type,
+                    skip_types_join: scope.skip_types_join,
+                    as_of: scope.as_of,
+                    service_day: scope.service_day,
+                };
+                let lines = ctx
+                    .data_unchecked::<DataLoader<StationLinesLoader>>()
+                    .load_one(key)
+                    .await?
+                    .unwrap_or_default();
+                Ok(Some(
+                    lines
+                        .iter()
+                        .map(|l| LineNested::from_entity(l.clone(), scope))
+                        .collect(),
+                ))
+            }
+
+            async fn line(&self, ctx: &Context<'_>) -> Option<Box<LineNested>> {
+                match &self.source {
+                    StationSource::Model(v) => v.line.clone().map(|l| Box::new((*l).into())),
+                    StationSource::Row { nested: true, .. } => None,
+                    StationSource::Row { row, scope, .. } => {
+                        let mut line = use_case(ctx).extract_line_from_station(row);
+                        line.station = Some((**row).clone());
+                        Some(Box::new(LineNested::from_entity(line, scope)))
+                    }
+                }
+            }
+
+            async fn name_ipa(&self) -> Option<String> {
+                match &self.source {
+                    StationSource::Model(v) => v.name_ipa.clone(),
+                    StationSource::Row { .. } => self.ipa()?.name_ipa.clone(),
+                }
+            }
+
+            // 日本語音声向けに、name_ipa へアクセントの下がり目 (ꜜ) を付けたもの
+            async fn name_ipa_accented(&self) -> Option<String> {
+                station_name_to_accented_ipa(self.name.as_deref()?, self.name_katakana.as_deref()?)
+            }
+
+            async fn name_roman_ipa(&self) -> Option<String> {
+                match &self.source {
+                    StationSource::Model(v) => v.name_roman_ipa.clone(),
+                    StationSource::Row { .. } => self.ipa()?.name_roman_ipa.clone(),
+                }
+            }
+
+            async fn name_tts_segments(&self) -> Option<Vec<TtsSegment>> {
+                let segments = match &self.source {
+                    StationSource::Model(v) => v.name_tts_segments.clone(),
+                    StationSource::Row { .. } => to_tts_segments(&self.ipa()?.tts_segments),
+                };
+                Some(segments.into_iter().map(Into::into).collect())
+            }
+
+            // 案内放送の SSML。lang は ja-JP か en-US
+            async fn announcement_ssml(
+                &self,
+                ctx: &Context<'_>,
+                lang: String,
+                template: AnnouncementTemplate,
+            ) -> GqlResult<Option<String>> {
+                let lang = AnnouncementLang::parse(&lang).ok_or_else(|| {
+                    async_graphql::Error::new("lang は ja-JP か en-US で指定してください")
+                })?;
+                let (Some(name), Some(name_katakana)) = (&self.name, &self.name_katakana) else {
+                    return Ok(None);
+                };
+                let station_numbers: Vec<String> = match &self.source {
+                    StationSource::Model(v) => v
+                        .station_numbers
+                        .iter()
+                        .map(|n| n.station_number.clone())
+                        .collect(),
+                    StationSource::Row { row, .. } => use_case(ctx)
+                        .get_station_numbers(row)
+                        .into_iter()
+                        .map(|n| n.station_number)
+                        .collect(),
+                };
+                let station = AnnouncedStation {
+                    name,
+                    name_katakana,
+                    name_roman: self.name_roman.as_deref(),
+                    station_numbers: &station_numbers,
+                };
+                Ok(Some(announcement_ssml(&station, lang, template.into())))
+            }
+
+            // 駅グループ単位の徒歩連絡。要求されたときだけ引く
+            async fn transfers(&self, ctx: &Context<'_>) -> GqlResult<Vec<StationTransfer>> {
+                let Some(UInt32(group_id)) = self.group_id else {
+                    return Ok(vec![]);
+                };
+                let found = use_case(ctx).get_station_transfers(group_id).await?;
+                Ok(found.into_iter().map(Into::into).collect())
+            }
+        }
     };
 }
 
+/// 路線の入れ子のフィールド (会社・停車駅・路線記号・IPA) をどこから解決するか。
+#[derive(Clone)]
+enum LineSource {
+    Model(Arc<model::Line>),
+    /// 路線の行。会社はローダーで引き、停車駅は `scope` に従って解決する。
+    Row {
+        line: Arc<entity::Line>,
+        scope: Arc<StationScope>,
+    },
+}
+
 macro_rules! define_line {
     ($ident:ident, $name:literal) => {
         #[derive(SimpleObject, Clone)]
-        #[graphql(name = $name)]
+        #[graphql(name = $name, complex)]
         pub struct $ident {
             pub id: Option<i32>,
             pub name_short: Option<String>,
@@ -197,52 +451,164 @@ macro_rules! define_line {
             pub name_korean: Option<String>,
             pub color: Option<String>,
             pub line_type: Option<LineType>,
-            pub line_symbols: Option<Vec<LineSymbol>>,
             pub status: Option<OperationStatus>,
-            pub station: Option<Box<StationNested>>,
-            pub company: Option<Company>,
-            pub train_type: Option<Box<TrainTypeNested>>,
             pub average_distance: Option<f64>,
             pub transport_type: Option<TransportType>,
-            pub name_ipa: Option<String>,
-            pub name_roman_ipa: Option<String>,
-            pub name_tts_segments: Option<Vec<TtsSegment>>,
+            #[graphql(skip)]
+            source: LineSource,
         }
 
         impl From<model::Line> for $ident {
             fn from(v: model::Line) -> Self {
                 Self {
                     id: Some(v.id as i32),
-                    name_short: Some(v.name_short),
-                    name_katakana: Some(v.name_katakana),
-                    name_full: Some(v.name_full),
-                    name_roman: v.name_roman,
-                    name_chinese: v.name_chinese,
-                    name_korean: v.name_korean,
-                    color: Some(v.color),
+                    name_short: Some(v.name_short.clone()),
+                    name_katakana: Some(v.name_katakana.clone()),
+                    name_full: Some(v.name_full.clone()),
+                    name_roman: v.name_roman.clone(),
+                    name_chinese: v.name_chinese.clone(),
+                    name_korean: v.name_korean.clone(),
+                    color: Some(v.color.clone()),
                     line_type: Some(LineType::from(v.line_type)),
-                    line_symbols: Some(v.line_symbols.into_iter().map(Into::into).collect()),
                     status: Some(OperationStatus::from(v.status)),
-                    station: v.station.map(|s| Box::new((*s).into())),
-                    company: v.company.map(Into::into),
-                    train_type: v.train_type.map(|t| Box::new((*t).into())),
                     average_distance: Some(v.average_distance),
                     transport_type: Some(TransportType::from(v.transport_type)),
-                    name_ipa: v.name_ipa,
-                    name_roman_ipa: v.name_roman_ipa,
-                    name_tts_segments: Some(
-                        v.name_tts_segments.into_iter().map(Into::into).collect(),
-                    ),
+                    source: LineSource::Model(Arc::new(v)),
+                }
+            }
+        }
+
+        impl $ident {
+            /// 路線の行から作る。スカラーの変換は use_case の DTO と揃える。
+            pub fn from_entity(line: entity::Line, scope: &Arc<StationScope>) -> Self {
+                // バス路線の line_type は OtherLineType (0) に倒す
+                let line_type = if line.transport_type == entity::TransportType::Bus {
+                    0
+                } else {
+                    line.line_type.unwrap_or_default()
+                };
+                Self {
+                    id: Some(line.line_cd),
+                    name_short: Some(line.line_name.clone()),
+                    name_katakana: Some(line.line_name_k.clone()),
+                    name_full: Some(line.line_name_h.clone()),
+                    name_roman: Some(line.line_name_r.clone().unwrap_or_default()),
+                    name_chinese: line.line_name_zh.clone(),
+                    name_korean: line.line_name_ko.clone(),
+                    color: Some(line.line_color_c.clone().unwrap_or_default()),
+                    line_type: Some(LineType::from(line_type)),
+                    status: Some(OperationStatus::from(line.e_status)),
+                    average_distance: Some(line.average_distance.unwrap_or(0.0)),
+                    transport_type: Some(TransportType::from(i32::from(line.transport_type))),
+                    source: LineSource::Row {
+                        line: Arc::new(line),
+                        scope: scope.clone(),
+                    },
+                }
+            }
+
+            fn ipa(&self) -> Option<Arc<IpaResult>> {
+                match &self.source {
+                    LineSource::Model(_) => None,
+                    LineSource::Row { line, .. } => Some(compute_line_ipa_cached(
+                        &line.line_name_k,
+                        line.line_name_r.as_deref(),
+                    )),
                 }
             }
         }
+
+        #[ComplexObject]
+        impl $ident {
+            async fn line_symbols(&self, ctx: &Context<'_>) -> Option<Vec<LineSymbol>> {
+                let symbols = match &self.source {
+                    LineSource::Model(v) => v.line_symbols.clone(),
+                    LineSource::Row { line, .. } => use_case(ctx)
+                        .get_line_symbols(line)
+                        .into_iter()
+                        .map(Into::into)
+                        .collect(),
+                };
+                Some(symbols.into_iter().map(Into::into).collect())
+            }
+
+            async fn station(&self) -> Option<Box<StationNested>> {
+                match &self.source {
+                    LineSource::Model(v) => v.station.clone().map(|s| Box::new((*s).into())),
+                    LineSource::Row { line, scope } => {
+                        let station = line.station.clone()?;
+                        Some(Box::new(StationNested::row(
+                            Arc::new(station),
+                            scope.clone(),
+                            true,
+                        )))
+                    }
+                }
+            }
+
+            async fn company(&self, ctx: &Context<'_>) -> GqlResult<Option<Company>> {
+                let found = match &self.source {
+                    LineSource::Model(v) => v.company.clone(),
+                    LineSource::Row { line, .. } => ctx
+                        .data_unchecked::<DataLoader<CompanyLoader>>()
+                        .load_one(line.company_cd)
+                        .await?
+                        .map(Into::into),
+                };
+                Ok(found.map(Into::into))
+            }
+
+            async fn train_type(&self) -> Option<Box<TrainTypeNested>> {
+                let found = match &self.source {
+                    LineSource::Model(v) => v.train_type.clone().map(|t| *t),
+                    LineSource::Row { line, .. } => line.train_type.clone().map(Into::into),
+                };
+                found.map(|t| Box::new(t.into()))
+            }
+
+            async fn name_ipa(&self) -> Option<String> {
+                match &self.source {
+                    LineSource::Model(v) => v.name_ipa.clone(),
+                    LineSource::Row { .. } => self.ipa()?.name_ipa.clone(),
+                }
+            }
+
+            async fn name_roman_ipa(&self) -> Option<String> {
+                match &self.source {
+                    LineSource::Model(v) => v.name_roman_ipa.clone(),
+                    LineSource::Row { .. } => self.ipa()?.name_roman_ipa.clone(),
+                }
+            }
+
+            async fn name_tts_segments(&self) -> Option<Vec<TtsSegment>> {
+                let segments = match &self.source {
+                    LineSource::Model(v) => v.name_tts_segments.clone(),
+                    LineSource::Row { .. } => to_tts_segments(&self.ipa()?.tts_segments),
+                };
+                Some(segments.into_iter().map(Into::into).collect())
+            }
+
+            // 直通運転でつながる路線。要求されたときだけ引く
+            async fn through_service_lines(&self, ctx: &Context<'_>) -> GqlResult<Vec<LineNested>> {
+                let Some(line_id) = self.id else {
+                    return Ok(vec![]);
+                };
+                let found = use_case(ctx)
+                    .get_through_service_lines(line_id as u32)
+                    .await?;
+                Ok(found
+                    .into_iter()
+                    .map(|line| LineNested::from_entity(line, &Arc::default()))
+                    .collect())
+            }
+        }
     };
 }
 
 macro_rules! define_train_type {
     ($ident:ident, $name:literal) => {
         #[derive(SimpleObject, Clone)]
-        #[graphql(name = $name)]
+        #[graphql(name = $name, complex)]
         pub struct $ident {
             pub id: Option<i32>,
             pub type_id: Option<i32>,
@@ -253,13 +619,14 @@ macro_rules! define_train_type {
             pub name_chinese: Option<String>,
             pub name_korean: Option<String>,
             pub color: Option<String>,
-            pub lines: Option<Vec<LineNested>>,
-            pub line: Option<Box<LineNested>>,
             pub direction: Option<TrainDirection>,
             pub kind: Option<TrainTypeKind>,
             pub name_ipa: Option<String>,
             pub name_roman_ipa: Option<String>,
             pub name_tts_segments: Option<Vec<TtsSegment>>,
+            // 停車する路線は要求されたときだけ GraphQL の型へ変換する
+            #[graphql(skip)]
+            source: Arc<model::TrainType>,
         }
 
         impl From<model::TrainType> for $ident {
@@ -268,29 +635,68 @@ macro_rules! define_train_type {
                     id: Some(v.id as i32),
                     type_id: Some(v.type_id as i32),
                     group_id: Some(UInt32(v.group_id)),
-                    name: Some(v.name),
-                    name_katakana: Some(v.name_katakana),
-                    name_roman: v.name_roman,
-                    name_chinese: v.name_chinese,
-                    name_korean: v.name_korean,
-                    color: Some(v.color),
-                    lines: Some(v.lines.into_iter().map(Into::into).collect()),
-                    line: v.line.map(|l| Box::new((*l).into())),
+                    name: Some(v.name.clone()),
+                    name_katakana: Some(v.name_katakana.clone()),
+                    name_roman: v.name_roman.clone(),
+                    name_chinese: v.name_chinese.clone(),
+                    name_korean: v.name_korean.clone(),
+                    color: Some(v.color.clone()),
                     direction: Some(TrainDirection::from(v.direction)),
                     kind: Some(TrainTypeKind::from(v.kind)),
-                    name_ipa: v.name_ipa,
-                    name_roman_ipa: v.name_roman_ipa,
+                    name_ipa: v.name_ipa.clone(),
+                    name_roman_ipa: v.name_roman_ipa.clone(),
                     name_tts_segments: Some(
-                        v.name_tts_segments.into_iter().map(Into::into).collect(),
+                        v.name_tts_segments
+                            .iter()
+                            .cloned()
+                            .map(Into::into)
+                            .collect(),
                     ),
+                    source: Arc::new(v),
                 }
             }
         }
+
+        #[ComplexObject]
+        impl $ident {
+            async fn lines(&self) -> Option<Vec<LineNested>> {
+                Some(self.source.lines.iter().cloned().map(Into::into).collect())
+            }
+
+            async fn line(&self) -> Option<Box<LineNested>> {
+                self.source.line.clone().map(|l| Box::new((*l).into()))
+            }
+
+            // 系統が路線をまたいで直通するところ (駅列の順)
+            async fn through_services(&self, ctx: &Context<'_>) -> GqlResult<Vec<ThroughService>> {
+                let Some(UInt32(group_id)) = self.group_id.filter(|id| id.0 != 0) else {
+                    return Ok(vec![]);
+                };
+                let found = use_case(ctx).get_through_services(group_id).await?;
+                // UseCase が駅を引いた条件
+                let scope = Arc::new(StationScope {
+                    transport_type: TransportTypeFilter::RailAndBus,
+                    ..Default::default()
+                });
+                Ok(found
+                    .into_iter()
+                    .map(|v| ThroughService::from_link(v, &scope))
+                    .collect())
+            }
+        }
     };
 }
 
 define_station!(Station, "Station");
 define_station!(StationNested, "StationNested");
+
+impl Station {
+    /// 駅の行から作る。入れ子のフィールドは `scope` に従って後から引く。
+    pub fn from_row(row: entity::Station, scope: &Arc<StationScope>) -> Self {
+        Self::row(Arc::new(row), scope.clone(), false)
+    }
+}
+
 define_line!(Line, "Line");
 define_line!(LineNested, "LineNested");
 define_train_type!(TrainType, "TrainType");
@@ -336,6 +742,18 @@ pub struct EstimatedArrivalStop {
     pub departure_cumulative_minutes: Option<f64>,
 }
 
+impl From<EstimatedStop> for EstimatedArrivalStop {
+    fn from(v: EstimatedStop) -> Self {
+        Self {
+            station_id: Some(v.station_cd),
+            station_group_id: Some(UInt32(v.station_g_cd as u32)),
+            cumulative_minutes: Some(v.cumulative_minutes),
+            stops_here: Some(v.stops_here),
+            departure_cumulative_minutes: Some(v.departure_cumulative_minutes),
+        }
+    }
+}
+
 #[derive(SimpleObject)]
 #[graphql(name = "EstimatedArrivalRoute")]
 pub struct EstimatedArrivalRoute {
@@ -349,6 +767,207 @@ pub struct EstimatedArrivalPage {
     pub routes: Option<Vec<EstimatedArrivalRoute>>,
 }
 
+#[derive(SimpleObject)]
+#[graphql(name = "JourneyLeg")]
+pub struct JourneyLeg {
+    pub line_group_id: Option<i32>,
+    pub line_id: Option<i32>,
+    pub transfer_minutes: Option<f64>,
+    pub departure_minutes: Option<f64>,
+    pub arrival_minutes: Option<f64>,
+    pub stops: Option<Vec<EstimatedArrivalStop>>,
+}
+
+impl From<journey::JourneyLeg> for JourneyLeg {
+    fn from(v: journey::JourneyLeg) -> Self {
+        Self {
+            line_group_id: Some(v.line_group_cd),
+            line_id: Some(v.line_cd),
+            transfer_minutes: Some(v.transfer_minutes),
+            departure_minutes: Some(v.departure_minutes),
+            arrival_minutes: Some(v.arrival_minutes),
+            stops: Some(v.stops.into_iter().map(Into::into).collect()),
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "Journey")]
+pub struct Journey {
+    pub id: Option<UInt32>,
+    pub departure_minutes: Option<f64>,
+    pub arrival_minutes: Option<f64>,
+    pub total_minutes: Option<f64>,
+    pub transfer_count: Option<i32>,
+    pub legs: Option<Vec<JourneyLeg>>,
+}
+
+impl From<journey::Journey> for Journey {
+    fn from(v: journey::Journey) -> Self {
+        Self {
+            id: Some(UInt32(v.id)),
+            departure_minutes: Some(v.departure_minutes),
+            arrival_minutes: Some(v.arrival_minutes),
+            total_minutes: Some(v.total_minutes),
+            transfer_count: Some(v.transfer_count as i32),
+            legs: Some(v.legs.into_iter().map(Into::into).collect()),
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "TrainTypeComparisonStation")]
+pub struct TrainTypeComparisonStation {
+    pub station: Option<StationNested>,
+    pub status_a: Option<StopStatus>,
+    pub status_b: Option<StopStatus>,
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "TrainTypeComparisonSummary")]
+pub struct TrainTypeComparisonSummary {
+    pub stops_both: Option<i32>,
+    pub stops_only_a: Option<i32>,
+    pub stops_only_b: Option<i32>,
+    // どちらかが走るが、どちらも止まらない駅
+    pub stops_neither: Option<i32>,
+    // 両方が走る駅
+    pub shared_stations: Option<i32>,
+}
+
+impl From<stop_pattern::StopPatternSummary> for TrainTypeComparisonSummary {
+    fn from(v: stop_pattern::StopPatternSummary) -> Self {
+        Self {
+            stops_both: Some(v.stops_both as i32),
+            stops_only_a: Some(v.stops_only_a as i32),
+            stops_only_b: Some(v.stops_only_b as i32),
+            stops_neither: Some(v.stops_neither as i32),
+            shared_stations: Some(v.shared_stations as i32),
+        }
+    }
+}
+
+// 停車駅が食い違う区間。from/to は両方が止まる駅 (駅列の端なら null)
+#[derive(SimpleObject)]
+#[graphql(name = "TrainTypeDivergence")]
+pub struct TrainTypeDivergence {
+    pub from: Option<StationNested>,
+    pub to: Option<StationNested>,
+    pub stops_only_a: Option<Vec<StationNested>>,
+    pub stops_only_b: Option<Vec<StationNested>>,
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "TrainTypeComparison")]
+pub struct TrainTypeComparison {
+    pub stations: Option<Vec<TrainTypeComparisonStation>>,
+    pub summary: Option<TrainTypeComparisonSummary>,
+    pub divergences: Option<Vec<TrainTypeDivergence>>,
+}
+
+impl From<stop_pattern::StopPatternComparison> for TrainTypeComparison {
+    fn from(v: stop_pattern::StopPatternComparison) -> Self {
+        let nested = |index: usize| -> StationNested {
+            model::Station::from(v.rows[index].station().clone()).into()
+        };
+        let divergences = v
+            .divergences
+            .iter()
+            .map(|d| TrainTypeDivergence {
+                from: d.from.map(nested),
+                to: d.to.map(nested),
+                stops_only_a: Some(d.only_a.iter().copied().map(nested).collect()),
+                stops_only_b: Some(d.only_b.iter().copied().map(nested).collect()),
+            })
+            .collect();
+        let stations = v
+            .rows
+            .iter()
+            .map(|row| TrainTypeComparisonStation {
+                station: Some(model::Station::from(row.station().clone()).into()),
+                status_a: Some(row.status_a().into()),
+                status_b: Some(row.status_b().into()),
+            })
+            .collect();
+        Self {
+            stations: Some(stations),
+            summary: Some(v.summary.into()),
+            divergences: Some(divergences),
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "ThroughService")]
+pub struct ThroughService {
+    pub from_line: Option<LineNested>,
+    pub to_line: Option<LineNested>,
+    // 直通先の路線で最初に通る駅
+    pub station: Option<StationNested>,
+}
+
+impl ThroughService {
+    /// 駅は行のまま持ち、路線などは要求されたときにローダーで引く。
+    /// 1 リクエストで多くの種別の直通を引いても、駅の路線はまとめて 1 回で済む。
+    fn from_link(v: connection::ThroughServiceLink, scope: &Arc<StationScope>) -> Self {
+        Self {
+            from_line: Some(LineNested::from_entity(v.from_line, &Arc::default())),
+            to_line: Some(LineNested::from_entity(v.to_line, &Arc::default())),
+            station: Some(StationNested::row(
+                Arc::new(v.station),
+                scope.clone(),
+                false,
+            )),
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "StationTransfer")]
+pub struct StationTransfer {
+    pub station: Option<StationNested>,
+    // 駅間の直線距離 (メートル)
+    pub distance: Option<f64>,
+    pub walking_minutes: Option<f64>,
+}
+
+impl From<connection::StationTransfer> for StationTransfer {
+    fn from(v: connection::StationTransfer) -> Self {
+        Self {
+            station: Some(model::Station::from(v.station).into()),
+            distance: Some(v.distance),
+            walking_minutes: Some(journey::walking_minutes(v.distance)),
+        }
+    }
+}
+
+// date / time は実際に発車する暦日と時刻。深夜便は serviceDate が前日になる
+#[derive(SimpleObject)]
+#[graphql(name = "Departure")]
+pub struct Departure {
+    pub station_id: Option<i32>,
+    pub line_id: Option<i32>,
+    pub headsign: Option<String>,
+    pub date: Option<String>,
+    pub time: Option<String>,
+    pub service_date: Option<String>,
+}
+
+impl From<timetable::Departure> for Departure {
+    fn from(v: timetable::Departure) -> Self {
+        let date = v.date().to_string();
+        let time = timetable::format_clock(v.minutes_of_day());
+        Self {
+            station_id: Some(v.station_cd),
+            line_id: Some(v.line_cd),
+            headsign: Some(v.headsign),
+            date: Some(date),
+            time: Some(time),
+            service_date: Some(v.service_date.to_string()),
+        }
+    }
+}
+
 #[derive(SimpleObject)]
 #[graphql(name = "TrainRouteSegment")]
 pub struct TrainRouteSegment {
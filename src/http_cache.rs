@@ -0,0 +1,146 @@
+//! GET で受けた GraphQL の HTTP キャッシュ。
+//!
+//! データはビルド時に埋め込んでいるので、同じデプロイなら同じ URL には
+//! 必ず同じ応答を返す。そこで `build.rs` が計算したデータとコードの指紋を
+//! そのまま強い ETag にし、`If-None-Match` が一致すればクエリを実行せず 304 を返す。
+
+use std::borrow::Cow;
+
+use async_graphql::Request as GqlRequest;
+use serde_json::{Map, Value};
+
+/// 埋め込んだ CSV (と sst.bin) とコードの FNV-1a。`build.rs` が出力する。
+/// データかコードのどちらかが変わったデプロイでは別の値になる。
+pub const BUILD_HASH: &str = env!("BUILD_HASH");
+
+/// ETag は URL ごとに比べられるので、ビルドの指紋だけで足りる。
+pub fn etag() -> String {
+    format!("\"{BUILD_HASH}\"")
+}
+
+/// 成功した応答に付ける。データはデプロイでしか変わらないが、デプロイ直後に
+/// 古い応答が残り続けないよう鮮度は短めにし、あとは ETag で再検証させる。
+pub const CACHE_CONTROL: &str = "public, max-age=300";
+
+/// `If-None-Match` が現在の ETag を含むか。
+///
+/// `If-None-Match` は弱い比較なので `W/` を外してから比べる。`*` は常に一致する。
+pub fn matches_if_none_match(header: &str, etag: &str) -> bool {
+    header.split(',').map(str::trim).any(|candidate| {
+        candidate == "*" || candidate.strip_prefix("W/").unwrap_or(candidate) == etag
+    })
+}
+
+/// GET のクエリ文字列 (`query` / `operationName` / `variables` / `extensions`) を
+/// GraphQL リクエストにする。
+///
+/// `query` の代わりに APQ のハッシュ (`extensions.persistedQuery.sha256Hash`) だけでもよい。
+/// 全文を送るとキャッシュの鍵が長くなるので、アプリには APQ を勧める。
+pub fn request_from_query_pairs<'a>(
+    pairs: impl IntoIterator<Item = (Cow<'a, str>, Cow<'a, str>)>,
+) -> Result<GqlRequest, String> {
+    let mut body = Map::new();
+    for (key, value) in pairs {
+        match key.as_ref() {
+            "query" | "operationName" => {
+                body.insert(key.into_owned(), Value::String(value.into_owned()));
+            }
+            "variables" | "extensions" => {
+                let parsed: Value = serde_json::from_str(&value)
+                    .map_err(|e| format!("{key} を JSON として読めません: {e}"))?;
+                body.insert(key.into_owned(), parsed);
+            }
+            _ => {}
+        }
+    }
+    let has_query = body
+        .get("query")
+        .and_then(Value::as_str)
+        .is_some_and(|query| !query.is_empty());
+    let has_hash = body
+        .get("extensions")
+        .and_then(|extensions| extensions.get("persistedQuery"))
+        .and_then(|persisted| persisted.get("sha256Hash"))
+        .is_some_and(Value::is_string);
+    if !has_query && !has_hash {
+        return Err("query か extensions.persistedQuery.sha256Hash が必要です".to_string());
+    }
+    serde_json::from_value(Value::Object(body))
+        .map_err(|e| format!("GraphQL リクエストを解釈できません: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs(items: &[(&'static str, &'static str)]) -> Result<GqlRequest, String> {
+        request_from_query_pairs(
+            items
+                .iter()
+                .map(|(key, value)| (Cow::Borrowed(*key), Cow::Borrowed(*value))),
+        )
+    }
+
+    const HASH_ONLY: &str = r#"{"persistedQuery":{"version":1,"sha256Hash":"abc"}}"#;
+
+    #[test]
+    fn if_none_match_accepts_lists_weak_tags_and_wildcard() {
+        let etag = "\"0123abcd\"";
+        assert!(matches_if_none_match("\"0123abcd\"", etag));
+        assert!(matches_if_none_match("W/\"0123abcd\"", etag));
+        assert!(matches_if_none_match("\"old\", W/\"0123abcd\"", etag));
+        assert!(matches_if_none_match("*", etag));
+        assert!(!matches_if_none_match("\"old\", W/\"older\"", etag));
+        // 引用符の無い値は別物として扱う
+        assert!(!matches_if_none_match("0123abcd", etag));
+        assert!(!matches_if_none_match("", etag));
+    }
+
+    #[test]
+    fn query_pairs_become_a_request() {
+        let request = pairs(&[
+            (
+                "query",
+                "query Station($id: Int!) { station(id: $id) { id } }",
+            ),
+            ("operationName", "Station"),
+            ("variables", r#"{"id":1130205}"#),
+            ("unknown", "ignored"),
+        ])
+        .unwrap();
+        assert!(request.query.starts_with("query Station"));
+        assert_eq!(request.operation_name.as_deref(), Some("Station"));
+        assert_eq!(
+            request.variables.get("id").map(ToString::to_string),
+            Some("1130205".to_string())
+        );
+    }
+
+    #[test]
+    fn hash_alone_is_enough() {
+        let request = pairs(&[("extensions", HASH_ONLY)]).unwrap();
+        assert!(request.query.is_empty());
+        assert!(request.extensions.contains_key("persistedQuery"));
+    }
+
+    #[test]
+    fn malformed_json_is_rejected() {
+        let error = pairs(&[("query", "{ __typename }"), ("variables", "{id:1}")]).unwrap_err();
+        assert!(error.starts_with("variables"), "{error}");
+        let error = pairs(&[("extensions", "{\"persistedQuery\":")]).unwrap_err();
+        assert!(error.starts_with("extensions"), "{error}");
+    }
+
+    #[test]
+    fn query_or_hash_is_required() {
+        assert!(pairs(&[]).is_err());
+        assert!(pairs(&[("query", "")]).is_err());
+        assert!(pairs(&[("operationName", "Station")]).is_err());
+        // sha256Hash が文字列でなければハッシュとみなさない
+        assert!(pairs(&[(
+            "extensions",
+            r#"{"persistedQuery":{"version":1,"sha256Hash":1}}"#
+        )])
+        .is_err());
+    }
+}
@@ -1,13 +1,20 @@
 //! CSV をバイナリに埋め込み、isolate 起動時に一度だけパースしてメモリに保持する。
-//! 検索は全件走査と HashMap 参照で行う。
+//! 検索は全件走査と HashMap 参照で行う。座標検索だけは固定格子の空間索引を使う。
 
+use stationapi::domain::calendar::Date;
 use stationapi::domain::entity::company::Company;
-use stationapi::domain::entity::gtfs::TransportType;
+use stationapi::domain::entity::connection::{Connection, ThroughService};
+use stationapi::domain::entity::gtfs::{GtfsCalendar, GtfsCalendarDate, TransportType};
 use stationapi::domain::entity::line::Line;
 use stationapi::domain::entity::station::Station;
-use stationapi::domain::normalize::normalize_for_search;
+use stationapi::domain::geo::BoundingBox;
+use stationapi::domain::name_search::{romaji_key, MatchKind, NameQuery, StationNames};
+use stationapi::domain::romaji::katakana_to_romaji;
+use stationapi::domain::service_period::{
+    ServicePeriod, STATUS_CLOSED, STATUS_DISABLED, STATUS_PLANNED,
+};
 use stationapi::model::StopCondition;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::OnceLock;
 
 const STATIONS_CSV: &str = include_str!(concat!(env!("OUT_DIR"), "/stations.csv"));
@@ -71,11 +78,8 @@ pub struct StationRecord {
     pub name: String,
     pub name_katakana: String,
     pub name_roman: Option<String>,
-    /// station_name_rn (マクロンを含まない綴り) を小文字化したもの。
-    /// ILIKE 相当の比較を全件走査で行うため、検索のたびに 11,148 件分
-    /// to_lowercase() を呼ばないよう索引時に持つ。応答には使わないので
-    /// 小文字版だけを保持する。
-    name_roman_lower: Option<String>,
+    /// station_name_rn (マクロンを含まない綴り)。名前検索の鍵を作るためだけに持つ。
+    name_roman_plain: Option<String>,
     pub name_chinese: Option<String>,
     pub name_korean: Option<String>,
     pub station_number1: Option<String>,
@@ -92,6 +96,8 @@ pub struct StationRecord {
     pub opened_at: String,
     pub closed_at: String,
     pub e_status: i32,
+    /// 開業日・廃止日・e_status。`asOf` を指定した問い合わせはこれで絞る。
+    pub period: ServicePeriod,
     pub e_sort: i32,
     /// 0 = 鉄道, 1 = バス。GTFS 統合後の DB から書き出した CSV に含まれる。
     /// data/*.csv にフォールバックした場合は列が無いので Rail 扱いになる。
@@ -99,6 +105,11 @@ pub struct StationRecord {
 }
 
 impl StationRecord {
+    /// `as_of` の日に営業していたか。`None` なら今の状態 (e_status = 0)。
+    pub fn is_open(&self, as_of: Option<Date>) -> bool {
+        self.period.is_open(as_of)
+    }
+
     /// 路線側の属性を埋めた Station を返す。
     /// 列車種別 (type_* / line_group_cd / pass) は UseCase 層が後から付与する。
     ///
@@ -246,13 +257,17 @@ fn build_stations() -> Vec<StationRecord> {
         ) else {
             continue;
         };
+        let opened_at = text(&r, c.at("open_ymd"));
+        let closed_at = text(&r, c.at("close_ymd"));
+        let e_status = i32_or(&r, c.at("e_status"), 0);
         out.push(StationRecord {
+            period: ServicePeriod::from_columns(&opened_at, &closed_at, e_status),
             station_cd,
             station_g_cd,
             name: text(&r, c.at("station_name")),
             name_katakana: text(&r, c.at("station_name_k")),
             name_roman: opt_text(&r, c.at("station_name_r")),
-            name_roman_lower: opt_text(&r, c.at("station_name_rn")).map(|v| v.to_lowercase()),
+            name_roman_plain: opt_text(&r, c.at("station_name_rn")),
             name_chinese: opt_text(&r, c.at("station_name_zh")),
             name_korean: opt_text(&r, c.at("station_name_ko")),
             station_number1: opt_text(&r, c.at("station_number1")),
@@ -266,9 +281,9 @@ fn build_stations() -> Vec<StationRecord> {
             address: text(&r, c.at("address")),
             lat,
             lon,
-            opened_at: text(&r, c.at("open_ymd")),
-            closed_at: text(&r, c.at("close_ymd")),
-            e_status: i32_or(&r, c.at("e_status"), 0),
+            opened_at,
+            closed_at,
+            e_status,
             e_sort: i32_or(&r, c.at("e_sort"), 0),
             transport_type: TransportType::from(i32_or(&r, c.at("transport_type"), 0)),
         });
@@ -392,6 +407,42 @@ fn build_lines() -> Vec<Line> {
     out
 }
 
+/// line_cd -> 路線の営業期間
+static LINE_PERIODS: OnceLock<HashMap<i32, ServicePeriod>> = OnceLock::new();
+
+/// `as_of` の日に営業していた路線か。`None` なら今の状態 (e_status = 0)。
+///
+/// 路線には日付の列が無いので、駅の日付から決める。廃止・無効化された路線は駅の
+/// 廃止日のうち最も遅い日、運用前の路線は駅の開業日のうち最も早い日を使う。
+/// 駅に廃止日が 1 つも無い廃止路線は廃止日不明 (`Closure::Unknown`) として
+/// 出し続け、無効化された路線は出さない (`ServicePeriod` と同じ規則)。
+pub fn line_is_open(line: &Line, as_of: Option<Date>) -> bool {
+    let Some(date) = as_of else {
+        return line.e_status == 0;
+    };
+    line_period(line.line_cd).is_some_and(|period| period.is_open_on(date))
+}
+
+/// 駅の日付から求めた路線の営業期間。
+fn line_period(line_cd: i32) -> Option<&'static ServicePeriod> {
+    let periods = LINE_PERIODS.get_or_init(|| {
+        lines()
+            .iter()
+            .map(|l| {
+                let records = || stations_by_line(l.line_cd);
+                let opened = (l.e_status == STATUS_PLANNED)
+                    .then(|| records().filter_map(|s| s.period.opened).min())
+                    .flatten();
+                let closed = matches!(l.e_status, STATUS_CLOSED | STATUS_DISABLED)
+                    .then(|| records().filter_map(|s| s.period.closed()).max())
+                    .flatten();
+                (l.line_cd, ServicePeriod::new(opened, closed, l.e_status))
+            })
+            .collect()
+    });
+    periods.get(&line_cd)
+}
+
 /// 路線が存在する駅だけを通す。lines に無い line_cd の駅は結果に出さない。
 /// 座標検索がこの条件。
 fn joins_line(record: &StationRecord) -> bool {
@@ -399,9 +450,9 @@ fn joins_line(record: &StationRecord) -> bool {
 }
 
 /// 名前検索は路線が有効であることも要求する。
-/// これを見ないと廃止路線 (例: 成田エクスプレス, e_status=3) の駅が混ざる。
-fn joins_active_line(record: &StationRecord) -> bool {
-    line_by_cd(record.line_cd).is_some_and(|l| l.e_status == 0)
+/// これを見ないと廃止路線 (例: 成田エクスプレス, e_status=3) の駅が今の状態に混ざる。
+fn joins_active_line(record: &StationRecord, as_of: Option<Date>) -> bool {
+    line_by_cd(record.line_cd).is_some_and(|l| line_is_open(l, as_of))
 }
 
 // ---------------------------------------------------------------- 事業者
@@ -451,14 +502,222 @@ fn build_companies() -> Vec<Company> {
     out
 }
 
+// ---------------------------------------------------------------- 空間索引
+
+/// 格子の 1 辺 (度)。緯度方向で約 5.6 km。都市部なら最初の 1 回で
+/// 既定の件数が集まり、数セルを見るだけで済む。
+const GRID_CELL_DEG: f64 = 0.05;
+
+/// 駅を緯度経度の固定格子に振り分けたもの。
+///
+/// `asOf` で廃止駅や運用前の駅も引けるよう、廃止日の無い無効化 (e_status = 3) 以外は載せ、
+/// 営業しているかは引くときに見る。
+///
+/// バス停は GTFS のフィードを足すほど増えるので、鉄道とは別の格子に載せる。
+/// 鉄道の近傍を引くときにバス停を数えずに済み、逆も同じ。
+struct SpatialGrid {
+    cells: HashMap<(i32, i32), Vec<usize>>,
+    /// 載っている駅の緯度の絶対値の最大。経度方向の探索幅を決めるのに使う。
+    max_abs_lat: f64,
+}
+
+/// transport_type -> 格子
+static STATION_GRIDS: OnceLock<BTreeMap<i32, SpatialGrid>> = OnceLock::new();
+
+fn station_grids() -> &'static BTreeMap<i32, SpatialGrid> {
+    STATION_GRIDS.get_or_init(|| {
+        let mut grids: BTreeMap<i32, SpatialGrid> = BTreeMap::new();
+        for (i, s) in stations().iter().enumerate() {
+            if s.period.is_hidden() {
+                continue;
+            }
+            let grid = grids
+                .entry(s.transport_type as i32)
+                .or_insert_with(|| SpatialGrid {
+                    cells: HashMap::new(),
+                    max_abs_lat: 0.0,
+                });
+            grid.cells
+                .entry(grid_cell(s.lat, s.lon))
+                .or_default()
+                .push(i);
+            grid.max_abs_lat = grid.max_abs_lat.max(s.lat.abs());
+        }
+        grids
+    })
+}
+
+fn grid_cell(lat: f64, lon: f64) -> (i32, i32) {
+    (
+        (lat / GRID_CELL_DEG).floor() as i32,
+        (lon / GRID_CELL_DEG).floor() as i32,
+    )
+}
+
+impl SpatialGrid {
+    /// 距離の近い順に `accept` を満たす駅を limit 件返す。
+    ///
+    /// 半径を倍々に広げながら `within` を引き、limit 件集まった時点で切る。
+    /// 半径 r の内側を漏れなく集めているので、その中の上位 limit 件が
+    /// 全体の上位 limit 件と一致する。
+    fn nearest(
+        &self,
+        lat: f64,
+        lon: f64,
+        limit: usize,
+        accept: impl Fn(&StationRecord) -> bool,
+    ) -> Vec<(&'static StationRecord, f64)> {
+        if limit == 0 {
+            return Vec::new();
+        }
+        let whole_sphere = std::f64::consts::PI * EARTH_RADIUS_KM;
+        let mut radius_km = GRID_CELL_DEG.to_radians() * EARTH_RADIUS_KM;
+        loop {
+            let (mut hits, scanned_all) = self.within(lat, lon, radius_km, &accept);
+            if hits.len() >= limit || radius_km >= whole_sphere {
+                hits.truncate(limit);
+                return hits;
+            }
+            // 全件を見ても足りないなら、残りは半径の外にしかない。倍々を続けずに一度で広げる
+            radius_km = if scanned_all {
+                whole_sphere
+            } else {
+                radius_km * 2.0
+            };
+        }
+    }
+
+    /// (lat, lon) から radius_km 以内で `accept` を満たす駅を距離順に返す。
+    /// 距離が同じときは CSV の行順。
+    ///
+    /// 2 つ目の値は、探索範囲のセル数が埋まっているセル数を超えたため
+    /// 全セルを走査したかどうか。
+    fn within(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+        accept: impl Fn(&StationRecord) -> bool,
+    ) -> (Vec<(&'static StationRecord, f64)>, bool) {
+        let records = stations();
+        let dlat = (radius_km / EARTH_RADIUS_KM).to_degrees();
+        let (row_lo, _) = grid_cell(lat - dlat, lon);
+        let (row_hi, _) = grid_cell(lat + dlat, lon);
+        // 日付変更線をまたぐ範囲は列の番号が連続しないので全走査に回す
+        let cols = self
+            .lon_span(lat, dlat, radius_km)
+            .filter(|dlon| lon - dlon >= -180.0 && lon + dlon <= 180.0)
+            .map(|dlon| {
+                let (_, col_lo) = grid_cell(lat, lon - dlon);
+                let (_, col_hi) = grid_cell(lat, lon + dlon);
+                (col_lo, col_hi)
+            });
+
+        let mut scored: Vec<(usize, f64)> = Vec::new();
+        let mut visit = |indices: &[usize]| {
+            for &i in indices {
+                let s = &records[i];
+                let d = haversine_km(lat, lon, s.lat, s.lon);
+                if d <= radius_km && accept(s) {
+                    scored.push((i, d));
+                }
+            }
+        };
+        let cell_count = cols.map(|(col_lo, col_hi)| {
+            (i64::from(row_hi) - i64::from(row_lo) + 1)
+                * (i64::from(col_hi) - i64::from(col_lo) + 1)
+        });
+        let scanned_all = match (cols, cell_count) {
+            (Some((col_lo, col_hi)), Some(count)) if count <= self.cells.len() as i64 => {
+                for row in row_lo..=row_hi {
+                    for col in col_lo..=col_hi {
+                        if let Some(indices) = self.cells.get(&(row, col)) {
+                            visit(indices);
+                        }
+                    }
+                }
+                false
+            }
+            // 探索範囲が広いときは、空のセルを引くより埋まっているセルを全部見るほうが速い
+            _ => {
+                for indices in self.cells.values() {
+                    visit(indices);
+                }
+                true
+            }
+        };
+
+        scored.sort_unstable_by(|a, b| {
+            a.1.partial_cmp(&b.1)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.0.cmp(&b.0))
+        });
+        let hits = scored.into_iter().map(|(i, d)| (&records[i], d)).collect();
+        (hits, scanned_all)
+    }
+
+    /// 矩形内の駅の行番号。順序は決めない。
+    fn in_bounds(&self, bounds: &BoundingBox) -> Vec<usize> {
+        let records = stations();
+        let (row_lo, col_lo) = grid_cell(bounds.min_lat, bounds.min_lon);
+        let (row_hi, col_hi) = grid_cell(bounds.max_lat, bounds.max_lon);
+        let cell_count = (i64::from(row_hi) - i64::from(row_lo) + 1)
+            * (i64::from(col_hi) - i64::from(col_lo) + 1);
+
+        let mut out = Vec::new();
+        let mut visit = |indices: &[usize]| {
+            out.extend(
+                indices
+                    .iter()
+                    .copied()
+                    .filter(|&i| bounds.contains(records[i].lat, records[i].lon)),
+            );
+        };
+        if cell_count <= self.cells.len() as i64 {
+            for row in row_lo..=row_hi {
+                for col in col_lo..=col_hi {
+                    if let Some(indices) = self.cells.get(&(row, col)) {
+                        visit(indices);
+                    }
+                }
+            }
+        } else {
+            for indices in self.cells.values() {
+                visit(indices);
+            }
+        }
+        out
+    }
+
+    /// 半径 radius_km の円が経度方向に広がる幅 (度)。経度の差が Δλ の 2 点は
+    /// haversine の式から `sin(d / 2R) >= sqrt(cos φ1 cos φ2) sin(Δλ / 2)` を満たすので、
+    /// 格子に載っている緯度の範囲で cos φ2 を下から抑えて幅を決める。
+    /// 極に届くなど幅を決められないときは `None`。
+    fn lon_span(&self, lat: f64, dlat: f64, radius_km: f64) -> Option<f64> {
+        let far_lat = (lat.abs() + dlat).min(self.max_abs_lat);
+        let cos_product = lat.to_radians().cos() * far_lat.to_radians().cos();
+        let half_angle = radius_km / (2.0 * EARTH_RADIUS_KM);
+        if cos_product <= 0.0 || half_angle >= std::f64::consts::FRAC_PI_2 {
+            return None;
+        }
+        let ratio = half_angle.sin() / cos_product.sqrt();
+        if ratio >= 1.0 {
+            return None;
+        }
+        let dlon = (2.0 * ratio.asin()).to_degrees();
+        Some(dlon)
+    }
+}
+
 // ---------------------------------------------------------------- 検索
 
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
 /// 球面距離 (km)。
 ///
 /// 度単位のユークリッド距離だと緯度と経度を同じスケールで扱うことになり、
 /// 東西方向を過大評価する。ここでは実距離で並べる。
 pub fn haversine_km(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
-    const EARTH_RADIUS_KM: f64 = 6371.0;
     let (p1, p2) = (lat1.to_radians(), lat2.to_radians());
     let dlat = (lat2 - lat1).to_radians();
     let dlon = (lon2 - lon1).to_radians();
@@ -466,17 +725,19 @@ pub fn haversine_km(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
     2.0 * EARTH_RADIUS_KM * a.sqrt().clamp(-1.0, 1.0).asin()
 }
 
-/// 全件走査で最近傍 limit 件を返す。11,148 駅なので索引なしで十分速い。
+/// 最近傍 limit 件を返す。
 ///
 /// `want` は種別の絞り込み。未指定 (RailAndBus) のときは
 /// 鉄道を先・バスを後に並べたうえで距離順になる。
+/// `as_of` の日に営業していた駅だけを数える (`None` なら e_status = 0)。
 pub fn nearest(
     lat: f64,
     lon: f64,
     limit: usize,
     want: Option<i32>,
+    as_of: Option<Date>,
 ) -> Vec<(&'static StationRecord, f64)> {
-    nearest_inner(lat, lon, limit, want, true)
+    nearest_inner(lat, lon, limit, want, as_of, true)
 }
 
 /// 路線の存在を条件にしないまま最近傍を取る。
@@ -488,8 +749,9 @@ pub fn nearest_without_line_join(
     lon: f64,
     limit: usize,
     want: Option<i32>,
+    as_of: Option<Date>,
 ) -> Vec<(&'static StationRecord, f64)> {
-    nearest_inner(lat, lon, limit, want, false)
+    nearest_inner(lat, lon, limit, want, as_of, false)
 }
 
 fn nearest_inner(
@@ -497,74 +759,132 @@ fn nearest_inner(
     lon: f64,
     limit: usize,
     want: Option<i32>,
+    as_of: Option<Date>,
     require_line: bool,
 ) -> Vec<(&'static StationRecord, f64)> {
-    let mut scored: Vec<(&StationRecord, f64)> = stations()
-        .iter()
-        .filter(|s| s.e_status == 0)
-        .filter(|s| !require_line || joins_line(s))
-        .filter(|s| want.is_none_or(|w| s.transport_type as i32 == w))
-        .map(|s| (s, haversine_km(lat, lon, s.lat, s.lon)))
-        .collect();
+    let accept = |s: &StationRecord| s.is_open(as_of) && (!require_line || joins_line(s));
+    let grids = station_grids();
+    if let Some(w) = want {
+        return grids
+            .get(&w)
+            .map(|grid| grid.nearest(lat, lon, limit, accept))
+            .unwrap_or_default();
+    }
+    // 種別ごとの格子は transport_type の昇順に並ぶので、鉄道の近傍で埋まらなかった
+    // 分だけバスで埋めれば「鉄道が先、種別内は距離順」になる
+    let mut out = Vec::new();
+    for grid in grids.values() {
+        if out.len() >= limit {
+            break;
+        }
+        out.extend(grid.nearest(lat, lon, limit - out.len(), accept));
+    }
+    out
+}
 
-    // 種別指定がある場合は第1キーが定数 0 になるので距離だけで並ぶ
-    let rank = move |s: &StationRecord| -> i32 {
-        if want.is_none() {
-            s.transport_type as i32
-        } else {
-            0
+/// 矩形内の駅を、鉄道を先・バスを後に並べたうえで矩形の中心からの距離順に返す。
+/// 距離は km。`nearest` と同じく路線の有無は見るが、路線の有効・無効は見ない。
+pub fn in_bounds(
+    bounds: &BoundingBox,
+    want: Option<i32>,
+    as_of: Option<Date>,
+) -> Vec<(&'static StationRecord, f64)> {
+    let records = stations();
+    let (center_lat, center_lon) = bounds.center();
+    let mut out = Vec::new();
+    for (&kind, grid) in station_grids() {
+        if want.is_some_and(|w| w != kind) {
+            continue;
         }
-    };
-    let cmp = move |a: &(&StationRecord, f64), b: &(&StationRecord, f64)| {
-        rank(a.0)
-            .cmp(&rank(b.0))
-            .then_with(|| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
-    };
-    // 全体ソートを避け、上位 limit 件だけを確定させる
-    if limit < scored.len() {
-        scored.select_nth_unstable_by(limit, cmp);
-        scored.truncate(limit);
+        let mut scored: Vec<(usize, f64)> = grid
+            .in_bounds(bounds)
+            .into_iter()
+            .filter(|&i| records[i].is_open(as_of) && joins_line(&records[i]))
+            .map(|i| {
+                let s = &records[i];
+                (i, haversine_km(center_lat, center_lon, s.lat, s.lon))
+            })
+            .collect();
+        scored.sort_unstable_by(|a, b| {
+            a.1.partial_cmp(&b.1)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.0.cmp(&b.0))
+        });
+        out.extend(scored.into_iter().map(|(i, d)| (&records[i], d)));
     }
-    scored.sort_unstable_by(cmp);
-    scored
+    out
 }
 
-/// 駅名・読み・ローマ字・中国語・韓国語のいずれかへの部分一致で引く。
-/// 正規化には domain 層の `normalize_for_search` を使う。
+/// 駅名・読み・ローマ字・中国語・韓国語で引き、一致の強い順に返す。
+/// 照合と一致の強さは domain 層の `NameQuery` が決める。
+///
+/// 一致の強さ (完全 > 前方 > 部分 > 近似) が同じなら、乗り入れ路線の多い駅を先にする。
+/// 「しんじゅく」で新宿三丁目より新宿を、西新宿より新宿三丁目を先に出すため。
 ///
 /// 件数の絞り込みは呼び出し側で行う。出発駅による絞り込みの後に件数を切る
 /// 必要があるため、ここで切ると結果が変わる。
-pub fn search_by_name(query: &str, want: Option<i32>) -> Vec<&'static StationRecord> {
+pub fn search_by_name(
+    query: &str,
+    want: Option<i32>,
+    as_of: Option<Date>,
+) -> Vec<&'static StationRecord> {
+    let query = NameQuery::new(query);
     if query.is_empty() {
         return Vec::new();
     }
-    // s.station_name_k LIKE $4 用: ひらがな→カタカナ、全角→半角
-    let katakana = normalize_for_search(query);
-    // s.station_name_rn ILIKE $3 用
-    let lowered = query.to_lowercase();
 
-    let mut hits: Vec<&'static StationRecord> = stations()
+    let mut hits: Vec<(MatchKind, usize, &'static StationRecord)> = stations()
         .iter()
-        .filter(|s| s.e_status == 0)
-        .filter(|s| joins_active_line(s))
-        .filter(|s| want.is_none_or(|w| s.transport_type as i32 == w))
-        .filter(|s| {
-            s.name.contains(query)
-                || s.name_roman_lower
-                    .as_deref()
-                    .is_some_and(|v| v.contains(&lowered))
-                || s.name_katakana.contains(&katakana)
-                || s.name_chinese.as_deref().is_some_and(|v| v.contains(query))
-                || s.name_korean.as_deref().is_some_and(|v| v.contains(query))
+        .zip(romaji_keys())
+        .filter(|(s, _)| s.is_open(as_of))
+        .filter(|(s, _)| joins_active_line(s, as_of))
+        .filter(|(s, _)| want.is_none_or(|w| s.transport_type as i32 == w))
+        .filter_map(|(s, key)| {
+            let kind = query.rank(&StationNames {
+                name: &s.name,
+                katakana: &s.name_katakana,
+                romaji_key: key.as_deref(),
+                chinese: s.name_chinese.as_deref(),
+                korean: s.name_korean.as_deref(),
+            })?;
+            Some((kind, line_count(s.station_g_cd, as_of), s))
         })
         .collect();
 
     hits.sort_unstable_by(|a, b| {
-        a.station_g_cd
-            .cmp(&b.station_g_cd)
-            .then_with(|| a.name.cmp(&b.name))
+        a.0.cmp(&b.0)
+            .then_with(|| b.1.cmp(&a.1))
+            .then_with(|| a.2.station_g_cd.cmp(&b.2.station_g_cd))
+            .then_with(|| a.2.name.cmp(&b.2.name))
+            .then_with(|| a.2.station_cd.cmp(&b.2.station_cd))
     });
-    hits
+    hits.into_iter().map(|(_, _, s)| s).collect()
+}
+
+/// 駅グループに乗り入れる有効な路線の数。名前検索で主要駅を先にするのに使う。
+fn line_count(station_g_cd: i32, as_of: Option<Date>) -> usize {
+    stations_by_group(station_g_cd)
+        .filter(|s| s.is_open(as_of))
+        .count()
+}
+
+/// stations() と同じ並びの、名前検索用のローマ字の鍵。
+/// 起動を重くしないよう、最初の名前検索で作る。
+static ROMAJI_KEYS: OnceLock<Vec<Option<String>>> = OnceLock::new();
+
+fn romaji_keys() -> &'static [Option<String>] {
+    ROMAJI_KEYS.get_or_init(|| {
+        stations()
+            .iter()
+            .map(|s| {
+                // ローマ字を持たない駅は読みから起こす
+                s.name_roman_plain
+                    .clone()
+                    .or_else(|| katakana_to_romaji(&s.name_katakana))
+                    .map(|romaji| romaji_key(&romaji))
+            })
+            .collect()
+    })
 }
 
 // ---------------------------------------------------------------- 列車種別
@@ -788,6 +1108,152 @@ pub fn stations_by_line(line_cd: i32) -> impl Iterator<Item = &'static StationRe
         .map(|&i| &stations()[i])
 }
 
+/// line_group_cd -> 直通 (系統の駅列で路線が変わるところ)。駅列の順。
+static THROUGH_SERVICES: OnceLock<HashMap<i32, Vec<ThroughService>>> = OnceLock::new();
+/// line_cd -> 直通でつながる line_cd (向きは問わない)。昇順。
+static THROUGH_SERVICE_LINES: OnceLock<HashMap<i32, Vec<i32>>> = OnceLock::new();
+
+fn through_services() -> &'static HashMap<i32, Vec<ThroughService>> {
+    THROUGH_SERVICES.get_or_init(|| {
+        // 系統ごとの駅列。同じ駅が路線ごとに続けて載っている行は 1 駅にまとめる
+        let mut stops: HashMap<i32, Vec<Stop>> = HashMap::new();
+        for sst in ssts() {
+            let (Some(group), Some(record)) = (sst.line_group_cd, station_by_cd(sst.station_cd))
+            else {
+                continue;
+            };
+            let stops = stops.entry(group).or_default();
+            match stops.last_mut() {
+                Some(stop) if stop[0].station_g_cd == record.station_g_cd => stop.push(record),
+                _ => stops.push(vec![record]),
+            }
+        }
+        stops
+            .into_iter()
+            .map(|(group, stops)| (group, hand_offs(group, &stops)))
+            .filter(|(_, services)| !services.is_empty())
+            .collect()
+    })
+}
+
+/// 系統が通る 1 駅。その駅を載せた sst の行 (路線ごと) を並び順に持つ。
+type Stop = Vec<&'static StationRecord>;
+
+fn stop_on(stop: &Stop, line_cd: i32) -> Option<&'static StationRecord> {
+    stop.iter().copied().find(|s| s.line_cd == line_cd)
+}
+
+/// 駅列を、なるべく少ない路線の乗り継ぎで覆ったときの直通箇所。
+///
+/// 同じ駅を複数の路線の行で載せている系統がある (大阪を JR京都線・JR神戸線・
+/// JR宝塚線の 3 行で持つなど) ので、行の路線が変わるたびに直通とすると
+/// A→B→A の行き来が出る。各駅でそこから最も先まで続く路線を選べば乗り継ぎの数が
+/// 最小になり、こうした行き来は消える (中央・総武線→東西線→中央・総武線のように
+/// 本当に戻る直通は残る)。路線名が同じ乗り継ぎ (東海道本線のように会社境界で
+/// line_cd が分かれるもの) は直通として出さない。
+fn hand_offs(line_group_cd: i32, stops: &[Stop]) -> Vec<ThroughService> {
+    // stops[i] から line_cd で続けて行ける最後の駅
+    let reach = |line_cd: i32, i: usize| {
+        (i + 1..stops.len())
+            .take_while(|&j| stop_on(&stops[j], line_cd).is_some())
+            .last()
+            .unwrap_or(i)
+    };
+    let mut out = Vec::new();
+    // (乗っている路線, その路線で最後に通る駅)
+    let mut current: Option<(i32, usize)> = None;
+    let mut i = 0;
+    while i < stops.len() {
+        // 同じだけ続くなら今の路線、次に並びの先の行を選ぶ
+        let stay = current
+            .filter(|&(line_cd, _)| stop_on(&stops[i], line_cd).is_some())
+            .map(|(line_cd, _)| (line_cd, reach(line_cd, i)));
+        let Some((line_cd, end)) = stops[i].iter().fold(stay, |best, s| {
+            let end = reach(s.line_cd, i);
+            match best {
+                Some((_, best_end)) if best_end >= end => best,
+                _ => Some((s.line_cd, end)),
+            }
+        }) else {
+            break;
+        };
+        if let Some((from, last)) = current.filter(|&(from, _)| from != line_cd) {
+            out.extend(hand_off(
+                line_group_cd,
+                from,
+                line_cd,
+                &stops[last],
+                &stops[i],
+            ));
+        }
+        current = Some((line_cd, end));
+        // 次の路線はこの路線の最後の駅から選ぶ。1 駅も続かなければ次の駅へ飛ぶ
+        i = if end > i { end } else { i + 1 };
+    }
+    out
+}
+
+/// 路線 `from` の最後の駅 `last` から、路線 `to` の最初の駅 `first` へ乗り継ぐところ。
+///
+/// 直通する駅 (両路線が載っている駅グループ) を探し、そこでの両路線の行を返す。
+/// 駅列が直通駅を飛ばしている (東北新幹線 新青森→北海道新幹線 奥津軽いまべつ) ときは、
+/// もう一方の路線も載っている側の駅を直通駅とする。どちらにも無ければ
+/// (仙石東北ラインの塩釜→高城町のように連絡線で入るもの) 行をそのまま使う。
+fn hand_off(
+    line_group_cd: i32,
+    from: i32,
+    to: i32,
+    last: &Stop,
+    first: &Stop,
+) -> Option<ThroughService> {
+    let (from_line, to_line) = (line_by_cd(from)?, line_by_cd(to)?);
+    if from_line.line_name == to_line.line_name {
+        return None;
+    }
+    let at = |stop: &Stop, line_cd: i32| {
+        stop_on(stop, line_cd)
+            .or_else(|| stations_by_group(stop[0].station_g_cd).find(|s| s.line_cd == line_cd))
+    };
+    let (from_station, to_station) = match (at(last, to), at(first, from)) {
+        (Some(to_station), _) => (stop_on(last, from)?, to_station),
+        (None, Some(from_station)) => (from_station, stop_on(first, to)?),
+        (None, None) => (stop_on(last, from)?, stop_on(first, to)?),
+    };
+    Some(ThroughService {
+        line_group_cd: line_group_cd as u32,
+        from_line_cd: from as u32,
+        to_line_cd: to as u32,
+        from_station_cd: from_station.station_cd as u32,
+        to_station_cd: to_station.station_cd as u32,
+    })
+}
+
+/// 系統の直通箇所。路線をまたがない系統は空。
+pub fn through_services_by_group(line_group_cd: i32) -> &'static [ThroughService] {
+    through_services()
+        .get(&line_group_cd)
+        .map(Vec::as_slice)
+        .unwrap_or(&[])
+}
+
+/// その路線と直通でつながる路線。
+pub fn through_service_lines(line_cd: i32) -> &'static [i32] {
+    let idx = THROUGH_SERVICE_LINES.get_or_init(|| {
+        let mut map: HashMap<i32, Vec<i32>> = HashMap::new();
+        for service in through_services().values().flatten() {
+            let (from, to) = (service.from_line_cd as i32, service.to_line_cd as i32);
+            map.entry(from).or_default().push(to);
+            map.entry(to).or_default().push(from);
+        }
+        for lines in map.values_mut() {
+            lines.sort_unstable();
+            lines.dedup();
+        }
+        map
+    });
+    idx.get(&line_cd).map(Vec::as_slice).unwrap_or(&[])
+}
+
 // ---------------------------------------------------------------- 路線名の別名
 
 const ALIASES_CSV: &str = include_str!(concat!(env!("OUT_DIR"), "/aliases.csv"));
@@ -907,3 +1373,415 @@ pub fn apply_line_alias(line: &mut Line, station_cd: i32) {
     line.line_name_ko = pick(alias.line_name_ko.as_ref(), line.line_name_ko.clone());
     line.line_color_c = pick(alias.line_color_c.as_ref(), line.line_color_c.clone());
 }
+
+// ---------------------------------------------------------------- 徒歩連絡
+
+const CONNECTIONS_CSV: &str = include_str!(concat!(env!("OUT_DIR"), "/connections.csv"));
+
+static CONNECTIONS: OnceLock<Vec<Connection>> = OnceLock::new();
+/// station_g_cd -> connections() の添字リスト。両端の駅グループから引ける。
+static CONNECTION_BY_GROUP: OnceLock<HashMap<i32, Vec<usize>>> = OnceLock::new();
+
+/// 駅グループをまたぐ徒歩連絡。preprocessor が駅座標と駅名から生成する。
+pub fn connections() -> &'static [Connection] {
+    CONNECTIONS.get_or_init(build_connections)
+}
+
+fn build_connections() -> Vec<Connection> {
+    let mut rdr = reader(CONNECTIONS_CSV);
+    let Ok(headers) = rdr.headers().cloned() else {
+        return Vec::new();
+    };
+    let c = Cols::of(&headers);
+    let (Some(i_id), Some(i_cd1), Some(i_cd2), Some(i_distance)) = (
+        c.at("id"),
+        c.at("station_cd1"),
+        c.at("station_cd2"),
+        c.at("distance"),
+    ) else {
+        panic!("connections の CSV に id / station_cd1 / station_cd2 / distance が必要です");
+    };
+    rdr.records()
+        .flatten()
+        .filter_map(|r| {
+            Some(Connection::new(
+                opt_i32(&r, Some(i_id))? as u32,
+                opt_i32(&r, Some(i_cd1))? as u32,
+                opt_i32(&r, Some(i_cd2))? as u32,
+                opt_f64(&r, Some(i_distance))?,
+            ))
+        })
+        .collect()
+}
+
+/// 駅グループに端点を持つ徒歩連絡。駅索引に無い駅を指す行は引けない。
+pub fn connections_by_group(station_g_cd: i32) -> impl Iterator<Item = &'static Connection> {
+    let idx = CONNECTION_BY_GROUP.get_or_init(|| {
+        let mut map: HashMap<i32, Vec<usize>> = HashMap::new();
+        for (i, conn) in connections().iter().enumerate() {
+            let groups = [conn.station_cd1, conn.station_cd2]
+                .map(|cd| station_by_cd(cd as i32).map(|s| s.station_g_cd));
+            let [Some(g1), Some(g2)] = groups else {
+                continue;
+            };
+            map.entry(g1).or_default().push(i);
+            if g2 != g1 {
+                map.entry(g2).or_default().push(i);
+            }
+        }
+        map
+    });
+    idx.get(&station_g_cd)
+        .map(Vec::as_slice)
+        .unwrap_or(&[])
+        .iter()
+        .map(|&i| &connections()[i])
+}
+
+// ---------------------------------------------------------------- バス時刻表
+
+const BUS_CALENDARS_CSV: &str = include_str!(concat!(env!("OUT_DIR"), "/bus_calendars.csv"));
+const BUS_CALENDAR_DATES_CSV: &str =
+    include_str!(concat!(env!("OUT_DIR"), "/bus_calendar_dates.csv"));
+const BUS_TIMETABLES_CSV: &str = include_str!(concat!(env!("OUT_DIR"), "/bus_timetables.csv"));
+
+/// 1 停留所・1 運行日・1 行先ぶんの発車時刻。運行日は `service_cd` で引く。
+pub struct BusTimetableRecord {
+    pub station_cd: i32,
+    pub service_cd: i32,
+    pub headsign: String,
+    /// 運行日 0 時からの分。昇順。
+    pub departures: Vec<u32>,
+}
+
+static BUS_CALENDARS: OnceLock<HashMap<i32, GtfsCalendar>> = OnceLock::new();
+static BUS_CALENDAR_DATES: OnceLock<HashMap<i32, Vec<GtfsCalendarDate>>> = OnceLock::new();
+static BUS_TIMETABLES: OnceLock<Vec<BusTimetableRecord>> = OnceLock::new();
+static BUS_TIMETABLE_BY_STATION: OnceLock<HashMap<i32, Vec<usize>>> = OnceLock::new();
+
+/// service_cd -> 曜日と有効期間。calendar_dates だけで運行日を表す service_cd は無い。
+pub fn bus_calendar(service_cd: i32) -> Option<&'static GtfsCalendar> {
+    BUS_CALENDARS
+        .get_or_init(build_bus_calendars)
+        .get(&service_cd)
+}
+
+fn build_bus_calendars() -> HashMap<i32, GtfsCalendar> {
+    let mut rdr = reader(BUS_CALENDARS_CSV);
+    let Ok(headers) = rdr.headers().cloned() else {
+        return HashMap::new();
+    };
+    let c = Cols::of(&headers);
+    let i_service = c.at("service_cd");
+    let days = [
+        "monday",
+        "tuesday",
+        "wednesday",
+        "thursday",
+        "friday",
+        "saturday",
+        "sunday",
+    ]
+    .map(|name| c.at(name));
+    let (i_start, i_end) = (c.at("start_date"), c.at("end_date"));
+    rdr.records()
+        .flatten()
+        .filter_map(|r| {
+            let service_cd = opt_i32(&r, i_service)?;
+            let runs = days.map(|i| opt_i32(&r, i) == Some(1));
+            Some((
+                service_cd,
+                GtfsCalendar::new(
+                    service_cd.to_string(),
+                    runs[0],
+                    runs[1],
+                    runs[2],
+                    runs[3],
+                    runs[4],
+                    runs[5],
+                    runs[6],
+                    text(&r, i_start),
+                    text(&r, i_end),
+                ),
+            ))
+        })
+        .collect()
+}
+
+/// service_cd -> 運行日の例外 (運行追加・運休)。
+pub fn bus_calendar_dates(service_cd: i32) -> &'static [GtfsCalendarDate] {
+    BUS_CALENDAR_DATES
+        .get_or_init(build_bus_calendar_dates)
+        .get(&service_cd)
+        .map(Vec::as_slice)
+        .unwrap_or(&[])
+}
+
+fn build_bus_calendar_dates() -> HashMap<i32, Vec<GtfsCalendarDate>> {
+    let mut rdr = reader(BUS_CALENDAR_DATES_CSV);
+    let Ok(headers) = rdr.headers().cloned() else {
+        return HashMap::new();
+    };
+    let c = Cols::of(&headers);
+    let (i_id, i_service, i_date, i_exception) = (
+        c.at("id"),
+        c.at("service_cd"),
+        c.at("date"),
+        c.at("exception_type"),
+    );
+    let mut map: HashMap<i32, Vec<GtfsCalendarDate>> = HashMap::new();
+    for r in rdr.records().flatten() {
+        let (Some(id), Some(service_cd), Some(exception_type)) = (
+            opt_i32(&r, i_id),
+            opt_i32(&r, i_service),
+            opt_i32(&r, i_exception),
+        ) else {
+            continue;
+        };
+        map.entry(service_cd)
+            .or_default()
+            .push(GtfsCalendarDate::new(
+                id,
+                service_cd.to_string(),
+                text(&r, i_date),
+                exception_type,
+            ));
+    }
+    map
+}
+
+pub fn bus_timetables() -> &'static [BusTimetableRecord] {
+    BUS_TIMETABLES.get_or_init(build_bus_timetables)
+}
+
+fn build_bus_timetables() -> Vec<BusTimetableRecord> {
+    let mut rdr = reader(BUS_TIMETABLES_CSV);
+    let Ok(headers) = rdr.headers().cloned() else {
+        return Vec::new();
+    };
+    let c = Cols::of(&headers);
+    let (i_station, i_service, i_headsign, i_departures) = (
+        c.at("station_cd"),
+        c.at("service_cd"),
+        c.at("headsign"),
+        c.at("departures"),
+    );
+    rdr.records()
+        .flatten()
+        .filter_map(|r| {
+            let mut departures: Vec<u32> = text(&r, i_departures)
+                .split_ascii_whitespace()
+                .filter_map(|v| v.parse().ok())
+                .collect();
+            departures.sort_unstable();
+            Some(BusTimetableRecord {
+                station_cd: opt_i32(&r, i_station)?,
+                service_cd: opt_i32(&r, i_service)?,
+                headsign: text(&r, i_headsign),
+                departures,
+            })
+        })
+        .collect()
+}
+
+pub fn bus_timetables_by_station(
+    station_cd: i32,
+) -> impl Iterator<Item = &'static BusTimetableRecord> {
+    let idx = BUS_TIMETABLE_BY_STATION.get_or_init(|| {
+        let mut map: HashMap<i32, Vec<usize>> = HashMap::new();
+        for (i, t) in bus_timetables().iter().enumerate() {
+            map.entry(t.station_cd).or_default().push(i);
+        }
+        map
+    });
+    idx.get(&station_cd)
+        .map(Vec::as_slice)
+        .unwrap_or(&[])
+        .iter()
+        .map(|&i| &bus_timetables()[i])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    /// 格子を使わずに全駅を並べた、座標検索の期待値。鉄道を先・バスを後に、
+    /// 種別内は距離順、同じ距離なら CSV の行順。
+    fn nearest_by_full_scan(
+        lat: f64,
+        lon: f64,
+        want: Option<i32>,
+        as_of: Option<Date>,
+        require_line: bool,
+    ) -> Vec<(i32, f64)> {
+        let mut scored: Vec<(i32, f64, usize, i32)> = stations()
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| {
+                !s.period.is_hidden()
+                    && s.is_open(as_of)
+                    && (!require_line || joins_line(s))
+                    && want.is_none_or(|w| s.transport_type as i32 == w)
+            })
+            .map(|(i, s)| {
+                let d = haversine_km(lat, lon, s.lat, s.lon);
+                (s.transport_type as i32, d, i, s.station_cd)
+            })
+            .collect();
+        scored.sort_by(|a, b| {
+            a.0.cmp(&b.0)
+                .then_with(|| a.1.total_cmp(&b.1))
+                .then_with(|| a.2.cmp(&b.2))
+        });
+        scored.into_iter().map(|(_, d, _, cd)| (cd, d)).collect()
+    }
+
+    /// 渋谷駅と神泉駅を囲む表示範囲。
+    fn around_shibuya() -> BoundingBox {
+        BoundingBox::new(35.655, 139.690, 35.662, 139.706).unwrap()
+    }
+
+    #[test]
+    fn in_bounds_returns_the_stations_inside_the_box() {
+        let bounds = around_shibuya();
+        let hits = in_bounds(&bounds, None, None);
+
+        let mut expected: Vec<i32> = stations()
+            .iter()
+            .filter(|s| {
+                !s.period.is_hidden()
+                    && s.is_open(None)
+                    && joins_line(s)
+                    && bounds.contains(s.lat, s.lon)
+            })
+            .map(|s| s.station_cd)
+            .collect();
+        let mut got: Vec<i32> = hits.iter().map(|(s, _)| s.station_cd).collect();
+        expected.sort_unstable();
+        got.sort_unstable();
+        assert_eq!(got, expected);
+
+        let groups: BTreeSet<i32> = hits.iter().map(|(s, _)| s.station_g_cd).collect();
+        assert_eq!(groups, BTreeSet::from([1130205, 2400602])); // 渋谷, 神泉
+        assert!(hits.iter().any(|(s, _)| s.station_cd == 2801016)); // 副都心線の渋谷
+                                                                    // 中心からの距離順
+        assert!(hits.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+    }
+
+    #[test]
+    fn in_bounds_filters_by_transport_type_and_date() {
+        let bounds = around_shibuya();
+        let all: Vec<i32> = in_bounds(&bounds, None, None)
+            .iter()
+            .map(|(s, _)| s.station_cd)
+            .collect();
+        let rail: Vec<i32> = in_bounds(&bounds, Some(TransportType::Rail as i32), None)
+            .iter()
+            .map(|(s, _)| s.station_cd)
+            .collect();
+        assert_eq!(rail, all);
+        assert!(in_bounds(&bounds, Some(TransportType::Bus as i32), None)
+            .iter()
+            .all(|(s, _)| s.transport_type == TransportType::Bus));
+
+        // 開業日を持つのは山手線の渋谷 (1885-03-01) だけなので、その前はそれだけが消える
+        let before: Vec<i32> = in_bounds(&bounds, None, Date::new(1880, 1, 1))
+            .iter()
+            .map(|(s, _)| s.station_cd)
+            .collect();
+        let without_yamanote: Vec<i32> = all.into_iter().filter(|&cd| cd != 1130205).collect();
+        assert_eq!(before, without_yamanote);
+    }
+
+    #[test]
+    fn nearest_matches_a_full_scan() {
+        let points = [
+            (35.6580, 139.7016), // 渋谷
+            (43.0687, 141.3508), // 札幌
+            (26.2124, 127.6792), // 那覇 (近くの鉄道はゆいレールだけ)
+            (45.4156, 141.6733), // 稚内
+            (30.0, 150.0),       // 太平洋上。全件まで広げる
+            (-33.9, 151.2),      // 南半球
+        ];
+        let as_ofs = [None, Date::new(1990, 4, 1)];
+        for (lat, lon) in points {
+            for want in [None, Some(0), Some(1)] {
+                for as_of in as_ofs {
+                    for require_line in [true, false] {
+                        let expected = nearest_by_full_scan(lat, lon, want, as_of, require_line);
+                        for limit in [0, 1, 5, 40, 300] {
+                            let got: Vec<(i32, f64)> =
+                                nearest_inner(lat, lon, limit, want, as_of, require_line)
+                                    .into_iter()
+                                    .map(|(s, d)| (s.station_cd, d))
+                                    .collect();
+                            let want_len = limit.min(expected.len());
+                            assert_eq!(
+                                got,
+                                expected[..want_len],
+                                "({lat}, {lon}) limit={limit} want={want:?} as_of={as_of:?} require_line={require_line}"
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// (直通元, 直通先, 直通元の駅, 直通先の駅)
+    fn hand_offs_of(line_group_cd: i32) -> Vec<(i32, i32, i32, i32)> {
+        through_services_by_group(line_group_cd)
+            .iter()
+            .map(|s| {
+                (
+                    s.from_line_cd as i32,
+                    s.to_line_cd as i32,
+                    s.from_station_cd as i32,
+                    s.to_station_cd as i32,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn through_services_follow_den_en_toshi_and_hanzomon_lines() {
+        // 田園都市線 (26003) → 半蔵門線 (28008) → 東武伊勢崎線 (21002)
+        assert_eq!(
+            hand_offs_of(162),
+            vec![
+                (26003, 28008, 2600301, 2800801), // 渋谷
+                (28008, 21002, 2800813, 2100203), // 押上
+            ]
+        );
+        assert!(through_service_lines(28008).contains(&26003));
+        assert!(through_service_lines(26003).contains(&28008));
+    }
+
+    #[test]
+    fn stations_listed_under_several_lines_do_not_flip_flop() {
+        // こうのとり。新大阪・大阪・塚本・尼崎を JR京都線・JR神戸線・JR宝塚線の行で
+        // 持っているが、JR宝塚線 (11629) で通して福知山線・山陰本線へ入る
+        assert_eq!(
+            hand_offs_of(300),
+            vec![
+                (11629, 11630, 1162923, 1163001), // 篠山口
+                (11630, 11615, 1163010, 1161514), // 福知山 (豊岡の山陰本線どうしは出さない)
+            ]
+        );
+    }
+
+    #[test]
+    fn hand_off_is_reported_at_the_junction_station() {
+        // 駅列は東北新幹線の新青森から北海道新幹線の奥津軽いまべつへ飛ぶが、
+        // 直通駅は北海道新幹線にも載っている新青森
+        let service = through_services_by_group(7)
+            .iter()
+            .find(|s| s.to_line_cd == 1011)
+            .unwrap();
+        assert_eq!(service.from_station_cd, 100423);
+        let junction = station_by_cd(service.to_station_cd as i32).unwrap();
+        assert_eq!(junction.name, "新青森");
+        assert_eq!(junction.line_cd, 1011);
+    }
+}
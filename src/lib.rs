@@ -10,12 +10,18 @@
 //! データベースは持たず、ビルド時に埋め込んだ CSV をインメモリ索引にする。
 //! UseCase 層は共有のまま、repository トレイトの実装だけを差し替えている。
 
+mod apq;
+mod export;
 mod graphql;
+mod http_cache;
 mod index;
 mod repository;
 
 use async_graphql::http::GraphiQLSource;
-use async_graphql::Request as GqlRequest;
+use async_graphql::{BatchRequest, BatchResponse};
+use graphql::limits::QueryLimits;
+use graphql::tracing::{Tracing, TRACE_HEADER};
+use std::sync::atomic::{AtomicBool, Ordering};
 use worker::*;
 
 use stationapi::use_case::interactor::query::QueryInteractor;
@@ -31,28 +37,52 @@ pub type Interactor = QueryInteractor<
     MemCompanyRepository,
 >;
 
-/// repository は状態を持たない (索引は OnceLock 側にある) ので毎回生成して問題ない。
+/// repository は `as_of` しか持たない (索引は OnceLock 側にある) ので毎回生成して問題ない。
 fn interactor() -> Interactor {
+    interactor_for(None, None)
+}
+
+/// 日付を指定した問い合わせ用。`as_of` は駅・路線をその日の開業・廃止で絞り、
+/// `service_day` は平日のみ・土休日のみの停車をそのダイヤで解く。
+pub(crate) fn interactor_for(
+    as_of: Option<stationapi::domain::calendar::Date>,
+    service_day: Option<stationapi::domain::calendar::ServiceDay>,
+) -> Interactor {
     QueryInteractor {
-        station_repository: MemStationRepository,
-        line_repository: MemLineRepository,
+        station_repository: MemStationRepository { as_of, service_day },
+        line_repository: MemLineRepository { as_of, service_day },
         train_type_repository: MemTrainTypeRepository,
         company_repository: MemCompanyRepository,
     }
 }
 
+/// この isolate が一度でも操作を実行したか。構造化ログのコールドスタート判定に使う。
+static WARM: AtomicBool = AtomicBool::new(false);
+
+/// リクエストごとの計測設定。デバッグ用ヘッダがあれば応答にも所要時間を載せる。
+fn tracing(req: &Request) -> Result<Tracing> {
+    Ok(Tracing {
+        clock: || Date::now().as_millis() as f64,
+        log: |line| console_log!("{line}"),
+        cold_start: || !WARM.swap(true, Ordering::Relaxed),
+        expose: req.headers().get(TRACE_HEADER)?.is_some(),
+    })
+}
+
 #[event(fetch)]
-async fn fetch(req: Request, _env: Env, _ctx: Context) -> Result<Response> {
+async fn fetch(req: Request, env: Env, _ctx: Context) -> Result<Response> {
     console_error_panic_hook::set_once();
+    // 上限は wrangler の vars (GRAPHQL_MAX_DEPTH など) で変えられる
+    let limits = QueryLimits::from_lookup(|name| env.var(name).ok().map(|v| v.to_string()));
     // Workers は 500 の本文を伏せるので、失敗理由をログに残す
-    let result = route(req).await;
+    let result = route(req, limits).await;
     if let Err(e) = &result {
         console_error!("request failed: {e}");
     }
     result
 }
 
-async fn route(req: Request) -> Result<Response> {
+async fn route(req: Request, limits: QueryLimits) -> Result<Response> {
     let method = req.method();
     let path = req.path();
 
@@ -74,34 +104,135 @@ async fn route(req: Request) -> Result<Response> {
     }
     // スキーマを配る。CI はこれと schema/public.graphql を突き合わせる。
     if method == Method::Get && path == "/__schema" {
-        let schema = graphql::build_schema(interactor());
+        let schema = graphql::build_schema(interactor(), limits, None);
         return with_cors(Response::ok(schema.sdl())?);
     }
-    // クライアント互換のため、サブドメイン直下で GraphQL を受ける
+    // 解析や地図タイルの生成向けに、埋め込んだデータを丸ごと配る
+    if method == Method::Get {
+        if let Some(name) = path.strip_prefix("/__export/") {
+            return handle_export(&req, name);
+        }
+    }
+    // クライアント互換のため、サブドメイン直下で GraphQL を受ける。
+    // GET はクエリ (または APQ のハッシュ) を持つときだけ実行し、それ以外は GraphiQL を返す
     if method == Method::Get && path == "/" {
+        let url = req.url()?;
+        if url
+            .query_pairs()
+            .any(|(key, _)| key == "query" || key == "extensions")
+        {
+            return handle_get(&req, &url, limits).await;
+        }
         return Response::from_html(GraphiQLSource::build().endpoint("/").finish());
     }
     if method == Method::Post && path == "/" {
-        return handle_graphql(req).await;
+        return handle_graphql(req, limits).await;
     }
 
     Response::error("Not Found", 404)
 }
 
-async fn handle_graphql(mut req: Request) -> Result<Response> {
+/// POST のクエリ。本文が配列ならバッチとして同じスキーマで順に実行し、応答も配列で返す。
+/// 画面を開いたときの複数の操作を 1 回の呼び出しにまとめられる。
+async fn handle_graphql(mut req: Request, limits: QueryLimits) -> Result<Response> {
+    let tracing = tracing(&req)?;
     let body = req.text().await?;
-    let request: GqlRequest = serde_json::from_str(&body)
+    let request: BatchRequest = serde_json::from_str(&body)
         .map_err(|e| Error::RustError(format!("GraphQL リクエストを解釈できません: {e}")))?;
 
-    let schema = graphql::build_schema(interactor());
+    if let Err(error) = limits.check_batch(request.iter().count()) {
+        let response = async_graphql::Response::from_errors(vec![error]);
+        return Ok(json_response(&response.into(), None)?.with_status(400));
+    }
+
+    // 計測のログは操作ごとに 1 行なので、バッチなら操作の数だけ出る
+    let schema = graphql::build_schema(interactor(), limits, Some(tracing));
+    let response = schema.execute_batch(request).await;
+    json_response(&response, None)
+}
+
+/// GET のクエリ。URL とデータだけで応答が決まるので、成功した応答は
+/// ETag を付けてエッジとクライアントにキャッシュさせる。
+///
+/// `If-None-Match` が一致すればクエリを実行せずに 304 を返す。
+/// `PersistedQueryNotFound` などのエラーはキャッシュさせない。
+/// 残すと、登録し直した後も同じエラーが返り続ける。
+///
+/// 計測付きの応答はその実行にしか当てはまらないので、304 も返さずキャッシュもさせない。
+async fn handle_get(req: &Request, url: &Url, limits: QueryLimits) -> Result<Response> {
+    let tracing = tracing(req)?;
+    let etag = http_cache::etag();
+    let if_none_match = if tracing.expose {
+        None
+    } else {
+        req.headers().get("if-none-match")?
+    };
+    if let Some(header) = if_none_match {
+        if http_cache::matches_if_none_match(&header, &etag) {
+            return not_modified(&etag);
+        }
+    }
+
+    let request = match http_cache::request_from_query_pairs(url.query_pairs()) {
+        Ok(request) => request,
+        Err(message) => return Response::error(message, 400),
+    };
+    let schema = graphql::build_schema(interactor(), limits, Some(tracing));
     let response = schema.execute(request).await;
+    let cache = (response.is_ok() && !tracing.expose).then_some(etag.as_str());
+    json_response(&response.into(), cache)
+}
+
+/// `/__export/stations.geojson` などの書き出し。中身はデータだけで決まるので、
+/// GraphQL の GET と同じ ETag でキャッシュさせる。
+fn handle_export(req: &Request, name: &str) -> Result<Response> {
+    let Some(export) = export::Export::from_name(name) else {
+        return Response::error("Not Found", 404);
+    };
+    let etag = http_cache::etag();
+    if let Some(header) = req.headers().get("if-none-match")? {
+        if http_cache::matches_if_none_match(&header, &etag) {
+            return not_modified(&etag);
+        }
+    }
+
+    let headers = Headers::new();
+    headers.set("content-type", export.content_type())?;
+    headers.set("access-control-allow-origin", "*")?;
+    headers.set("etag", &etag)?;
+    headers.set("cache-control", http_cache::CACHE_CONTROL)?;
+    headers.set("access-control-expose-headers", "etag")?;
+    headers.set(
+        "content-disposition",
+        &format!("attachment; filename=\"{name}\""),
+    )?;
+    Ok(Response::from_bytes(export.render())?.with_headers(headers))
+}
+
+fn not_modified(etag: &str) -> Result<Response> {
+    let headers = Headers::new();
+    headers.set("etag", etag)?;
+    headers.set("cache-control", http_cache::CACHE_CONTROL)?;
+    headers.set("access-control-allow-origin", "*")?;
+    headers.set("access-control-expose-headers", "etag")?;
+    Ok(Response::empty()?.with_status(304).with_headers(headers))
+}
 
-    let payload = serde_json::to_string(&response)
+/// `etag` を渡すと、キャッシュさせる応答として ETag と Cache-Control を付ける。
+fn json_response(response: &BatchResponse, etag: Option<&str>) -> Result<Response> {
+    let payload = serde_json::to_string(response)
         .map_err(|e| Error::RustError(format!("GraphQL レスポンスを作れません: {e}")))?;
 
     let headers = Headers::new();
     headers.set("content-type", "application/json")?;
     headers.set("access-control-allow-origin", "*")?;
+    if let Some(etag) = etag {
+        headers.set("etag", etag)?;
+        headers.set("cache-control", http_cache::CACHE_CONTROL)?;
+        headers.set("access-control-expose-headers", "etag")?;
+        // 計測ヘッダ付きの GET にキャッシュ済みの応答を返さないよう、キーに含めさせる
+        headers.set("vary", TRACE_HEADER)?;
+    }
     Ok(Response::ok(payload)?.with_headers(headers))
 }
 
@@ -109,7 +240,10 @@ fn preflight() -> Result<Response> {
     let headers = Headers::new();
     headers.set("access-control-allow-origin", "*")?;
     headers.set("access-control-allow-methods", "POST,GET,OPTIONS")?;
-    headers.set("access-control-allow-headers", "content-type")?;
+    headers.set(
+        "access-control-allow-headers",
+        "content-type,if-none-match,x-stationapi-trace",
+    )?;
     headers.set("access-control-max-age", "86400")?;
     Ok(Response::empty()?.with_headers(headers))
 }
@@ -7,40 +7,44 @@
 use async_trait::async_trait;
 use std::collections::{HashMap, HashSet};
 
+use stationapi::domain::calendar::{Date, ServiceDay};
 use stationapi::domain::entity::company::Company;
+use stationapi::domain::entity::connection::{Connection, ThroughService};
 use stationapi::domain::entity::gtfs::TransportType;
 use stationapi::domain::entity::line::Line;
 use stationapi::domain::entity::station::Station;
 use stationapi::domain::entity::train_type::TrainType;
 use stationapi::domain::error::DomainError;
+use stationapi::domain::geo::BoundingBox;
 use stationapi::domain::repository::company_repository::CompanyRepository;
 use stationapi::domain::repository::line_repository::LineRepository;
 use stationapi::domain::repository::station_repository::StationRepository;
 use stationapi::domain::repository::train_type_repository::TrainTypeRepository;
+use stationapi::domain::timetable::BusTimetable;
 use stationapi::model::StopCondition;
 
 use crate::index;
 
-/// 有効な (e_status = 0) 路線だけを返す。
+/// 有効な (e_status = 0、`as_of` 指定時はその日に営業していた) 路線だけを返す。
 /// 大半の問い合わせは有効な路線しか対象にしないため、無効な路線を混ぜない。
-fn active_line(line_cd: i32) -> Option<&'static Line> {
-    index::line_by_cd(line_cd).filter(|l| l.e_status == 0)
+fn active_line(line_cd: i32, as_of: Option<Date>) -> Option<&'static Line> {
+    index::line_by_cd(line_cd).filter(|l| index::line_is_open(l, as_of))
 }
 
 /// 駅グループ ID 群に属する有効な駅を、路線の属性を埋めた Station として返す。
 /// 無効な路線の駅は除く。
-fn stations_of_groups(group_ids: &[u32]) -> Vec<Station> {
+fn stations_of_groups(group_ids: &[u32], as_of: Option<Date>) -> Vec<Station> {
     let mut out = Vec::new();
     for &gid in group_ids {
         for record in index::stations_by_group(gid as i32) {
-            if record.e_status != 0 {
+            if !record.is_open(as_of) {
                 continue;
             }
             // 路線が引けない駅は返さない
             let Some(line) = index::line_by_cd(record.line_cd) else {
                 continue;
             };
-            if line.e_status != 0 {
+            if !index::line_is_open(line, as_of) {
                 continue;
             }
             out.push(record.to_entity(Some(line)));
@@ -50,7 +54,13 @@ fn stations_of_groups(group_ids: &[u32]) -> Vec<Station> {
 }
 
 /// 系統 (station_station_types) と列車種別 (types) の内容を Station に反映する。
-fn apply_train_type(station: &mut Station, sst: &index::SstRecord, ty: &index::TypeRecord) {
+/// `day` があれば、平日のみ・土休日のみの停車をその日のダイヤで解く。
+fn apply_train_type(
+    station: &mut Station,
+    sst: &index::SstRecord,
+    ty: &index::TypeRecord,
+    day: Option<ServiceDay>,
+) {
     station.sst_id = Some(sst.id);
     station.type_cd = Some(sst.type_cd);
     station.line_group_cd = sst.line_group_cd;
@@ -65,23 +75,34 @@ fn apply_train_type(station: &mut Station, sst: &index::SstRecord, ty: &index::T
     station.direction = ty.direction;
     station.kind = ty.kind;
     station.has_train_types = sst.line_group_cd.is_some();
-    station.stop_condition = match sst.pass.unwrap_or(0) {
+    let condition = stop_condition(sst);
+    station.stop_condition = day.map_or(condition, |day| day.resolve(condition));
+}
+
+/// その系統がその駅に止まるか。`day` が無ければ通過 (pass = 1) 以外はすべて止まる扱い。
+fn stops_at(sst: &index::SstRecord, day: Option<ServiceDay>) -> bool {
+    let condition = stop_condition(sst);
+    day.map_or(condition, |day| day.resolve(condition)) != StopCondition::Not
+}
+
+fn stop_condition(sst: &index::SstRecord) -> StopCondition {
+    match sst.pass.unwrap_or(0) {
         1 => StopCondition::Not,
         2 => StopCondition::Partial,
         3 => StopCondition::Weekday,
         4 => StopCondition::Holiday,
         5 => StopCondition::PartialStop,
         _ => StopCondition::All,
-    };
+    }
 }
 
 /// その駅が属する系統のうち先頭 (= 最小の sst.id) を 1 つだけ反映する。
-fn apply_first_train_type(station: &mut Station) {
+fn apply_first_train_type(station: &mut Station, day: Option<ServiceDay>) {
     let Some(sst) = index::sst_by_station(station.station_cd).next() else {
         return;
     };
     match index::type_by_cd(sst.type_cd) {
-        Some(ty) => apply_train_type(station, sst, ty),
+        Some(ty) => apply_train_type(station, sst, ty, day),
         // 種別が引けなくても系統の情報は入れる
         None => {
             station.sst_id = Some(sst.id);
@@ -95,20 +116,24 @@ fn apply_first_train_type(station: &mut Station) {
 
 /// 指定した系統の停車駅を返す。並びは指定された系統の順、各系統内は sst.id 昇順。
 /// 駅・路線・種別のいずれかが引けない行は落とす。
-fn stations_of_line_groups(group_ids: &[u32]) -> Vec<Station> {
+fn stations_of_line_groups(
+    group_ids: &[u32],
+    as_of: Option<Date>,
+    day: Option<ServiceDay>,
+) -> Vec<Station> {
     let mut out = Vec::new();
     for &group_id in group_ids {
         for sst in index::sst_by_group(group_id as i32) {
             let Some(record) = index::station_by_cd(sst.station_cd) else {
                 continue;
             };
-            if record.e_status != 0 {
+            if !record.is_open(as_of) {
                 continue;
             }
             let Some(line) = index::line_by_cd(record.line_cd) else {
                 continue;
             };
-            if line.e_status != 0 {
+            if !index::line_is_open(line, as_of) {
                 continue;
             }
             // 種別が引けない系統は落とす
@@ -116,7 +141,7 @@ fn stations_of_line_groups(group_ids: &[u32]) -> Vec<Station> {
                 continue;
             };
             let mut station = record.to_entity(Some(line));
-            apply_train_type(&mut station, sst, ty);
+            apply_train_type(&mut station, sst, ty, day);
             out.push(station);
         }
     }
@@ -125,8 +150,14 @@ fn stations_of_line_groups(group_ids: &[u32]) -> Vec<Station> {
 
 // ---------------------------------------------------------------- 駅
 
+/// `as_of` を指定すると、駅・路線の有効・無効を e_status ではなくその日の
+/// 開業・廃止で判定する (`ServicePeriod`)。`service_day` を指定すると、
+/// 平日のみ・土休日のみの停車をそのダイヤで解いた停車条件を返す。
 #[derive(Clone, Default)]
-pub struct MemStationRepository;
+pub struct MemStationRepository {
+    pub as_of: Option<Date>,
+    pub service_day: Option<ServiceDay>,
+}
 
 #[async_trait]
 impl StationRepository for MemStationRepository {
@@ -140,7 +171,7 @@ impl StationRepository for MemStationRepository {
         // 未指定なら 1 件
         let limit = limit.unwrap_or(1).min(1_000) as usize;
         let want = transport_type.map(|t| t as i32);
-        Ok(index::nearest(latitude, longitude, limit, want)
+        Ok(index::nearest(latitude, longitude, limit, want, self.as_of)
             .into_iter()
             .map(|(record, distance_km)| {
                 // NOTE: 座標検索は路線の有効・無効を見ない
@@ -154,6 +185,33 @@ impl StationRepository for MemStationRepository {
             .collect())
     }
 
+    /// 地図の表示範囲。座標検索と同じく路線の有効・無効は見ない。
+    /// 路線種別と駅グループの重複除去は件数で切る前にかける。
+    async fn get_in_bounds(
+        &self,
+        bounds: BoundingBox,
+        line_type: Option<i32>,
+        transport_type: Option<TransportType>,
+        limit: u32,
+        dedupe_by_group: bool,
+    ) -> Result<Vec<Station>, DomainError> {
+        let want = transport_type.map(|t| t as i32);
+        let mut seen_groups = HashSet::new();
+        Ok(index::in_bounds(&bounds, want, self.as_of)
+            .into_iter()
+            .filter_map(|(record, _)| Some((record, index::line_by_cd(record.line_cd)?)))
+            .filter(|(_, line)| line_type.is_none_or(|t| line.line_type == Some(t)))
+            .filter(|(record, _)| !dedupe_by_group || seen_groups.insert(record.station_g_cd))
+            .take(limit as usize)
+            .map(|(record, line)| {
+                let mut station = record.to_entity(Some(line));
+                station.line_group_cd = index::first_line_group_cd(record.station_cd);
+                station.has_train_types = station.line_group_cd.is_some();
+                station
+            })
+            .collect())
+    }
+
     /// 名前の部分一致に加えて、`from_station_group_id` が指定された場合は
     /// 「その駅から乗り換えなしで行けるか」で絞り込む。条件は次のどちらか。
     ///
@@ -172,7 +230,7 @@ impl StationRepository for MemStationRepository {
         // 未指定なら実質全件
         let limit = limit.unwrap_or(u32::MAX).min(10_000) as usize;
         let want = transport_type.map(|t| t as i32);
-        let hits = index::search_by_name(&station_name, want);
+        let hits = index::search_by_name(&station_name, want, self.as_of);
 
         let Some(group_id) = from_station_group_id else {
             return Ok(hits
@@ -189,7 +247,7 @@ impl StationRepository for MemStationRepository {
         let mut from_groups: HashSet<i32> = HashSet::new();
         let mut from_line_cds: HashSet<i32> = HashSet::new();
         let mut lines_without_types: HashSet<i32> = HashSet::new();
-        for from in index::stations_by_group(group_id as i32).filter(|s| s.e_status == 0) {
+        for from in index::stations_by_group(group_id as i32).filter(|s| s.is_open(self.as_of)) {
             from_line_cds.insert(from.line_cd);
             let mut has_sst = false;
             for sst in index::sst_by_station(from.station_cd) {
@@ -244,13 +302,13 @@ impl StationRepository for MemStationRepository {
         let mut out = Vec::new();
         for &group_id in station_group_id_vec {
             for record in index::stations_by_group(group_id as i32) {
-                if record.e_status != 0 {
+                if !record.is_open(self.as_of) {
                     continue;
                 }
                 let Some(line) = index::line_by_cd(record.line_cd) else {
                     continue;
                 };
-                if line.e_status != 0 {
+                if !index::line_is_open(line, self.as_of) {
                     continue;
                 }
 
@@ -261,7 +319,7 @@ impl StationRepository for MemStationRepository {
                         continue;
                     };
                     let mut station = record.to_entity(Some(line));
-                    apply_train_type(&mut station, sst, ty);
+                    apply_train_type(&mut station, sst, ty, self.service_day);
                     out.push(station);
                     matched = true;
                 }
@@ -279,7 +337,7 @@ impl StationRepository for MemStationRepository {
         &self,
         station_group_id_vec: &[u32],
     ) -> Result<Vec<Station>, DomainError> {
-        let mut out = stations_of_groups(station_group_id_vec);
+        let mut out = stations_of_groups(station_group_id_vec, self.as_of);
         for station in out.iter_mut() {
             station.line_group_cd = index::first_line_group_cd(station.station_cd);
             station.has_train_types = station.line_group_cd.is_some();
@@ -294,14 +352,55 @@ impl StationRepository for MemStationRepository {
         self.get_by_station_group_id_vec(&[station_group_id]).await
     }
 
+    /// 連絡の向きを揃え、station_cd1 を問い合わせた駅グループ側にして返す。
+    async fn get_connections_by_station_group_id(
+        &self,
+        station_group_id: u32,
+    ) -> Result<Vec<Connection>, DomainError> {
+        let in_group = |station_cd: u32| {
+            index::station_by_cd(station_cd as i32)
+                .is_some_and(|s| s.station_g_cd == station_group_id as i32)
+        };
+        Ok(index::connections_by_group(station_group_id as i32)
+            .map(|conn| {
+                if in_group(conn.station_cd1) {
+                    conn.clone()
+                } else {
+                    Connection::new(conn.id, conn.station_cd2, conn.station_cd1, conn.distance)
+                }
+            })
+            .collect())
+    }
+
+    /// line_cd は駅索引から引く。駅として書き出されていない停留所の時刻表は返さない。
+    async fn get_bus_timetables_by_station_ids(
+        &self,
+        station_ids: &[u32],
+    ) -> Result<Vec<BusTimetable>, DomainError> {
+        Ok(station_ids
+            .iter()
+            .filter_map(|&id| index::station_by_cd(id as i32))
+            .flat_map(|station| {
+                index::bus_timetables_by_station(station.station_cd).map(|t| BusTimetable {
+                    station_cd: t.station_cd,
+                    line_cd: station.line_cd,
+                    headsign: t.headsign.clone(),
+                    calendar: index::bus_calendar(t.service_cd).cloned(),
+                    calendar_dates: index::bus_calendar_dates(t.service_cd).to_vec(),
+                    departure_minutes: t.departures.clone(),
+                })
+            })
+            .collect())
+    }
+
     /// 系統と種別を 1 件だけ反映する。埋めないと hasTrainTypes が常に false になる。
     async fn find_by_id(&self, id: u32) -> Result<Option<Station>, DomainError> {
         Ok(index::station_by_cd(id as i32)
-            .filter(|r| r.e_status == 0)
-            .filter(|r| active_line(r.line_cd).is_some())
+            .filter(|r| r.is_open(self.as_of))
+            .filter(|r| active_line(r.line_cd, self.as_of).is_some())
             .map(|r| {
-                let mut station = r.to_entity(active_line(r.line_cd));
-                apply_first_train_type(&mut station);
+                let mut station = r.to_entity(active_line(r.line_cd, self.as_of));
+                apply_first_train_type(&mut station, self.service_day);
                 station
             }))
     }
@@ -311,10 +410,10 @@ impl StationRepository for MemStationRepository {
         Ok(ids
             .iter()
             .filter_map(|&id| index::station_by_cd(id as i32))
-            .filter(|r| r.e_status == 0)
-            .filter(|r| active_line(r.line_cd).is_some())
+            .filter(|r| r.is_open(self.as_of))
+            .filter(|r| active_line(r.line_cd, self.as_of).is_some())
             .map(|r| {
-                let mut station = r.to_entity(active_line(r.line_cd));
+                let mut station = r.to_entity(active_line(r.line_cd, self.as_of));
                 station.line_group_cd = index::first_line_group_cd(r.station_cd);
                 station.has_train_types = station.line_group_cd.is_some();
                 station
@@ -335,11 +434,13 @@ impl StationRepository for MemStationRepository {
         let mut out = Vec::new();
 
         for &(source_g_cd, lat, lon) in coords {
-            for (record, _distance) in index::nearest_without_line_join(lat, lon, limit, want) {
+            for (record, _distance) in
+                index::nearest_without_line_join(lat, lon, limit, want, self.as_of)
+            {
                 let Some(line) = index::line_by_cd(record.line_cd) else {
                     continue;
                 };
-                if line.e_status != 0 {
+                if !index::line_is_open(line, self.as_of) {
                     continue;
                 }
                 let mut station = record.to_entity(Some(line));
@@ -395,7 +496,7 @@ impl StationRepository for MemStationRepository {
                 let Some(record) = index::station_by_cd(sst.station_cd) else {
                     continue;
                 };
-                if record.e_status != 0 {
+                if !record.is_open(self.as_of) {
                     continue;
                 }
                 if index::type_by_cd(sst.type_cd).is_none() {
@@ -404,7 +505,7 @@ impl StationRepository for MemStationRepository {
                 let Some(line) = index::line_by_cd(record.line_cd) else {
                     continue;
                 };
-                if line.e_status != 0 {
+                if !index::line_is_open(line, self.as_of) {
                     continue;
                 }
                 typed.push((sst.id, record, sst));
@@ -417,9 +518,9 @@ impl StationRepository for MemStationRepository {
                 return Ok(typed
                     .into_iter()
                     .map(|(_, record, sst)| {
-                        let mut station = record.to_entity(active_line(record.line_cd));
+                        let mut station = record.to_entity(active_line(record.line_cd, self.as_of));
                         if let Some(ty) = index::type_by_cd(sst.type_cd) {
-                            apply_train_type(&mut station, sst, ty);
+                            apply_train_type(&mut station, sst, ty, self.service_day);
                         }
                         station
                     })
@@ -431,11 +532,11 @@ impl StationRepository for MemStationRepository {
         let Some(line) = index::line_by_cd(line_id as i32) else {
             return Ok(Vec::new());
         };
-        if line.e_status != 0 {
+        if !index::line_is_open(line, self.as_of) {
             return Ok(Vec::new());
         }
         let mut records: Vec<&index::StationRecord> = index::stations_by_line(line_id as i32)
-            .filter(|r| r.e_status == 0)
+            .filter(|r| r.is_open(self.as_of))
             .collect();
         records.sort_by(|a, b| {
             a.e_sort
@@ -463,11 +564,11 @@ impl StationRepository for MemStationRepository {
             let Some(line) = index::line_by_cd(line_id as i32) else {
                 continue;
             };
-            if line.e_status != 0 {
+            if !index::line_is_open(line, self.as_of) {
                 continue;
             }
             let mut records: Vec<_> = index::stations_by_line(line_id as i32)
-                .filter(|s| s.e_status == 0)
+                .filter(|s| s.is_open(self.as_of))
                 .collect();
             records.sort_by(|a, b| {
                 a.e_sort
@@ -494,7 +595,7 @@ impl StationRepository for MemStationRepository {
         let mut seen: HashSet<i32> = HashSet::new();
         for &line_id in line_ids {
             for record in index::stations_by_line(line_id as i32) {
-                if record.e_status == 0 && seen.insert(record.station_g_cd) {
+                if record.is_open(self.as_of) && seen.insert(record.station_g_cd) {
                     group_ids.push(record.station_g_cd);
                 }
             }
@@ -503,13 +604,13 @@ impl StationRepository for MemStationRepository {
         let mut out = Vec::new();
         for group_id in group_ids {
             for record in index::stations_by_group(group_id) {
-                if record.e_status != 0 {
+                if !record.is_open(self.as_of) {
                     continue;
                 }
                 let Some(line) = index::line_by_cd(record.line_cd) else {
                     continue;
                 };
-                if line.e_status != 0 {
+                if !index::line_is_open(line, self.as_of) {
                     continue;
                 }
                 let mut station = record.to_entity(Some(line));
@@ -521,14 +622,22 @@ impl StationRepository for MemStationRepository {
         Ok(out)
     }
     async fn get_by_line_group_id(&self, line_group_id: u32) -> Result<Vec<Station>, DomainError> {
-        Ok(stations_of_line_groups(&[line_group_id]))
+        Ok(stations_of_line_groups(
+            &[line_group_id],
+            self.as_of,
+            self.service_day,
+        ))
     }
 
     async fn get_by_line_group_id_vec(
         &self,
         line_group_ids: &[u32],
     ) -> Result<Vec<Station>, DomainError> {
-        Ok(stations_of_line_groups(line_group_ids))
+        Ok(stations_of_line_groups(
+            line_group_ids,
+            self.as_of,
+            self.service_day,
+        ))
     }
     /// 発着の双方に停車する系統の停車駅を、路線をまたいだまま sst.id 順で返す。
     ///
@@ -548,9 +657,9 @@ impl StationRepository for MemStationRepository {
         // 双方の駅に通過ではない停車を持つ系統
         let stopping_groups = |group_id: u32| -> HashSet<i32> {
             index::stations_by_group(group_id as i32)
-                .filter(|s| s.e_status == 0)
+                .filter(|s| s.is_open(self.as_of))
                 .flat_map(|s| index::sst_by_station(s.station_cd))
-                .filter(|sst| sst.pass != Some(1))
+                .filter(|sst| stops_at(sst, self.service_day))
                 .filter_map(|sst| sst.line_group_cd)
                 .collect()
         };
@@ -564,7 +673,7 @@ impl StationRepository for MemStationRepository {
                 let Some(record) = index::station_by_cd(sst.station_cd) else {
                     continue;
                 };
-                if record.e_status != 0 {
+                if !record.is_open(self.as_of) {
                     continue;
                 }
                 if !via_line_ids.is_empty() && !via_line_ids.contains(&(record.line_cd as u32)) {
@@ -573,7 +682,7 @@ impl StationRepository for MemStationRepository {
                 let Some(line) = index::line_by_cd(record.line_cd) else {
                     continue;
                 };
-                if line.e_status != 0 {
+                if !index::line_is_open(line, self.as_of) {
                     continue;
                 }
                 // 種別が引けない系統は落とす
@@ -589,7 +698,7 @@ impl StationRepository for MemStationRepository {
             .into_iter()
             .map(|(sst, record, train_type)| {
                 let mut station = record.to_entity(index::line_by_cd(record.line_cd));
-                apply_train_type(&mut station, sst, train_type);
+                apply_train_type(&mut station, sst, train_type, self.service_day);
                 station
             })
             .collect())
@@ -616,7 +725,7 @@ impl StationRepository for MemStationRepository {
         // 双方の駅に通過ではない停車を持つ系統
         let groups_of = |station_cd: i32| -> HashSet<i32> {
             index::sst_by_station(station_cd)
-                .filter(|sst| sst.pass != Some(1))
+                .filter(|sst| stops_at(sst, self.service_day))
                 .filter_map(|sst| sst.line_group_cd)
                 .collect()
         };
@@ -635,15 +744,15 @@ impl StationRepository for MemStationRepository {
         // --- untyped: common_lines 上で種別経路に含まれない駅 ---
         // station_cd は一意なので common_lines は「両駅が同じ line_cd を持つか」に帰着する
         let mut untyped: Vec<&index::StationRecord> = Vec::new();
-        if from_record.e_status == 0
-            && to_record.e_status == 0
+        if from_record.is_open(self.as_of)
+            && to_record.is_open(self.as_of)
             && from_record.line_cd == to_record.line_cd
             && via_ok(from_record.line_cd)
-            && active_line(from_record.line_cd).is_some()
+            && active_line(from_record.line_cd, self.as_of).is_some()
         {
             untyped.extend(
                 index::stations_by_line(from_record.line_cd)
-                    .filter(|r| r.e_status == 0 && !excluded.contains(&r.station_cd)),
+                    .filter(|r| r.is_open(self.as_of) && !excluded.contains(&r.station_cd)),
             );
         }
         untyped.sort_by(|a, b| {
@@ -662,7 +771,7 @@ impl StationRepository for MemStationRepository {
                 let Some(record) = index::station_by_cd(sst.station_cd) else {
                     continue;
                 };
-                if record.e_status != 0 || !via_ok(record.line_cd) {
+                if !record.is_open(self.as_of) || !via_ok(record.line_cd) {
                     continue;
                 }
                 if index::type_by_cd(sst.type_cd).is_none() {
@@ -671,7 +780,7 @@ impl StationRepository for MemStationRepository {
                 let Some(line) = index::line_by_cd(record.line_cd) else {
                     continue;
                 };
-                if line.e_status != 0 {
+                if !index::line_is_open(line, self.as_of) {
                     continue;
                 }
                 typed.push((sst.id, record, sst));
@@ -685,12 +794,12 @@ impl StationRepository for MemStationRepository {
         // 種別なしの駅の後に種別ありの駅を連結する
         let mut out: Vec<Station> = untyped
             .into_iter()
-            .map(|record| record.to_entity(active_line(record.line_cd)))
+            .map(|record| record.to_entity(active_line(record.line_cd, self.as_of)))
             .collect();
         for (_, record, sst) in typed {
-            let mut station = record.to_entity(active_line(record.line_cd));
+            let mut station = record.to_entity(active_line(record.line_cd, self.as_of));
             if let Some(ty) = index::type_by_cd(sst.type_cd) {
-                apply_train_type(&mut station, sst, ty);
+                apply_train_type(&mut station, sst, ty, self.service_day);
             }
             out.push(station);
         }
@@ -700,17 +809,23 @@ impl StationRepository for MemStationRepository {
 
 // ---------------------------------------------------------------- 路線
 
+/// `as_of` / `service_day` は `MemStationRepository` と同じ。
 #[derive(Clone, Default)]
-pub struct MemLineRepository;
+pub struct MemLineRepository {
+    pub as_of: Option<Date>,
+    pub service_day: Option<ServiceDay>,
+}
 
 /// - 系統を 1 つも持たない駅 -> 通す
 /// - 系統を持つ駅 -> 停車する系統が 1 つでもあれば通す
-fn passes_stop_condition(station_cd: i32) -> bool {
+///
+/// `day` があれば、平日のみ・土休日のみの停車はその日に止まる系統だけを数える。
+fn passes_stop_condition(station_cd: i32, day: Option<ServiceDay>) -> bool {
     let mut has_group = false;
     for sst in index::sst_by_station(station_cd) {
         if sst.line_group_cd.is_some() {
             has_group = true;
-            if sst.pass != Some(1) {
+            if stops_at(sst, day) {
                 return true;
             }
         }
@@ -720,21 +835,22 @@ fn passes_stop_condition(station_cd: i32) -> bool {
 
 /// 駅グループに属する各駅の所属路線を、駅の識別子付きで返す。
 /// UseCase 層は `line.station_g_cd` で駅に紐付けるため、ここを埋める必要がある。
-fn lines_of_groups(group_ids: &[u32]) -> Vec<Line> {
+fn lines_of_groups(group_ids: &[u32], as_of: Option<Date>, day: Option<ServiceDay>) -> Vec<Line> {
     let mut out = Vec::new();
     for &gid in group_ids {
         for record in index::stations_by_group(gid as i32) {
-            if record.e_status != 0 {
+            if !record.is_open(as_of) {
                 continue;
             }
             let Some(line) = index::line_by_cd(record.line_cd) else {
                 continue;
             };
-            // 無効化された路線は返さない (例: 成田エクスプレスは e_status = 3)
-            if line.e_status != 0 {
+            // 営業していない路線は返さない (例: 成田エクスプレスは e_status = 3 で、
+            // 廃止日の 2021-03-13 より前を指定したときだけ出る)
+            if !index::line_is_open(line, as_of) {
                 continue;
             }
-            if !passes_stop_condition(record.station_cd) {
+            if !passes_stop_condition(record.station_cd, day) {
                 continue;
             }
             let mut line = line.clone();
@@ -754,7 +870,11 @@ impl LineRepository for MemLineRepository {
         &self,
         station_group_id_vec: &[u32],
     ) -> Result<Vec<Line>, DomainError> {
-        Ok(lines_of_groups(station_group_id_vec))
+        Ok(lines_of_groups(
+            station_group_id_vec,
+            self.as_of,
+            self.service_day,
+        ))
     }
 
     /// no_types 版が省くのはネストした駅に載せる種別情報だけで、返す路線の
@@ -764,7 +884,11 @@ impl LineRepository for MemLineRepository {
         &self,
         station_group_id_vec: &[u32],
     ) -> Result<Vec<Line>, DomainError> {
-        Ok(lines_of_groups(station_group_id_vec))
+        Ok(lines_of_groups(
+            station_group_id_vec,
+            self.as_of,
+            self.service_day,
+        ))
     }
 
     async fn get_by_station_group_id(
@@ -776,7 +900,7 @@ impl LineRepository for MemLineRepository {
 
     /// 無効な路線は返さない
     async fn find_by_id(&self, id: u32) -> Result<Option<Line>, DomainError> {
-        Ok(active_line(id as i32).cloned())
+        Ok(active_line(id as i32, self.as_of).cloned())
     }
 
     /// 無効な路線は ID を指定されても返さない。
@@ -786,7 +910,7 @@ impl LineRepository for MemLineRepository {
         Ok(ids
             .iter()
             .filter(|id| seen.insert(**id))
-            .filter_map(|&id| active_line(id as i32))
+            .filter_map(|&id| active_line(id as i32, self.as_of))
             .cloned()
             .collect())
     }
@@ -829,14 +953,14 @@ impl LineRepository for MemLineRepository {
                 let Some(station) = index::station_by_cd(sst.station_cd) else {
                     continue;
                 };
-                if station.e_status != 0 {
+                if !station.is_open(self.as_of) {
                     continue;
                 }
                 // l.line_cd = s.line_cd AND l.e_status = 0
                 let Some(line) = index::line_by_cd(station.line_cd) else {
                     continue;
                 };
-                if line.e_status != 0 {
+                if !index::line_is_open(line, self.as_of) {
                     continue;
                 }
                 let mut line = line.clone();
@@ -866,13 +990,13 @@ impl LineRepository for MemLineRepository {
                 let Some(station) = index::station_by_cd(sst.station_cd) else {
                     continue;
                 };
-                if station.e_status != 0 {
+                if !station.is_open(self.as_of) {
                     continue;
                 }
                 let Some(line) = index::line_by_cd(station.line_cd) else {
                     continue;
                 };
-                if line.e_status != 0 {
+                if !index::line_is_open(line, self.as_of) {
                     continue;
                 }
                 let mut line = line.clone();
@@ -901,7 +1025,7 @@ impl LineRepository for MemLineRepository {
             if out.len() >= limit {
                 break;
             }
-            if line.e_status != 0 {
+            if !index::line_is_open(line, self.as_of) {
                 continue;
             }
             let hit = line.line_name.contains(&line_name)
@@ -921,6 +1045,21 @@ impl LineRepository for MemLineRepository {
         }
         Ok(out)
     }
+
+    /// 路線の有効・無効は見ない。路線を引く側 (`get_by_ids`) で絞る。
+    async fn get_through_services_by_line_group_id(
+        &self,
+        line_group_id: u32,
+    ) -> Result<Vec<ThroughService>, DomainError> {
+        Ok(index::through_services_by_group(line_group_id as i32).to_vec())
+    }
+
+    async fn get_through_service_line_ids(&self, line_id: u32) -> Result<Vec<u32>, DomainError> {
+        Ok(index::through_service_lines(line_id as i32)
+            .iter()
+            .map(|&line_cd| line_cd as u32)
+            .collect())
+    }
 }
 
 // ---------------------------------------------------------------- 事業者
@@ -1136,3 +1275,187 @@ impl TrainTypeRepository for MemTrainTypeRepository {
         Ok(out)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stationapi::use_case::traits::query::QueryUseCase;
+
+    const NARITA_EXPRESS: u32 = 11328;
+    const SANKO_LINE: u32 = 11707;
+
+    fn lines_on(as_of: Option<&str>) -> MemLineRepository {
+        MemLineRepository {
+            as_of: as_of.and_then(Date::parse),
+            service_day: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn disabled_line_with_closing_date_appears_before_it() {
+        let line = lines_on(Some("2021-03-12"))
+            .find_by_id(NARITA_EXPRESS)
+            .await
+            .unwrap();
+        assert_eq!(line.unwrap().line_name, "成田エクスプレス");
+        for as_of in [None, Some("2021-03-13")] {
+            let line = lines_on(as_of).find_by_id(NARITA_EXPRESS).await.unwrap();
+            assert!(line.is_none(), "{as_of:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn disabled_line_joins_station_group_before_closing_date() {
+        let shinjuku = index::station_by_cd(1132806).unwrap().station_g_cd as u32;
+        let names =
+            |lines: Vec<Line>| -> Vec<String> { lines.into_iter().map(|l| l.line_name).collect() };
+        let before = lines_on(Some("2020-01-01"))
+            .get_by_station_group_id(shinjuku)
+            .await
+            .unwrap();
+        assert!(names(before).contains(&"成田エクスプレス".to_string()));
+        let after = lines_on(None)
+            .get_by_station_group_id(shinjuku)
+            .await
+            .unwrap();
+        assert!(!names(after).contains(&"成田エクスプレス".to_string()));
+    }
+
+    /// (直通元, 直通先, 直通駅)
+    async fn through_services(line_group_id: u32) -> Vec<(String, String, String)> {
+        crate::interactor_for(None, None)
+            .get_through_services(line_group_id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|s| {
+                (
+                    s.from_line.line_name,
+                    s.to_line.line_name,
+                    s.station.station_name,
+                )
+            })
+            .collect()
+    }
+
+    fn owned(services: &[(&str, &str, &str)]) -> Vec<(String, String, String)> {
+        services
+            .iter()
+            .map(|&(a, b, c)| (a.to_string(), b.to_string(), c.to_string()))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn through_services_from_den_en_toshi_to_hanzomon_line() {
+        assert_eq!(
+            through_services(162).await,
+            owned(&[
+                ("東急田園都市線", "東京メトロ半蔵門線", "渋谷"),
+                (
+                    "東京メトロ半蔵門線",
+                    "東武伊勢崎線",
+                    "押上〈スカイツリー前〉"
+                ),
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn through_services_do_not_flip_flop_between_shared_stations() {
+        assert_eq!(
+            through_services(300).await,
+            owned(&[
+                ("JR宝塚線", "福知山線", "篠山口"),
+                ("福知山線", "山陰本線", "福知山"),
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn closed_line_without_closing_date_stays_listed() {
+        let repo = lines_on(Some("2024-01-01"));
+        let line = repo.find_by_id(SANKO_LINE).await.unwrap();
+        assert_eq!(line.unwrap().line_name, "三江線");
+        assert!(lines_on(None)
+            .find_by_id(SANKO_LINE)
+            .await
+            .unwrap()
+            .is_none());
+
+        let stations = MemStationRepository {
+            as_of: Date::parse("2024-01-01"),
+            service_day: None,
+        }
+        .get_by_line_id(SANKO_LINE, None, None)
+        .await
+        .unwrap();
+        assert_eq!(stations.len(), 35);
+    }
+
+    /// 渋谷駅と神泉駅を囲む表示範囲。
+    fn around_shibuya() -> BoundingBox {
+        BoundingBox::new(35.655, 139.690, 35.662, 139.706).unwrap()
+    }
+
+    async fn in_bounds(line_type: Option<i32>, limit: u32, dedupe: bool) -> Vec<(String, String)> {
+        MemStationRepository::default()
+            .get_in_bounds(around_shibuya(), line_type, None, limit, dedupe)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|s| (s.station_name, s.line_name.unwrap_or_default()))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn in_bounds_dedupes_by_group_before_the_limit() {
+        let all = in_bounds(None, 100, false).await;
+        assert_eq!(all.len(), 12);
+
+        // 駅グループごとに中心に近い 1 駅。件数で切る前に重複を落とすので神泉も残る
+        let deduped = in_bounds(None, 2, true).await;
+        let names: Vec<&str> = deduped.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["渋谷", "神泉"]);
+        assert_eq!(deduped[0], all[0]);
+
+        assert_eq!(in_bounds(None, 3, false).await, all[..3]);
+    }
+
+    #[tokio::test]
+    async fn in_bounds_filters_by_line_type() {
+        // 3 = 地下鉄
+        let mut subway: Vec<String> = in_bounds(Some(3), 100, false)
+            .await
+            .into_iter()
+            .map(|(_, line)| line)
+            .collect();
+        subway.sort();
+        assert_eq!(
+            subway,
+            [
+                "東京メトロ副都心線",
+                "東京メトロ半蔵門線",
+                "東京メトロ銀座線"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn stations_in_bounds_caps_the_limit() {
+        use stationapi::domain::entity::gtfs::TransportTypeFilter;
+        use stationapi::use_case::interactor::query::STATIONS_IN_BOUNDS_MAX_LIMIT;
+
+        let japan = BoundingBox::new(20.0, 122.0, 46.0, 154.0).unwrap();
+        let list = crate::interactor_for(None, None)
+            .get_stations_in_bounds(
+                japan,
+                None,
+                Some(u32::MAX),
+                false,
+                TransportTypeFilter::Rail,
+            )
+            .await
+            .unwrap();
+        assert_eq!(list.stations.len(), STATIONS_IN_BOUNDS_MAX_LIMIT as usize);
+    }
+}
@@ -1,9 +1,17 @@
+pub mod announcement;
 pub mod arrival_estimation;
+pub mod calendar;
 pub mod entity;
 pub mod error;
+pub mod geo;
 pub mod ipa;
+pub mod journey;
+pub mod name_search;
 pub mod normalize;
 pub mod repository;
 pub mod romaji;
 pub mod segment_speed_table;
+pub mod service_period;
 pub mod speed_table;
+pub mod stop_pattern;
+pub mod timetable;
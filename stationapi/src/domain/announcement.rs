@@ -0,0 +1,327 @@
+//! 駅の案内放送の SSML。
+//!
+//! `ipa.rs` の 2 つの読みを音声の言語で使い分ける。日本語音声には honest な ɾ の
+//! `name_ipa` を、英語音声にはら行を l にした TTS セグメントを渡す。駅ナンバリングは
+//! 1 文字ずつ読ませる。`<voice>` は音声エンジンごとに名前が違うので付けない。
+
+use super::ipa::{compute_ipa_cached, TtsAlphabetKind, TtsNameSegment};
+
+/// 読み上げる音声の言語。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnnouncementLang {
+    Japanese,
+    English,
+}
+
+impl AnnouncementLang {
+    /// `ja-JP` / `en-US` を読む。大文字・小文字は問わない。
+    pub fn parse(tag: &str) -> Option<Self> {
+        let tag = tag.trim();
+        if tag.eq_ignore_ascii_case("ja-JP") {
+            Some(Self::Japanese)
+        } else if tag.eq_ignore_ascii_case("en-US") {
+            Some(Self::English)
+        } else {
+            None
+        }
+    }
+
+    pub fn tag(self) -> &'static str {
+        match self {
+            Self::Japanese => "ja-JP",
+            Self::English => "en-US",
+        }
+    }
+}
+
+/// 案内の文面。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnnouncementTemplate {
+    /// 次は〇〇 / The next station is 〇〇
+    NextStation,
+    /// まもなく〇〇 / We will soon be arriving at 〇〇
+    Arriving,
+}
+
+/// 案内に使う駅の情報。
+#[derive(Clone, Copy, Debug)]
+pub struct AnnouncedStation<'a> {
+    pub name: &'a str,
+    pub name_katakana: &'a str,
+    pub name_roman: Option<&'a str>,
+    /// `JY-17` のような駅ナンバリング。空なら読まない。
+    pub station_numbers: &'a [String],
+}
+
+/// `<speak>` で包んだ SSML を返す。
+pub fn announcement_ssml(
+    station: &AnnouncedStation<'_>,
+    lang: AnnouncementLang,
+    template: AnnouncementTemplate,
+) -> String {
+    let name = match lang {
+        AnnouncementLang::Japanese => japanese_name(station),
+        AnnouncementLang::English => english_name(station),
+    };
+    let numbers = station_numbers(station.station_numbers, lang);
+    let body = match (lang, template) {
+        (AnnouncementLang::Japanese, AnnouncementTemplate::NextStation) => {
+            format!("次は、{name}{}です。", with_prefix("、", &numbers))
+        }
+        (AnnouncementLang::Japanese, AnnouncementTemplate::Arriving) => {
+            format!("まもなく、{name}{}です。", with_prefix("、", &numbers))
+        }
+        (AnnouncementLang::English, AnnouncementTemplate::NextStation) => format!(
+            "The next station is {name}{}.",
+            with_prefix(", station number ", &numbers)
+        ),
+        (AnnouncementLang::English, AnnouncementTemplate::Arriving) => format!(
+            "We will soon be arriving at {name}{}.",
+            with_prefix(", station number ", &numbers)
+        ),
+    };
+    format!(
+        r#"<speak version="1.0" xmlns="http://www.w3.org/2001/10/synthesis" xml:lang="{}">{body}</speak>"#,
+        lang.tag()
+    )
+}
+
+fn with_prefix(prefix: &str, value: &str) -> String {
+    if value.is_empty() {
+        String::new()
+    } else {
+        format!("{prefix}{value}")
+    }
+}
+
+/// 日本語音声は表記 (漢字) を読みの IPA で包む。IPA にできない名前はカナで読ませる。
+fn japanese_name(station: &AnnouncedStation<'_>) -> String {
+    let ipa = compute_ipa_cached(station.name_katakana, station.name_roman);
+    match &ipa.name_ipa {
+        Some(name_ipa) => phoneme(name_ipa, station.name),
+        None if !station.name_katakana.is_empty() => format!(
+            r#"<sub alias="{}">{}</sub>"#,
+            escape(station.name_katakana),
+            escape(station.name)
+        ),
+        None => escape(station.name),
+    }
+}
+
+/// 英語音声はセグメントごとにその記法で読ませる。
+/// セグメントが作れない名前はローマ字表記をそのまま読ませる。
+fn english_name(station: &AnnouncedStation<'_>) -> String {
+    let ipa = compute_ipa_cached(station.name_katakana, station.name_roman);
+    if ipa.tts_segments.is_empty() {
+        return escape(
+            station
+                .name_roman
+                .filter(|name| !name.trim().is_empty())
+                .unwrap_or(station.name),
+        );
+    }
+    ipa.tts_segments
+        .iter()
+        .map(|segment| format!("{}{}", segment_ssml(segment), escape(&segment.separator)))
+        .collect()
+}
+
+fn segment_ssml(segment: &TtsNameSegment) -> String {
+    if segment.pronunciation.is_empty() {
+        return escape(&segment.surface);
+    }
+    match segment.alphabet {
+        TtsAlphabetKind::Ipa => phoneme(&segment.pronunciation, &segment.surface),
+        TtsAlphabetKind::Yomigana => format!(
+            r#"<sub alias="{}">{}</sub>"#,
+            escape(&segment.pronunciation),
+            escape(&segment.surface)
+        ),
+        TtsAlphabetKind::Plain => escape(&segment.surface),
+    }
+}
+
+fn phoneme(ipa: &str, surface: &str) -> String {
+    format!(
+        r#"<phoneme alphabet="ipa" ph="{}">{}</phoneme>"#,
+        escape(ipa),
+        escape(surface)
+    )
+}
+
+/// 駅ナンバリングは区切りのハイフンを落として 1 文字ずつ読ませる (JY-17 → J Y 1 7)。
+fn station_numbers(numbers: &[String], lang: AnnouncementLang) -> String {
+    let separator = match lang {
+        AnnouncementLang::Japanese => "、",
+        AnnouncementLang::English => " and ",
+    };
+    numbers
+        .iter()
+        .map(|number| number.replace('-', ""))
+        .filter(|number| !number.trim().is_empty())
+        .map(|number| {
+            format!(
+                r#"<say-as interpret-as="characters">{}</say-as>"#,
+                escape(number.trim())
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+/// テキストと属性値のどちらにも使えるようにエスケープする。
+fn escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn station<'a>(
+        name: &'a str,
+        name_katakana: &'a str,
+        name_roman: Option<&'a str>,
+        station_numbers: &'a [String],
+    ) -> AnnouncedStation<'a> {
+        AnnouncedStation {
+            name,
+            name_katakana,
+            name_roman,
+            station_numbers,
+        }
+    }
+
+    #[test]
+    fn parse_accepts_supported_language_tags() {
+        assert_eq!(
+            AnnouncementLang::parse("ja-JP"),
+            Some(AnnouncementLang::Japanese)
+        );
+        assert_eq!(
+            AnnouncementLang::parse("en-us"),
+            Some(AnnouncementLang::English)
+        );
+        assert_eq!(AnnouncementLang::parse("ja"), None);
+        assert_eq!(AnnouncementLang::parse("zh-CN"), None);
+    }
+
+    #[test]
+    fn japanese_voice_reads_honest_ipa_over_the_kanji_name() {
+        let numbers = vec!["JY-17".to_string()];
+        let ssml = announcement_ssml(
+            &station("新宿", "シンジュク", Some("Shinjuku"), &numbers),
+            AnnouncementLang::Japanese,
+            AnnouncementTemplate::NextStation,
+        );
+        assert_eq!(
+            ssml,
+            concat!(
+                r#"<speak version="1.0" xmlns="http://www.w3.org/2001/10/synthesis" xml:lang="ja-JP">"#,
+                r#"次は、<phoneme alphabet="ipa" ph="ɕiɲdʑɯkɯ">新宿</phoneme>、"#,
+                r#"<say-as interpret-as="characters">JY17</say-as>です。</speak>"#
+            )
+        );
+    }
+
+    #[test]
+    fn japanese_voice_keeps_the_flap_and_english_voice_lateralizes_it() {
+        let ja = announcement_ssml(
+            &station("取手", "トリデ", Some("Toride"), &[]),
+            AnnouncementLang::Japanese,
+            AnnouncementTemplate::Arriving,
+        );
+        assert!(ja.contains(r#"ph="toɾide""#), "{ja}");
+        assert!(ja.contains("まもなく、"), "{ja}");
+
+        let en = announcement_ssml(
+            &station("取手", "トリデ", Some("Toride"), &[]),
+            AnnouncementLang::English,
+            AnnouncementTemplate::Arriving,
+        );
+        assert!(en.contains(r#"ph="tolide">Toride</phoneme>"#), "{en}");
+        assert!(en.contains("We will soon be arriving at "), "{en}");
+        assert!(!en.contains("station number"), "{en}");
+    }
+
+    #[test]
+    fn english_voice_wraps_each_segment_and_keeps_separators() {
+        let numbers = vec!["JY-01".to_string(), "JK-26".to_string()];
+        let ssml = announcement_ssml(
+            &station("東京", "トウキョウ", Some("Tokyo"), &numbers),
+            AnnouncementLang::English,
+            AnnouncementTemplate::NextStation,
+        );
+        assert!(
+            ssml.starts_with(
+                r#"<speak version="1.0" xmlns="http://www.w3.org/2001/10/synthesis" xml:lang="en-US">The next station is <phoneme alphabet="ipa""#
+            ),
+            "{ssml}"
+        );
+        assert!(
+            ssml.ends_with(concat!(
+                r#">Tokyo</phoneme>, station number <say-as interpret-as="characters">JY01</say-as>"#,
+                r#" and <say-as interpret-as="characters">JK26</say-as>.</speak>"#
+            )),
+            "{ssml}"
+        );
+
+        let multi = announcement_ssml(
+            &station(
+                "葛西臨海公園",
+                "カサイリンカイコウエン",
+                Some("Kasai-Rinkai Park"),
+                &[],
+            ),
+            AnnouncementLang::English,
+            AnnouncementTemplate::NextStation,
+        );
+        assert_eq!(multi.matches("<phoneme ").count(), 3, "{multi}");
+        assert!(multi.contains(r#">Kasai</phoneme> <phoneme"#), "{multi}");
+        assert!(multi.contains(r#">Rinkai</phoneme> <phoneme"#), "{multi}");
+    }
+
+    #[test]
+    fn names_without_ipa_fall_back_to_readable_text() {
+        let ja = announcement_ssml(
+            &station("Ｘ駅", "Ｘ", None, &[]),
+            AnnouncementLang::Japanese,
+            AnnouncementTemplate::NextStation,
+        );
+        assert!(ja.contains(r#"<sub alias="Ｘ">Ｘ駅</sub>"#), "{ja}");
+
+        let en = announcement_ssml(
+            &station("Ｘ駅", "Ｘ", None, &[]),
+            AnnouncementLang::English,
+            AnnouncementTemplate::NextStation,
+        );
+        assert!(en.contains("The next station is Ｘ駅."), "{en}");
+    }
+
+    #[test]
+    fn escapes_markup_in_text_and_attributes() {
+        assert_eq!(
+            escape(r#"A&B <"x"> 'y'"#),
+            "A&amp;B &lt;&quot;x&quot;&gt; &apos;y&apos;"
+        );
+        let numbers = vec!["<1>".to_string()];
+        let ssml = announcement_ssml(
+            &station("A&B", "", None, &numbers),
+            AnnouncementLang::Japanese,
+            AnnouncementTemplate::NextStation,
+        );
+        assert!(ssml.contains("次は、A&amp;B、"), "{ssml}");
+        assert!(ssml.contains(">&lt;1&gt;</say-as>"), "{ssml}");
+    }
+}
@@ -169,26 +169,26 @@ pub fn is_circular_route(stops: &[&Station]) -> bool {
     if stops[0].station_cd == stops[n - 1].station_cd {
         return false;
     }
-    let mut gaps: Vec<f64> = (1..n)
-        .map(|i| {
-            haversine_distance(
-                stops[i - 1].lat,
-                stops[i - 1].lon,
-                stops[i].lat,
-                stops[i].lon,
-            )
-        })
-        .collect();
+    let points: Vec<(f64, f64)> = stops.iter().map(|s| (s.lat, s.lon)).collect();
+    is_circular_path(&points)
+}
+
+/// `is_circular_route` の座標だけの版。`(緯度, 経度)` の並びを受け取る。
+///
+/// 駅エンティティを持たないデータ検査 (`data_validator`) からも同じ基準で
+/// 環状を判定するために分けてある。
+pub fn is_circular_path(points: &[(f64, f64)]) -> bool {
+    let n = points.len();
+    if n < CIRCULAR_MIN_STATIONS {
+        return false;
+    }
+    let distance = |a: (f64, f64), b: (f64, f64)| haversine_distance(a.0, a.1, b.0, b.1);
+    let mut gaps: Vec<f64> = (1..n).map(|i| distance(points[i - 1], points[i])).collect();
     let mean_gap = gaps.iter().sum::<f64>() / gaps.len() as f64;
     if mean_gap <= 0.0 {
         return false;
     }
-    let seam = haversine_distance(
-        stops[n - 1].lat,
-        stops[n - 1].lon,
-        stops[0].lat,
-        stops[0].lon,
-    );
+    let seam = distance(points[n - 1], points[0]);
     if seam > CIRCULAR_SEAM_MAX_METERS || seam > CIRCULAR_SEAM_MEAN_RATIO * mean_gap {
         return false;
     }
@@ -409,7 +409,8 @@ fn is_stop(station: &Station, is_endpoint: bool) -> bool {
         return true;
     }
     // 通過: pass == Some(1) もしくは stop_condition == Not。
-    // Partial/Weekday/Holiday は MVP では停車扱い(将来は曜日入力で精緻化)。
+    // 運転日を指定すると Weekday/Holiday は All/Not に解かれて届く。
+    // 解かれていない Partial/Weekday/Holiday は停車扱い。
     station.pass != Some(1) && station.stop_condition != StopCondition::Not
 }
 
@@ -0,0 +1,262 @@
+//! 日付の計算。
+//!
+//! 外部クレートを入れずに済むよう、先発グレゴリオ暦の日付だけを扱う。
+//! 通日は 1970-01-01 を 0 とする (Howard Hinnant の days_from_civil と同じ)。
+//! 祝日は [`holiday`]、鉄道から見た平日・土曜・休日の区分は [`DayKind`] で求める。
+
+use std::fmt;
+
+use crate::model::StopCondition;
+
+pub mod holiday;
+
+pub use holiday::{is_public_holiday, is_year_end_holiday, Holiday};
+
+/// 暦日。時刻やタイムゾーンは持たない (日本の日付として扱う)。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    /// 存在しない日付 (2 月 30 日など) は `None`。
+    pub fn new(year: i32, month: u32, day: u32) -> Option<Self> {
+        if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+            return None;
+        }
+        Some(Self { year, month, day })
+    }
+
+    /// `YYYY-MM-DD` と GTFS の `YYYYMMDD` のどちらも読む。
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        // 利用者の入力が来るので、バイト位置で切る前にマルチバイト文字を弾く
+        if !value.is_ascii() {
+            return None;
+        }
+        let (year, month, day) = match value.len() {
+            10 if value.as_bytes()[4] == b'-' && value.as_bytes()[7] == b'-' => {
+                (&value[0..4], &value[5..7], &value[8..10])
+            }
+            8 => (&value[0..4], &value[4..6], &value[6..8]),
+            _ => return None,
+        };
+        if ![year, month, day]
+            .iter()
+            .all(|part| part.bytes().all(|b| b.is_ascii_digit()))
+        {
+            return None;
+        }
+        Self::new(year.parse().ok()?, month.parse().ok()?, day.parse().ok()?)
+    }
+
+    /// 曜日。0 = 月曜 … 6 = 日曜 (`GtfsCalendar::runs_on_weekday` と同じ並び)。
+    pub fn weekday(self) -> u32 {
+        // 1970-01-01 は木曜
+        (self.days_since_epoch() + 3).rem_euclid(7) as u32
+    }
+
+    /// 前日。
+    pub fn pred(self) -> Self {
+        Self::from_days_since_epoch(self.days_since_epoch() - 1)
+    }
+
+    /// 翌日。
+    pub fn succ(self) -> Self {
+        Self::from_days_since_epoch(self.days_since_epoch() + 1)
+    }
+
+    /// `days` 日後 (負なら前)。
+    pub fn add_days(self, days: i64) -> Self {
+        Self::from_days_since_epoch(self.days_since_epoch() + days)
+    }
+
+    /// GTFS の `calendar.txt` / `calendar_dates.txt` と同じ `YYYYMMDD`。
+    pub fn to_gtfs(self) -> String {
+        format!("{:04}{:02}{:02}", self.year, self.month, self.day)
+    }
+
+    pub fn days_since_epoch(self) -> i64 {
+        let y = i64::from(self.year) - i64::from(self.month <= 2);
+        let era = y.div_euclid(400);
+        let yoe = y - era * 400;
+        let m = i64::from(self.month);
+        let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + i64::from(self.day) - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        era * 146_097 + doe - 719_468
+    }
+
+    pub fn from_days_since_epoch(days: i64) -> Self {
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z - era * 146_097;
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = (yoe + era * 400 + i64::from(month <= 2)) as i32;
+        Self { year, month, day }
+    }
+}
+
+/// `YYYY-MM-DD`。駅の開業日 (`open_ymd`) と同じ書式。
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+pub fn is_leap_year(year: i32) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+pub fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        _ => 0,
+    }
+}
+
+/// 鉄道から見た日の区分。日曜・祝日・年末年始 (12/30〜1/3) を休日とし、
+/// 土曜は平日とも休日とも別に扱う (土曜ダイヤを持つ事業者がある)。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DayKind {
+    Weekday,
+    Saturday,
+    Holiday,
+}
+
+impl DayKind {
+    pub fn of(date: Date) -> Self {
+        if date.weekday() == 6 || is_public_holiday(date) || is_year_end_holiday(date) {
+            Self::Holiday
+        } else if date.weekday() == 5 {
+            Self::Saturday
+        } else {
+            Self::Weekday
+        }
+    }
+}
+
+/// 平日ダイヤか土休日ダイヤか。土曜は土休日ダイヤで走る。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ServiceDay {
+    Weekday,
+    Holiday,
+}
+
+impl ServiceDay {
+    pub fn of(date: Date) -> Self {
+        match DayKind::of(date) {
+            DayKind::Weekday => Self::Weekday,
+            DayKind::Saturday | DayKind::Holiday => Self::Holiday,
+        }
+    }
+
+    /// 平日のみ・土休日のみの停車を、この日に止まる (`All`) か止まらない (`Not`) かに解く。
+    /// 一部停車のように日付で決まらない条件はそのまま返す。
+    pub fn resolve(self, condition: StopCondition) -> StopCondition {
+        match (condition, self) {
+            (StopCondition::Weekday, Self::Weekday) | (StopCondition::Holiday, Self::Holiday) => {
+                StopCondition::All
+            }
+            (StopCondition::Weekday, Self::Holiday) | (StopCondition::Holiday, Self::Weekday) => {
+                StopCondition::Not
+            }
+            (other, _) => other,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> Date {
+        Date::new(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn parse_accepts_iso_and_gtfs_forms() {
+        assert_eq!(Date::parse("2024-02-29"), Some(date(2024, 2, 29)));
+        assert_eq!(Date::parse("20240229"), Some(date(2024, 2, 29)));
+        assert_eq!(Date::parse("2023-02-29"), None);
+        assert_eq!(Date::parse("2024/02/29"), None);
+        assert_eq!(Date::parse("2024-1-1"), None);
+        assert_eq!(Date::parse("0000-00-00"), None);
+    }
+
+    #[test]
+    fn parse_rejects_multibyte_input_without_panicking() {
+        // 8 バイトと 10 バイトで、文字境界がバイト位置とずれるもの
+        assert_eq!(Date::parse("abcéxyz"), None);
+        assert_eq!(Date::parse("2024年2"), None);
+        assert_eq!(Date::parse("2024-é-29"), None);
+        assert_eq!(Date::parse("2024-0é-9"), None);
+    }
+
+    #[test]
+    fn weekday_starts_on_monday() {
+        assert_eq!(date(1970, 1, 1).weekday(), 3); // 木曜
+        assert_eq!(date(2024, 1, 1).weekday(), 0); // 月曜
+        assert_eq!(date(2024, 3, 24).weekday(), 6); // 日曜
+    }
+
+    #[test]
+    fn pred_and_succ_cross_month_and_year() {
+        assert_eq!(date(2024, 3, 1).pred(), date(2024, 2, 29));
+        assert_eq!(date(2023, 12, 31).succ(), date(2024, 1, 1));
+        assert_eq!(date(2024, 1, 1).add_days(-366), date(2022, 12, 31));
+    }
+
+    #[test]
+    fn days_since_epoch_round_trips() {
+        for days in [-800_000, -1, 0, 1, 19_723, 60_000] {
+            assert_eq!(Date::from_days_since_epoch(days).days_since_epoch(), days);
+        }
+    }
+
+    #[test]
+    fn day_kind_separates_saturdays_and_year_end() {
+        assert_eq!(DayKind::of(date(2024, 6, 3)), DayKind::Weekday);
+        assert_eq!(DayKind::of(date(2024, 6, 1)), DayKind::Saturday);
+        assert_eq!(DayKind::of(date(2024, 6, 2)), DayKind::Holiday);
+        assert_eq!(DayKind::of(date(2024, 2, 12)), DayKind::Holiday); // 振替休日
+        assert_eq!(DayKind::of(date(2024, 12, 30)), DayKind::Holiday); // 年末
+        assert_eq!(DayKind::of(date(2025, 1, 3)), DayKind::Holiday); // 年始
+        assert_eq!(DayKind::of(date(2025, 1, 6)), DayKind::Weekday);
+    }
+
+    #[test]
+    fn service_day_resolves_weekday_and_holiday_stops() {
+        let weekday = ServiceDay::of(date(2024, 6, 3));
+        let saturday = ServiceDay::of(date(2024, 6, 1));
+        let holiday = ServiceDay::of(date(2024, 2, 12));
+        assert_eq!(weekday, ServiceDay::Weekday);
+        assert_eq!(saturday, ServiceDay::Holiday);
+        assert_eq!(holiday, ServiceDay::Holiday);
+        assert_eq!(ServiceDay::of(date(2024, 12, 31)), ServiceDay::Holiday);
+
+        assert_eq!(weekday.resolve(StopCondition::Weekday), StopCondition::All);
+        assert_eq!(weekday.resolve(StopCondition::Holiday), StopCondition::Not);
+        assert_eq!(holiday.resolve(StopCondition::Weekday), StopCondition::Not);
+        assert_eq!(holiday.resolve(StopCondition::Holiday), StopCondition::All);
+        assert_eq!(
+            holiday.resolve(StopCondition::Partial),
+            StopCondition::Partial
+        );
+        assert_eq!(holiday.resolve(StopCondition::Not), StopCondition::Not);
+    }
+
+    #[test]
+    fn formats() {
+        assert_eq!(date(2024, 4, 1).to_string(), "2024-04-01");
+        assert_eq!(date(2024, 4, 1).to_gtfs(), "20240401");
+    }
+}
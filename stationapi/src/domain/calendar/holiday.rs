@@ -0,0 +1,324 @@
+//! 休日の判定。
+//!
+//! 「国民の祝日に関する法律」(1948-07-20 施行) の改正を年ごとにたどり、
+//! 国民の祝日・振替休日・国民の休日を求める。皇室の慶弔で一度だけ休日になった日と、
+//! 東京オリンピック・パラリンピックで祝日が移った 2020・2021 年も含む。
+//! 春分日・秋分日は国立天文台の暦要項に合う近似式で、1900〜2150 年の範囲で求まる。
+
+use super::Date;
+
+/// 休日の種類。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Holiday {
+    /// 国民の祝日 (名前つき)。
+    National(&'static str),
+    /// 祝日が日曜に当たったときの振替休日。
+    Substitute,
+    /// 祝日に挟まれた平日 (国民の休日)。
+    Citizens,
+}
+
+impl Holiday {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::National(name) => name,
+            Self::Substitute => "振替休日",
+            Self::Citizens => "国民の休日",
+        }
+    }
+}
+
+/// 祝日法が休日とする日か。
+pub fn holiday(date: Date) -> Option<Holiday> {
+    if let Some(name) = national_holiday(date) {
+        return Some(Holiday::National(name));
+    }
+    if is_substitute_holiday(date) {
+        return Some(Holiday::Substitute);
+    }
+    if is_citizens_holiday(date) {
+        return Some(Holiday::Citizens);
+    }
+    None
+}
+
+/// 国民の祝日・振替休日・国民の休日のどれかか。
+pub fn is_public_holiday(date: Date) -> bool {
+    holiday(date).is_some()
+}
+
+/// 年末年始 (12/30〜1/3)。祝日ではないが、多くの事業者が休日ダイヤで走る。
+pub fn is_year_end_holiday(date: Date) -> bool {
+    matches!((date.month, date.day), (12, 30..=31) | (1, 1..=3))
+}
+
+/// 国民の祝日の名前。
+fn national_holiday(date: Date) -> Option<&'static str> {
+    // 祝日法の施行日より前に祝日は無い
+    if date < ymd(1948, 7, 20) {
+        return None;
+    }
+    if let Some(name) = one_off_holiday(date) {
+        return Some(name);
+    }
+    let Date { year, month, day } = date;
+    let name = match (month, day) {
+        (1, 1) => "元日",
+        (1, _) if is_coming_of_age_day(date) => "成人の日",
+        (2, 11) if year >= 1967 => "建国記念の日",
+        (2, 23) if year >= 2020 => "天皇誕生日",
+        (3, _) if equinox_day(year, Equinox::Vernal) == Some(day) => "春分の日",
+        (4, 29) if year <= 1988 => "天皇誕生日",
+        (4, 29) if year <= 2006 => "みどりの日",
+        (4, 29) => "昭和の日",
+        (5, 3) => "憲法記念日",
+        (5, 4) if year >= 2007 => "みどりの日",
+        (5, 5) => "こどもの日",
+        (7, _) if is_marine_day(date) => "海の日",
+        (7, _) | (10, _) if is_sports_day(date) => {
+            if year >= 2020 {
+                "スポーツの日"
+            } else {
+                "体育の日"
+            }
+        }
+        (8, _) if is_mountain_day(date) => "山の日",
+        (9, _) if is_respect_for_the_aged_day(date) => "敬老の日",
+        (9, _) if equinox_day(year, Equinox::Autumnal) == Some(day) => "秋分の日",
+        (11, 3) => "文化の日",
+        (11, 23) => "勤労感謝の日",
+        (12, 23) if (1989..=2018).contains(&year) => "天皇誕生日",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// 皇室の慶弔などで、その年だけ休日になった日。
+fn one_off_holiday(date: Date) -> Option<&'static str> {
+    match (date.year, date.month, date.day) {
+        (1959, 4, 10) => Some("皇太子明仁親王の結婚の儀"),
+        (1989, 2, 24) => Some("昭和天皇の大喪の礼"),
+        (1990, 11, 12) => Some("即位礼正殿の儀"),
+        (1993, 6, 9) => Some("皇太子徳仁親王の結婚の儀"),
+        (2019, 5, 1) => Some("天皇の即位の日"),
+        (2019, 10, 22) => Some("即位礼正殿の儀"),
+        _ => None,
+    }
+}
+
+/// 1/15 から 2000 年に 1 月第 2 月曜へ。
+fn is_coming_of_age_day(date: Date) -> bool {
+    if date.year >= 2000 {
+        is_nth_monday(date, 2)
+    } else {
+        date.month == 1 && date.day == 15
+    }
+}
+
+/// 1996 年に 7/20 で始まり、2003 年から 7 月第 3 月曜。2020・2021 年は五輪で移した。
+fn is_marine_day(date: Date) -> bool {
+    match date.year {
+        ..=1995 => false,
+        1996..=2002 => date.month == 7 && date.day == 20,
+        2020 => date.month == 7 && date.day == 23,
+        2021 => date.month == 7 && date.day == 22,
+        _ => date.month == 7 && is_nth_monday(date, 3),
+    }
+}
+
+/// 1966 年に 10/10 の体育の日で始まり、2000 年から 10 月第 2 月曜。
+/// 2020 年にスポーツの日へ改め、2020・2021 年は五輪で 7 月に移した。
+fn is_sports_day(date: Date) -> bool {
+    match date.year {
+        ..=1965 => false,
+        1966..=1999 => date.month == 10 && date.day == 10,
+        2020 => date.month == 7 && date.day == 24,
+        2021 => date.month == 7 && date.day == 23,
+        _ => date.month == 10 && is_nth_monday(date, 2),
+    }
+}
+
+/// 2016 年から 8/11。2020・2021 年は五輪で移した。
+fn is_mountain_day(date: Date) -> bool {
+    match date.year {
+        ..=2015 => false,
+        2020 => date.month == 8 && date.day == 10,
+        2021 => date.month == 8 && date.day == 8,
+        _ => date.month == 8 && date.day == 11,
+    }
+}
+
+/// 1966 年に 9/15 で始まり、2003 年から 9 月第 3 月曜。
+fn is_respect_for_the_aged_day(date: Date) -> bool {
+    match date.year {
+        ..=1965 => false,
+        1966..=2002 => date.month == 9 && date.day == 15,
+        _ => date.month == 9 && is_nth_monday(date, 3),
+    }
+}
+
+/// 振替休日。1973-04-12 からは日曜の祝日の翌月曜、2007 年からは日曜の祝日のあと
+/// 最初の祝日でない日。
+fn is_substitute_holiday(date: Date) -> bool {
+    if date < ymd(1973, 4, 12) || national_holiday(date).is_some() {
+        return false;
+    }
+    if date.year < 2007 {
+        let sunday = date.pred();
+        return sunday.weekday() == 6 && national_holiday(sunday).is_some();
+    }
+    let mut day = date.pred();
+    while national_holiday(day).is_some() {
+        if day.weekday() == 6 {
+            return true;
+        }
+        day = day.pred();
+    }
+    false
+}
+
+/// 国民の休日。1985-12-27 から、前日と翌日が祝日の日。2006 年までは日曜を除く。
+fn is_citizens_holiday(date: Date) -> bool {
+    if date < ymd(1985, 12, 27) || national_holiday(date).is_some() {
+        return false;
+    }
+    if date.year < 2007 && date.weekday() == 6 {
+        return false;
+    }
+    national_holiday(date.pred()).is_some() && national_holiday(date.succ()).is_some()
+}
+
+/// その月の第 n 月曜日か (ハッピーマンデー)。
+fn is_nth_monday(date: Date, n: u32) -> bool {
+    date.weekday() == 0 && (date.day - 1) / 7 + 1 == n
+}
+
+#[derive(Clone, Copy)]
+enum Equinox {
+    Vernal,
+    Autumnal,
+}
+
+/// 春分日・秋分日 (3 月・9 月の日)。近似式の範囲外の年は `None`。
+fn equinox_day(year: i32, equinox: Equinox) -> Option<u32> {
+    // (基準日, 閏年補正の起点)。補正は 0 に向けて切り捨てる
+    let (base, leap_origin) = match (year, equinox) {
+        (1900..=1979, Equinox::Vernal) => (20.8357, 1983),
+        (1900..=1979, Equinox::Autumnal) => (23.2588, 1983),
+        (1980..=2099, Equinox::Vernal) => (20.8431, 1980),
+        (1980..=2099, Equinox::Autumnal) => (23.2488, 1980),
+        (2100..=2150, Equinox::Vernal) => (21.8510, 1980),
+        (2100..=2150, Equinox::Autumnal) => (24.2488, 1980),
+        _ => return None,
+    };
+    let drift = 0.242194 * f64::from(year - 1980);
+    let leap = f64::from((year - leap_origin) / 4);
+    Some((base + drift - leap).floor() as u32)
+}
+
+fn ymd(year: i32, month: u32, day: u32) -> Date {
+    Date { year, month, day }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn name(year: i32, month: u32, day: u32) -> Option<&'static str> {
+        holiday(ymd(year, month, day)).map(Holiday::name)
+    }
+
+    #[test]
+    fn happy_mondays_and_equinoxes() {
+        assert_eq!(name(2024, 1, 8), Some("成人の日"));
+        assert_eq!(name(2024, 1, 15), None);
+        assert_eq!(name(1999, 1, 15), Some("成人の日"));
+        assert_eq!(name(2024, 3, 20), Some("春分の日"));
+        assert_eq!(name(2024, 9, 22), Some("秋分の日"));
+        assert_eq!(name(2025, 9, 23), Some("秋分の日"));
+        assert_eq!(name(2024, 7, 15), Some("海の日"));
+        assert_eq!(name(2024, 10, 14), Some("スポーツの日"));
+        assert_eq!(name(2019, 10, 14), Some("体育の日"));
+        assert_eq!(name(2002, 9, 15), Some("敬老の日"));
+        assert_eq!(name(2024, 6, 3), None);
+    }
+
+    #[test]
+    fn equinox_formula_covers_older_and_later_years() {
+        assert_eq!(equinox_day(1948, Equinox::Autumnal), Some(23));
+        assert_eq!(equinox_day(1960, Equinox::Vernal), Some(20));
+        assert_eq!(equinox_day(1979, Equinox::Autumnal), Some(24));
+        assert_eq!(equinox_day(2012, Equinox::Autumnal), Some(22));
+        assert!(equinox_day(2150, Equinox::Autumnal).is_some());
+        assert_eq!(equinox_day(2151, Equinox::Vernal), None);
+        assert_eq!(equinox_day(1899, Equinox::Vernal), None);
+    }
+
+    #[test]
+    fn holidays_follow_the_law_of_each_year() {
+        assert_eq!(name(1948, 5, 3), None); // 施行前
+        assert_eq!(name(1948, 11, 3), Some("文化の日"));
+        assert_eq!(name(1966, 2, 11), None);
+        assert_eq!(name(1988, 4, 29), Some("天皇誕生日"));
+        assert_eq!(name(2006, 4, 29), Some("みどりの日"));
+        assert_eq!(name(2007, 4, 29), Some("昭和の日"));
+        assert_eq!(name(2018, 12, 23), Some("天皇誕生日"));
+        assert_eq!(name(2019, 12, 23), None);
+        assert_eq!(name(2019, 2, 23), None);
+        assert_eq!(name(2020, 2, 23), Some("天皇誕生日"));
+        assert_eq!(name(2015, 8, 11), None);
+        assert_eq!(name(2016, 8, 11), Some("山の日"));
+    }
+
+    #[test]
+    fn olympic_years_move_summer_holidays() {
+        assert_eq!(name(2020, 7, 23), Some("海の日"));
+        assert_eq!(name(2020, 7, 24), Some("スポーツの日"));
+        assert_eq!(name(2020, 8, 10), Some("山の日"));
+        assert_eq!(name(2020, 10, 12), None);
+        assert_eq!(name(2021, 7, 22), Some("海の日"));
+        assert_eq!(name(2021, 7, 23), Some("スポーツの日"));
+        assert_eq!(name(2021, 8, 8), Some("山の日"));
+        assert_eq!(name(2021, 8, 9), Some("振替休日"));
+        assert_eq!(name(2021, 8, 11), None);
+    }
+
+    #[test]
+    fn one_off_imperial_holidays() {
+        assert_eq!(name(1989, 2, 24), Some("昭和天皇の大喪の礼"));
+        assert_eq!(name(2019, 5, 1), Some("天皇の即位の日"));
+        assert_eq!(name(2019, 10, 22), Some("即位礼正殿の儀"));
+    }
+
+    #[test]
+    fn substitute_holiday_follows_a_sunday_holiday() {
+        // 2024-02-11 (日) 建国記念の日 → 12 日が振替休日
+        assert_eq!(name(2024, 2, 12), Some("振替休日"));
+        // 2025-05-04 (日) みどりの日 → 5 日はこどもの日なので 6 日が振替休日
+        assert_eq!(name(2025, 5, 6), Some("振替休日"));
+        assert_eq!(name(2025, 5, 7), None);
+        // 2006 年までは翌月曜だけ。2003-11-23 (日) → 24 日
+        assert_eq!(name(2003, 11, 24), Some("振替休日"));
+        // 振替休日の制度より前
+        assert_eq!(name(1973, 2, 12), None);
+    }
+
+    #[test]
+    fn citizens_holiday_sits_between_holidays() {
+        assert_eq!(name(2015, 9, 22), Some("国民の休日"));
+        assert_eq!(name(2026, 9, 22), Some("国民の休日"));
+        assert_eq!(name(2019, 4, 30), Some("国民の休日"));
+        assert_eq!(name(2019, 5, 2), Some("国民の休日"));
+        assert_eq!(name(1999, 5, 4), Some("国民の休日"));
+        // 1985 年の改正前
+        assert_eq!(name(1982, 5, 4), None);
+    }
+
+    #[test]
+    fn year_end_holidays_span_new_year() {
+        assert!(is_year_end_holiday(ymd(2024, 12, 30)));
+        assert!(is_year_end_holiday(ymd(2025, 1, 3)));
+        assert!(!is_year_end_holiday(ymd(2025, 1, 4)));
+        assert!(!is_year_end_holiday(ymd(2024, 12, 29)));
+    }
+}
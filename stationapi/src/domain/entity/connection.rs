@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use super::{line::Line, station::Station};
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct Connection {
     pub id: u32,
@@ -19,6 +21,34 @@ impl Connection {
     }
 }
 
+/// 駅グループをまたいで徒歩で乗り換えられる相手の駅。
+#[derive(Clone, Debug, PartialEq)]
+pub struct StationTransfer {
+    pub station: Station,
+    /// 駅間の直線距離 (メートル)。
+    pub distance: f64,
+}
+
+/// 系統が路線をまたいで直通するところ。`from_station_cd`・`to_station_cd` は
+/// 直通駅での直通元・直通先の路線の駅で、同じ駅グループに属する。両路線の載った駅が
+/// 無い直通 (連絡線で入るもの) だけは、直通元で最後に通る駅と直通先で最初に通る駅。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ThroughService {
+    pub line_group_cd: u32,
+    pub from_line_cd: u32,
+    pub to_line_cd: u32,
+    pub from_station_cd: u32,
+    pub to_station_cd: u32,
+}
+
+/// 直通 1 か所の路線と、直通先の路線に入る駅 (駅の行。路線などは付けない)。
+#[derive(Clone, Debug, PartialEq)]
+pub struct ThroughServiceLink {
+    pub from_line: Line,
+    pub to_line: Line,
+    pub station: Station,
+}
+
 #[cfg(test)]
 mod tests {
     use super::Connection;
@@ -233,6 +233,11 @@ impl GtfsCalendar {
             _ => false,
         }
     }
+
+    /// Check if a `YYYYMMDD` date falls within `start_date..=end_date`
+    pub fn covers_date(&self, date: &str) -> bool {
+        self.start_date.as_str() <= date && date <= self.end_date.as_str()
+    }
 }
 
 /// GTFS Calendar Date (Service exception)
@@ -456,7 +461,7 @@ impl From<i32> for TransportType {
 }
 
 /// Transport type filter for API requests
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
 pub enum TransportTypeFilter {
     #[default]
     Rail,
@@ -562,6 +567,8 @@ mod tests {
         assert!(calendar.runs_on_weekday(4)); // Friday
         assert!(!calendar.runs_on_weekday(5)); // Saturday
         assert!(!calendar.runs_on_weekday(6)); // Sunday
+        assert!(calendar.covers_date("20240101"));
+        assert!(!calendar.covers_date("20250101"));
     }
 
     #[test]
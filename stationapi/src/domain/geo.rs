@@ -0,0 +1,71 @@
+//! 緯度経度の矩形。
+//!
+//! 地図の表示範囲をそのまま受け取るためのもの。対象は日本国内なので、
+//! 日付変更線をまたぐ矩形は扱わない。
+
+/// 南西端 (min) と北東端 (max) で表す矩形 (度)。
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoundingBox {
+    pub min_lat: f64,
+    pub min_lon: f64,
+    pub max_lat: f64,
+    pub max_lon: f64,
+}
+
+impl BoundingBox {
+    /// 緯度経度の範囲外、NaN、min > max のときは `None`。
+    pub fn new(min_lat: f64, min_lon: f64, max_lat: f64, max_lon: f64) -> Option<Self> {
+        let lat_ok = |v: f64| (-90.0..=90.0).contains(&v);
+        let lon_ok = |v: f64| (-180.0..=180.0).contains(&v);
+        if !(lat_ok(min_lat) && lat_ok(max_lat) && lon_ok(min_lon) && lon_ok(max_lon)) {
+            return None;
+        }
+        if min_lat > max_lat || min_lon > max_lon {
+            return None;
+        }
+        Some(Self {
+            min_lat,
+            min_lon,
+            max_lat,
+            max_lon,
+        })
+    }
+
+    /// 境界上の点も含む。
+    pub fn contains(&self, lat: f64, lon: f64) -> bool {
+        (self.min_lat..=self.max_lat).contains(&lat) && (self.min_lon..=self.max_lon).contains(&lon)
+    }
+
+    /// 中心。件数で切るときはここに近い駅から残す。
+    pub fn center(&self) -> (f64, f64) {
+        (
+            (self.min_lat + self.max_lat) / 2.0,
+            (self.min_lon + self.max_lon) / 2.0,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_inverted_or_out_of_range_corners() {
+        assert!(BoundingBox::new(35.6, 139.6, 35.7, 139.8).is_some());
+        assert!(BoundingBox::new(35.7, 139.6, 35.6, 139.8).is_none());
+        assert!(BoundingBox::new(35.6, 139.8, 35.7, 139.6).is_none());
+        assert!(BoundingBox::new(-91.0, 139.6, 35.7, 139.8).is_none());
+        assert!(BoundingBox::new(35.6, 139.6, 35.7, 181.0).is_none());
+        assert!(BoundingBox::new(f64::NAN, 139.6, 35.7, 139.8).is_none());
+    }
+
+    #[test]
+    fn contains_includes_edges() {
+        let bounds = BoundingBox::new(35.0, 139.0, 36.0, 140.0).unwrap();
+        assert!(bounds.contains(35.5, 139.5));
+        assert!(bounds.contains(35.0, 140.0));
+        assert!(!bounds.contains(36.01, 139.5));
+        assert!(!bounds.contains(35.5, 138.99));
+        assert_eq!(bounds.center(), (35.5, 139.5));
+    }
+}
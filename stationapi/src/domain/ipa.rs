@@ -114,6 +114,14 @@ pub fn replace_line_name_suffix(input: &str) -> (&str, &str) {
 /// Convert a katakana string to its IPA transcription.
 /// Returns `None` if the input contains characters that cannot be converted.
 pub fn katakana_to_ipa(input: &str) -> Option<String> {
+    katakana_to_ipa_with_kernel(input, None)
+}
+
+/// `katakana_to_ipa` with a downstep mark (ꜜ) after the accent kernel.
+/// `kernel` is the 1-based mora the pitch falls after; `None` or `Some(0)`
+/// (平板) leaves the output unmarked. The mark follows the whole syllable,
+/// so a kernel before ー or ン drops after the long vowel / nasal.
+fn katakana_to_ipa_with_kernel(input: &str, kernel: Option<usize>) -> Option<String> {
     if input.is_empty() {
         return Some(String::new());
     }
@@ -122,21 +130,34 @@ pub fn katakana_to_ipa(input: &str) -> Option<String> {
     let len = chars.len();
     let mut result = Vec::new();
     let mut i = 0;
+    let mut mora = 0;
+    let mut pending_kernel = kernel.filter(|&k| k > 0);
 
     while i < len {
         // Try two-character combinations first (palatalized sounds: キョ, シャ, etc.)
-        if i + 1 < len {
-            if let Some(ipa) = lookup_digraph(chars[i], chars[i + 1]) {
-                result.push(ipa);
-                i += 2;
-                continue;
+        let digraph = chars
+            .get(i + 1)
+            .and_then(|&next| lookup_digraph(chars[i], next));
+        let (phoneme, width) = match digraph {
+            Some(ipa) => (ipa, 2),
+            // Single character lookup — return None on unknown characters
+            None => (lookup_single(chars[i])?, 1),
+        };
+
+        if starts_mora(chars[i]) {
+            if !matches!(chars[i], 'ー' | 'ン') && pending_kernel.is_some_and(|k| mora >= k) {
+                result.push(Phoneme::Downstep);
+                pending_kernel = None;
             }
+            mora += 1;
         }
+        result.push(phoneme);
+        i += width;
+    }
 
-        // Single character lookup — return None on unknown characters
-        result.push(lookup_single(chars[i])?);
-
-        i += 1;
+    // 尾高: 語末で下がる
+    if pending_kernel.is_some_and(|k| mora >= k) {
+        result.push(Phoneme::Downstep);
     }
 
     // 区切り文字由来の先頭・末尾・連続した空白を 1 つに正規化する。
@@ -152,6 +173,110 @@ pub fn station_name_to_ipa(name_katakana: &str, name_roman: Option<&str>) -> Opt
     non_empty_ipa(join_tts_segment_pronunciations(&segments))
 }
 
+/// Whether the character begins a new mora. Small kana belong to the
+/// preceding mora and separators carry none.
+fn starts_mora(c: char) -> bool {
+    !matches!(
+        c,
+        'ァ' | 'ィ' | 'ゥ' | 'ェ' | 'ォ' | 'ャ' | 'ュ' | 'ョ' | 'ヮ'
+    ) && !matches!(lookup_single(c), Some(Phoneme::Regular(" ")))
+}
+
+fn mora_count(input: &str) -> usize {
+    input.chars().filter(|&c| starts_mora(c)).count()
+}
+
+/// 駅名のアクセント核 (何拍目の後で下がるか。0 は平板)。
+/// 複合語の規則 (`ACCENT_SUFFIX_RULES`) に合わない駅名と、規則の例外を載せる。
+const STATION_ACCENT_DICTIONARY: &[(&str, &str, usize)] = &[
+    ("東京", "トウキョウ", 0),
+    ("新宿", "シンジュク", 0),
+    ("渋谷", "シブヤ", 0),
+    ("池袋", "イケブクロ", 0),
+    ("品川", "シナガワ", 0),
+    ("上野", "ウエノ", 0),
+    ("赤羽", "アカバネ", 0),
+    ("赤坂", "アカサカ", 0),
+    ("秋葉原", "アキハバラ", 3),
+    ("神田", "カンダ", 1),
+    ("日本橋", "ニホンバシ", 2),
+    ("札幌", "サッポロ", 0),
+    ("仙台", "センダイ", 1),
+    ("横浜", "ヨコハマ", 0),
+    ("名古屋", "ナゴヤ", 1),
+    ("京都", "キョウト", 1),
+    ("大阪", "オオサカ", 0),
+    ("神戸", "コウベ", 1),
+    ("広島", "ヒロシマ", 0),
+    ("博多", "ハカタ", 0),
+];
+
+/// 複合語の後部要素ごとのアクセント。
+#[derive(Clone, Copy)]
+enum SuffixAccent {
+    /// 平板 (〜橋 しんばし、〜田 かまた)
+    Heiban,
+    /// 前部要素の最後の拍で下がる (〜前 だいがくꜜまえ、〜駅)
+    BeforeSuffix,
+    /// 後部要素の最初の拍で下がる (〜公園 よよぎこꜜうえん)
+    OnSuffix,
+}
+
+/// (後部要素の表記, 読み, アクセント)。読みまで一致したときだけ使う。
+const ACCENT_SUFFIX_RULES: &[(&str, &str, SuffixAccent)] = &[
+    ("駅", "エキ", SuffixAccent::BeforeSuffix),
+    ("前", "マエ", SuffixAccent::BeforeSuffix),
+    ("口", "グチ", SuffixAccent::Heiban),
+    ("口", "クチ", SuffixAccent::Heiban),
+    ("橋", "バシ", SuffixAccent::Heiban),
+    ("橋", "ハシ", SuffixAccent::Heiban),
+    ("田", "ダ", SuffixAccent::Heiban),
+    ("田", "タ", SuffixAccent::Heiban),
+    ("町", "チョウ", SuffixAccent::Heiban),
+    ("町", "マチ", SuffixAccent::BeforeSuffix),
+    ("公園", "コウエン", SuffixAccent::OnSuffix),
+];
+
+/// 駅名のアクセント核を辞書、次に複合語の規則で決める。どちらにも無ければ `None`。
+/// 核が特殊拍 (ー・ン・ッ) に来たときは一つ前の拍へずらす。
+pub fn station_accent_kernel(name: &str, name_katakana: &str) -> Option<usize> {
+    let mut kernel = STATION_ACCENT_DICTIONARY
+        .iter()
+        .find(|(n, k, _)| *n == name && *k == name_katakana)
+        .map(|&(_, _, kernel)| kernel)
+        .or_else(|| {
+            ACCENT_SUFFIX_RULES
+                .iter()
+                .find_map(|&(suffix, reading, accent)| {
+                    name.strip_suffix(suffix).filter(|stem| !stem.is_empty())?;
+                    let stem = name_katakana
+                        .strip_suffix(reading)
+                        .filter(|stem| !stem.is_empty())?;
+                    Some(match accent {
+                        SuffixAccent::Heiban => 0,
+                        SuffixAccent::BeforeSuffix => mora_count(stem),
+                        SuffixAccent::OnSuffix => mora_count(stem) + 1,
+                    })
+                })
+        })?;
+
+    let morae: Vec<char> = name_katakana.chars().filter(|&c| starts_mora(c)).collect();
+    if kernel > morae.len() {
+        return None;
+    }
+    while kernel > 1 && matches!(morae[kernel - 1], 'ー' | 'ン' | 'ッ') {
+        kernel -= 1;
+    }
+    Some(kernel)
+}
+
+/// 駅名の読みの IPA にアクセントの下がり目 (ꜜ) を付ける。平板なら ꜜ は付かない。
+/// アクセントが分からない駅名は `None`。
+pub fn station_name_to_accented_ipa(name: &str, name_katakana: &str) -> Option<String> {
+    let kernel = station_accent_kernel(name, name_katakana)?;
+    non_empty_ipa(katakana_to_ipa_with_kernel(name_katakana, Some(kernel)))
+}
+
 pub fn katakana_name_to_ipa(input: &str) -> Option<String> {
     non_empty_ipa(katakana_to_ipa(input))
 }
@@ -1165,6 +1290,7 @@ enum Phoneme {
     MoraicNasal, // ン - assimilates to following consonant
     Geminate,    // ッ - doubles following consonant
     LongVowel,   // ー - lengthens preceding vowel
+    Downstep,    // ꜜ - pitch falls after the accent kernel
 }
 
 /// Extract the leading consonant cluster from an IPA string.
@@ -1273,6 +1399,10 @@ fn apply_phonological_rules(phonemes: &[Phoneme]) -> String {
                 }
                 i += 1;
             }
+            Phoneme::Downstep => {
+                output.push('ꜜ');
+                i += 1;
+            }
             Phoneme::LongVowel => {
                 // Lengthen the preceding vowel
                 if last_vowel(&output).is_some() {
@@ -1839,4 +1969,81 @@ mod tests {
         // "セン" だけの場合、stemが空になるので除去しない
         assert_eq!(replace_line_name_suffix("セン"), ("セン", ""));
     }
+
+    // ============================================
+    // Pitch accent
+    // ============================================
+
+    #[test]
+    fn test_accent_marks_kernel_from_dictionary() {
+        assert_eq!(
+            station_name_to_accented_ipa("秋葉原", "アキハバラ").as_deref(),
+            Some("akʲihaꜜbaɾa")
+        );
+        // 核の後ろの ン は同じ音節に含めてから下げる
+        assert_eq!(
+            station_name_to_accented_ipa("神田", "カンダ").as_deref(),
+            Some("kanꜜda")
+        );
+    }
+
+    #[test]
+    fn test_accent_heiban_matches_plain_ipa() {
+        assert_eq!(
+            station_name_to_accented_ipa("赤羽", "アカバネ"),
+            katakana_name_to_ipa("アカバネ")
+        );
+        assert_eq!(
+            station_name_to_accented_ipa("新橋", "シンバシ"),
+            katakana_name_to_ipa("シンバシ")
+        );
+    }
+
+    #[test]
+    fn test_accent_suffix_rules() {
+        assert_eq!(station_accent_kernel("大学前", "ダイガクマエ"), Some(4));
+        assert_eq!(
+            station_name_to_accented_ipa("大学前", "ダイガクマエ").as_deref(),
+            Some("da.igakɯꜜma.e")
+        );
+        assert_eq!(
+            station_accent_kernel("代々木公園", "ヨヨギコウエン"),
+            Some(4)
+        );
+        assert_eq!(station_accent_kernel("御徒町", "オカチマチ"), Some(3));
+        assert_eq!(station_accent_kernel("有楽町", "ユウラクチョウ"), Some(0));
+        // 辞書が規則より優先される (〜橋 は平板だが日本橋は ほ で下がる)
+        assert_eq!(station_accent_kernel("日本橋", "ニホンバシ"), Some(2));
+    }
+
+    #[test]
+    fn test_accent_kernel_moves_off_special_mora() {
+        // センター|前 の核は ー に来るので タ へずらし、ー の後で下げる
+        assert_eq!(station_accent_kernel("センター前", "センターマエ"), Some(3));
+        assert_eq!(
+            station_name_to_accented_ipa("センター前", "センターマエ").as_deref(),
+            Some("sentaːꜜma.e")
+        );
+    }
+
+    #[test]
+    fn test_accent_unknown_names() {
+        assert_eq!(station_name_to_accented_ipa("熊谷", "クマガヤ"), None);
+        // 表記が合っても読みが規則と違えば使わない
+        assert_eq!(station_accent_kernel("御前", "ゴゼン"), None);
+        // 後部要素だけの名前は複合語ではない
+        assert_eq!(station_accent_kernel("前", "マエ"), None);
+    }
+
+    #[test]
+    fn test_accent_odaka_marks_word_end() {
+        assert_eq!(
+            katakana_to_ipa_with_kernel("ハシ", Some(2)).as_deref(),
+            Some("haɕiꜜ")
+        );
+        assert_eq!(
+            katakana_to_ipa_with_kernel("ハシ", Some(0)),
+            katakana_to_ipa("ハシ")
+        );
+    }
 }
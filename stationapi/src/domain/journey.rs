@@ -0,0 +1,305 @@
+//! 乗換を含む旅程(複数の乗車区間)の所要時間を見積もり、順位付けする純粋ロジック。
+//!
+//! 各乗車区間(レグ)は単一の `line_group_cd` に乗り通す区間なので、
+//! [`estimate_arrival_minutes_calibrated`] でそのまま推定できる。レグの間には
+//! 乗換時間を加える。乗換時間は固定の乗換余裕と、降車駅から乗車駅までの
+//! 徒歩時間(直線距離に迂回係数を掛け、歩行速度で割ったもの)の和とする。
+//! 同じ駅グループ内でも路線ごとに駅座標が異なる(例: 地下鉄と JR の駅舎)ため、
+//! 座標差がそのまま構内・駅間の歩行距離の目安になる。
+//!
+//! 時刻はすべて「出発可能時刻(`depart_after_minutes`)を起点とした分」で表す。
+//! 時刻表が無い前提なので待ち時間は加算しない。
+
+use std::cmp::Ordering;
+
+use crate::domain::arrival_estimation::{
+    estimate_arrival_minutes_calibrated, haversine_distance, EstimatedStop, EstimationParams,
+};
+use crate::domain::entity::station::Station;
+
+/// 乗換 1 回あたりの固定の乗換余裕(分)。階段・改札・ホームでの待ちを含む実効値。
+pub const TRANSFER_BASE_MINUTES: f64 = 3.0;
+
+/// 徒歩速度(m/分)。不動産表示の徒歩 1 分 = 80m に合わせる。
+pub const WALKING_METERS_PER_MINUTE: f64 = 80.0;
+
+/// 徒歩経路の迂回係数。駅構内・道路は直線で歩けないぶんの補正。
+pub const WALKING_DETOUR: f64 = 1.3;
+
+/// 旅程の 1 乗車区間。
+#[derive(Clone, Debug, PartialEq)]
+pub struct JourneyLeg {
+    pub line_group_cd: i32,
+    /// 乗車駅の路線。
+    pub line_cd: i32,
+    /// このレグに乗る前の乗換時間(分)。最初のレグは 0。
+    pub transfer_minutes: f64,
+    /// 乗車駅の出発時刻。
+    pub departure_minutes: f64,
+    /// 降車駅の到着時刻。
+    pub arrival_minutes: f64,
+    /// 乗車駅から降車駅までの各駅。`cumulative_minutes` などは旅程の時刻に
+    /// 置き換えてある(レグ内の相対時刻ではない)。
+    pub stops: Vec<EstimatedStop>,
+}
+
+/// 乗換を含む旅程 1 件。
+#[derive(Clone, Debug, PartialEq)]
+pub struct Journey {
+    /// 経路候補の ID。`get_connected_routes` の仮想 line_group_cd と同じ値。
+    pub id: u32,
+    pub departure_minutes: f64,
+    pub arrival_minutes: f64,
+    /// 出発から到着までの所要時間(分)。乗換時間を含む。
+    pub total_minutes: f64,
+    pub transfer_count: u32,
+    pub legs: Vec<JourneyLeg>,
+}
+
+/// 推定に使う 1 レグ分の入力。
+pub struct JourneyLegInput<'a> {
+    /// 乗車駅→降車駅の順に並んだ駅(通過駅を含む)。
+    pub stops: Vec<&'a Station>,
+    /// 迂回係数の較正母数にする系統全体の駅列。
+    pub calibration_stops: Vec<&'a Station>,
+}
+
+/// 直線距離(メートル)を歩くのに要する時間(分)。迂回係数を含む。
+pub fn walking_minutes(distance_meters: f64) -> f64 {
+    distance_meters * WALKING_DETOUR / WALKING_METERS_PER_MINUTE
+}
+
+/// 降車駅から乗車駅へ乗り換えるのに要する時間(分)。
+pub fn transfer_minutes(alight: &Station, board: &Station) -> f64 {
+    let walk_meters = haversine_distance(alight.lat, alight.lon, board.lat, board.lon);
+    TRANSFER_BASE_MINUTES + walking_minutes(walk_meters)
+}
+
+/// レグ列から旅程を組み立てる。空のレグを含む場合は `None`。
+pub fn build_journey(
+    id: u32,
+    legs: &[JourneyLegInput<'_>],
+    depart_after_minutes: f64,
+    params: &EstimationParams,
+) -> Option<Journey> {
+    if legs.is_empty() {
+        return None;
+    }
+
+    let mut clock = depart_after_minutes;
+    let mut previous_alight: Option<&Station> = None;
+    let mut out = Vec::with_capacity(legs.len());
+
+    for leg in legs {
+        let (Some(&board), Some(&alight)) = (leg.stops.first(), leg.stops.last()) else {
+            return None;
+        };
+        if leg.stops.len() < 2 {
+            return None;
+        }
+        let transfer = previous_alight.map_or(0.0, |prev| transfer_minutes(prev, board));
+        clock += transfer;
+
+        let departure = clock;
+        let stops: Vec<EstimatedStop> =
+            estimate_arrival_minutes_calibrated(&leg.stops, &leg.calibration_stops, params)
+                .into_iter()
+                .map(|mut stop| {
+                    stop.cumulative_minutes += departure;
+                    stop.departure_cumulative_minutes += departure;
+                    stop
+                })
+                .collect();
+        let arrival = stops.last().map_or(departure, |s| s.cumulative_minutes);
+        clock = arrival;
+
+        out.push(JourneyLeg {
+            line_group_cd: board.line_group_cd.unwrap_or(board.line_cd),
+            line_cd: board.line_cd,
+            transfer_minutes: transfer,
+            departure_minutes: departure,
+            arrival_minutes: arrival,
+            stops,
+        });
+        previous_alight = Some(alight);
+    }
+
+    Some(Journey {
+        id,
+        departure_minutes: depart_after_minutes,
+        arrival_minutes: clock,
+        total_minutes: clock - depart_after_minutes,
+        transfer_count: (out.len() - 1) as u32,
+        legs: out,
+    })
+}
+
+/// 到着の早い順、同着なら乗換の少ない順、さらに ID 順に並べる。
+pub fn rank_journeys(journeys: &mut [Journey]) {
+    journeys.sort_by(|a, b| {
+        a.arrival_minutes
+            .partial_cmp(&b.arrival_minutes)
+            .unwrap_or(Ordering::Equal)
+            .then(a.transfer_count.cmp(&b.transfer_count))
+            .then(a.id.cmp(&b.id))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entity::gtfs::TransportType;
+    use crate::model::StopCondition;
+
+    fn station(station_cd: i32, line_group_cd: i32, lat: f64, lon: f64) -> Station {
+        Station {
+            station_cd,
+            station_g_cd: station_cd,
+            station_name: String::new(),
+            station_name_k: String::new(),
+            station_name_r: None,
+            station_name_zh: None,
+            station_name_ko: None,
+            station_numbers: vec![],
+            station_number1: None,
+            station_number2: None,
+            station_number3: None,
+            station_number4: None,
+            three_letter_code: None,
+            line_cd: line_group_cd,
+            line: None,
+            lines: vec![],
+            pref_cd: 13,
+            post: String::new(),
+            address: String::new(),
+            lon,
+            lat,
+            open_ymd: String::new(),
+            close_ymd: String::new(),
+            e_status: 0,
+            e_sort: station_cd,
+            stop_condition: StopCondition::All,
+            distance: None,
+            has_train_types: false,
+            train_type: None,
+            company_cd: Some(1),
+            line_name: None,
+            line_name_k: None,
+            line_name_h: None,
+            line_name_r: None,
+            line_name_zh: None,
+            line_name_ko: None,
+            line_color_c: None,
+            line_type: Some(2),
+            line_symbol1: None,
+            line_symbol2: None,
+            line_symbol3: None,
+            line_symbol4: None,
+            line_symbol1_color: None,
+            line_symbol2_color: None,
+            line_symbol3_color: None,
+            line_symbol4_color: None,
+            line_symbol1_shape: None,
+            line_symbol2_shape: None,
+            line_symbol3_shape: None,
+            line_symbol4_shape: None,
+            average_distance: None,
+            type_id: None,
+            sst_id: None,
+            type_cd: None,
+            line_group_cd: Some(line_group_cd),
+            pass: None,
+            type_name: None,
+            type_name_k: None,
+            type_name_r: None,
+            type_name_zh: None,
+            type_name_ko: None,
+            color: None,
+            direction: None,
+            kind: None,
+            transport_type: TransportType::Rail,
+        }
+    }
+
+    fn leg<'a>(stops: &'a [Station]) -> JourneyLegInput<'a> {
+        let refs: Vec<&Station> = stops.iter().collect();
+        JourneyLegInput {
+            stops: refs.clone(),
+            calibration_stops: refs,
+        }
+    }
+
+    #[test]
+    fn transfer_minutes_adds_walking_time_to_base() {
+        let a = station(1, 100, 35.0, 139.0);
+        let same = station(2, 200, 35.0, 139.0);
+        assert!((transfer_minutes(&a, &same) - TRANSFER_BASE_MINUTES).abs() < 1e-9);
+
+        // 緯度 0.001 度 ≒ 111m → 111 * 1.3 / 80 ≒ 1.8 分
+        let apart = station(3, 200, 35.001, 139.0);
+        let minutes = transfer_minutes(&a, &apart);
+        assert!(minutes > TRANSFER_BASE_MINUTES + 1.5 && minutes < TRANSFER_BASE_MINUTES + 2.1);
+    }
+
+    #[test]
+    fn build_journey_chains_legs_with_transfer() {
+        let first = [station(1, 100, 35.00, 139.0), station(2, 100, 35.01, 139.0)];
+        let second = [station(3, 200, 35.01, 139.0), station(4, 200, 35.02, 139.0)];
+        let journey = build_journey(
+            7,
+            &[leg(&first), leg(&second)],
+            480.0,
+            &EstimationParams::default(),
+        )
+        .expect("journey");
+
+        assert_eq!(journey.id, 7);
+        assert_eq!(journey.transfer_count, 1);
+        assert_eq!(journey.legs.len(), 2);
+        assert_eq!(journey.legs[0].transfer_minutes, 0.0);
+        assert_eq!(journey.legs[0].departure_minutes, 480.0);
+        assert_eq!(journey.legs[1].transfer_minutes, TRANSFER_BASE_MINUTES);
+        assert!(
+            (journey.legs[1].departure_minutes
+                - (journey.legs[0].arrival_minutes + TRANSFER_BASE_MINUTES))
+                .abs()
+                < 1e-9
+        );
+        // レグ内の各駅は旅程の時刻に置き換わっている
+        assert_eq!(
+            journey.legs[1].stops[0].cumulative_minutes,
+            journey.legs[1].departure_minutes
+        );
+        assert_eq!(journey.arrival_minutes, journey.legs[1].arrival_minutes);
+        assert!((journey.total_minutes - (journey.arrival_minutes - 480.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn build_journey_rejects_empty_or_single_stop_leg() {
+        let single = [station(1, 100, 35.0, 139.0)];
+        let params = EstimationParams::default();
+        assert!(build_journey(1, &[], 0.0, &params).is_none());
+        assert!(build_journey(1, &[leg(&single)], 0.0, &params).is_none());
+    }
+
+    #[test]
+    fn rank_journeys_prefers_earlier_arrival_then_fewer_transfers() {
+        let journey = |id: u32, arrival: f64, transfers: u32| Journey {
+            id,
+            departure_minutes: 0.0,
+            arrival_minutes: arrival,
+            total_minutes: arrival,
+            transfer_count: transfers,
+            legs: vec![],
+        };
+        let mut journeys = vec![
+            journey(1, 30.0, 0),
+            journey(2, 20.0, 2),
+            journey(3, 20.0, 1),
+            journey(4, 20.0, 1),
+        ];
+        rank_journeys(&mut journeys);
+        let ids: Vec<u32> = journeys.iter().map(|j| j.id).collect();
+        assert_eq!(ids, vec![3, 4, 2, 1]);
+    }
+}
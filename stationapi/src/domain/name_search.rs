@@ -0,0 +1,310 @@
+//! 駅名検索の照合と順位付け。
+//!
+//! 漢字・カナ・ローマ字・中国語・韓国語の名前に対し、完全一致 > 前方一致 >
+//! 部分一致 > 編集距離での近似一致の順に強さを決める。ローマ字はヘボン式の
+//! 揺れ (jyu/ju, shi/si, ou/ō/o など) を畳んだ鍵で比べるので、"shinjyuku" や
+//! マクロン無しの "tokyo" でも引ける。仮名で入力された場合もローマ字へ直して
+//! 同じ鍵で比べる。
+
+use crate::domain::normalize::normalize_for_search;
+use crate::domain::romaji::{katakana_to_romaji, strip_macrons};
+
+/// 一致の強さ。小さいほど強い (`Ord` の順に並べればよい)。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MatchKind {
+    Exact,
+    Prefix,
+    Substring,
+    /// ローマ字の鍵どうしの編集距離 (候補の先頭部分との最小値)。
+    Fuzzy(u8),
+}
+
+/// 照合に使う駅の名前一式。ローマ字は `romaji_key` を通したもの。
+pub struct StationNames<'a> {
+    pub name: &'a str,
+    pub katakana: &'a str,
+    pub romaji_key: Option<&'a str>,
+    pub chinese: Option<&'a str>,
+    pub korean: Option<&'a str>,
+}
+
+/// 入力を照合用に前処理したもの。1 回の検索で全駅に使い回す。
+pub struct NameQuery {
+    raw: String,
+    katakana: String,
+    romaji_key: Option<String>,
+}
+
+impl NameQuery {
+    pub fn new(input: &str) -> Self {
+        let raw = input.trim().to_string();
+        let katakana = normalize_for_search(&raw);
+        // 仮名ならローマ字に直し、英字ならそのまま鍵にする。漢字を含むものは鍵を持たない
+        let romaji_key = if raw
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || is_romaji_punct(c))
+        {
+            Some(romaji_key(&raw))
+        } else {
+            katakana_to_romaji(&katakana).map(|romaji| romaji_key(&romaji))
+        }
+        .filter(|key| !key.is_empty());
+        Self {
+            raw,
+            katakana,
+            romaji_key,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.raw.is_empty()
+    }
+
+    /// 名前のどれかに当たれば最も強い一致を返す。
+    pub fn rank(&self, names: &StationNames<'_>) -> Option<MatchKind> {
+        let mut best = [Some(names.name), names.chinese, names.korean]
+            .into_iter()
+            .flatten()
+            .filter_map(|candidate| text_match(&self.raw, candidate))
+            .chain(text_match(&self.katakana, names.katakana))
+            .min();
+
+        if let (Some(query), Some(candidate)) = (self.romaji_key.as_deref(), names.romaji_key) {
+            let romaji = text_match(query, candidate).or_else(|| {
+                fuzzy_prefix_distance(query, candidate, max_edit_distance(query))
+                    .map(|d| MatchKind::Fuzzy(d as u8))
+            });
+            best = best.into_iter().chain(romaji).min();
+        }
+        best
+    }
+}
+
+/// ローマ字の表記揺れを畳んだ比較用の鍵。
+///
+/// 小文字化してマクロンと記号・空白を落とし、ヘボン式・訓令式の綴りを
+/// 1 通りに寄せてから長音を 1 文字にする。問い合わせと駅名の両方に同じ変換をかけるので、
+/// 畳みすぎても別の駅が同じ鍵になるだけで、引けなくなることはない。
+pub fn romaji_key(input: &str) -> String {
+    let plain: Vec<u8> = strip_macrons(&input.to_lowercase())
+        .bytes()
+        .filter(u8::is_ascii_alphanumeric)
+        .collect();
+
+    // 長い綴りから順に試す。sh 行は x、ch 行は c に置き換え、揺れる綴りを同じ長さにそろえる
+    // (綴りの長さが変わると、打ち間違いの編集距離が揺れの分だけ膨らむため)
+    const SPELLINGS: [(&[u8], &[u8]); 28] = [
+        (b"jya", b"ja"),
+        (b"jyu", b"ju"),
+        (b"jyo", b"jo"),
+        (b"zya", b"ja"),
+        (b"zyu", b"ju"),
+        (b"zyo", b"jo"),
+        (b"sha", b"xa"),
+        (b"shu", b"xu"),
+        (b"sho", b"xo"),
+        (b"shi", b"xi"),
+        (b"sya", b"xa"),
+        (b"syu", b"xu"),
+        (b"syo", b"xo"),
+        (b"cha", b"ca"),
+        (b"chu", b"cu"),
+        (b"cho", b"co"),
+        (b"chi", b"ci"),
+        (b"tya", b"ca"),
+        (b"tyu", b"cu"),
+        (b"tyo", b"co"),
+        (b"tsu", b"tu"),
+        (b"si", b"xi"),
+        (b"ti", b"ci"),
+        (b"zi", b"ji"),
+        (b"di", b"ji"),
+        (b"fu", b"hu"),
+        (b"mb", b"nb"),
+        (b"mp", b"np"),
+    ];
+    let mut spelled: Vec<u8> = Vec::with_capacity(plain.len());
+    let mut i = 0;
+    'outer: while i < plain.len() {
+        for (from, to) in SPELLINGS {
+            if plain[i..].starts_with(from) {
+                spelled.extend_from_slice(to);
+                i += from.len();
+                continue 'outer;
+            }
+        }
+        spelled.push(plain[i]);
+        i += 1;
+    }
+
+    // 長音 (ou / oo / uu) と、IME 式に打った撥音 (nn) を 1 文字に
+    let mut out = String::with_capacity(spelled.len());
+    let mut prev = None;
+    for b in spelled {
+        let long = matches!(
+            (prev, b),
+            (Some(b'o'), b'u' | b'o') | (Some(b'u'), b'u') | (Some(b'n'), b'n')
+        );
+        if !long {
+            out.push(b as char);
+        }
+        prev = Some(b);
+    }
+    out
+}
+
+fn is_romaji_punct(c: char) -> bool {
+    matches!(c, ' ' | '-' | '\'' | '.' | '・') || "āīūēōĀĪŪĒŌ".contains(c)
+}
+
+fn text_match(query: &str, candidate: &str) -> Option<MatchKind> {
+    if query.is_empty() {
+        return None;
+    }
+    if candidate == query {
+        Some(MatchKind::Exact)
+    } else if candidate.starts_with(query) {
+        Some(MatchKind::Prefix)
+    } else if candidate.contains(query) {
+        Some(MatchKind::Substring)
+    } else {
+        None
+    }
+}
+
+/// 許す編集距離。短い入力で誤りを許すと無関係な駅が大量に当たるので、
+/// 4 文字以下は許さない。
+fn max_edit_distance(query: &str) -> usize {
+    match query.len() {
+        0..=4 => 0,
+        5..=7 => 1,
+        _ => 2,
+    }
+}
+
+/// `query` と `candidate` の先頭部分との編集距離の最小値。`max` を超えるなら `None`。
+///
+/// 入力途中の "shinjk" (鍵は "xinjk") が "xinjuku" の先頭に近いことを拾うため、候補全体ではなく
+/// 先頭部分と比べる (DP の最終行の最小値)。
+fn fuzzy_prefix_distance(query: &str, candidate: &str, max: usize) -> Option<usize> {
+    if max == 0 {
+        return None;
+    }
+    let (q, c) = (query.as_bytes(), candidate.as_bytes());
+    // prev[j] = q[..i] と c[..j] の距離
+    let mut prev: Vec<usize> = (0..=c.len()).collect();
+    let mut cur = vec![0; c.len() + 1];
+    for i in 1..=q.len() {
+        cur[0] = i;
+        for j in 1..=c.len() {
+            let substitution = prev[j - 1] + usize::from(q[i - 1] != c[j - 1]);
+            cur[j] = substitution.min(prev[j] + 1).min(cur[j - 1] + 1);
+        }
+        // この行の最小値が上限を超えたら、以降の行でも下がらない
+        if cur.iter().min().is_some_and(|&d| d > max) {
+            return None;
+        }
+        std::mem::swap(&mut prev, &mut cur);
+    }
+    prev.into_iter().min().filter(|&d| d <= max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rank(query: &str, name: &str, katakana: &str, romaji: &str) -> Option<MatchKind> {
+        let key = romaji_key(romaji);
+        let station = StationNames {
+            name,
+            katakana,
+            romaji_key: Some(&key),
+            chinese: None,
+            korean: None,
+        };
+        NameQuery::new(query).rank(&station)
+    }
+
+    #[test]
+    fn romaji_key_folds_hepburn_variants() {
+        assert_eq!(romaji_key("Shinjuku"), romaji_key("shinjyuku"));
+        assert_eq!(romaji_key("Shinjuku"), romaji_key("shinnjuku"));
+        assert_eq!(romaji_key("Shinjuku"), romaji_key("sinzyuku"));
+        assert_eq!(romaji_key("Tōkyō"), romaji_key("tokyo"));
+        assert_eq!(romaji_key("Tōkyō"), romaji_key("toukyou"));
+        assert_eq!(romaji_key("Shimbashi"), romaji_key("shinbashi"));
+        assert_eq!(romaji_key("Shin-Ōsaka"), romaji_key("shin osaka"));
+        assert_eq!(romaji_key("Chōfu"), romaji_key("tyouhu"));
+        assert_ne!(romaji_key("Shibuya"), romaji_key("Shinjuku"));
+    }
+
+    #[test]
+    fn exact_beats_prefix_beats_substring() {
+        assert_eq!(
+            rank("しんじゅく", "新宿", "シンジュク", "Shinjuku"),
+            Some(MatchKind::Exact)
+        );
+        assert_eq!(
+            rank(
+                "しんじゅく",
+                "新宿三丁目",
+                "シンジュクサンチョウメ",
+                "Shinjuku-sanchome"
+            ),
+            Some(MatchKind::Prefix)
+        );
+        assert_eq!(
+            rank("しんじゅく", "西新宿", "ニシシンジュク", "Nishi-Shinjuku"),
+            Some(MatchKind::Substring)
+        );
+        assert!(MatchKind::Exact < MatchKind::Prefix);
+        assert!(MatchKind::Substring < MatchKind::Fuzzy(1));
+    }
+
+    #[test]
+    fn romaji_queries_match_through_the_key() {
+        assert_eq!(
+            rank("shinjyuku", "新宿", "シンジュク", "Shinjuku"),
+            Some(MatchKind::Exact)
+        );
+        assert_eq!(
+            rank("tokyo", "東京", "トウキョウ", "Tōkyō"),
+            Some(MatchKind::Exact)
+        );
+        assert_eq!(
+            rank("Toukyou", "東京", "トウキョウ", "Tōkyō"),
+            Some(MatchKind::Exact)
+        );
+        assert_eq!(
+            rank("新宿", "新宿", "シンジュク", "Shinjuku"),
+            Some(MatchKind::Exact)
+        );
+    }
+
+    #[test]
+    fn typos_match_within_the_edit_distance() {
+        assert_eq!(
+            rank("shinjku", "新宿", "シンジュク", "Shinjuku"),
+            Some(MatchKind::Fuzzy(1))
+        );
+        assert_eq!(
+            rank("shibya", "渋谷", "シブヤ", "Shibuya"),
+            Some(MatchKind::Fuzzy(1))
+        );
+        assert_eq!(
+            rank("ikebkurro", "池袋", "イケブクロ", "Ikebukuro"),
+            Some(MatchKind::Fuzzy(2))
+        );
+        // 短い入力では誤りを許さない
+        assert_eq!(rank("ebs", "恵比寿", "エビス", "Ebisu"), None);
+        assert_eq!(rank("osaka", "渋谷", "シブヤ", "Shibuya"), None);
+    }
+
+    #[test]
+    fn fuzzy_distance_is_measured_against_prefixes() {
+        assert_eq!(fuzzy_prefix_distance("sinzk", "sinzyuku", 1), Some(1));
+        assert_eq!(fuzzy_prefix_distance("sibuya", "sibuya", 1), Some(0));
+        assert_eq!(fuzzy_prefix_distance("abcdef", "sibuya", 2), None);
+        assert_eq!(fuzzy_prefix_distance("sibuya", "sibuya", 0), None);
+    }
+}
@@ -1,6 +1,9 @@
 use async_trait::async_trait;
 
-use crate::domain::{entity::line::Line, error::DomainError};
+use crate::domain::{
+    entity::{connection::ThroughService, line::Line},
+    error::DomainError,
+};
 
 #[async_trait]
 pub trait LineRepository: Send + Sync + 'static {
@@ -34,6 +37,23 @@ pub trait LineRepository: Send + Sync + 'static {
         line_name: String,
         limit: Option<u32>,
     ) -> Result<Vec<Line>, DomainError>;
+    /// Places where the line group runs through from one line onto another,
+    /// in the group's running order.
+    ///
+    /// The default returns no through services for repositories without
+    /// station-type data.
+    async fn get_through_services_by_line_group_id(
+        &self,
+        _line_group_id: u32,
+    ) -> Result<Vec<ThroughService>, DomainError> {
+        Ok(vec![])
+    }
+    /// Lines that some line group runs through to or from the given line.
+    ///
+    /// The default returns no lines for repositories without station-type data.
+    async fn get_through_service_line_ids(&self, _line_id: u32) -> Result<Vec<u32>, DomainError> {
+        Ok(vec![])
+    }
 }
 
 #[cfg(test)]
@@ -1,8 +1,10 @@
 use async_trait::async_trait;
 
 use crate::domain::{
-    entity::{gtfs::TransportType, station::Station},
+    entity::{connection::Connection, gtfs::TransportType, station::Station},
     error::DomainError,
+    geo::BoundingBox,
+    timetable::BusTimetable,
 };
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -82,6 +84,44 @@ pub trait StationRepository: Send + Sync + 'static {
             })
             .collect())
     }
+    /// Walking connections touching the given station group.
+    ///
+    /// `station_cd1` of each returned connection belongs to the queried group,
+    /// so `station_cd2` is always the station to walk to. The default returns
+    /// no connections for repositories without connection data.
+    async fn get_connections_by_station_group_id(
+        &self,
+        _station_group_id: u32,
+    ) -> Result<Vec<Connection>, DomainError> {
+        Ok(vec![])
+    }
+    /// Bus timetables departing from the given bus stations.
+    ///
+    /// The default returns no timetables for repositories without GTFS timetable data.
+    async fn get_bus_timetables_by_station_ids(
+        &self,
+        _station_ids: &[u32],
+    ) -> Result<Vec<BusTimetable>, DomainError> {
+        Ok(vec![])
+    }
+    /// Active stations inside `bounds`, for map viewports.
+    ///
+    /// Rail stations come before bus stops (unless `transport_type` narrows it
+    /// to one kind), each ordered by distance from the center of `bounds`, and
+    /// at most `limit` are returned. `line_type` keeps only stations whose line
+    /// has that `line_type`. With `dedupe_by_group` only the first station of
+    /// each `station_g_cd` is kept, before `limit` is applied. The default
+    /// returns no stations for repositories without a spatial lookup.
+    async fn get_in_bounds(
+        &self,
+        _bounds: BoundingBox,
+        _line_type: Option<i32>,
+        _transport_type: Option<TransportType>,
+        _limit: u32,
+        _dedupe_by_group: bool,
+    ) -> Result<Vec<Station>, DomainError> {
+        Ok(vec![])
+    }
     async fn get_bus_stops_near_stations(
         &self,
         coords: &[(u32, f64, f64)], // (station_g_cd, lat, lon)
@@ -24,6 +24,28 @@
 //! Anything the tables cannot map (kanji, Latin letters, digits) makes the whole
 //! conversion return `None`, so callers fall back to leaving the name empty rather
 //! than emitting a partial, misleading transcription.
+//!
+//! Other spellings are available through [`RomanizationSystem`] for partners
+//! that need them (Kunrei-shiki for government data, passport-style `oh`). They
+//! share the long-vowel detection above and differ only in the kana tables and
+//! in how a long vowel is written.
+
+/// Romanization style for [`katakana_to_romaji_in`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RomanizationSystem {
+    /// Modified Hepburn with macrons (Tōkyō) — the rail dataset's style.
+    #[default]
+    Hepburn,
+    /// Modified Hepburn in plain ASCII (Tokyo), as in `station_name_rn`.
+    HepburnNoMacron,
+    /// Kunrei-shiki (ISO 3602): si/ti/tu/hu/zi, circumflex long vowels (Tôkyô).
+    Kunrei,
+    /// Nihon-shiki: Kunrei-shiki that keeps ヂ/ヅ/ヲ apart as di/du/wo.
+    Nihon,
+    /// Passport (外務省) Hepburn: long o as `oh`, other long vowels dropped,
+    /// and ン before b/m/p as `m` (Namba).
+    Passport,
+}
 
 /// One romanized mora, tracking enough state to apply long-vowel rules.
 struct Syllable {
@@ -42,6 +64,11 @@ struct Syllable {
 /// kana tables, so the caller can decline to supplement rather than emit a
 /// partial transcription.
 pub fn katakana_to_romaji(input: &str) -> Option<String> {
+    katakana_to_romaji_in(input, RomanizationSystem::Hepburn)
+}
+
+/// [`katakana_to_romaji`] in the given romanization system.
+pub fn katakana_to_romaji_in(input: &str, system: RomanizationSystem) -> Option<String> {
     if input.trim().is_empty() {
         return None;
     }
@@ -101,7 +128,7 @@ pub fn katakana_to_romaji(input: &str) -> Option<String> {
 
         // Two-character digraphs (palatalised キャ etc. and loanword ファ etc.).
         if i + 1 < len {
-            if let Some(romaji) = lookup_digraph(c, chars[i + 1]) {
+            if let Some(romaji) = lookup_digraph_in(system, c, chars[i + 1]) {
                 let is_yoon = matches!(chars[i + 1], 'ャ' | 'ュ' | 'ョ');
                 push_syllable(&mut syllables, romaji, is_yoon, &mut pending_sokuon);
                 i += 2;
@@ -110,7 +137,7 @@ pub fn katakana_to_romaji(input: &str) -> Option<String> {
         }
 
         // Single kana.
-        if let Some(romaji) = lookup_single(c) {
+        if let Some(romaji) = lookup_single_in(system, c) {
             push_syllable(&mut syllables, romaji, false, &mut pending_sokuon);
             i += 1;
             continue;
@@ -120,8 +147,19 @@ pub fn katakana_to_romaji(input: &str) -> Option<String> {
         return None;
     }
 
-    let result: String = syllables.iter().map(|s| s.text.as_str()).collect();
-    let result = result.trim().to_string();
+    let mut result = String::new();
+    for (index, syllable) in syllables.iter().enumerate() {
+        let next = syllables.get(index + 1).map(|s| s.text.as_str());
+        if system == RomanizationSystem::Passport
+            && syllable.text == "n"
+            && next.is_some_and(|next| next.starts_with(['b', 'm', 'p']))
+        {
+            result.push('m');
+        } else {
+            result.push_str(&syllable.text);
+        }
+    }
+    let result = respell_long_vowels(result.trim(), system);
     if result.is_empty() {
         None
     } else {
@@ -132,7 +170,33 @@ pub fn katakana_to_romaji(input: &str) -> Option<String> {
 /// Convert a kana reading into a Title-Cased display name suitable for the
 /// romanized `stop_name_r` / `station_name_r` fields.
 pub fn romaji_display_name(input: &str) -> Option<String> {
-    katakana_to_romaji(input).map(|romaji| title_case(&romaji))
+    romaji_display_name_in(input, RomanizationSystem::Hepburn)
+}
+
+/// [`romaji_display_name`] in the given romanization system.
+pub fn romaji_display_name_in(input: &str, system: RomanizationSystem) -> Option<String> {
+    katakana_to_romaji_in(input, system).map(|romaji| title_case(&romaji))
+}
+
+/// Rewrite the macron long vowels produced by the converter in the system's
+/// own notation.
+fn respell_long_vowels(romaji: &str, system: RomanizationSystem) -> String {
+    match system {
+        RomanizationSystem::Hepburn => romaji.to_string(),
+        RomanizationSystem::HepburnNoMacron => strip_macrons(romaji),
+        RomanizationSystem::Kunrei | RomanizationSystem::Nihon => romaji
+            .chars()
+            .map(|c| match c {
+                'ā' => 'â',
+                'ī' => 'î',
+                'ū' => 'û',
+                'ē' => 'ê',
+                'ō' => 'ô',
+                other => other,
+            })
+            .collect(),
+        RomanizationSystem::Passport => strip_macrons(&romaji.replace('ō', "oh")),
+    }
 }
 
 /// Strip macron accents from a romanized name, mapping each long vowel back to
@@ -401,6 +465,60 @@ fn apply_handakuten(c: char) -> Option<char> {
     }
 }
 
+/// Digraph lookup that applies the Kunrei/Nihon-shiki spellings before
+/// falling back to the Hepburn table.
+fn lookup_digraph_in(system: RomanizationSystem, c1: char, c2: char) -> Option<&'static str> {
+    let nihon = system == RomanizationSystem::Nihon;
+    let romaji = match system {
+        RomanizationSystem::Kunrei | RomanizationSystem::Nihon => match (c1, c2) {
+            ('シ', 'ャ') => "sya",
+            ('シ', 'ュ') => "syu",
+            ('シ', 'ョ') => "syo",
+            ('チ', 'ャ') => "tya",
+            ('チ', 'ュ') => "tyu",
+            ('チ', 'ョ') => "tyo",
+            ('ジ', 'ャ') => "zya",
+            ('ジ', 'ュ') => "zyu",
+            ('ジ', 'ョ') => "zyo",
+            ('ヂ', 'ャ') if nihon => "dya",
+            ('ヂ', 'ュ') if nihon => "dyu",
+            ('ヂ', 'ョ') if nihon => "dyo",
+            ('ヂ', 'ャ') => "zya",
+            ('ヂ', 'ュ') => "zyu",
+            ('ヂ', 'ョ') => "zyo",
+            _ => return lookup_digraph(c1, c2),
+        },
+        _ => return lookup_digraph(c1, c2),
+    };
+    Some(romaji)
+}
+
+/// Single-kana lookup that applies the Kunrei/Nihon-shiki spellings before
+/// falling back to the Hepburn table.
+fn lookup_single_in(system: RomanizationSystem, c: char) -> Option<&'static str> {
+    let nihon = system == RomanizationSystem::Nihon;
+    let romaji = match system {
+        RomanizationSystem::Kunrei | RomanizationSystem::Nihon => match c {
+            'シ' => "si",
+            'チ' => "ti",
+            'ツ' => "tu",
+            'フ' => "hu",
+            'ジ' => "zi",
+            'ヂ' if nihon => "di",
+            'ヅ' if nihon => "du",
+            'ヰ' if nihon => "wi",
+            'ヱ' if nihon => "we",
+            'ヲ' if nihon => "wo",
+            'ヂ' => "zi",
+            'ヰ' => "i",
+            'ヱ' => "e",
+            _ => return lookup_single(c),
+        },
+        _ => return lookup_single(c),
+    };
+    Some(romaji)
+}
+
 /// Two-character katakana combinations (palatalised sounds and loanword kana).
 fn lookup_digraph(c1: char, c2: char) -> Option<&'static str> {
     let romaji = match (c1, c2) {
@@ -717,4 +835,67 @@ mod tests {
         );
         assert_eq!(romaji_display_name("渋谷"), None);
     }
+
+    fn romaji_in(input: &str, system: RomanizationSystem) -> String {
+        katakana_to_romaji_in(input, system).expect("expected convertible katakana")
+    }
+
+    #[test]
+    fn hepburn_without_macrons_matches_rn_column() {
+        let system = RomanizationSystem::HepburnNoMacron;
+        assert_eq!(romaji_in("トウキョウ", system), "tokyo");
+        assert_eq!(romaji_in("カゴシマチュウオウ", system), "kagoshimachuo");
+        assert_eq!(romaji_in("マッチャ", system), "matcha");
+    }
+
+    #[test]
+    fn kunrei_uses_si_ti_tu_hu_and_circumflex() {
+        let system = RomanizationSystem::Kunrei;
+        assert_eq!(romaji_in("トウキョウ", system), "tôkyô");
+        assert_eq!(romaji_in("シンジュク", system), "sinzyuku");
+        assert_eq!(romaji_in("チャツボ", system), "tyatubo");
+        assert_eq!(romaji_in("フジサワ", system), "huzisawa");
+        assert_eq!(romaji_in("ジュウモンジ", system), "zyûmonzi");
+        // っち is plain gemination, not Hepburn's tch
+        assert_eq!(romaji_in("マッチャ", system), "mattya");
+        assert_eq!(romaji_in("スーパー", system), "sûpâ");
+        // Kunrei-shiki merges ヂ/ヅ/ヲ into zi/zu/o
+        assert_eq!(romaji_in("ハナヂ", system), "hanazi");
+        assert_eq!(romaji_in("ミカヅキ", system), "mikazuki");
+    }
+
+    #[test]
+    fn nihon_keeps_di_du_wo_apart() {
+        let system = RomanizationSystem::Nihon;
+        assert_eq!(romaji_in("ハナヂ", system), "hanadi");
+        assert_eq!(romaji_in("ミカヅキ", system), "mikaduki");
+        assert_eq!(romaji_in("ヲ", system), "wo");
+        assert_eq!(romaji_in("シンジュク", system), "sinzyuku");
+    }
+
+    #[test]
+    fn passport_writes_long_o_as_oh_and_n_before_labials_as_m() {
+        let system = RomanizationSystem::Passport;
+        assert_eq!(romaji_in("トウキョウ", system), "tohkyoh");
+        assert_eq!(romaji_in("オオサカ", system), "ohsaka");
+        assert_eq!(romaji_in("ナンバ", system), "namba");
+        assert_eq!(romaji_in("シンバシ", system), "shimbashi");
+        assert_eq!(romaji_in("ホンマチ", system), "hommachi");
+        assert_eq!(romaji_in("シンジュク", system), "shinjuku");
+        // Long u is written without a mark
+        assert_eq!(romaji_in("ジュウモンジ", system), "jumonji");
+    }
+
+    #[test]
+    fn default_system_is_the_existing_hepburn() {
+        assert_eq!(RomanizationSystem::default(), RomanizationSystem::Hepburn);
+        assert_eq!(
+            romaji_in("カゴシマチュウオウ", RomanizationSystem::Hepburn),
+            romaji("カゴシマチュウオウ")
+        );
+        assert_eq!(
+            romaji_display_name_in("トウキョウ", RomanizationSystem::Kunrei).as_deref(),
+            Some("Tôkyô")
+        );
+    }
 }
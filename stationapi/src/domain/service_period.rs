@@ -0,0 +1,167 @@
+//! 駅・路線の営業期間。
+//!
+//! 既定では `e_status` (0:運用中, 1:運用前, 2:廃止, 3:無効化) だけで有効な駅・路線を
+//! 決める。日付を指定したときは開業日 (`open_ymd`) と廃止日 (`close_ymd`) で判定する。
+//! 日付が `0000-00-00` (不明) の側は `e_status` で補う。
+//!
+//! 無効化 (3) は廃止日が分かれば廃止 (2) と同じに扱う (例: 成田エクスプレスは
+//! 2021-03-13 より前なら出る)。廃止日の無い無効化はデータ整備の都合で API から
+//! 外したものなので、日付を指定しても出さない。廃止されたが廃止日の分からない駅・路線は
+//! [`Closure::Unknown`] として、開業後のどの日付でも出す。
+
+use super::calendar::Date;
+
+/// `e_status` の値。
+pub const STATUS_ACTIVE: i32 = 0;
+pub const STATUS_PLANNED: i32 = 1;
+pub const STATUS_CLOSED: i32 = 2;
+pub const STATUS_DISABLED: i32 = 3;
+
+/// 廃止の状態。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Closure {
+    /// 廃止されていない。
+    Open,
+    /// この日から営業していない (廃止日の前日が最終営業日)。
+    On(Date),
+    /// 廃止されたが廃止日が分からない。いつまで営業していたか決められないので、
+    /// 日付を指定したときは開業後ずっと営業していたものとして出す。
+    Unknown,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ServicePeriod {
+    pub opened: Option<Date>,
+    pub closure: Closure,
+    pub status: i32,
+}
+
+impl ServicePeriod {
+    /// `open_ymd` / `close_ymd` の列から作る。読めない日付は不明として扱う。
+    pub fn from_columns(open_ymd: &str, close_ymd: &str, status: i32) -> Self {
+        Self::new(Date::parse(open_ymd), Date::parse(close_ymd), status)
+    }
+
+    /// 廃止日が分かっていれば `e_status` によらず [`Closure::On`]。
+    pub fn new(opened: Option<Date>, closed: Option<Date>, status: i32) -> Self {
+        let closure = match closed {
+            Some(date) => Closure::On(date),
+            None if matches!(status, STATUS_CLOSED | STATUS_DISABLED) => Closure::Unknown,
+            None => Closure::Open,
+        };
+        Self {
+            opened,
+            closure,
+            status,
+        }
+    }
+
+    /// 廃止日の分かっている日。
+    pub fn closed(&self) -> Option<Date> {
+        match self.closure {
+            Closure::On(date) => Some(date),
+            Closure::Open | Closure::Unknown => None,
+        }
+    }
+
+    /// どの日付を指定しても出ないか (廃止日の無い無効化)。
+    pub fn is_hidden(&self) -> bool {
+        self.status == STATUS_DISABLED && self.closed().is_none()
+    }
+
+    /// `as_of` の日に営業していたか。`None` なら今の状態 (`e_status == 0`)。
+    pub fn is_open(&self, as_of: Option<Date>) -> bool {
+        match as_of {
+            None => self.status == STATUS_ACTIVE,
+            Some(date) => self.is_open_on(date),
+        }
+    }
+
+    pub fn is_open_on(&self, date: Date) -> bool {
+        if self.is_hidden() {
+            return false;
+        }
+        let opened = match self.opened {
+            Some(opened) => opened <= date,
+            None => self.status != STATUS_PLANNED,
+        };
+        let not_closed = match self.closure {
+            Closure::On(closed) => date < closed,
+            Closure::Open | Closure::Unknown => true,
+        };
+        opened && not_closed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> Date {
+        Date::new(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn defaults_to_the_current_status() {
+        let closed = ServicePeriod::from_columns("1910-11-23", "2023-04-01", STATUS_CLOSED);
+        assert!(!closed.is_open(None));
+        let active = ServicePeriod::from_columns("2026-03-14", "0000-00-00", STATUS_ACTIVE);
+        assert!(active.is_open(None));
+    }
+
+    #[test]
+    fn closed_station_is_open_until_the_day_before_closure() {
+        let period = ServicePeriod::from_columns("1910-11-23", "2023-04-01", STATUS_CLOSED);
+        assert!(!period.is_open(Some(date(1910, 11, 22))));
+        assert!(period.is_open(Some(date(1910, 11, 23))));
+        assert!(period.is_open(Some(date(2023, 3, 31))));
+        assert!(!period.is_open(Some(date(2023, 4, 1))));
+    }
+
+    #[test]
+    fn opening_date_previews_planned_and_new_stations() {
+        let planned = ServicePeriod::from_columns("2031-03-01", "0000-00-00", STATUS_PLANNED);
+        assert!(!planned.is_open(Some(date(2031, 2, 28))));
+        assert!(planned.is_open(Some(date(2031, 3, 1))));
+
+        let new = ServicePeriod::from_columns("2026-03-14", "0000-00-00", STATUS_ACTIVE);
+        assert!(!new.is_open(Some(date(2026, 3, 13))));
+        assert!(new.is_open(Some(date(2026, 3, 14))));
+    }
+
+    #[test]
+    fn unknown_dates_fall_back_to_the_status() {
+        let active = ServicePeriod::from_columns("0000-00-00", "0000-00-00", STATUS_ACTIVE);
+        assert!(active.is_open(Some(date(1900, 1, 1))));
+        // 開業日の分からない予定駅は、どの日付でも出さない
+        let planned = ServicePeriod::from_columns("0000-00-00", "0000-00-00", STATUS_PLANNED);
+        assert!(!planned.is_open(Some(date(2100, 1, 1))));
+    }
+
+    #[test]
+    fn unknown_closure_stays_open_after_opening() {
+        let closed = ServicePeriod::from_columns("1960-01-01", "0000-00-00", STATUS_CLOSED);
+        assert_eq!(closed.closure, Closure::Unknown);
+        assert!(!closed.is_open(None));
+        assert!(!closed.is_open(Some(date(1959, 12, 31))));
+        assert!(closed.is_open(Some(date(1970, 1, 1))));
+        assert!(closed.is_open(Some(date(2030, 1, 1))));
+    }
+
+    #[test]
+    fn disabled_rows_without_a_closing_date_never_appear() {
+        let disabled = ServicePeriod::from_columns("1914-12-20", "0000-00-00", STATUS_DISABLED);
+        assert!(disabled.is_hidden());
+        assert!(!disabled.is_open(None));
+        assert!(!disabled.is_open(Some(date(2000, 1, 1))));
+    }
+
+    #[test]
+    fn disabled_rows_with_a_closing_date_behave_like_closed_ones() {
+        let disabled = ServicePeriod::from_columns("0000-00-00", "2021-03-13", STATUS_DISABLED);
+        assert!(!disabled.is_hidden());
+        assert!(!disabled.is_open(None));
+        assert!(disabled.is_open(Some(date(2021, 3, 12))));
+        assert!(!disabled.is_open(Some(date(2021, 3, 13))));
+    }
+}
@@ -0,0 +1,362 @@
+//! 2 つの系統 (列車種別) の停車駅を比べる純粋ロジック。
+//!
+//! 系統ごとの駅列 (`sst_by_group` の順) を駅グループ (`station_g_cd`) で突き合わせ、
+//! 駅ごとに「止まる・通過する・走らない」を並べる。突き合わせは最長共通部分列で
+//! 取るので、片方だけが支線に入る・片方が途中で終わるといった系統でも並びが崩れない。
+//! 向きが逆の系統どうしは、片方を反転したほうがよく揃うならそちらで揃える。
+
+use crate::domain::entity::station::Station;
+use crate::model::StopCondition;
+
+/// ある駅での系統の扱い。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StopStatus {
+    Stops,
+    Passes,
+    /// その系統の駅列に無い。
+    NotServed,
+}
+
+impl StopStatus {
+    fn of(station: Option<&Station>) -> Self {
+        match station {
+            None => Self::NotServed,
+            Some(s) if s.stop_condition == StopCondition::Not => Self::Passes,
+            Some(_) => Self::Stops,
+        }
+    }
+}
+
+/// 比較表の 1 駅分。駅の行は系統ごとに持つ (走らない側は `None`)。
+#[derive(Clone, Debug, PartialEq)]
+pub struct StopPatternRow {
+    pub station_g_cd: i32,
+    pub a: Option<Station>,
+    pub b: Option<Station>,
+}
+
+impl StopPatternRow {
+    pub fn status_a(&self) -> StopStatus {
+        StopStatus::of(self.a.as_ref())
+    }
+
+    pub fn status_b(&self) -> StopStatus {
+        StopStatus::of(self.b.as_ref())
+    }
+
+    /// 表示に使う駅の行。A が走る駅は A の行を使う。
+    pub fn station(&self) -> &Station {
+        self.a
+            .as_ref()
+            .or(self.b.as_ref())
+            .expect("row has at least one station")
+    }
+
+    fn stops_a(&self) -> bool {
+        self.status_a() == StopStatus::Stops
+    }
+
+    fn stops_b(&self) -> bool {
+        self.status_b() == StopStatus::Stops
+    }
+}
+
+/// 駅数の集計。
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StopPatternSummary {
+    /// 両方が止まる駅。
+    pub stops_both: u32,
+    pub stops_only_a: u32,
+    pub stops_only_b: u32,
+    /// どちらかが走るが、どちらも止まらない駅。
+    pub stops_neither: u32,
+    /// 両方が走る駅 (止まるかどうかは問わない)。
+    pub shared_stations: u32,
+}
+
+/// 停車駅が食い違う区間。両端は両方が止まる駅 (駅列の端で終わるときは `None`)。
+#[derive(Clone, Debug, PartialEq)]
+pub struct StopPatternDivergence {
+    pub from: Option<usize>,
+    pub to: Option<usize>,
+    /// 区間内で A だけ・B だけが止まる駅 (`rows` の添字)。
+    pub only_a: Vec<usize>,
+    pub only_b: Vec<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct StopPatternComparison {
+    pub rows: Vec<StopPatternRow>,
+    pub summary: StopPatternSummary,
+    pub divergences: Vec<StopPatternDivergence>,
+}
+
+/// 2 系統の駅列を突き合わせる。駅列はそれぞれの系統の運転順。
+pub fn compare_stop_patterns(a: &[Station], b: &[Station]) -> StopPatternComparison {
+    let forward = common_subsequence(a, b.iter());
+    let backward = common_subsequence(a, b.iter().rev());
+    let (b, pairs): (Vec<&Station>, _) = if backward.len() > forward.len() {
+        (b.iter().rev().collect(), backward)
+    } else {
+        (b.iter().collect(), forward)
+    };
+
+    let rows = merge(a, &b, &pairs);
+    let summary = summarize(&rows);
+    let divergences = divergences(&rows);
+    StopPatternComparison {
+        rows,
+        summary,
+        divergences,
+    }
+}
+
+/// `station_g_cd` の最長共通部分列を、揃った添字の組で返す。
+fn common_subsequence<'a>(
+    a: &[Station],
+    b: impl Iterator<Item = &'a Station>,
+) -> Vec<(usize, usize)> {
+    let b: Vec<i32> = b.map(|s| s.station_g_cd).collect();
+    let (n, m) = (a.len(), b.len());
+    // lengths[i][j] = a[i..] と b[j..] の最長共通部分列の長さ
+    let mut lengths = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lengths[i][j] = if a[i].station_g_cd == b[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+    let mut pairs = Vec::with_capacity(lengths[0][0] as usize);
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if a[i].station_g_cd == b[j] {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+/// 揃った駅を軸に 1 本の駅列へまとめる。揃わない駅は、軸の間に A・B の順で入れる。
+fn merge(a: &[Station], b: &[&Station], pairs: &[(usize, usize)]) -> Vec<StopPatternRow> {
+    let mut rows = Vec::with_capacity(a.len() + b.len() - pairs.len());
+    let only = |station: &Station, in_a: bool| StopPatternRow {
+        station_g_cd: station.station_g_cd,
+        a: in_a.then(|| station.clone()),
+        b: (!in_a).then(|| station.clone()),
+    };
+    let (mut i, mut j) = (0, 0);
+    for &(pi, pj) in pairs.iter().chain(std::iter::once(&(a.len(), b.len()))) {
+        rows.extend(a[i..pi].iter().map(|s| only(s, true)));
+        rows.extend(b[j..pj].iter().map(|s| only(s, false)));
+        if pi < a.len() {
+            rows.push(StopPatternRow {
+                station_g_cd: a[pi].station_g_cd,
+                a: Some(a[pi].clone()),
+                b: Some(b[pj].clone()),
+            });
+        }
+        (i, j) = (pi + 1, pj + 1);
+    }
+    rows
+}
+
+fn summarize(rows: &[StopPatternRow]) -> StopPatternSummary {
+    let mut summary = StopPatternSummary::default();
+    for row in rows {
+        if row.a.is_some() && row.b.is_some() {
+            summary.shared_stations += 1;
+        }
+        match (row.stops_a(), row.stops_b()) {
+            (true, true) => summary.stops_both += 1,
+            (true, false) => summary.stops_only_a += 1,
+            (false, true) => summary.stops_only_b += 1,
+            (false, false) => summary.stops_neither += 1,
+        }
+    }
+    summary
+}
+
+/// 両方が止まる駅で区切り、間に片方だけが止まる駅を含む区間を挙げる。
+fn divergences(rows: &[StopPatternRow]) -> Vec<StopPatternDivergence> {
+    let mut found = Vec::new();
+    let mut current = StopPatternDivergence {
+        from: None,
+        to: None,
+        only_a: Vec::new(),
+        only_b: Vec::new(),
+    };
+    for (index, row) in rows.iter().enumerate() {
+        match (row.stops_a(), row.stops_b()) {
+            (true, true) => {
+                current.to = Some(index);
+                let next = StopPatternDivergence {
+                    from: Some(index),
+                    to: None,
+                    only_a: Vec::new(),
+                    only_b: Vec::new(),
+                };
+                let done = std::mem::replace(&mut current, next);
+                if !done.only_a.is_empty() || !done.only_b.is_empty() {
+                    found.push(done);
+                }
+            }
+            (true, false) => current.only_a.push(index),
+            (false, true) => current.only_b.push(index),
+            (false, false) => {}
+        }
+    }
+    if !current.only_a.is_empty() || !current.only_b.is_empty() {
+        found.push(current);
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entity::gtfs::TransportType;
+
+    fn station(station_g_cd: i32, stops: bool) -> Station {
+        Station {
+            station_cd: station_g_cd,
+            station_g_cd,
+            station_name: String::new(),
+            station_name_k: String::new(),
+            station_name_r: None,
+            station_name_zh: None,
+            station_name_ko: None,
+            station_numbers: vec![],
+            station_number1: None,
+            station_number2: None,
+            station_number3: None,
+            station_number4: None,
+            three_letter_code: None,
+            line_cd: 1,
+            line: None,
+            lines: vec![],
+            pref_cd: 13,
+            post: String::new(),
+            address: String::new(),
+            lon: 0.0,
+            lat: 0.0,
+            open_ymd: String::new(),
+            close_ymd: String::new(),
+            e_status: 0,
+            e_sort: station_g_cd,
+            stop_condition: if stops {
+                StopCondition::All
+            } else {
+                StopCondition::Not
+            },
+            distance: None,
+            has_train_types: false,
+            train_type: None,
+            company_cd: Some(1),
+            line_name: None,
+            line_name_k: None,
+            line_name_h: None,
+            line_name_r: None,
+            line_name_zh: None,
+            line_name_ko: None,
+            line_color_c: None,
+            line_type: Some(2),
+            line_symbol1: None,
+            line_symbol2: None,
+            line_symbol3: None,
+            line_symbol4: None,
+            line_symbol1_color: None,
+            line_symbol2_color: None,
+            line_symbol3_color: None,
+            line_symbol4_color: None,
+            line_symbol1_shape: None,
+            line_symbol2_shape: None,
+            line_symbol3_shape: None,
+            line_symbol4_shape: None,
+            average_distance: None,
+            type_id: None,
+            sst_id: None,
+            type_cd: None,
+            line_group_cd: Some(1),
+            pass: None,
+            type_name: None,
+            type_name_k: None,
+            type_name_r: None,
+            type_name_zh: None,
+            type_name_ko: None,
+            color: None,
+            direction: None,
+            kind: None,
+            transport_type: TransportType::Rail,
+        }
+    }
+
+    fn pattern(stops: &[(i32, bool)]) -> Vec<Station> {
+        stops.iter().map(|&(g, s)| station(g, s)).collect()
+    }
+
+    #[test]
+    fn identical_patterns_have_no_divergence() {
+        let a = pattern(&[(1, true), (2, false), (3, true)]);
+        let result = compare_stop_patterns(&a, &a);
+        assert_eq!(result.rows.len(), 3);
+        assert_eq!(result.summary.stops_both, 2);
+        assert_eq!(result.summary.stops_neither, 1);
+        assert_eq!(result.summary.shared_stations, 3);
+        assert!(result.divergences.is_empty());
+    }
+
+    #[test]
+    fn divergence_is_bounded_by_common_stops() {
+        // 急行は 2・4 に、快速急行は 3 に止まる
+        let a = pattern(&[(1, true), (2, true), (3, false), (4, true), (5, true)]);
+        let b = pattern(&[(1, true), (2, false), (3, true), (4, false), (5, true)]);
+        let result = compare_stop_patterns(&a, &b);
+        assert_eq!(result.summary.stops_only_a, 2);
+        assert_eq!(result.summary.stops_only_b, 1);
+        assert_eq!(
+            result.divergences,
+            vec![StopPatternDivergence {
+                from: Some(0),
+                to: Some(4),
+                only_a: vec![1, 3],
+                only_b: vec![2],
+            }]
+        );
+    }
+
+    #[test]
+    fn stations_served_by_one_pattern_are_not_served_by_the_other() {
+        // B は 3 で分かれて支線の 10・11 へ入る
+        let a = pattern(&[(1, true), (2, true), (3, true), (4, true)]);
+        let b = pattern(&[(1, true), (3, true), (10, true), (11, true)]);
+        let result = compare_stop_patterns(&a, &b);
+        let order: Vec<i32> = result.rows.iter().map(|r| r.station_g_cd).collect();
+        assert_eq!(order, vec![1, 2, 3, 4, 10, 11]);
+        assert_eq!(result.rows[1].status_b(), StopStatus::NotServed);
+        assert_eq!(result.rows[4].status_a(), StopStatus::NotServed);
+        assert_eq!(result.summary.shared_stations, 2);
+        assert_eq!(result.divergences.len(), 2);
+        assert_eq!(result.divergences[1].from, Some(2));
+        assert_eq!(result.divergences[1].to, None);
+    }
+
+    #[test]
+    fn reversed_pattern_is_aligned_to_the_first() {
+        let a = pattern(&[(1, true), (2, true), (3, true)]);
+        let b = pattern(&[(3, true), (2, false), (1, true)]);
+        let result = compare_stop_patterns(&a, &b);
+        let order: Vec<i32> = result.rows.iter().map(|r| r.station_g_cd).collect();
+        assert_eq!(order, vec![1, 2, 3]);
+        assert_eq!(result.rows[1].status_b(), StopStatus::Passes);
+        assert_eq!(result.summary.stops_only_a, 1);
+    }
+}
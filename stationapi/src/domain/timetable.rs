@@ -0,0 +1,255 @@
+//! バス停の時刻表から次の発車を引く純粋ロジック。
+//!
+//! 時刻表は GTFS の運行日 (service day) 基準で持つ。深夜便は 24:30 のように
+//! 24 時を超える分で表されるので、ある日付の発車を求めるには当日の運行日に加えて
+//! 前日の運行日のうち 24 時以降の便も見る。
+//!
+//! 運行日に走るかどうかは `calendar_dates` の例外を先に見て、無ければ
+//! `calendar` の有効期間と曜日で決める (GTFS の定義どおり)。曜日は [`DayKind`] で
+//! 読み替え、祝日・年末年始は日曜の列で走るかを見る。祝日を例外に載せていない
+//! フィード (ODPT から作ったものなど) でも、平日ダイヤの便が祝日に出てこない。
+
+use crate::domain::calendar::{Date, DayKind};
+use crate::domain::entity::gtfs::{GtfsCalendar, GtfsCalendarDate};
+
+/// 1 日の分数。
+pub const MINUTES_PER_DAY: u32 = 24 * 60;
+
+/// 1 停留所・1 運行パターン・1 行先ぶんの発車時刻。
+#[derive(Clone, Debug, PartialEq)]
+pub struct BusTimetable {
+    /// 系統ごとのバス停 (`stations.station_cd`)。
+    pub station_cd: i32,
+    /// 系統 (`lines.line_cd`)。
+    pub line_cd: i32,
+    pub headsign: String,
+    pub calendar: Option<GtfsCalendar>,
+    pub calendar_dates: Vec<GtfsCalendarDate>,
+    /// 運行日 0 時からの分。昇順。
+    pub departure_minutes: Vec<u32>,
+}
+
+impl BusTimetable {
+    /// この時刻表の便が `date` を運行日として走るか。
+    pub fn runs_on(&self, date: Date) -> bool {
+        let ymd = date.to_gtfs();
+        if let Some(exception) = self.calendar_dates.iter().find(|d| d.date == ymd) {
+            return exception.is_added();
+        }
+        // 祝日は日曜ダイヤ (GTFS の sunday 列) で走る
+        let weekday = match DayKind::of(date) {
+            DayKind::Weekday | DayKind::Saturday => date.weekday(),
+            DayKind::Holiday => 6,
+        };
+        self.calendar
+            .as_ref()
+            .is_some_and(|c| c.covers_date(&ymd) && c.runs_on_weekday(weekday))
+    }
+}
+
+/// 発車 1 本。
+#[derive(Clone, Debug, PartialEq)]
+pub struct Departure {
+    pub station_cd: i32,
+    pub line_cd: i32,
+    pub headsign: String,
+    /// 便が属する運行日。
+    pub service_date: Date,
+    /// 運行日 0 時からの分。24 時を超えうる。
+    pub departure_minutes: u32,
+}
+
+impl Departure {
+    /// 実際に発車する暦日。
+    pub fn date(&self) -> Date {
+        self.service_date
+            .add_days(i64::from(self.departure_minutes / MINUTES_PER_DAY))
+    }
+
+    /// 実際に発車する時刻 (0 時からの分、0..1440)。
+    pub fn minutes_of_day(&self) -> u32 {
+        self.departure_minutes % MINUTES_PER_DAY
+    }
+}
+
+/// `HH:MM` を 0 時からの分へ。24 時以降は受け付けない。
+pub fn parse_clock(value: &str) -> Option<u32> {
+    let (hours, minutes) = value.trim().split_once(':')?;
+    if hours.is_empty() || hours.len() > 2 || minutes.len() != 2 {
+        return None;
+    }
+    let hours: u32 = hours.parse().ok()?;
+    let minutes: u32 = minutes.parse().ok()?;
+    (hours < 24 && minutes < 60).then_some(hours * 60 + minutes)
+}
+
+/// 0 時からの分を `HH:MM` へ。
+pub fn format_clock(minutes: u32) -> String {
+    format!("{:02}:{:02}", minutes / 60, minutes % 60)
+}
+
+/// `date` の `after_minutes` 以降 (その分ちょうどを含む) に発車する便を早い順に返す。
+///
+/// 翌日の便は含めない。同時刻の便は station_cd、行先の順に並べる。
+pub fn next_departures(
+    timetables: &[BusTimetable],
+    date: Date,
+    after_minutes: u32,
+    limit: usize,
+) -> Vec<Departure> {
+    let previous = date.pred();
+    let mut out: Vec<Departure> = Vec::new();
+    // (運行日, その運行日基準での範囲)。当日運行日の 24 時以降は翌日の発車なので除く
+    for (service_date, from, until) in [
+        (
+            previous,
+            after_minutes + MINUTES_PER_DAY,
+            2 * MINUTES_PER_DAY,
+        ),
+        (date, after_minutes, MINUTES_PER_DAY),
+    ] {
+        for timetable in timetables.iter().filter(|t| t.runs_on(service_date)) {
+            let start = timetable.departure_minutes.partition_point(|&m| m < from);
+            out.extend(
+                timetable.departure_minutes[start..]
+                    .iter()
+                    .take_while(|&&m| m < until)
+                    .take(limit)
+                    .map(|&departure_minutes| Departure {
+                        station_cd: timetable.station_cd,
+                        line_cd: timetable.line_cd,
+                        headsign: timetable.headsign.clone(),
+                        service_date,
+                        departure_minutes,
+                    }),
+            );
+        }
+    }
+
+    // 当日 0 時からの分で比べる (前日運行日の 24:30 は当日の 0:30)
+    let absolute = |d: &Departure| {
+        d.departure_minutes as i64
+            - (date.days_since_epoch() - d.service_date.days_since_epoch()) * MINUTES_PER_DAY as i64
+    };
+    out.sort_by(|a, b| {
+        absolute(a)
+            .cmp(&absolute(b))
+            .then(a.station_cd.cmp(&b.station_cd))
+            .then_with(|| a.headsign.cmp(&b.headsign))
+    });
+    out.truncate(limit);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn weekday_calendar() -> GtfsCalendar {
+        GtfsCalendar::new(
+            "1".to_string(),
+            true,
+            true,
+            true,
+            true,
+            true,
+            false,
+            false,
+            "20240101".to_string(),
+            "20241231".to_string(),
+        )
+    }
+
+    fn timetable(station_cd: i32, headsign: &str, minutes: &[u32]) -> BusTimetable {
+        BusTimetable {
+            station_cd,
+            line_cd: station_cd,
+            headsign: headsign.to_string(),
+            calendar: Some(weekday_calendar()),
+            calendar_dates: vec![],
+            departure_minutes: minutes.to_vec(),
+        }
+    }
+
+    fn date(value: &str) -> Date {
+        Date::parse(value).unwrap()
+    }
+
+    #[test]
+    fn runs_on_uses_weekday_range_and_exceptions() {
+        let mut t = timetable(1, "渋谷駅", &[600]);
+        assert!(t.runs_on(date("2024-04-01"))); // 月曜
+        assert!(!t.runs_on(date("2024-04-06"))); // 土曜
+        assert!(!t.runs_on(date("2025-01-06"))); // 有効期間外
+
+        t.calendar_dates = vec![
+            GtfsCalendarDate::new(1, "1".to_string(), "20240401".to_string(), 2),
+            GtfsCalendarDate::new(2, "1".to_string(), "20240406".to_string(), 1),
+        ];
+        assert!(!t.runs_on(date("2024-04-01")));
+        assert!(t.runs_on(date("2024-04-06")));
+    }
+
+    #[test]
+    fn holidays_use_the_sunday_pattern_unless_listed_as_exceptions() {
+        let weekday = timetable(1, "渋谷駅", &[600]);
+        let mut holiday = timetable(2, "渋谷駅", &[660]);
+        holiday.calendar.as_mut().unwrap().sunday = true;
+        holiday.calendar.as_mut().unwrap().monday = false;
+        // 2024-02-12 は振替休日 (月曜)
+        assert!(!weekday.runs_on(date("2024-02-12")));
+        assert!(holiday.runs_on(date("2024-02-12")));
+        assert!(holiday.runs_on(date("2024-12-31"))); // 年末
+
+        // 例外が書かれていればそちらに従う
+        let mut exception = timetable(3, "渋谷駅", &[600]);
+        exception.calendar_dates = vec![GtfsCalendarDate::new(
+            1,
+            "1".to_string(),
+            "20240212".to_string(),
+            1,
+        )];
+        assert!(exception.runs_on(date("2024-02-12")));
+    }
+
+    #[test]
+    fn next_departures_merges_and_orders_timetables() {
+        let timetables = [
+            timetable(1, "渋谷駅", &[480, 500, 520]),
+            timetable(2, "目黒駅", &[490, 500]),
+        ];
+        let found = next_departures(&timetables, date("2024-04-01"), 500, 3);
+        let summary: Vec<(i32, u32)> = found
+            .iter()
+            .map(|d| (d.station_cd, d.departure_minutes))
+            .collect();
+        assert_eq!(summary, vec![(1, 500), (2, 500), (1, 520)]);
+    }
+
+    #[test]
+    fn after_midnight_trips_belong_to_previous_service_day() {
+        // 月曜運行日の 24:30 は火曜 0:30 に発車する
+        let timetables = [timetable(1, "深夜", &[1470]), timetable(2, "始発", &[300])];
+        let found = next_departures(&timetables, date("2024-04-02"), 0, 10);
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].station_cd, 1);
+        assert_eq!(found[0].service_date, date("2024-04-01"));
+        assert_eq!(found[0].date(), date("2024-04-02"));
+        assert_eq!(format_clock(found[0].minutes_of_day()), "00:30");
+        assert_eq!(found[1].station_cd, 2);
+
+        // 土曜は前日 (金曜) 運行日の深夜便だけ。土曜運行日の便は走らない
+        let found = next_departures(&timetables, date("2024-04-06"), 0, 10);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].service_date, date("2024-04-05"));
+    }
+
+    #[test]
+    fn parse_clock_rejects_out_of_range() {
+        assert_eq!(parse_clock("08:05"), Some(485));
+        assert_eq!(parse_clock("8:05"), Some(485));
+        assert_eq!(parse_clock("24:00"), None);
+        assert_eq!(parse_clock("08:60"), None);
+        assert_eq!(parse_clock("0805"), None);
+    }
+}
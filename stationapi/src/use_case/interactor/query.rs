@@ -4,6 +4,11 @@ const CONNECTED_ROUTE_MAX_SEGMENTS: usize = 8;
 const CONNECTED_ROUTE_MAX_STATES: usize = 4096;
 const CONNECTED_ROUTE_MAX_CANDIDATES: usize = 65_536;
 const CONNECTED_ROUTE_MAX_RESULTS: usize = 32;
+const DEPARTURES_DEFAULT_LIMIT: usize = 10;
+const DEPARTURES_MAX_LIMIT: usize = 100;
+/// 地図の表示範囲に出す駅の既定件数と上限。GraphQL 層の複雑さの見積もりにも使う。
+pub const STATIONS_IN_BOUNDS_DEFAULT_LIMIT: u32 = 200;
+pub const STATIONS_IN_BOUNDS_MAX_LIMIT: u32 = 1_000;
 
 #[derive(Clone)]
 struct ConnectedRouteState {
@@ -11,6 +16,17 @@ struct ConnectedRouteState {
     line_group_ids: Vec<u32>,
     visited_station_groups: HashSet<u32>,
     stops: Vec<ConnectedRouteStopRef>,
+    /// 各レグの乗車駅。`stops` は乗換駅を前レグの降車駅として 1 回だけ持つため、
+    /// レグごとの所要時間を推定するときに使う。
+    boardings: Vec<ConnectedRouteStopRef>,
+}
+
+/// 停車駅の詳細まで解決した連結経路の候補。
+struct ResolvedConnectedRoute {
+    id: u32,
+    line_group_ids: Vec<u32>,
+    stops: Vec<Station>,
+    boardings: Vec<Station>,
 }
 
 #[derive(Clone, Copy)]
@@ -47,8 +63,10 @@ use crate::{
             estimate_arrival_minutes_calibrated, is_circular_route, select_circular_arc,
             EstimatedStop, EstimationParams,
         },
+        calendar::Date,
         entity::{
             company::Company,
+            connection::{StationTransfer, ThroughServiceLink},
             gtfs::{TransportType, TransportTypeFilter},
             line::Line,
             line_symbol::LineSymbol,
@@ -56,6 +74,8 @@ use crate::{
             station_number::StationNumber,
             train_type::TrainType,
         },
+        geo::BoundingBox,
+        journey::{build_journey, rank_journeys, Journey, JourneyLegInput},
         normalize::normalize_for_search,
         repository::{
             company_repository::CompanyRepository,
@@ -64,10 +84,14 @@ use crate::{
             train_type_repository::TrainTypeRepository,
         },
         segment_speed_table::{segment_override_applies_to_kind, segment_speed_override_kmh},
+        stop_pattern::{compare_stop_patterns, StopPatternComparison},
+        timetable::{next_departures, Departure},
     },
     model::{self, Route},
     use_case::{
-        dto::simulation::resolve_speed_profile, error::UseCaseError, traits::query::QueryUseCase,
+        dto::simulation::resolve_speed_profile,
+        error::UseCaseError,
+        traits::query::{QueryUseCase, StationList, StationScope},
     },
 };
 use async_trait::async_trait;
@@ -92,42 +116,49 @@ where
         &self,
         station_id: u32,
         transport_type: TransportTypeFilter,
-    ) -> Result<Option<Station>, UseCaseError> {
-        let Some(station) = self.station_repository.find_by_id(station_id).await? else {
-            return Ok(None);
+    ) -> Result<StationList, UseCaseError> {
+        let scope = StationScope {
+            transport_type,
+            skip_types_join: true,
+            ..Default::default()
         };
-        // Filter by transport_type
-        if !matches_transport_filter(station.transport_type, transport_type) {
-            return Ok(None);
-        }
         let stations = self
-            .update_station_vec_with_attributes(vec![station], None, transport_type, true)
-            .await?;
+            .station_repository
+            .find_by_id(station_id)
+            .await?
+            // Filter by transport_type
+            .filter(|s| matches_transport_filter(s.transport_type, transport_type))
+            .into_iter()
+            .collect();
 
-        Ok(stations.into_iter().next())
+        Ok(StationList { stations, scope })
     }
     async fn get_stations_by_id_vec(
         &self,
         station_ids: &[u32],
         transport_type: TransportTypeFilter,
-    ) -> Result<Vec<Station>, UseCaseError> {
+    ) -> Result<StationList, UseCaseError> {
         let stations = self.station_repository.get_by_id_vec(station_ids).await?;
         // Filter by transport_type
         let stations: Vec<Station> = stations
             .into_iter()
             .filter(|s| matches_transport_filter(s.transport_type, transport_type))
             .collect();
-        let stations = self
-            .update_station_vec_with_attributes(stations, None, transport_type, true)
-            .await?;
 
-        Ok(stations)
+        Ok(StationList {
+            stations,
+            scope: StationScope {
+                transport_type,
+                skip_types_join: true,
+                ..Default::default()
+            },
+        })
     }
     async fn get_stations_by_group_id(
         &self,
         station_group_id: u32,
         transport_type: TransportTypeFilter,
-    ) -> Result<Vec<Station>, UseCaseError> {
+    ) -> Result<StationList, UseCaseError> {
         let stations = self
             .station_repository
             .get_by_station_group_id(station_group_id)
@@ -139,16 +170,14 @@ where
             .filter(|s| matches_transport_filter(s.transport_type, transport_type))
             .collect();
 
-        let stations = self
-            .update_station_vec_with_attributes(
-                stations,
-                Some(station_group_id),
+        Ok(StationList {
+            stations,
+            scope: StationScope {
+                line_group_id: Some(station_group_id),
                 transport_type,
-                false,
-            )
-            .await?;
-
-        Ok(stations)
+                ..Default::default()
+            },
+        })
     }
     async fn get_stations_by_group_id_vec(
         &self,
@@ -178,7 +207,7 @@ where
         longitude: f64,
         limit: Option<u32>,
         transport_type: TransportTypeFilter,
-    ) -> Result<Vec<Station>, UseCaseError> {
+    ) -> Result<StationList, UseCaseError> {
         let stations = self
             .station_repository
             .get_by_coordinates(
@@ -189,11 +218,45 @@ where
             )
             .await?;
 
+        Ok(StationList {
+            stations,
+            scope: StationScope {
+                transport_type,
+                skip_types_join: true,
+                ..Default::default()
+            },
+        })
+    }
+    async fn get_stations_in_bounds(
+        &self,
+        bounds: BoundingBox,
+        line_type: Option<i32>,
+        limit: Option<u32>,
+        dedupe_by_group: bool,
+        transport_type: TransportTypeFilter,
+    ) -> Result<StationList, UseCaseError> {
+        let limit = limit
+            .unwrap_or(STATIONS_IN_BOUNDS_DEFAULT_LIMIT)
+            .min(STATIONS_IN_BOUNDS_MAX_LIMIT);
         let stations = self
-            .update_station_vec_with_attributes(stations, None, transport_type, true)
+            .station_repository
+            .get_in_bounds(
+                bounds,
+                line_type,
+                filter_to_db_type(transport_type),
+                limit,
+                dedupe_by_group,
+            )
             .await?;
 
-        Ok(stations)
+        Ok(StationList {
+            stations,
+            scope: StationScope {
+                transport_type,
+                skip_types_join: true,
+                ..Default::default()
+            },
+        })
     }
     async fn get_stations_by_line_id(
         &self,
@@ -201,7 +264,7 @@ where
         station_id: Option<u32>,
         direction_id: Option<u32>,
         transport_type: TransportTypeFilter,
-    ) -> Result<Vec<Station>, UseCaseError> {
+    ) -> Result<StationList, UseCaseError> {
         let stations = self
             .station_repository
             .get_by_line_id(line_id, station_id, direction_id)
@@ -216,25 +279,23 @@ where
             None
         };
 
-        let stations = self
-            .update_station_vec_with_attributes(
-                stations,
-                line_group_id.map(|id| id as u32),
+        Ok(StationList {
+            stations,
+            scope: StationScope {
+                line_group_id: line_group_id.map(|id| id as u32),
                 transport_type,
-                false,
-            )
-            .await?;
-
-        Ok(stations)
+                ..Default::default()
+            },
+        })
     }
     async fn get_stations_by_line_id_vec(
         &self,
         line_ids: &[u32],
         transport_type: TransportTypeFilter,
-    ) -> Result<Vec<Station>, UseCaseError> {
+    ) -> Result<StationList, UseCaseError> {
         // Fetch all stations at station groups matching the requested line IDs.
         // This returns a superset that includes stations on other lines at the same
-        // physical locations, eliminating the need for a separate group station query.
+        // physical locations; only stations on the requested lines are kept below.
         let all_group_stations = self
             .station_repository
             .get_by_line_id_vec_with_group_stations(line_ids)
@@ -268,17 +329,14 @@ where
                 .then(a.station_cd.cmp(&b.station_cd))
         });
 
-        let stations = self
-            .update_station_vec_with_attributes_inner(
-                stations,
-                None,
+        Ok(StationList {
+            stations,
+            scope: StationScope {
                 transport_type,
-                true,
-                Some(all_group_stations),
-            )
-            .await?;
-
-        Ok(stations)
+                skip_types_join: true,
+                ..Default::default()
+            },
+        })
     }
     async fn get_stations_by_name(
         &self,
@@ -286,7 +344,7 @@ where
         limit: Option<u32>,
         from_station_group_id: Option<u32>,
         transport_type: TransportTypeFilter,
-    ) -> Result<Vec<Station>, UseCaseError> {
+    ) -> Result<StationList, UseCaseError> {
         let stations = self
             .station_repository
             .get_by_name(
@@ -297,11 +355,14 @@ where
             )
             .await?;
 
-        let stations = self
-            .update_station_vec_with_attributes(stations, None, transport_type, true)
-            .await?;
-
-        Ok(stations)
+        Ok(StationList {
+            stations,
+            scope: StationScope {
+                transport_type,
+                skip_types_join: true,
+                ..Default::default()
+            },
+        })
     }
     async fn find_company_by_id_vec(
         &self,
@@ -329,28 +390,26 @@ where
         &self,
         line_group_id: u32,
         transport_type: TransportTypeFilter,
-    ) -> Result<Vec<Station>, UseCaseError> {
+    ) -> Result<StationList, UseCaseError> {
         let stations = self
             .station_repository
             .get_by_line_group_id(line_group_id)
             .await?;
 
-        let stations = self
-            .update_station_vec_with_attributes(
-                stations,
-                Some(line_group_id),
+        Ok(StationList {
+            stations,
+            scope: StationScope {
+                line_group_id: Some(line_group_id),
                 transport_type,
-                false,
-            )
-            .await?;
-
-        Ok(stations)
+                ..Default::default()
+            },
+        })
     }
     async fn get_stations_by_line_group_id_vec(
         &self,
         line_group_ids: &[u32],
         transport_type: TransportTypeFilter,
-    ) -> Result<Vec<Station>, UseCaseError> {
+    ) -> Result<StationList, UseCaseError> {
         let stations = self
             .station_repository
             .get_by_line_group_id_vec(line_group_ids)
@@ -361,16 +420,38 @@ where
             .filter(|s| matches_transport_filter(s.transport_type, transport_type))
             .collect();
 
-        // lines, companies, station_numbers等を付与（train_typeはNoneで空になる）
-        // train_typeは後続のget_by_line_group_id_vecで取得するためここでは付けない
+        // train_type は系統ごとに引き直すので、ここでは系統を 1 つに絞らない
+        Ok(StationList {
+            stations,
+            scope: StationScope {
+                line_group_ids: line_group_ids.to_vec(),
+                transport_type,
+                skip_types_join: true,
+                ..Default::default()
+            },
+        })
+    }
+    async fn attach_station_attributes(
+        &self,
+        stations: Vec<Station>,
+        scope: &StationScope,
+    ) -> Result<Vec<Station>, UseCaseError> {
         let mut stations = self
-            .update_station_vec_with_attributes(stations, None, transport_type, true)
+            .update_station_vec_with_attributes(
+                stations,
+                scope.line_group_id,
+                scope.transport_type,
+                scope.skip_types_join,
+            )
             .await?;
+        if scope.line_group_ids.is_empty() {
+            return Ok(stations);
+        }
 
         // 複数line_group_idの列車種別を一括取得してセット
         let train_types = self
             .train_type_repository
-            .get_by_line_group_id_vec(line_group_ids)
+            .get_by_line_group_id_vec(&scope.line_group_ids)
             .await?;
         let train_type_map: std::collections::HashMap<i32, &TrainType> = train_types
             .iter()
@@ -405,6 +486,17 @@ where
 
         Ok(stations)
     }
+    async fn get_station_lines(
+        &self,
+        stations: &[Station],
+        transport_type: TransportTypeFilter,
+        skip_types_join: bool,
+    ) -> Result<Vec<Vec<Line>>, UseCaseError> {
+        let (lines, _) = self
+            .join_station_lines(stations, transport_type, skip_types_join)
+            .await?;
+        Ok(lines)
+    }
     fn get_station_numbers(&self, station: &Station) -> Vec<StationNumber> {
         let line_symbols_raw = [
             &station.line_symbol1,
@@ -950,7 +1042,7 @@ where
             entity_type: "line group",
             entity_id: "unspecified".to_string(),
         })?;
-        let stations = self
+        let StationList { stations, scope } = self
             .get_stations_by_line_group_id(line_group_id, TransportTypeFilter::RailAndBus)
             .await?;
 
@@ -976,6 +1068,8 @@ where
             v.reverse();
             v
         };
+        // 路線や列車種別は区間内の駅にだけ付ければよい
+        let sliced = self.attach_station_attributes(sliced, &scope).await?;
 
         let mut segments: Vec<model::TrainRouteSegment> = Vec::with_capacity(sliced.len());
         // 経路スライス内で路線ごとに通過駅があるか。通過駅が無い路線では優等種別でも
@@ -1069,15 +1163,366 @@ where
         from_station_group_id: u32,
         to_station_group_id: u32,
     ) -> Result<Vec<Route>, UseCaseError> {
-        if from_station_group_id == to_station_group_id {
+        let (candidates, _) = self
+            .resolve_connected_routes(from_station_group_id, to_station_group_id)
+            .await?;
+
+        let mut routes = Vec::with_capacity(candidates.len());
+        for candidate in candidates {
+            let virtual_line_group_id = candidate.id;
+            let stops = candidate
+                .stops
+                .into_iter()
+                .map(|row| {
+                    let extracted_line = self.extract_line_from_station(&row);
+                    let train_type = TrainType {
+                        id: row.type_id,
+                        station_cd: Some(row.station_cd),
+                        type_cd: row.type_cd,
+                        line_group_cd: Some(virtual_line_group_id as i32),
+                        pass: row.pass,
+                        type_name: row.type_name.clone().unwrap_or_default(),
+                        type_name_k: row.type_name_k.clone().unwrap_or_default(),
+                        type_name_r: row.type_name_r.clone(),
+                        type_name_zh: row.type_name_zh.clone(),
+                        type_name_ko: row.type_name_ko.clone(),
+                        color: row.color.clone().unwrap_or_default(),
+                        direction: row.direction,
+                        kind: row.kind,
+                        line: Some(Box::new(extracted_line.clone())),
+                        lines: vec![extracted_line.clone()],
+                    };
+                    let mut stop = self.build_station_from_row(
+                        &row,
+                        &extracted_line,
+                        Some(Box::new(train_type)),
+                    );
+                    stop.line_group_cd = Some(virtual_line_group_id as i32);
+                    model::Station::from(stop)
+                })
+                .collect();
+            routes.push(Route {
+                id: virtual_line_group_id,
+                stops,
+            });
+        }
+        Ok(routes)
+    }
+
+    /// 連結経路の候補ごとに、レグ単位の推定所要時間と乗換時間を積み上げた旅程を返す。
+    /// 到着の早い順に並べ、`max_transfers` を超える乗換を要する候補は除く。
+    async fn get_journeys(
+        &self,
+        from_station_group_id: u32,
+        to_station_group_id: u32,
+        depart_after_minutes: Option<u32>,
+        max_transfers: Option<u32>,
+    ) -> Result<Vec<Journey>, UseCaseError> {
+        let (candidates, line_group_stops) = self
+            .resolve_connected_routes(from_station_group_id, to_station_group_id)
+            .await?;
+        let params = EstimationParams::default();
+        let depart_after = f64::from(depart_after_minutes.unwrap_or(0));
+
+        let mut journeys = Vec::with_capacity(candidates.len());
+        for candidate in &candidates {
+            let transfers = candidate.line_group_ids.len().saturating_sub(1);
+            if max_transfers.is_some_and(|max| transfers > max as usize) {
+                continue;
+            }
+            let legs: Vec<JourneyLegInput> = candidate
+                .line_group_ids
+                .iter()
+                .enumerate()
+                .map(|(index, &line_group_id)| {
+                    // 2 レグ目以降は乗車駅が `stops` に含まれないので先頭に補う
+                    let mut stops: Vec<&Station> = Vec::new();
+                    if index > 0 {
+                        stops.extend(candidate.boardings.get(index));
+                    }
+                    stops.extend(
+                        candidate
+                            .stops
+                            .iter()
+                            .filter(|stop| stop.line_group_cd == Some(line_group_id as i32)),
+                    );
+                    JourneyLegInput {
+                        stops,
+                        calibration_stops: line_group_stops
+                            .get(&line_group_id)
+                            .map(|all| all.iter().collect())
+                            .unwrap_or_default(),
+                    }
+                })
+                .collect();
+            if let Some(journey) = build_journey(candidate.id, &legs, depart_after, &params) {
+                journeys.push(journey);
+            }
+        }
+        rank_journeys(&mut journeys);
+        Ok(journeys)
+    }
+
+    /// バス停の次の発車を早い順に返す。同じ停留所 (駅グループ) を通る
+    /// 他の系統の便もまとめて返す。鉄道駅を指定した場合は空。
+    async fn get_departures(
+        &self,
+        station_id: u32,
+        date: Date,
+        after_minutes: u32,
+        limit: Option<u32>,
+    ) -> Result<Vec<Departure>, UseCaseError> {
+        let Some(station) = self.station_repository.find_by_id(station_id).await? else {
+            return Ok(vec![]);
+        };
+        if station.transport_type != TransportType::Bus {
+            return Ok(vec![]);
+        }
+        let mut station_ids: Vec<u32> = vec![station_id];
+        for stop in self
+            .station_repository
+            .get_by_station_group_id(station.station_g_cd as u32)
+            .await?
+        {
+            let id = stop.station_cd as u32;
+            if stop.transport_type == TransportType::Bus && !station_ids.contains(&id) {
+                station_ids.push(id);
+            }
+        }
+
+        let timetables = self
+            .station_repository
+            .get_bus_timetables_by_station_ids(&station_ids)
+            .await?;
+        let limit = limit
+            .map_or(DEPARTURES_DEFAULT_LIMIT, |v| v as usize)
+            .min(DEPARTURES_MAX_LIMIT);
+        Ok(next_departures(&timetables, date, after_minutes, limit))
+    }
+
+    /// 駅グループから徒歩で乗り換えられる鉄道駅を近い順に返す。
+    /// 連絡先の駅が廃止済みなどで引けない連絡は落とす。
+    async fn get_station_transfers(
+        &self,
+        station_group_id: u32,
+    ) -> Result<Vec<StationTransfer>, UseCaseError> {
+        let connections = self
+            .station_repository
+            .get_connections_by_station_group_id(station_group_id)
+            .await?;
+        if connections.is_empty() {
             return Ok(vec![]);
         }
+        let ids: Vec<u32> = connections.iter().map(|c| c.station_cd2).collect();
+        let StationList { stations, scope } = self
+            .get_stations_by_id_vec(&ids, TransportTypeFilter::Rail)
+            .await?;
+        let stations: HashMap<i32, Station> = self
+            .attach_station_attributes(stations, &scope)
+            .await?
+            .into_iter()
+            .map(|s| (s.station_cd, s))
+            .collect();
+
+        let mut transfers: Vec<StationTransfer> = connections
+            .iter()
+            .filter_map(|c| {
+                Some(StationTransfer {
+                    station: stations.get(&(c.station_cd2 as i32))?.clone(),
+                    distance: c.distance,
+                })
+            })
+            .collect();
+        transfers.sort_by(|a, b| {
+            a.distance
+                .total_cmp(&b.distance)
+                .then(a.station.station_cd.cmp(&b.station.station_cd))
+        });
+        Ok(transfers)
+    }
+
+    async fn get_through_services(
+        &self,
+        line_group_id: u32,
+    ) -> Result<Vec<ThroughServiceLink>, UseCaseError> {
+        let services = self
+            .line_repository
+            .get_through_services_by_line_group_id(line_group_id)
+            .await?;
+        if services.is_empty() {
+            return Ok(vec![]);
+        }
+        let line_ids: Vec<u32> = services
+            .iter()
+            .flat_map(|s| [s.from_line_cd, s.to_line_cd])
+            .collect();
+        let station_ids: Vec<u32> = services.iter().map(|s| s.to_station_cd).collect();
+        let lines = self.line_repository.get_by_ids(&line_ids).await?;
+        // 駅は行のまま返す。路線などの属性は GraphQL 層がローダーでまとめて引く
+        let StationList { stations, .. } = self
+            .get_stations_by_id_vec(&station_ids, TransportTypeFilter::RailAndBus)
+            .await?;
+        let lines: HashMap<i32, Line> = lines.into_iter().map(|l| (l.line_cd, l)).collect();
+        let stations: HashMap<i32, Station> =
+            stations.into_iter().map(|s| (s.station_cd, s)).collect();
+
+        // 無効な路線・駅をまたぐ直通は出さない
+        Ok(services
+            .iter()
+            .filter_map(|s| {
+                Some(ThroughServiceLink {
+                    from_line: lines.get(&(s.from_line_cd as i32))?.clone(),
+                    to_line: lines.get(&(s.to_line_cd as i32))?.clone(),
+                    station: stations.get(&(s.to_station_cd as i32))?.clone(),
+                })
+            })
+            .collect())
+    }
+
+    async fn get_through_service_lines(&self, line_id: u32) -> Result<Vec<Line>, UseCaseError> {
+        let ids = self
+            .line_repository
+            .get_through_service_line_ids(line_id)
+            .await?;
+        Ok(self.line_repository.get_by_ids(&ids).await?)
+    }
+
+    async fn compare_train_types(
+        &self,
+        line_group_id_a: u32,
+        line_group_id_b: u32,
+    ) -> Result<StopPatternComparison, UseCaseError> {
+        let (a, b) = tokio::try_join!(
+            self.line_group_stops(line_group_id_a),
+            self.line_group_stops(line_group_id_b),
+        )?;
+        Ok(compare_stop_patterns(&a, &b))
+    }
+
+    /// `from_station_id` から `to_station_id` までの区間の各駅について、始点からの
+    /// 推定到着時間(分)を返す。経路候補ごとに両端が含まれる区間だけへ絞り込み、
+    /// `direction_id` の有無に関わらず from→to 順になるよう並べ替える。
+    async fn estimate_route_arrival_times(
+        &self,
+        from_station_id: u32,
+        to_station_id: u32,
+        via_line_ids: &[u32],
+        direction_id: Option<u32>,
+    ) -> Result<Vec<EstimatedStop>, UseCaseError> {
+        let stops = self
+            .station_repository
+            .get_route_stops_by_station_cd(
+                from_station_id,
+                to_station_id,
+                via_line_ids,
+                direction_id,
+            )
+            .await?;
+
+        let route_row_tree_map = self.build_route_tree_map(&stops);
+        let params = EstimationParams::default();
+
+        let mut result: Vec<EstimatedStop> = Vec::new();
+        for group_stops in route_row_tree_map.values() {
+            // 先頭駅が末尾にも重複格納された「閉じた」環状データ(ポートライナー等)は、
+            // そのままだとラップ時に閉じ駅が二重になるため重複終端を除いてから
+            // 環状判定・弧選択する。
+            let mut route_stops: &[&Station] = group_stops.as_slice();
+            if route_stops.len() > 1
+                && route_stops[0].station_cd == route_stops[route_stops.len() - 1].station_cd
+            {
+                route_stops = &route_stops[..route_stops.len() - 1];
+            }
+
+            let from_pos = route_stops
+                .iter()
+                .position(|s| s.station_cd as u32 == from_station_id);
+            let to_pos = route_stops
+                .iter()
+                .position(|s| s.station_cd as u32 == to_station_id);
+
+            // 始点・終点の両方がこの経路候補に含まれない、または同一駅の場合は対象外。
+            let (Some(fi), Some(ti)) = (from_pos, to_pos) else {
+                continue;
+            };
+            if fi == ti {
+                continue;
+            }
+
+            // 経路全体ではなく、始点→終点の区間だけに絞り込んで推定する。
+            // 環状経路(山手線・大阪環状線など)は線形スライスだと格納順の
+            // 継ぎ目(例: 品川⇔大崎)を跨ぐ乗車で逆側の弧を返してしまうため、
+            // シームをラップする弧を選択する。
+            // 迂回係数の較正母数には切り出し前の経路全体を渡す。切り出した区間の
+            // 駅間隔が路線平均と異なると較正が破綻するため(スライス較正バグ)。
+            if is_circular_route(route_stops) {
+                let arc = select_circular_arc(route_stops, fi, ti, direction_id.is_some());
+                result.extend(estimate_arrival_minutes_calibrated(
+                    &arc,
+                    route_stops,
+                    &params,
+                ));
+            } else if fi < ti {
+                result.extend(estimate_arrival_minutes_calibrated(
+                    &route_stops[fi..=ti],
+                    route_stops,
+                    &params,
+                ));
+            } else {
+                let mut segment: Vec<&Station> = route_stops[ti..=fi].to_vec();
+                segment.reverse();
+                result.extend(estimate_arrival_minutes_calibrated(
+                    &segment,
+                    route_stops,
+                    &params,
+                ));
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+impl<SR, LR, TR, CR> QueryInteractor<SR, LR, TR, CR>
+where
+    SR: StationRepository,
+    LR: LineRepository,
+    TR: TrainTypeRepository,
+    CR: CompanyRepository,
+{
+    /// 系統の駅列 (運転順) に路線・列車種別を付けたもの。駅が無い系統は `NotFound`。
+    async fn line_group_stops(&self, line_group_id: u32) -> Result<Vec<Station>, UseCaseError> {
+        let StationList { stations, scope } = self
+            .get_stations_by_line_group_id(line_group_id, TransportTypeFilter::RailAndBus)
+            .await?;
+        if stations.is_empty() {
+            return Err(UseCaseError::NotFound {
+                entity_type: "line group",
+                entity_id: line_group_id.to_string(),
+            });
+        }
+        self.attach_station_attributes(stations, &scope).await
+    }
+
+    /// 駅グループ間を系統の乗り継ぎで結ぶ経路候補を探索し、停車駅の詳細まで解決する。
+    ///
+    /// 2 つ目の戻り値は候補に現れる系統ごとの全停車駅(sst.id 順)で、
+    /// レグごとの所要時間を推定するときの較正母数に使う。
+    async fn resolve_connected_routes(
+        &self,
+        from_station_group_id: u32,
+        to_station_group_id: u32,
+    ) -> Result<(Vec<ResolvedConnectedRoute>, HashMap<u32, Vec<Station>>), UseCaseError> {
+        if from_station_group_id == to_station_group_id {
+            return Ok((vec![], HashMap::new()));
+        }
 
         let mut states = vec![ConnectedRouteState {
             current_group_id: from_station_group_id,
             line_group_ids: vec![],
             visited_station_groups: HashSet::from([from_station_group_id]),
             stops: vec![],
+            boardings: vec![],
         }];
         let mut line_groups_by_station: HashMap<u32, Vec<u32>> = HashMap::new();
         let mut stops_by_line_group: HashMap<u32, Vec<ConnectedRoutePatternStop>> = HashMap::new();
@@ -1238,12 +1683,20 @@ where
                             }
                             let mut line_group_ids = state.line_group_ids.clone();
                             line_group_ids.push(line_group_id);
+                            let mut boardings = state.boardings.clone();
+                            boardings.push(ConnectedRouteStopRef {
+                                line_group_id,
+                                station_station_type_id: pattern[start_index]
+                                    .station_station_type_id,
+                                station_group_id: pattern[start_index].station_group_id,
+                            });
 
                             let candidate = ConnectedRouteState {
                                 current_group_id: destination_group_id,
                                 line_group_ids,
                                 visited_station_groups,
                                 stops,
+                                boardings,
                             };
                             if destination_group_id == to_station_group_id {
                                 let signature = connected_route_signature(&candidate);
@@ -1266,7 +1719,7 @@ where
         }
 
         if completed.is_empty() {
-            return Ok(vec![]);
+            return Ok((vec![], HashMap::new()));
         }
 
         let detailed_line_group_ids: Vec<u32> = completed
@@ -1280,161 +1733,48 @@ where
             .get_by_line_group_id_vec(&detailed_line_group_ids)
             .await?;
         let mut detailed_stops_by_id: HashMap<(u32, i32), Station> = HashMap::new();
+        let mut line_group_stops: HashMap<u32, Vec<Station>> = HashMap::new();
         for stop in detailed_stops {
             if let (Some(line_group_id), Some(station_station_type_id)) =
                 (stop.line_group_cd.map(|id| id as u32), stop.sst_id)
             {
+                line_group_stops
+                    .entry(line_group_id)
+                    .or_default()
+                    .push(stop.clone());
                 detailed_stops_by_id.insert((line_group_id, station_station_type_id), stop);
             }
         }
-
-        let mut used_virtual_ids = HashSet::new();
-        let mut routes = Vec::new();
-        for candidate in completed {
-            let signature = connected_route_signature(&candidate);
-            let virtual_line_group_id =
-                connected_route_virtual_id(&signature, &mut used_virtual_ids);
-            let Some(detailed_stops): Option<Vec<Station>> = candidate
-                .stops
-                .iter()
+        let resolve = |refs: &[ConnectedRouteStopRef]| -> Option<Vec<Station>> {
+            refs.iter()
                 .map(|stop| {
                     detailed_stops_by_id
                         .get(&(stop.line_group_id, stop.station_station_type_id))
                         .cloned()
                 })
                 .collect()
+        };
+
+        let mut used_virtual_ids = HashSet::new();
+        let mut resolved = Vec::with_capacity(completed.len());
+        for candidate in completed {
+            let signature = connected_route_signature(&candidate);
+            let id = connected_route_virtual_id(&signature, &mut used_virtual_ids);
+            let (Some(stops), Some(boardings)) =
+                (resolve(&candidate.stops), resolve(&candidate.boardings))
             else {
                 continue;
             };
-            let stops = detailed_stops
-                .into_iter()
-                .map(|row| {
-                    let extracted_line = self.extract_line_from_station(&row);
-                    let train_type = TrainType {
-                        id: row.type_id,
-                        station_cd: Some(row.station_cd),
-                        type_cd: row.type_cd,
-                        line_group_cd: Some(virtual_line_group_id as i32),
-                        pass: row.pass,
-                        type_name: row.type_name.clone().unwrap_or_default(),
-                        type_name_k: row.type_name_k.clone().unwrap_or_default(),
-                        type_name_r: row.type_name_r.clone(),
-                        type_name_zh: row.type_name_zh.clone(),
-                        type_name_ko: row.type_name_ko.clone(),
-                        color: row.color.clone().unwrap_or_default(),
-                        direction: row.direction,
-                        kind: row.kind,
-                        line: Some(Box::new(extracted_line.clone())),
-                        lines: vec![extracted_line.clone()],
-                    };
-                    let mut stop = self.build_station_from_row(
-                        &row,
-                        &extracted_line,
-                        Some(Box::new(train_type)),
-                    );
-                    stop.line_group_cd = Some(virtual_line_group_id as i32);
-                    model::Station::from(stop)
-                })
-                .collect();
-            routes.push(Route {
-                id: virtual_line_group_id,
+            resolved.push(ResolvedConnectedRoute {
+                id,
+                line_group_ids: candidate.line_group_ids,
                 stops,
+                boardings,
             });
         }
-        Ok(routes)
-    }
-
-    /// `from_station_id` から `to_station_id` までの区間の各駅について、始点からの
-    /// 推定到着時間(分)を返す。経路候補ごとに両端が含まれる区間だけへ絞り込み、
-    /// `direction_id` の有無に関わらず from→to 順になるよう並べ替える。
-    async fn estimate_route_arrival_times(
-        &self,
-        from_station_id: u32,
-        to_station_id: u32,
-        via_line_ids: &[u32],
-        direction_id: Option<u32>,
-    ) -> Result<Vec<EstimatedStop>, UseCaseError> {
-        let stops = self
-            .station_repository
-            .get_route_stops_by_station_cd(
-                from_station_id,
-                to_station_id,
-                via_line_ids,
-                direction_id,
-            )
-            .await?;
-
-        let route_row_tree_map = self.build_route_tree_map(&stops);
-        let params = EstimationParams::default();
-
-        let mut result: Vec<EstimatedStop> = Vec::new();
-        for group_stops in route_row_tree_map.values() {
-            // 先頭駅が末尾にも重複格納された「閉じた」環状データ(ポートライナー等)は、
-            // そのままだとラップ時に閉じ駅が二重になるため重複終端を除いてから
-            // 環状判定・弧選択する。
-            let mut route_stops: &[&Station] = group_stops.as_slice();
-            if route_stops.len() > 1
-                && route_stops[0].station_cd == route_stops[route_stops.len() - 1].station_cd
-            {
-                route_stops = &route_stops[..route_stops.len() - 1];
-            }
-
-            let from_pos = route_stops
-                .iter()
-                .position(|s| s.station_cd as u32 == from_station_id);
-            let to_pos = route_stops
-                .iter()
-                .position(|s| s.station_cd as u32 == to_station_id);
-
-            // 始点・終点の両方がこの経路候補に含まれない、または同一駅の場合は対象外。
-            let (Some(fi), Some(ti)) = (from_pos, to_pos) else {
-                continue;
-            };
-            if fi == ti {
-                continue;
-            }
-
-            // 経路全体ではなく、始点→終点の区間だけに絞り込んで推定する。
-            // 環状経路(山手線・大阪環状線など)は線形スライスだと格納順の
-            // 継ぎ目(例: 品川⇔大崎)を跨ぐ乗車で逆側の弧を返してしまうため、
-            // シームをラップする弧を選択する。
-            // 迂回係数の較正母数には切り出し前の経路全体を渡す。切り出した区間の
-            // 駅間隔が路線平均と異なると較正が破綻するため(スライス較正バグ)。
-            if is_circular_route(route_stops) {
-                let arc = select_circular_arc(route_stops, fi, ti, direction_id.is_some());
-                result.extend(estimate_arrival_minutes_calibrated(
-                    &arc,
-                    route_stops,
-                    &params,
-                ));
-            } else if fi < ti {
-                result.extend(estimate_arrival_minutes_calibrated(
-                    &route_stops[fi..=ti],
-                    route_stops,
-                    &params,
-                ));
-            } else {
-                let mut segment: Vec<&Station> = route_stops[ti..=fi].to_vec();
-                segment.reverse();
-                result.extend(estimate_arrival_minutes_calibrated(
-                    &segment,
-                    route_stops,
-                    &params,
-                ));
-            }
-        }
-
-        Ok(result)
+        Ok((resolved, line_group_stops))
     }
-}
 
-impl<SR, LR, TR, CR> QueryInteractor<SR, LR, TR, CR>
-where
-    SR: StationRepository,
-    LR: LineRepository,
-    TR: TrainTypeRepository,
-    CR: CompanyRepository,
-{
     async fn get_stations_by_group_id_vec_no_types(
         &self,
         station_group_id_vec: &[u32],
@@ -1469,34 +1809,97 @@ where
             .get_bus_stops_near_stations(coords, limit_per_station)
             .await?;
 
-        Ok(result)
-    }
+        Ok(result)
+    }
+
+    async fn update_station_vec_with_attributes(
+        &self,
+        mut stations: Vec<Station>,
+        line_group_id: Option<u32>,
+        transport_type: TransportTypeFilter,
+        skip_types_join: bool,
+    ) -> Result<Vec<Station>, UseCaseError> {
+        let (station_lines, stations_by_group_ids) = self
+            .join_station_lines(&stations, transport_type, skip_types_join)
+            .await?;
+
+        let station_ids = stations_by_group_ids
+            .iter()
+            .map(|station| station.station_cd as u32)
+            .collect::<Vec<u32>>();
+
+        // Collect company IDs from the stations' own lines and every joined line
+        // 路線ごとに重複した会社IDをそのままIN句に並べないよう先に一意化する
+        let mut company_ids: Vec<u32> = stations
+            .iter()
+            .map(|s| s.company_cd.unwrap_or_default() as u32)
+            .chain(station_lines.iter().flatten().map(|l| l.company_cd as u32))
+            .collect();
+        company_ids.sort_unstable();
+        company_ids.dedup();
+
+        // Fetch companies and train types in one batch
+        let (companies, train_types) = tokio::try_join!(
+            self.find_company_by_id_vec(&company_ids),
+            self.get_train_types_by_station_id_vec(&station_ids, line_group_id),
+        )?;
+
+        // Build HashMap for O(1) company lookup
+        let company_map: std::collections::HashMap<i32, Company> =
+            companies.into_iter().map(|c| (c.company_cd, c)).collect();
+
+        // Build HashMap for O(1) train_type lookup by station_cd
+        let train_type_map: std::collections::HashMap<i32, &TrainType> = train_types
+            .iter()
+            .filter_map(|tt| tt.station_cd.map(|cd| (cd, tt)))
+            .collect();
+
+        for (station, mut station_lines) in stations.iter_mut().zip(station_lines) {
+            let mut line = self.extract_line_from_station(station);
+            line.line_symbols = self.get_line_symbols(&line);
+            line.company = company_map.get(&line.company_cd).cloned();
+
+            let station_numbers: Vec<StationNumber> = self.get_station_numbers(station);
+            station.station_numbers = station_numbers;
+            if let Some(tt) = train_type_map
+                .get(&station.station_cd)
+                .copied()
+                .cloned()
+                .map(Box::new)
+            {
+                station.train_type = Some(tt);
+            }
+
+            line.station = Some(station.clone());
+            station.line = Some(Box::new(line));
 
-    async fn update_station_vec_with_attributes(
-        &self,
-        stations: Vec<Station>,
-        line_group_id: Option<u32>,
-        transport_type: TransportTypeFilter,
-        skip_types_join: bool,
-    ) -> Result<Vec<Station>, UseCaseError> {
-        self.update_station_vec_with_attributes_inner(
-            stations,
-            line_group_id,
-            transport_type,
-            skip_types_join,
-            None,
-        )
-        .await
+            for line in station_lines.iter_mut() {
+                line.company = company_map.get(&line.company_cd).cloned();
+                if let Some(ref mut station_copy) = line.station {
+                    if let Some(tt) = train_type_map
+                        .get(&station_copy.station_cd)
+                        .copied()
+                        .cloned()
+                        .map(Box::new)
+                    {
+                        station_copy.train_type = Some(tt);
+                    };
+                }
+            }
+            station.lines = station_lines;
+        }
+
+        Ok(stations)
     }
 
-    async fn update_station_vec_with_attributes_inner(
+    /// 各駅の乗り入れ路線を組み立てる。駅と同じ並びで返し、あわせて
+    /// 駅グループに属する駅 (列車種別を引く対象) も返す。
+    async fn join_station_lines(
         &self,
-        mut stations: Vec<Station>,
-        line_group_id: Option<u32>,
+        stations: &[Station],
         transport_type: TransportTypeFilter,
         skip_types_join: bool,
-        prefetched_group_stations: Option<Vec<Station>>,
-    ) -> Result<Vec<Station>, UseCaseError> {
+    ) -> Result<(Vec<Vec<Line>>, Vec<Station>), UseCaseError> {
         let mut station_group_ids: Vec<u32> = stations
             .iter()
             .map(|station| station.station_g_cd as u32)
@@ -1524,20 +1927,11 @@ where
         // (used by the lineListStations query).
         // Also batch-fetch bus stop candidates in parallel.
         let (stations_by_group_ids, lines, bus_candidates_flat) = if skip_types_join {
-            if let Some(prefetched) = prefetched_group_stations {
-                // Group stations already fetched by expanded primary query
-                let (lines, bus) = tokio::try_join!(
-                    self.get_lines_by_station_group_id_vec_no_types(&station_group_ids),
-                    self.get_bus_stops_near_stations(&unique_bus_coords, 50),
-                )?;
-                (prefetched, lines, bus)
-            } else {
-                tokio::try_join!(
-                    self.get_stations_by_group_id_vec_no_types(&station_group_ids),
-                    self.get_lines_by_station_group_id_vec_no_types(&station_group_ids),
-                    self.get_bus_stops_near_stations(&unique_bus_coords, 50),
-                )?
-            }
+            tokio::try_join!(
+                self.get_stations_by_group_id_vec_no_types(&station_group_ids),
+                self.get_lines_by_station_group_id_vec_no_types(&station_group_ids),
+                self.get_bus_stops_near_stations(&unique_bus_coords, 50),
+            )?
         } else {
             let (s, l) = tokio::try_join!(
                 self.get_stations_by_group_id_vec(&station_group_ids),
@@ -1568,47 +1962,10 @@ where
             .collect();
         all_bus_station_group_ids.sort_unstable();
 
-        let station_ids = stations_by_group_ids
-            .iter()
-            .map(|station| station.station_cd as u32)
-            .collect::<Vec<u32>>();
-
-        // Collect company IDs from rail lines
-        // 路線ごとに重複した会社IDをそのままIN句に並べないよう先に一意化する
-        let mut company_ids: Vec<u32> = lines.iter().map(|l| l.company_cd as u32).collect();
-        company_ids.sort_unstable();
-        company_ids.dedup();
-
-        // Phase 2: dependent queries in parallel
-        // Fetch companies, train types, and all bus lines in one batch
-        let (companies, train_types, all_bus_lines) = tokio::try_join!(
-            self.find_company_by_id_vec(&company_ids),
-            self.get_train_types_by_station_id_vec(&station_ids, line_group_id),
-            self.get_lines_by_station_group_id_vec(&all_bus_station_group_ids),
-        )?;
-
-        // Collect bus company IDs and fetch any missing companies
-        let bus_company_ids: Vec<u32> = all_bus_lines.iter().map(|l| l.company_cd as u32).collect();
-        company_ids.extend(bus_company_ids);
-        company_ids.sort_unstable();
-        company_ids.dedup();
-
-        // Build HashMap for O(1) company lookup
-        let mut company_map: std::collections::HashMap<i32, Company> =
-            companies.into_iter().map(|c| (c.company_cd, c)).collect();
-
-        // Fetch any bus companies not already in the map
-        let missing_company_ids: Vec<u32> = company_ids
-            .iter()
-            .filter(|id| !company_map.contains_key(&(**id as i32)))
-            .copied()
-            .collect();
-        if !missing_company_ids.is_empty() {
-            let extra_companies = self.find_company_by_id_vec(&missing_company_ids).await?;
-            for c in extra_companies {
-                company_map.insert(c.company_cd, c);
-            }
-        }
+        // Phase 2: all bus lines in one batch
+        let all_bus_lines = self
+            .get_lines_by_station_group_id_vec(&all_bus_station_group_ids)
+            .await?;
 
         // Pre-index bus lines by station_g_cd for O(1) lookup
         let mut bus_lines_by_g_cd: std::collections::HashMap<i32, Vec<&Line>> =
@@ -1619,12 +1976,6 @@ where
             }
         }
 
-        // Build HashMap for O(1) train_type lookup by station_cd
-        let train_type_map: std::collections::HashMap<i32, &TrainType> = train_types
-            .iter()
-            .filter_map(|tt| tt.station_cd.map(|cd| (cd, tt)))
-            .collect();
-
         // Build lookup map for stations_by_group_ids: (line_cd, station_g_cd) -> Station
         let station_lookup: std::collections::HashMap<(i32, i32), &Station> = stations_by_group_ids
             .iter()
@@ -1640,25 +1991,8 @@ where
             }
         }
 
-        for station in stations.iter_mut() {
-            let mut line = self.extract_line_from_station(station);
-            line.line_symbols = self.get_line_symbols(&line);
-            line.company = company_map.get(&line.company_cd).cloned();
-
-            let station_numbers: Vec<StationNumber> = self.get_station_numbers(station);
-            station.station_numbers = station_numbers;
-            if let Some(tt) = train_type_map
-                .get(&station.station_cd)
-                .copied()
-                .cloned()
-                .map(Box::new)
-            {
-                station.train_type = Some(tt);
-            }
-
-            line.station = Some(station.clone());
-            station.line = Some(Box::new(line));
-
+        let mut out = Vec::with_capacity(stations.len());
+        for station in stations {
             let mut seen_line_cds = std::collections::HashSet::new();
             let mut station_lines: Vec<Line> = lines_by_g_cd
                 .get(&station.station_g_cd)
@@ -1726,7 +2060,6 @@ where
                         }
 
                         for bus_line in bus_lines.iter_mut() {
-                            bus_line.line_symbols = self.get_line_symbols(bus_line);
                             if let Some(&bus_stop) = bus_stop_by_line_cd.get(&bus_line.line_cd) {
                                 let mut station_copy = bus_stop.clone();
                                 station_copy.station_numbers =
@@ -1745,7 +2078,6 @@ where
             }
 
             for line in station_lines.iter_mut() {
-                line.company = company_map.get(&line.company_cd).cloned();
                 line.line_symbols = self.get_line_symbols(line);
                 if let Some(station_ref) = station_lookup.get(&(line.line_cd, station.station_g_cd))
                 {
@@ -1753,21 +2085,13 @@ where
                     let station_numbers: Vec<StationNumber> =
                         self.get_station_numbers(&station_copy);
                     station_copy.station_numbers = station_numbers;
-                    if let Some(tt) = train_type_map
-                        .get(&station_copy.station_cd)
-                        .copied()
-                        .cloned()
-                        .map(Box::new)
-                    {
-                        station_copy.train_type = Some(tt);
-                    };
                     line.station = Some(station_copy);
                 }
             }
-            station.lines = station_lines;
+            out.push(station_lines);
         }
 
-        Ok(stations)
+        Ok((out, stations_by_group_ids))
     }
 
     /// 停車駅を系統ごとにまとめる。
@@ -3521,12 +3845,17 @@ mod tests {
     mod update_station_vec_with_attributes_tests {
         use super::*;
         use crate::domain::{
-            entity::company::Company,
+            entity::{
+                company::Company,
+                connection::Connection,
+                gtfs::{GtfsCalendar, GtfsCalendarDate},
+            },
             error::DomainError,
             repository::{
                 company_repository::CompanyRepository, line_repository::LineRepository,
                 station_repository::StationRepository, train_type_repository::TrainTypeRepository,
             },
+            timetable::BusTimetable,
         };
 
         /// Configurable mock station repository for testing
@@ -3534,6 +3863,9 @@ mod tests {
             stations_by_group: Vec<Station>,
             bus_stops: Vec<Station>,
             stations_by_line_group: Vec<Station>,
+            stations_by_id: Vec<Station>,
+            connections: Vec<Connection>,
+            bus_timetables: Vec<BusTimetable>,
         }
 
         impl ConfigurableMockStationRepository {
@@ -3542,6 +3874,9 @@ mod tests {
                     stations_by_group,
                     bus_stops,
                     stations_by_line_group: vec![],
+                    stations_by_id: vec![],
+                    connections: vec![],
+                    bus_timetables: vec![],
                 }
             }
 
@@ -3549,15 +3884,47 @@ mod tests {
                 self.stations_by_line_group = stations;
                 self
             }
+
+            fn with_connections(
+                mut self,
+                stations: Vec<Station>,
+                connections: Vec<Connection>,
+            ) -> Self {
+                self.stations_by_id = stations;
+                self.connections = connections;
+                self
+            }
+
+            fn with_bus_timetables(
+                mut self,
+                stations: Vec<Station>,
+                timetables: Vec<BusTimetable>,
+            ) -> Self {
+                self.stations_by_id = stations;
+                self.bus_timetables = timetables;
+                self
+            }
         }
 
         #[async_trait::async_trait]
         impl StationRepository for ConfigurableMockStationRepository {
-            async fn find_by_id(&self, _: u32) -> Result<Option<Station>, DomainError> {
-                Ok(None)
+            async fn find_by_id(&self, id: u32) -> Result<Option<Station>, DomainError> {
+                Ok(self
+                    .stations_by_id
+                    .iter()
+                    .find(|s| s.station_cd == id as i32)
+                    .cloned())
             }
-            async fn get_by_id_vec(&self, _: &[u32]) -> Result<Vec<Station>, DomainError> {
-                Ok(vec![])
+            async fn get_by_id_vec(&self, ids: &[u32]) -> Result<Vec<Station>, DomainError> {
+                Ok(ids
+                    .iter()
+                    .filter_map(|&id| {
+                        self.stations_by_id
+                            .iter()
+                            .find(|s| s.station_cd == id as i32)
+                            .cloned()
+                    })
+                    .collect())
             }
             async fn get_by_line_id(
                 &self,
@@ -3576,6 +3943,23 @@ mod tests {
             ) -> Result<Vec<Station>, DomainError> {
                 Ok(self.stations_by_group.clone())
             }
+            async fn get_connections_by_station_group_id(
+                &self,
+                _: u32,
+            ) -> Result<Vec<Connection>, DomainError> {
+                Ok(self.connections.clone())
+            }
+            async fn get_bus_timetables_by_station_ids(
+                &self,
+                station_ids: &[u32],
+            ) -> Result<Vec<BusTimetable>, DomainError> {
+                Ok(self
+                    .bus_timetables
+                    .iter()
+                    .filter(|t| station_ids.contains(&(t.station_cd as u32)))
+                    .cloned()
+                    .collect())
+            }
             async fn get_by_station_group_id(&self, _: u32) -> Result<Vec<Station>, DomainError> {
                 Ok(vec![])
             }
@@ -4659,10 +5043,14 @@ mod tests {
                 company_repository: ConfigurableMockCompanyRepository::new(vec![company]),
             };
 
-            let result = interactor
+            let StationList { stations, scope } = interactor
                 .get_stations_by_line_group_id_vec(&[1000, 2000], TransportTypeFilter::Rail)
                 .await
                 .expect("Should succeed");
+            let result = interactor
+                .attach_station_attributes(stations, &scope)
+                .await
+                .expect("Should succeed");
 
             assert_eq!(result.len(), 2);
 
@@ -4731,10 +5119,14 @@ mod tests {
                 company_repository: ConfigurableMockCompanyRepository::new(vec![company]),
             };
 
-            let result = interactor
+            let StationList { stations, scope } = interactor
                 .get_stations_by_line_group_id_vec(&[1000], TransportTypeFilter::Rail)
                 .await
                 .expect("Should succeed");
+            let result = interactor
+                .attach_station_attributes(stations, &scope)
+                .await
+                .expect("Should succeed");
 
             assert_eq!(result.len(), 1);
 
@@ -4788,7 +5180,81 @@ mod tests {
                 .await
                 .expect("Should succeed");
 
-            assert!(result.is_empty());
+            assert!(result.stations.is_empty());
+        }
+
+        #[tokio::test]
+        async fn test_get_stations_by_line_group_id_vec_returns_rows_with_scope() {
+            let company = create_test_company(1, "JR東日本");
+
+            let mut station = create_test_station(101, 1001, 100, Some(1000));
+            station.company_cd = Some(1);
+            let train_type = create_test_train_type_for_station(101, "快速");
+            let line = create_test_line_for_station_group(100, 1001);
+
+            let station_repo =
+                ConfigurableMockStationRepository::new(vec![station.clone()], vec![])
+                    .with_line_group_stations(vec![station.clone()]);
+            let interactor = QueryInteractor {
+                station_repository: station_repo,
+                line_repository: ConfigurableMockLineRepository::new(vec![line]),
+                train_type_repository: ConfigurableMockTrainTypeRepository::new(vec![train_type]),
+                company_repository: ConfigurableMockCompanyRepository::new(vec![company]),
+            };
+
+            let result = interactor
+                .get_stations_by_line_group_id_vec(&[1000, 2000], TransportTypeFilter::Rail)
+                .await
+                .expect("Should succeed");
+
+            // 路線・列車種別は付けずに返し、付けるための条件を添える
+            assert_eq!(result.stations.len(), 1);
+            let row = &result.stations[0];
+            assert!(row.line.is_none());
+            assert!(row.lines.is_empty());
+            assert!(row.train_type.is_none());
+            assert_eq!(result.scope.line_group_ids, vec![1000, 2000]);
+            assert_eq!(result.scope.line_group_id, None);
+            assert!(result.scope.skip_types_join);
+        }
+
+        #[tokio::test]
+        async fn test_get_station_lines_leaves_company_and_train_type_to_caller() {
+            let company = create_test_company(1, "JR東日本");
+
+            let mut station = create_test_station(101, 1001, 100, Some(1000));
+            station.company_cd = Some(1);
+            let mut other = create_test_station(102, 1001, 200, Some(1000));
+            other.company_cd = Some(1);
+            let train_type = create_test_train_type_for_station(102, "快速");
+
+            let interactor = create_configurable_interactor_with_line_group_id(
+                vec![station.clone(), other],
+                vec![],
+                vec![
+                    create_test_line_for_station_group(100, 1001),
+                    create_test_line_for_station_group(200, 1001),
+                ],
+                vec![train_type],
+                vec![company],
+                Some(1000),
+            );
+
+            let lines = interactor
+                .get_station_lines(&[station], TransportTypeFilter::Rail, false)
+                .await
+                .expect("Should succeed");
+
+            assert_eq!(lines.len(), 1);
+            assert_eq!(lines[0].len(), 2);
+            for line in &lines[0] {
+                assert!(line.company.is_none());
+                assert!(!line.line_symbols.is_empty());
+                let stop = line.station.as_ref().expect("stop should be attached");
+                assert_eq!(stop.line_cd, line.line_cd);
+                assert!(!stop.station_numbers.is_empty());
+                assert!(stop.train_type.is_none());
+            }
         }
 
         fn create_connected_stop(
@@ -4921,6 +5387,219 @@ mod tests {
             let unreachable = interactor.get_connected_routes(1, 99).await.unwrap();
             assert!(unreachable.is_empty());
         }
+
+        #[tokio::test]
+        async fn test_get_journeys_ranks_by_arrival_and_adds_transfer_time() {
+            use crate::domain::journey::TRANSFER_BASE_MINUTES;
+
+            let interactor = create_connected_route_interactor();
+
+            let journeys = interactor
+                .get_journeys(1, 4, Some(480), None)
+                .await
+                .unwrap();
+            let routes = interactor.get_connected_routes(1, 4).await.unwrap();
+            assert_eq!(journeys.len(), routes.len());
+            assert!(journeys
+                .windows(2)
+                .all(|pair| pair[0].arrival_minutes <= pair[1].arrival_minutes));
+
+            // 全駅が同じ座標なので、乗換のない直通が最速になる
+            assert_eq!(journeys[0].transfer_count, 0);
+            assert_eq!(journeys[0].departure_minutes, 480.0);
+
+            let connected = journeys
+                .iter()
+                .find(|journey| {
+                    journey
+                        .legs
+                        .iter()
+                        .map(|leg| leg.line_group_cd)
+                        .collect::<Vec<_>>()
+                        == vec![100, 200, 300]
+                })
+                .expect("three-segment journey should be returned");
+            assert_eq!(connected.transfer_count, 2);
+            let boarding: Vec<i32> = connected
+                .legs
+                .iter()
+                .map(|leg| leg.stops[0].station_cd)
+                .collect();
+            assert_eq!(boarding, vec![101, 202, 303]);
+            assert_eq!(connected.legs[0].transfer_minutes, 0.0);
+            assert!(connected.legs[1..]
+                .iter()
+                .all(|leg| leg.transfer_minutes >= TRANSFER_BASE_MINUTES));
+            assert!(connected.total_minutes >= 2.0 * TRANSFER_BASE_MINUTES);
+        }
+
+        #[tokio::test]
+        async fn test_get_journeys_respects_max_transfers() {
+            let interactor = create_connected_route_interactor();
+
+            let journeys = interactor.get_journeys(1, 4, None, Some(0)).await.unwrap();
+            assert!(!journeys.is_empty());
+            assert!(journeys.iter().all(|journey| journey.transfer_count == 0));
+            assert_eq!(journeys[0].departure_minutes, 0.0);
+        }
+
+        #[tokio::test]
+        async fn test_get_departures_resolves_service_days() {
+            let mut bus = create_bus_stop(501, 35.0, 139.0, 500);
+            bus.station_g_cd = 5001;
+            let rail = create_test_station(101, 1001, 100, None);
+            let everyday = GtfsCalendar::new(
+                "1".to_string(),
+                true,
+                true,
+                true,
+                true,
+                true,
+                true,
+                true,
+                "20240101".to_string(),
+                "20241231".to_string(),
+            );
+            let timetable = |station_cd: i32, headsign: &str, minutes: Vec<u32>| BusTimetable {
+                station_cd,
+                line_cd: 500,
+                headsign: headsign.to_string(),
+                calendar: Some(everyday.clone()),
+                calendar_dates: vec![GtfsCalendarDate::new(
+                    1,
+                    "1".to_string(),
+                    "20240429".to_string(),
+                    2,
+                )],
+                departure_minutes: minutes,
+            };
+
+            let mut interactor =
+                create_configurable_interactor(vec![], vec![], vec![], vec![], vec![]);
+            interactor.station_repository = ConfigurableMockStationRepository::new(vec![], vec![])
+                .with_bus_timetables(
+                    vec![bus, rail],
+                    vec![
+                        timetable(501, "渋谷駅", vec![480, 490, 1450]),
+                        // 指定したバス停を通らない
+                        timetable(502, "目黒駅", vec![485]),
+                        timetable(101, "鉄道", vec![485]),
+                    ],
+                );
+
+            let date = Date::parse("2024-04-30").unwrap();
+            let departures = interactor
+                .get_departures(501, date, 485, None)
+                .await
+                .unwrap();
+            let minutes: Vec<u32> = departures.iter().map(|d| d.departure_minutes).collect();
+            // 4/29 は運休なので、その運行日の深夜便 (24:10) は出ない
+            assert_eq!(minutes, vec![490]);
+            assert!(departures.iter().all(|d| d.headsign == "渋谷駅"));
+
+            let departures = interactor
+                .get_departures(501, Date::parse("2024-05-01").unwrap(), 0, Some(1))
+                .await
+                .unwrap();
+            assert_eq!(departures.len(), 1);
+            assert_eq!(departures[0].departure_minutes, 1450);
+            assert_eq!(departures[0].service_date, date);
+
+            let departures = interactor.get_departures(101, date, 0, None).await.unwrap();
+            assert!(departures.is_empty());
+        }
+
+        #[tokio::test]
+        async fn test_get_departures_on_a_substitute_holiday() {
+            let mut bus = create_bus_stop(501, 35.0, 139.0, 500);
+            bus.station_g_cd = 5001;
+            let calendar = |service_id: &str, weekdays: bool, sunday: bool| {
+                GtfsCalendar::new(
+                    service_id.to_string(),
+                    weekdays,
+                    weekdays,
+                    weekdays,
+                    weekdays,
+                    weekdays,
+                    false,
+                    sunday,
+                    "20240101".to_string(),
+                    "20241231".to_string(),
+                )
+            };
+            let timetable = |calendar: GtfsCalendar, minutes: Vec<u32>| BusTimetable {
+                station_cd: 501,
+                line_cd: 500,
+                headsign: "渋谷駅".to_string(),
+                calendar: Some(calendar),
+                calendar_dates: vec![],
+                departure_minutes: minutes,
+            };
+
+            let mut interactor =
+                create_configurable_interactor(vec![], vec![], vec![], vec![], vec![]);
+            interactor.station_repository = ConfigurableMockStationRepository::new(vec![], vec![])
+                .with_bus_timetables(
+                    vec![bus],
+                    vec![
+                        timetable(calendar("weekday", true, false), vec![480, 500]),
+                        timetable(calendar("holiday", false, true), vec![490, 530]),
+                    ],
+                );
+
+            // 2024-02-12 は振替休日 (月曜)。日曜・休日ダイヤの便だけが出る
+            let departures = interactor
+                .get_departures(501, Date::parse("2024-02-12").unwrap(), 0, None)
+                .await
+                .unwrap();
+            let minutes: Vec<u32> = departures.iter().map(|d| d.departure_minutes).collect();
+            assert_eq!(minutes, vec![490, 530]);
+
+            let departures = interactor
+                .get_departures(501, Date::parse("2024-02-13").unwrap(), 0, None)
+                .await
+                .unwrap();
+            let minutes: Vec<u32> = departures.iter().map(|d| d.departure_minutes).collect();
+            assert_eq!(minutes, vec![480, 500]);
+        }
+
+        #[tokio::test]
+        async fn test_get_station_transfers_sorts_by_distance_and_drops_missing() {
+            let near = create_test_station(201, 2001, 200, None);
+            let far = create_test_station(301, 3001, 300, None);
+            let mut bus = create_test_station(401, 4001, 400, None);
+            bus.transport_type = TransportType::Bus;
+
+            let mut interactor = create_configurable_interactor(
+                vec![],
+                vec![],
+                vec![
+                    create_test_line_for_station_group(200, 2001),
+                    create_test_line_for_station_group(300, 3001),
+                ],
+                vec![],
+                vec![],
+            );
+            interactor.station_repository = ConfigurableMockStationRepository::new(vec![], vec![])
+                .with_connections(
+                    vec![near, far, bus],
+                    vec![
+                        Connection::new(1, 101, 301, 480.0),
+                        Connection::new(2, 101, 201, 120.5),
+                        // 連絡先が引けない
+                        Connection::new(3, 101, 999, 50.0),
+                        // バス停は徒歩連絡の対象外
+                        Connection::new(4, 101, 401, 10.0),
+                    ],
+                );
+
+            let transfers = interactor.get_station_transfers(1001).await.unwrap();
+            let summary: Vec<(i32, f64)> = transfers
+                .iter()
+                .map(|t| (t.station.station_cd, t.distance))
+                .collect();
+            assert_eq!(summary, vec![(201, 120.5), (301, 480.0)]);
+        }
     }
 
     // ========================================
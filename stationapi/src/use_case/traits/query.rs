@@ -3,32 +3,68 @@ use async_trait::async_trait;
 use crate::{
     domain::{
         arrival_estimation::EstimatedStop,
+        calendar::{Date, ServiceDay},
         entity::{
-            company::Company, gtfs::TransportTypeFilter, line::Line, line_symbol::LineSymbol,
-            station::Station, station_number::StationNumber, train_type::TrainType,
+            company::Company,
+            connection::{StationTransfer, ThroughServiceLink},
+            gtfs::TransportTypeFilter,
+            line::Line,
+            line_symbol::LineSymbol,
+            station::Station,
+            station_number::StationNumber,
+            train_type::TrainType,
         },
+        geo::BoundingBox,
+        journey::Journey,
+        stop_pattern::StopPatternComparison,
+        timetable::Departure,
     },
     model::{Route, TrainRouteSegment},
     use_case::error::UseCaseError,
 };
 
+/// 駅一覧をどの条件で引いたか。路線・会社・列車種別は、この条件を添えて
+/// 必要になったときに付ける (`attach_station_attributes`)。
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct StationScope {
+    /// 列車種別を引く系統。
+    pub line_group_id: Option<u32>,
+    /// 複数系統の駅一覧では、列車種別をここに挙げた系統のものにする。
+    pub line_group_ids: Vec<u32>,
+    pub transport_type: TransportTypeFilter,
+    /// 乗り入れ路線の停車駅に系統・種別の列を付けない。
+    pub skip_types_join: bool,
+    /// 開業・廃止をこの日付で判定した一覧か (`None` は今の状態)。
+    /// UseCase は日付を扱わないので、日付を指定して引いた呼び出し側が入れる。
+    pub as_of: Option<Date>,
+    /// 停車条件をこの日のダイヤで解いた一覧か。`as_of` と同じく呼び出し側が入れる。
+    pub service_day: Option<ServiceDay>,
+}
+
+/// 駅の行と、それを引いた条件。
+#[derive(Clone, Debug, Default)]
+pub struct StationList {
+    pub stations: Vec<Station>,
+    pub scope: StationScope,
+}
+
 #[async_trait]
 pub trait QueryUseCase: Send + Sync + 'static {
     async fn find_station_by_id(
         &self,
         station_id: u32,
         transport_type: TransportTypeFilter,
-    ) -> Result<Option<Station>, UseCaseError>;
+    ) -> Result<StationList, UseCaseError>;
     async fn get_stations_by_id_vec(
         &self,
         station_ids: &[u32],
         transport_type: TransportTypeFilter,
-    ) -> Result<Vec<Station>, UseCaseError>;
+    ) -> Result<StationList, UseCaseError>;
     async fn get_stations_by_group_id(
         &self,
         station_group_id: u32,
         transport_type: TransportTypeFilter,
-    ) -> Result<Vec<Station>, UseCaseError>;
+    ) -> Result<StationList, UseCaseError>;
     async fn get_stations_by_group_id_vec(
         &self,
         station_group_id_vec: &[u32],
@@ -39,26 +75,34 @@ pub trait QueryUseCase: Send + Sync + 'static {
         longitude: f64,
         limit: Option<u32>,
         transport_type: TransportTypeFilter,
-    ) -> Result<Vec<Station>, UseCaseError>;
+    ) -> Result<StationList, UseCaseError>;
+    async fn get_stations_in_bounds(
+        &self,
+        bounds: BoundingBox,
+        line_type: Option<i32>,
+        limit: Option<u32>,
+        dedupe_by_group: bool,
+        transport_type: TransportTypeFilter,
+    ) -> Result<StationList, UseCaseError>;
     async fn get_stations_by_line_id(
         &self,
         line_id: u32,
         station_id: Option<u32>,
         direction_id: Option<u32>,
         transport_type: TransportTypeFilter,
-    ) -> Result<Vec<Station>, UseCaseError>;
+    ) -> Result<StationList, UseCaseError>;
     async fn get_stations_by_line_id_vec(
         &self,
         line_ids: &[u32],
         transport_type: TransportTypeFilter,
-    ) -> Result<Vec<Station>, UseCaseError>;
+    ) -> Result<StationList, UseCaseError>;
     async fn get_stations_by_name(
         &self,
         station_name: String,
         limit: Option<u32>,
         from_station_group_id: Option<u32>,
         transport_type: TransportTypeFilter,
-    ) -> Result<Vec<Station>, UseCaseError>;
+    ) -> Result<StationList, UseCaseError>;
     async fn find_company_by_id_vec(
         &self,
         company_id_vec: &[u32],
@@ -74,16 +118,30 @@ pub trait QueryUseCase: Send + Sync + 'static {
     fn get_station_numbers(&self, station: &Station) -> Vec<StationNumber>;
     fn get_line_symbols(&self, line: &Line) -> Vec<LineSymbol>;
     fn extract_line_from_station(&self, station: &Station) -> Line;
+    /// 駅一覧に路線・会社・駅ナンバリング・列車種別をまとめて付ける。
+    async fn attach_station_attributes(
+        &self,
+        stations: Vec<Station>,
+        scope: &StationScope,
+    ) -> Result<Vec<Station>, UseCaseError>;
+    /// 各駅の乗り入れ路線 (近くのバス路線を含む)。路線記号と停車駅は付けるが、
+    /// 会社と列車種別は付けない。
+    async fn get_station_lines(
+        &self,
+        stations: &[Station],
+        transport_type: TransportTypeFilter,
+        skip_types_join: bool,
+    ) -> Result<Vec<Vec<Line>>, UseCaseError>;
     async fn get_stations_by_line_group_id(
         &self,
         line_group_id: u32,
         transport_type: TransportTypeFilter,
-    ) -> Result<Vec<Station>, UseCaseError>;
+    ) -> Result<StationList, UseCaseError>;
     async fn get_stations_by_line_group_id_vec(
         &self,
         line_group_ids: &[u32],
         transport_type: TransportTypeFilter,
-    ) -> Result<Vec<Station>, UseCaseError>;
+    ) -> Result<StationList, UseCaseError>;
     async fn get_train_types_by_station_id(
         &self,
         station_id: u32,
@@ -130,4 +188,36 @@ pub trait QueryUseCase: Send + Sync + 'static {
         via_line_ids: &[u32],
         direction_id: Option<u32>,
     ) -> Result<Vec<EstimatedStop>, UseCaseError>;
+    async fn get_journeys(
+        &self,
+        from_station_group_id: u32,
+        to_station_group_id: u32,
+        depart_after_minutes: Option<u32>,
+        max_transfers: Option<u32>,
+    ) -> Result<Vec<Journey>, UseCaseError>;
+    async fn get_departures(
+        &self,
+        station_id: u32,
+        date: Date,
+        after_minutes: u32,
+        limit: Option<u32>,
+    ) -> Result<Vec<Departure>, UseCaseError>;
+    async fn get_station_transfers(
+        &self,
+        station_group_id: u32,
+    ) -> Result<Vec<StationTransfer>, UseCaseError>;
+    /// 系統が路線をまたいで直通するところ。路線と直通先に入る駅を引いて返す。
+    /// 駅は行のままで、路線などは付けない (`TransportTypeFilter::RailAndBus` で引く)。
+    async fn get_through_services(
+        &self,
+        line_group_id: u32,
+    ) -> Result<Vec<ThroughServiceLink>, UseCaseError>;
+    /// その路線と直通でつながる路線。
+    async fn get_through_service_lines(&self, line_id: u32) -> Result<Vec<Line>, UseCaseError>;
+    /// 2 系統の停車駅を駅グループで突き合わせる。
+    async fn compare_train_types(
+        &self,
+        line_group_id_a: u32,
+        line_group_id_b: u32,
+    ) -> Result<StopPatternComparison, UseCaseError>;
 }